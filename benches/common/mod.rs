@@ -0,0 +1,99 @@
+// benches/common/mod.rs
+
+// Shared by the criterion benches: the settings and the regression check.
+// To compare a change against the code before it, save a baseline first and
+// then run the change against it with the check on:
+//
+//     git checkout main && cargo bench -- --save-baseline main
+//     git checkout my-branch && SNAKE_BENCH_CHECK=1 cargo bench -- --baseline main
+//
+// With `SNAKE_BENCH_CHECK` set, a bench binary exits with an error when a
+// bench of a group got slower than that group's threshold allows, taking
+// the low end of criterion's confidence interval so noise alone doesn't
+// trip it. Without it the benches only report, like plain criterion.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use criterion::Criterion;
+
+// Changes under 2% are reported as noise, and a warm-up of a second is
+// plenty for games this small
+pub fn criterion() -> Criterion {
+    Criterion::default().noise_threshold(0.02).significance_level(0.01).warm_up_time(Duration::from_secs(1))
+}
+
+// Runs `benches` (from `criterion_group!`), then checks the groups listed
+// in `thresholds`, each with the slowdown it may have in percent
+pub fn run(benches: fn(), thresholds: &[(&str, f64)]) {
+    let started = SystemTime::now();
+    benches();
+    criterion().configure_from_args().final_summary();
+    if std::env::var_os("SNAKE_BENCH_CHECK").is_none() {
+        return;
+    }
+    let regressions = check_regressions(started, thresholds);
+    if !regressions.is_empty() {
+        eprintln!("Slower than the baseline allows:");
+        for line in &regressions {
+            eprintln!("  {}", line);
+        }
+        std::process::exit(1);
+    }
+}
+
+fn criterion_dir() -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR").map_or_else(|| PathBuf::from("target"), PathBuf::from);
+    target.join("criterion")
+}
+
+// Every bench of the groups that ran since `started` and whose mean went up
+// by more than its threshold
+fn check_regressions(started: SystemTime, thresholds: &[(&str, f64)]) -> Vec<String> {
+    let mut regressions = Vec::new();
+    for &(group, percent) in thresholds {
+        let mut estimates = Vec::new();
+        find_estimates(&criterion_dir().join(group), &mut estimates);
+        for path in estimates {
+            // Left over from an earlier run, or filtered out of this one
+            let fresh = fs::metadata(&path).and_then(|m| m.modified()).is_ok_and(|modified| modified >= started);
+            if !fresh {
+                continue;
+            }
+            let Some(lower) = mean_change(&path) else { continue };
+            if lower * 100.0 > percent {
+                let bench = path.parent().and_then(Path::parent).unwrap_or(&path);
+                let name = bench.strip_prefix(criterion_dir()).unwrap_or(bench);
+                regressions.push(format!("{}: at least {:+.1}% (threshold {}%)", name.display(), lower * 100.0, percent));
+            }
+        }
+    }
+    regressions
+}
+
+// The `change/estimates.json` files criterion writes under `dir` when it
+// compared against a baseline
+fn find_estimates(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name == "change") {
+                let estimates = path.join("estimates.json");
+                if estimates.is_file() {
+                    found.push(estimates);
+                }
+            } else {
+                find_estimates(&path, found);
+            }
+        }
+    }
+}
+
+// Lower bound of the relative change of the mean, 0.05 for 5% slower
+fn mean_change(path: &Path) -> Option<f64> {
+    let text = fs::read_to_string(path).ok()?;
+    let estimates: serde_json::Value = serde_json::from_str(&text).ok()?;
+    estimates["mean"]["confidence_interval"]["lower_bound"].as_f64()
+}
@@ -0,0 +1,48 @@
+// benches/food.rs
+
+// Placing food when the board is nearly full, the case that used to list
+// every free cell: the snake covers everything but `free` cells and one
+// food is put on one of them. The time should hardly move with the board
+// size or the number of free cells.
+
+mod common;
+
+use criterion::{black_box, criterion_group, BatchSize, BenchmarkId, Criterion};
+use snake_game::bench_hooks::{near_full, spawn_food};
+use snake_game::FoodKind;
+
+const BOARD_SIZES: [i32; 2] = [100, 300];
+
+const FREE_CELLS: [usize; 4] = [1, 10, 100, 1_000];
+
+fn spawn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn_food");
+    for size in BOARD_SIZES {
+        for free in FREE_CELLS {
+            let game = near_full(size, free, 1).expect("benchmark boards are valid");
+            let id = BenchmarkId::new(format!("{}x{}", size, size), free);
+            // Each iteration fills one of the free cells, so it gets a copy
+            group.bench_with_input(id, &game, |b, game| {
+                b.iter_batched(
+                    || game.clone(),
+                    |mut game| {
+                        spawn_food(&mut game, FoodKind::Apple).expect("there is a free cell");
+                        black_box(game)
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = spawn
+}
+
+fn main() {
+    common::run(benches, &[("spawn_food", 5.0)]);
+}
@@ -0,0 +1,73 @@
+// benches/pathfinding.rs
+
+// What one move of the pathfinder AI costs: a search from the head to the
+// closest food it can reach, on a board some way into a round. Also prints
+// how many cells each search took off the queue, which doesn't depend on
+// the machine, so a change to the search shows up in it before the timings.
+// The large board has a pathfinder opponent instead, every tick of a game
+// like that runs one search of up to a million cells.
+
+mod common;
+
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion};
+use snake_game::bench_hooks::{counters, played, reset_counters, started};
+use snake_game::{Controller, Game};
+
+const BOARD_SIZES: [i32; 3] = [20, 50, 100];
+const LARGE_BOARD: i32 = 1000;
+
+// Far enough into a round for a snake with a few turns in it and food
+// somewhere away from the head
+fn mid_game(size: i32) -> Game {
+    played(size, Controller::Pathfinder, size as u64 * 20, 1).expect("benchmark boards are valid")
+}
+
+fn decide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pathfinder_move");
+    for size in BOARD_SIZES {
+        let game = mid_game(size);
+        reset_counters();
+        Controller::Pathfinder.decide(&game, 0);
+        println!(
+            "pathfinder_move/{}x{}: snake of {}, {} cells searched per move",
+            size,
+            size,
+            game.snake.body.len(),
+            counters().cells_searched
+        );
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &game, |b, game| {
+            b.iter(|| Controller::Pathfinder.decide(black_box(game), 0))
+        });
+    }
+    group.finish();
+}
+
+// Fresh from the start, the opponent's food is anywhere on the board
+fn opponent_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pathfinder_opponent");
+    group.sample_size(20);
+    let mut game = started(LARGE_BOARD, 1).expect("benchmark boards are valid");
+    let opponent = game.add_opponent_auto(Some(Controller::Pathfinder)).expect("the board has room");
+    reset_counters();
+    Controller::Pathfinder.decide(&game, opponent);
+    println!(
+        "pathfinder_opponent/{}x{}: {} cells searched per move",
+        LARGE_BOARD,
+        LARGE_BOARD,
+        counters().cells_searched
+    );
+    group.bench_function(format!("{}x{}", LARGE_BOARD, LARGE_BOARD), |b| {
+        b.iter(|| Controller::Pathfinder.decide(black_box(&game), opponent))
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = decide, opponent_move
+}
+
+fn main() {
+    common::run(benches, &[("pathfinder_move", 5.0), ("pathfinder_opponent", 5.0)]);
+}
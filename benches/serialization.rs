@@ -0,0 +1,73 @@
+// benches/serialization.rs
+
+// Saving and loading: replays in their binary and base64 forms, snapshots
+// and compact states as JSON (what save files and the network send), and
+// the in-memory snapshot/restore the search and rollback code lean on.
+// Each one is a full round trip, encoding and decoding.
+
+mod common;
+
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion};
+use snake_game::bench_hooks::{long_snake, played};
+use snake_game::{CompactState, Controller, Game, Replay, Snapshot};
+
+// A round played for a while on a normal board, and a long snake on a big
+// one where the body dominates
+fn games() -> [(&'static str, Game); 2] {
+    let played = played(50, Controller::Pathfinder, 1_000, 1).expect("benchmark boards are valid");
+    let long = long_snake(200, 10_000, 1).expect("benchmark boards are valid");
+    [("played", played), ("long", long)]
+}
+
+fn replays(c: &mut Criterion) {
+    let replay = played(50, Controller::Pathfinder, 1_000, 1).expect("benchmark boards are valid").replay();
+    let mut group = c.benchmark_group("replay");
+    group.bench_function("bytes", |b| {
+        b.iter(|| Replay::from_bytes(&black_box(&replay).to_bytes()).expect("replays round-trip"))
+    });
+    group.bench_function("base64", |b| {
+        b.iter(|| Replay::from_base64(&black_box(&replay).to_base64()).expect("replays round-trip"))
+    });
+    group.finish();
+}
+
+fn states(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state");
+    for (name, mut game) in games() {
+        group.bench_with_input(BenchmarkId::new("snapshot_json", name), &game, |b, game| {
+            b.iter(|| {
+                let json = serde_json::to_string(&black_box(game).snapshot()).expect("snapshots serialize");
+                serde_json::from_str::<Snapshot>(&json).expect("snapshots round-trip")
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("compact_json", name), &game, |b, game| {
+            b.iter(|| {
+                let json = serde_json::to_string(&black_box(game).compact_state()).expect("states serialize");
+                serde_json::from_str::<CompactState>(&json).expect("states round-trip")
+            })
+        });
+        group.bench_function(BenchmarkId::new("snapshot_restore", name), |b| {
+            b.iter(|| {
+                let snapshot = game.snapshot();
+                game.restore(black_box(&snapshot));
+            })
+        });
+        group.bench_function(BenchmarkId::new("compact_restore", name), |b| {
+            b.iter(|| {
+                let state = game.compact_state();
+                game.restore_compact(black_box(&state));
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = replays, states
+}
+
+fn main() {
+    common::run(benches, &[("replay", 5.0), ("state", 5.0)]);
+}
@@ -0,0 +1,100 @@
+// benches/ticks.rs
+
+// Criterion benches for the tick loop, see src/bench.rs for the headless
+// binary that also counts allocations. Each iteration runs a batch of ticks
+// on a fresh game, restarting whenever the snake dies. The long group is
+// the big-board target: 1000x1000 with a snake of 100k segments.
+
+mod common;
+
+use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion, Throughput};
+use snake_game::bench_hooks::{long_snake, steer_on_cycle};
+use snake_game::{Controller, Direction, Game, GameConfig};
+
+const TICKS: u64 = 1_000;
+
+const BOARD_SIZES: [i32; 3] = [20, 50, 100];
+
+const LONG_BOARD: i32 = 1000;
+const LONG_SNAKE: usize = 100_000;
+
+fn game(size: i32, autopilot: Option<Controller>, opponent: Option<Controller>) -> Game {
+    let config = GameConfig {
+        width: size,
+        height: size,
+        seed: Some(1),
+        wrap_walls: autopilot.is_none(),
+        opponents: opponent.map(Some).into_iter().collect(),
+        ..GameConfig::default()
+    };
+    let mut game = Game::from_config(&config).expect("benchmark boards are valid");
+    game.autopilot = autopilot;
+    game.start_game();
+    game
+}
+
+fn run(mut game: Game, scripted: bool) -> Game {
+    const TURNS: [Direction; 4] = [Direction::Right, Direction::Down, Direction::Left, Direction::Up];
+    for tick in 0..TICKS {
+        if game.game_over {
+            game.start_game();
+        }
+        if scripted && tick.is_multiple_of(7) {
+            game.queue_turn(TURNS[(tick / 7 % 4) as usize]);
+        }
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    game
+}
+
+fn ticks(c: &mut Criterion) {
+    // Name, the player's AI (none for scripted turns) and an AI opponent
+    let scenarios = [
+        ("scripted", None, None),
+        ("greedy", Some(Controller::Greedy), None),
+        ("pathfinder", Some(Controller::Pathfinder), None),
+        ("versus", Some(Controller::Greedy), Some(Controller::Pathfinder)),
+    ];
+    for (name, autopilot, opponent) in scenarios {
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(TICKS));
+        for size in BOARD_SIZES {
+            group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+                b.iter_batched(|| game(size, autopilot, opponent), |game| run(game, autopilot.is_none()), BatchSize::SmallInput)
+            });
+        }
+        group.finish();
+    }
+}
+
+// Building the board takes longer than the ticks, so it's built once and
+// the snake keeps going round the cycle from one iteration to the next
+fn long(c: &mut Criterion) {
+    let mut game = long_snake(LONG_BOARD, LONG_SNAKE, 1).expect("benchmark boards are valid");
+
+    let mut group = c.benchmark_group("long");
+    group.throughput(Throughput::Elements(TICKS));
+    group.bench_function(format!("{}x{}", LONG_BOARD, LONG_BOARD), |b| {
+        b.iter(|| {
+            for _ in 0..TICKS {
+                steer_on_cycle(&mut game);
+                game.tick();
+                game.drain_events().for_each(drop);
+            }
+        })
+    });
+    group.finish();
+    assert!(!game.game_over, "the long snake ran into something");
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = ticks, long
+}
+
+// The AI scenarios do more work per tick and vary more from run to run
+fn main() {
+    common::run(benches, &[("scripted", 5.0), ("greedy", 5.0), ("pathfinder", 10.0), ("versus", 10.0), ("long", 5.0)]);
+}
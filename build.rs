@@ -0,0 +1,71 @@
+// build.rs
+
+// Only does something for the cdylib's bindings. The `ffi` feature writes
+// the C header for `snake_game::ffi` to OUT_DIR, with the settings in
+// cbindgen.toml. Only src/ffi.rs is read, the rest of the crate has no C
+// API. A copy is committed as include/snake_game.h, so C hosts can build
+// against it without running cargo; the build warns when it's stale and
+// says where to copy the new one from, it doesn't touch the source tree. The `node` feature sets up the linker for a
+// Node.js addon (macOS needs Node's symbols left undefined). The `protobuf`
+// feature generates the Rust types for proto/snake_game.proto into OUT_DIR,
+// with a protoc binary that comes with the build dependency, and `grpc` the
+// service in proto/snake_remote.proto on top of them.
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    write_ffi_header();
+    #[cfg(feature = "node")]
+    napi_build::setup();
+    #[cfg(feature = "protobuf")]
+    compile_protos();
+    #[cfg(feature = "grpc")]
+    compile_service();
+}
+
+#[cfg(feature = "ffi")]
+fn write_ffi_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    println!("cargo::rerun-if-changed=src/ffi.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+    println!("cargo::rerun-if-changed=include/snake_game.h");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("cbindgen.toml is valid");
+    let header = format!("{}/snake_game.h", out_dir);
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/ffi.rs", crate_dir))
+        .generate()
+        .expect("the C header can be generated")
+        .write_to_file(&header);
+    let committed = format!("{}/include/snake_game.h", crate_dir);
+    if std::fs::read(&header).ok() != std::fs::read(&committed).ok() {
+        println!("cargo::warning=include/snake_game.h is out of date, copy it from {}", header);
+    }
+}
+
+#[cfg(feature = "protobuf")]
+fn prost_config() -> prost_build::Config {
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path().expect("protoc is vendored for this platform"));
+    config
+}
+
+#[cfg(feature = "protobuf")]
+fn compile_protos() {
+    println!("cargo::rerun-if-changed=proto/snake_game.proto");
+    prost_config()
+        .compile_protos(&["proto/snake_game.proto"], &["proto"])
+        .expect("proto/snake_game.proto compiles");
+}
+
+// Only the server side, for snake-server. The messages are the ones above,
+// as `snake_game::proto`.
+#[cfg(feature = "grpc")]
+fn compile_service() {
+    println!("cargo::rerun-if-changed=proto/snake_remote.proto");
+    tonic_prost_build::configure()
+        .build_client(false)
+        .extern_path(".snake_game", "::snake_game::proto")
+        .compile_with_config(prost_config(), &["proto/snake_remote.proto"], &["proto"])
+        .expect("proto/snake_remote.proto compiles");
+}
@@ -0,0 +1,68 @@
+// examples/embedded_sim.rs
+
+// The game on a simulated 128x64 monochrome OLED (the size of the common
+// SSD1306 modules), played by the pathfinder AI. The board is drawn after
+// every tick, only the cells that changed, and every few ticks the display
+// is saved as a PNG, scaled up and colored like a blue OLED:
+//
+//     cargo run --example embedded_sim --features embedded -- [output dir]
+//
+// On real hardware the display comes from its driver crate and the ticks
+// from a timer, the drawing code stays the same.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics_simulator::{BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay};
+use snake_game::embedded::BoardRenderer;
+use snake_game::{CellKind, Controller, Game, GameConfig};
+
+const DISPLAY: Size = Size::new(128, 64);
+// A text line on top, the board in 4x4 pixel cells below it
+const BOARD_TOP: i32 = 12;
+const TICKS: u64 = 600;
+const FRAME_EVERY: u64 = 50;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::args().nth(1).map_or_else(|| PathBuf::from("target/embedded_sim"), PathBuf::from);
+    std::fs::create_dir_all(&dir)?;
+
+    let config = GameConfig { width: 30, height: 13, seed: Some(7), ..GameConfig::default() };
+    let mut game = Game::from_config(&config)?;
+    game.autopilot = Some(Controller::Pathfinder);
+    game.start_game();
+
+    let mut display = SimulatorDisplay::<BinaryColor>::new(DISPLAY);
+    let board = Size::new(DISPLAY.width, DISPLAY.height - BOARD_TOP as u32);
+    let mut renderer = BoardRenderer::fit(&game, board);
+    renderer.origin.y += BOARD_TOP;
+    let text = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let settings = OutputSettingsBuilder::new().theme(BinaryColorTheme::OledBlue).scale(3).build();
+
+    let text_line = Rectangle::new(Point::zero(), Size::new(DISPLAY.width, BOARD_TOP as u32));
+    for tick in 0..=TICKS {
+        display.fill_solid(&text_line, BinaryColor::Off)?;
+        Text::with_baseline(&format!("Score {}", game.score), Point::zero(), text, Baseline::Top).draw(&mut display)?;
+        // One color: everything but the empty cells lit
+        renderer.draw_changes_with(&mut game, &mut display, |kind| match kind {
+            CellKind::Empty => BinaryColor::Off,
+            _ => BinaryColor::On,
+        })?;
+        if tick % FRAME_EVERY == 0 || game.game_over {
+            let path = dir.join(format!("frame_{:04}.png", tick));
+            display.to_rgb_output_image(&settings).save_png(&path)?;
+            println!("Saved {}", path.display());
+        }
+        if game.game_over {
+            break;
+        }
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+/*
+ * The snake game engine for C, C++ and anything else that loads a C
+ * library. Build the library with
+ *
+ *     cargo build --release --lib --no-default-features --features ffi
+ *
+ * and link against target/release/libsnake_game.so (snake_game.dll,
+ * libsnake_game.dylib). Every frame, pass the inputs to
+ * snake_game_queue_input, the time since the last frame to snake_game_tick
+ * and draw from snake_game_state_json. A new game waits for
+ * SnakeInput_Confirm.
+ *
+ * snake_game_new takes a GameConfig as JSON, NULL for the defaults, and
+ * returns NULL if it's invalid. The string from snake_game_state_json
+ * belongs to the game and is only valid until the next call with it. Free
+ * games with snake_game_free. A game can move between threads but must not
+ * be used from two at once.
+ */
+
+#ifndef SNAKE_GAME_H
+#define SNAKE_GAME_H
+
+/* Generated by cbindgen from src/ffi.rs with the `ffi` feature, don't edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+enum SnakeInput
+#if __STDC_VERSION__ >= 202311L
+  : uint32_t
+#endif // __STDC_VERSION__ >= 202311L
+ {
+  SnakeInput_Up = 0,
+  SnakeInput_Down = 1,
+  SnakeInput_Left = 2,
+  SnakeInput_Right = 3,
+  SnakeInput_Pause = 4,
+  SnakeInput_Restart = 5,
+  SnakeInput_Boost = 6,
+  SnakeInput_Confirm = 7,
+};
+#if __STDC_VERSION__ >= 202311L
+typedef enum SnakeInput SnakeInput;
+#else
+typedef uint32_t SnakeInput;
+#endif // __STDC_VERSION__ >= 202311L
+
+typedef struct SnakeGame SnakeGame;
+
+struct SnakeGame *snake_game_new(const char *config_json);
+
+uint32_t snake_game_tick(struct SnakeGame *game, double elapsed_seconds);
+
+bool snake_game_queue_input(struct SnakeGame *game, uint32_t input);
+
+const char *snake_game_state_json(struct SnakeGame *game);
+
+void snake_game_free(struct SnakeGame *game);
+
+#endif  /* SNAKE_GAME_H */
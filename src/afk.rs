@@ -0,0 +1,75 @@
+// src/afk.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, Game};
+
+// What a snake does while its player is away, see `Game::is_afk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum AfkMode {
+    // Keeps going the way it was heading, as without the feature
+    #[default]
+    Straight,
+    // Goes straight on, turning only when the cell ahead would kill it
+    Safe,
+}
+
+// In games with more than one snake, a snake steered by input (not by a
+// controller) that got none for `afk_ticks` ticks is away from the
+// keyboard: frontends can show it, and `afk_mode` steers it until its
+// player is back. A second player walking off doesn't hand the round to
+// the first on the next wall. `afk_ticks` 0 turns it off.
+//
+// The turns `AfkMode::Safe` takes are recorded like a controller's, so
+// replays play back the same. They don't tell who was away though, every
+// snake counts as present during playback.
+impl Game {
+    // Tick count when snake `index` last got input, 0 if it got none this
+    // round (or since a snapshot was restored)
+    pub fn last_input_tick(&self, index: usize) -> u64 {
+        match index {
+            0 => self.last_input,
+            _ => self.opponents.get(index - 1).map_or(0, |o| o.last_input),
+        }
+    }
+
+    pub fn ticks_since_input(&self, index: usize) -> u64 {
+        self.tick_count().saturating_sub(self.last_input_tick(index))
+    }
+
+    pub fn is_afk(&self, index: usize) -> bool {
+        self.afk_ticks > 0
+            && self.snake_count() > 1
+            && !self.is_replay()
+            && self.controller(index).is_none()
+            && self.snake_alive(index)
+            && self.ticks_since_input(index) >= self.afk_ticks
+    }
+
+    pub(crate) fn note_input(&mut self, index: usize) {
+        let tick = self.tick_count();
+        match index {
+            0 => self.last_input = tick,
+            _ => {
+                if let Some(opponent) = self.opponents.get_mut(index - 1) {
+                    opponent.last_input = tick;
+                }
+            }
+        }
+    }
+
+    // Everyone starts over as present, e.g. after a restore
+    pub(crate) fn reset_last_inputs(&mut self) {
+        for index in 0..self.snake_count() {
+            self.note_input(index);
+        }
+    }
+
+    // The turn `AfkMode::Safe` takes for snake `index`, if any
+    pub(crate) fn afk_move(&self, index: usize) -> Option<Direction> {
+        if self.afk_mode != AfkMode::Safe || !self.is_afk(index) {
+            return None;
+        }
+        self.dodge_move(index)
+    }
+}
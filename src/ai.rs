@@ -0,0 +1,237 @@
+// src/ai.rs
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, Game, Point};
+
+// Built-in AIs that can steer any snake, see `Game::set_controller`.
+// They're deterministic (no randomness, fixed tie-breaking), so games with
+// AI snakes replay and hash the same everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Controller {
+    // Heads for the closest food as the crow flies, only avoiding the next cell
+    Greedy,
+    // Follows the shortest path to the closest food around walls and bodies
+    Pathfinder,
+    // Ignores food and hunts the player's snake, heading for the cells next
+    // to its head. What steers a boss, see boss.rs.
+    Chaser,
+}
+
+// Order used to break ties
+pub(crate) const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+// What `search` keeps between searches, so a move on a big board doesn't
+// allocate (and zero) two board-sized vecs. A cell was reached in the
+// current search when its stamp is the current generation, starting a new
+// search is just a bump of it.
+#[derive(Default)]
+struct SearchScratch {
+    generation: u32,
+    stamps: Vec<u32>,
+    // The cell each reached cell was reached from and the move taken
+    came_from: Vec<(Point, Direction)>,
+    queue: VecDeque<Point>,
+}
+
+impl SearchScratch {
+    fn start(&mut self, cells: usize) {
+        self.queue.clear();
+        if self.stamps.len() != cells {
+            self.stamps.clear();
+            self.stamps.resize(cells, 0);
+            self.came_from.resize(cells, (Point { x: 0, y: 0 }, Direction::Up));
+            self.generation = 0;
+        }
+        self.generation = self.generation.wrapping_add(1);
+        // Stamps from 2^32 searches ago would count as reached
+        if self.generation == 0 {
+            self.stamps.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    fn reached(&self, cell: usize) -> bool {
+        self.stamps[cell] == self.generation
+    }
+
+    fn reach(&mut self, cell: usize, from: Point, direction: Direction) {
+        self.stamps[cell] = self.generation;
+        self.came_from[cell] = (from, direction);
+    }
+}
+
+// One scratch per thread, `Game` is shared between threads and stays
+// `Sync`. A search started from inside another one gets a fresh scratch.
+#[cfg(feature = "std")]
+fn with_scratch<R>(f: impl FnOnce(&mut SearchScratch) -> R) -> R {
+    use core::cell::RefCell;
+
+    std::thread_local! {
+        static SCRATCH: RefCell<SearchScratch> = RefCell::new(SearchScratch::default());
+    }
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => f(&mut scratch),
+        Err(_) => f(&mut SearchScratch::default()),
+    })
+}
+
+// Without `std` there are no thread locals, every search starts from scratch
+#[cfg(not(feature = "std"))]
+fn with_scratch<R>(f: impl FnOnce(&mut SearchScratch) -> R) -> R {
+    f(&mut SearchScratch::default())
+}
+
+pub(crate) fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Down,
+        Direction::Down => Direction::Up,
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+    }
+}
+
+impl Controller {
+    // The turn this AI would take for snake `index` on the next tick, `None`
+    // when every move is fatal (it then just keeps going)
+    pub fn decide(self, game: &Game, index: usize) -> Option<Direction> {
+        match self {
+            Controller::Greedy => game.greedy_move(index),
+            Controller::Pathfinder => game
+                .food_path(index)
+                .first()
+                .map(|&(direction, _)| direction)
+                .or_else(|| game.greedy_move(index)),
+            Controller::Chaser => game
+                .chase_path(index)
+                .first()
+                .map(|&(direction, _)| direction)
+                .or_else(|| game.chase_move(index)),
+        }
+    }
+}
+
+impl Game {
+    // The cells the pathfinder would follow to the closest reachable food,
+    // head excluded. Empty when nothing can be reached. Meant for debug
+    // overlays showing what an AI snake is up to.
+    pub fn ai_path(&self, index: usize) -> Vec<Point> {
+        self.food_path(index).into_iter().map(|(_, p)| p).collect()
+    }
+
+    pub(crate) fn blocked(&self, p: Point) -> bool {
+        self.board.is_wall(p) || self.board.is_snake(p)
+    }
+
+    // Moves the snake could make without dying right away
+    fn safe_moves(&self, index: usize) -> impl Iterator<Item = (Direction, Point)> + '_ {
+        let head = self.snake_body(index).front().copied();
+        let current = self.snake_direction(index);
+        DIRECTIONS.into_iter().filter_map(move |direction| {
+            let head = head?;
+            if current.map(opposite) == Some(direction) {
+                return None;
+            }
+            let p = self.step(head, direction).ok()?;
+            (!self.blocked(p)).then_some((direction, p))
+        })
+    }
+
+    fn greedy_move(&self, index: usize) -> Option<Direction> {
+        let distance = |p: Point| {
+            self.foods.iter().map(|f| (f.pos.x - p.x).abs() + (f.pos.y - p.y).abs()).min().unwrap_or(0)
+        };
+        self.safe_moves(index).min_by_key(|&(_, p)| distance(p)).map(|(direction, _)| direction)
+    }
+
+    // The safe move closest to the player's head as the crow flies
+    fn chase_move(&self, index: usize) -> Option<Direction> {
+        let target = self.snake.body.front().copied()?;
+        let distance = |p: Point| (target.x - p.x).abs() + (target.y - p.y).abs();
+        self.safe_moves(index).min_by_key(|&(_, p)| distance(p)).map(|(direction, _)| direction)
+    }
+
+    // The shortest way to a free cell next to the player's head, empty when
+    // there's none or the player is gone
+    fn chase_path(&self, index: usize) -> Vec<(Direction, Point)> {
+        let Some(&target) = self.snake.body.front().filter(|_| self.snake_alive(0)) else { return Vec::new() };
+        self.search(index, |p| DIRECTIONS.into_iter().any(|direction| self.step(p, direction) == Ok(target)))
+    }
+
+    // Straight on while that's safe, else the first safe way out. `None`
+    // when the snake can keep going, or has nowhere better to go.
+    pub(crate) fn dodge_move(&self, index: usize) -> Option<Direction> {
+        let current = self.snake_direction(index)?;
+        if self.safe_moves(index).any(|(direction, _)| direction == current) {
+            return None;
+        }
+        self.safe_moves(index).next().map(|(direction, _)| direction)
+    }
+
+    // Breadth-first search from the head, the first food found is the closest
+    fn food_path(&self, index: usize) -> Vec<(Direction, Point)> {
+        span!(TRACE, "food_path", snake = index);
+        self.search(index, |p| self.board.is_food(p))
+    }
+
+    // The shortest way for snake `index` to reach `target` as the board is
+    // now, empty when it can't. Bodies don't move during the search, so the
+    // snake may find a shorter one as its tail gets out of the way.
+    pub(crate) fn path_to(&self, index: usize, target: Point) -> Vec<(Direction, Point)> {
+        self.search(index, |p| p == target)
+    }
+
+    // Breadth-first search from the head to the closest cell `goal` accepts
+    fn search(&self, index: usize, goal: impl Fn(Point) -> bool) -> Vec<(Direction, Point)> {
+        with_scratch(|scratch| self.search_with(scratch, index, goal))
+    }
+
+    fn search_with(
+        &self,
+        scratch: &mut SearchScratch,
+        index: usize,
+        goal: impl Fn(Point) -> bool,
+    ) -> Vec<(Direction, Point)> {
+        let cell = |p: Point| (p.y * self.width + p.x) as usize;
+        scratch.start((self.width * self.height) as usize);
+        #[cfg(feature = "bench-hooks")]
+        crate::bench_hooks::count_path_searched();
+
+        // The first moves come from themselves, that's where a path stops
+        for (direction, p) in self.safe_moves(index) {
+            if !scratch.reached(cell(p)) {
+                scratch.reach(cell(p), p, direction);
+                scratch.queue.push_back(p);
+            }
+        }
+        while let Some(p) = scratch.queue.pop_front() {
+            #[cfg(feature = "bench-hooks")]
+            crate::bench_hooks::count_cell_searched();
+            if goal(p) {
+                let mut path = Vec::new();
+                let mut current = p;
+                loop {
+                    let (previous, direction) = scratch.came_from[cell(current)];
+                    path.push((direction, current));
+                    if previous == current {
+                        break;
+                    }
+                    current = previous;
+                }
+                path.reverse();
+                return path;
+            }
+            for direction in DIRECTIONS {
+                let Ok(next) = self.step(p, direction) else { continue };
+                if scratch.reached(cell(next)) || self.blocked(next) {
+                    continue;
+                }
+                scratch.reach(cell(next), p, direction);
+                scratch.queue.push_back(next);
+            }
+        }
+        Vec::new()
+    }
+}
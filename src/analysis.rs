@@ -0,0 +1,161 @@
+// src/analysis.rs
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::DIRECTIONS;
+use crate::{DeathCause, FoodKind, Game, GameError, GameEvent, Point, Replay};
+
+// A move into a cell with this many ways out or fewer counts as risky
+pub const RISKY_EXITS: u32 = 2;
+
+// The score after tick `tick`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScorePoint {
+    pub tick: u64,
+    pub score: u32,
+}
+
+// The tick interval from tick `tick` on, with the speed level it came from.
+// Slow-downs show up as longer intervals at the same level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeedPoint {
+    pub tick: u64,
+    pub interval_ms: u64,
+    pub level: u32,
+}
+
+// The head moved into `at` on tick `tick` with only `exits` free cells next
+// to it, 0 when the next move is fatal whatever the player does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskyMove {
+    pub tick: u64,
+    pub at: Point,
+    pub exits: u32,
+}
+
+// Food the player took the long way to. From tick `since` (when the last
+// food was eaten, or this one appeared) the pathfinder could reach it in
+// `shortest` moves along `path`, the snake got there in `taken`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissedPath {
+    pub since: u64,
+    pub eaten: u64,
+    pub food: Point,
+    pub kind: FoodKind,
+    pub taken: u64,
+    pub shortest: u64,
+    pub path: Vec<Point>, // Head excluded, ends on the food
+}
+
+// What `Replay::analyze` found playing a replay back. Each list is in tick
+// order, so a viewer can show what happened up to its position.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub ticks: u64,
+    pub score: u32,
+    pub death_cause: Option<DeathCause>,
+    // Starts at tick 0, then one point per change
+    pub score_timeline: Vec<ScorePoint>,
+    pub speed_timeline: Vec<SpeedPoint>,
+    pub risky_moves: Vec<RiskyMove>,
+    pub missed_paths: Vec<MissedPath>,
+}
+
+impl ReplayReport {
+    // Moves the player could have saved on the food they took the long way to
+    pub fn ticks_lost(&self) -> u64 {
+        self.missed_paths.iter().map(|m| m.taken - m.shortest).sum()
+    }
+}
+
+// Food on the board, since when it's been the snake's to go for and the
+// shortest way there at that time
+struct Target {
+    food: Point,
+    since: u64,
+    path: Vec<Point>,
+}
+
+// Starts tracking the food that isn't yet
+fn track_food(game: &Game, targets: &mut Vec<Target>) {
+    for food in &game.foods {
+        if !targets.iter().any(|target| target.food == food.pos) {
+            let path = game.path_to(0, food.pos).into_iter().map(|(_, p)| p).collect();
+            targets.push(Target { food: food.pos, since: game.tick_count(), path });
+        }
+    }
+}
+
+impl Replay {
+    // Plays the replay back tick by tick and reports how the round went,
+    // see `ReplayReport`. Deterministic like the playback itself, the same
+    // replay always gives the same report.
+    pub fn analyze(&self) -> Result<ReplayReport, GameError> {
+        span!(DEBUG, "analyze", ticks = self.ticks);
+        let mut game = self.game()?;
+        game.events.clear();
+        let mut report = ReplayReport::default();
+        report.score_timeline.push(ScorePoint { tick: 0, score: game.score });
+        let speed = |game: &Game| SpeedPoint {
+            tick: game.tick_count(),
+            interval_ms: game.tick_interval().as_millis() as u64,
+            level: game.speed_level(),
+        };
+        report.speed_timeline.push(speed(&game));
+        let mut targets = Vec::new();
+        track_food(&game, &mut targets);
+
+        while !game.game_over && game.tick_count() < self.ticks {
+            let tick = game.tick_count();
+            game.tick();
+            if game.tick_count() == tick {
+                break;
+            }
+            let tick = game.tick_count();
+            let mut ate = false;
+            for event in game.drain_events() {
+                let (at, kind) = match event {
+                    GameEvent::FoodEaten { at, kind, .. } | GameEvent::PowerUpCollected { at, kind, .. } => (at, kind),
+                    _ => continue,
+                };
+                ate = true;
+                let Some(i) = targets.iter().position(|target| target.food == at) else { continue };
+                let Target { since, path, .. } = targets.swap_remove(i);
+                let (taken, shortest) = (tick - since, path.len() as u64);
+                if !path.is_empty() && taken > shortest {
+                    report.missed_paths.push(MissedPath { since, eaten: tick, food: at, kind, taken, shortest, path });
+                }
+            }
+            if report.score_timeline.last().is_some_and(|p| p.score != game.score) {
+                report.score_timeline.push(ScorePoint { tick, score: game.score });
+            }
+            if report.speed_timeline.last().is_some_and(|p| p.interval_ms != speed(&game).interval_ms) {
+                report.speed_timeline.push(speed(&game));
+            }
+            if game.game_over {
+                break;
+            }
+            if let Some(&head) = game.snake.body.front() {
+                let exits = DIRECTIONS
+                    .into_iter()
+                    .filter(|&direction| game.step(head, direction).is_ok_and(|p| !game.blocked(p)))
+                    .count() as u32;
+                if exits <= RISKY_EXITS {
+                    report.risky_moves.push(RiskyMove { tick, at: head, exits });
+                }
+            }
+            // Eating starts the race to every food still there over again
+            targets.retain(|target| game.foods.iter().any(|food| food.pos == target.food));
+            if ate {
+                targets.clear();
+            }
+            track_food(&game, &mut targets);
+        }
+
+        report.ticks = game.tick_count();
+        report.score = game.score;
+        report.death_cause = game.death_cause;
+        Ok(report)
+    }
+}
@@ -0,0 +1,227 @@
+// src/ascii.rs
+
+// The board as text, one character per cell (see `CellKind::symbol`), for
+// the terminal, logs, bug reports and test failures. `Game::from_ascii`
+// reads the same text back, so a test can set up a board by drawing it:
+//
+//     .......
+//     .#...*.
+//     .#@oo..
+//     .#..o..
+//     .......
+//
+// The head's direction comes from the segment behind it (Right for a lone
+// head), and each body is followed from its head through the neighbouring
+// cells, so bodies shouldn't touch in a way that leaves two ways to go.
+// Opponents are their index (1 to 9) with '+' bodies, and portals 'O' are
+// linked in reading order: the first with the second, the third with the
+// fourth. Blank lines at the end are ignored.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::food::{BONUS_TTL, SLOW_DOWN_TTL};
+use crate::opponent::Opponent;
+use crate::{Direction, Food, FoodKind, Game, GameError, Level, Point, Snake, SnakeSpawn};
+
+impl Game {
+    pub fn render_ascii(&self) -> String {
+        self.to_string()
+    }
+
+    // A game in the middle of a round, laid out like the text and ready to
+    // `tick`. The seed is always 0 so the same text gives the same game.
+    pub fn from_ascii(text: &str) -> Result<Game, GameError> {
+        let mut walls = Vec::new();
+        let mut portal_ends = Vec::new();
+        let mut foods = Vec::new();
+        let mut head: Option<Point> = None;
+        let mut body = Vec::new();
+        let mut opponent_heads: [Option<Point>; 9] = [None; 9];
+        let mut opponent_bodies = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+
+        // An editor's final newlines aren't rows
+        let text = text.trim_end_matches(['\n', '\r']);
+        for (y, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let line_width = line.chars().count() as i32;
+            match width {
+                None => width = Some(line_width),
+                Some(w) if w != line_width => return Err(invalid(y, format!("expected {} columns, found {}", w, line_width))),
+                _ => (),
+            }
+
+            for (x, c) in line.chars().enumerate() {
+                let p = Point { x: x as i32, y: y as i32 };
+                let food = |kind, ttl| Food { pos: p, kind, ttl };
+                match c {
+                    '.' | ' ' => (),
+                    '#' => walls.push(p),
+                    'O' => portal_ends.push(p),
+                    '*' => foods.push(food(FoodKind::Apple, None)),
+                    '$' => foods.push(food(FoodKind::Bonus, Some(BONUS_TTL))),
+                    '%' => foods.push(food(FoodKind::SlowDown, Some(SLOW_DOWN_TTL))),
+                    '@' if head.is_some() => return Err(invalid(y, "more than one player head".to_string())),
+                    '@' => head = Some(p),
+                    'o' => body.push(p),
+                    '+' => opponent_bodies.push(p),
+                    '1'..='9' => {
+                        let slot = &mut opponent_heads[(c as u8 - b'1') as usize];
+                        if slot.is_some() {
+                            return Err(invalid(y, format!("more than one head for snake {}", c)));
+                        }
+                        *slot = Some(p);
+                    }
+                    other => return Err(invalid(y, format!("unknown cell character '{}'", other))),
+                }
+            }
+            height += 1;
+        }
+
+        let width = width.unwrap_or(0);
+        if width <= 0 || height <= 0 {
+            return Err(GameError::InvalidDimensions { width, height });
+        }
+        let head = head.ok_or_else(|| invalid(0, "no player head '@'".to_string()))?;
+        if portal_ends.len() % 2 != 0 {
+            return Err(invalid(portal_ends[portal_ends.len() - 1].y as usize, "a portal has only one end".to_string()));
+        }
+
+        let player = follow_body(head, &mut body);
+        if let Some(p) = body.first() {
+            return Err(invalid(p.y as usize, "body segment not attached to the player's head".to_string()));
+        }
+        let mut opponents = Vec::new();
+        for (i, slot) in opponent_heads.iter().enumerate() {
+            match (slot, opponent_heads[i..].iter().flatten().next()) {
+                (Some(head), _) => opponents.push(follow_body(*head, &mut opponent_bodies)),
+                (None, Some(later)) => return Err(invalid(later.y as usize, format!("snake {} is missing", i + 1))),
+                (None, None) => break,
+            }
+        }
+        if let Some(p) = opponent_bodies.first() {
+            return Err(invalid(p.y as usize, "body segment not attached to an opponent's head".to_string()));
+        }
+
+        // The level only places a lone head, the bodies don't have to be
+        // straight lines like a spawn's
+        let level = Level {
+            width,
+            height,
+            walls,
+            portals: portal_ends.chunks(2).map(|ends| (ends[0], ends[1])).collect(),
+            spawn: SnakeSpawn { head, direction: Direction::Right, length: 1 },
+        };
+        let mut game = Game::from_level_with_seed(&level, 0)?;
+        game.snake = player;
+        game.spawn = SnakeSpawn { head, direction: game.snake.direction, length: game.snake.body.len() };
+        for snake in opponents {
+            let spawn = SnakeSpawn { head: snake.body[0], direction: snake.direction, length: snake.body.len() };
+            let mut opponent = Opponent::new(spawn, None);
+            opponent.snake = snake;
+            game.opponents.push(opponent);
+        }
+        game.food_count = foods.iter().filter(|f| f.kind == FoodKind::Apple).count().max(1);
+        game.foods = foods;
+        game.game_started = true;
+        game.refresh_board();
+        Ok(game)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (p, kind) in self.cells() {
+            if p.x == 0 && p.y > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", kind.symbol())?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(y: usize, reason: String) -> GameError {
+    GameError::InvalidLevel { line: y + 1, reason }
+}
+
+// The snake whose head is at `head`, taking each next segment out of
+// `segments` among the cells next to the last one
+fn follow_body(head: Point, segments: &mut Vec<Point>) -> Snake {
+    let mut snake = Snake::new(head, Direction::Right);
+    while let Some(i) = segments.iter().position(|p| {
+        let last = snake.body[snake.body.len() - 1];
+        (p.x - last.x).abs() + (p.y - last.y).abs() == 1
+    }) {
+        snake.body.push_back(segments.remove(i));
+    }
+    if let Some(neck) = snake.body.get(1) {
+        snake.direction = match (head.x - neck.x, head.y - neck.y) {
+            (0, -1) => Direction::Up,
+            (0, 1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            _ => Direction::Right,
+        };
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = "\
+.........
+.#...*.O.
+.#@oo....
+.#..o.$..
+.O.......
+...1++...
+.....+.%.";
+
+    #[test]
+    fn renders_what_it_read() {
+        let game = Game::from_ascii(BOARD).unwrap();
+        assert_eq!(game.render_ascii(), BOARD);
+        assert_eq!(game.snake.body.len(), 4);
+        assert_eq!(game.snake.direction, Direction::Left);
+        assert_eq!(game.opponents.len(), 1);
+        assert_eq!(game.opponents[0].snake.body.len(), 4);
+        assert_eq!(game.level.portals, [(Point { x: 7, y: 1 }, Point { x: 1, y: 4 })]);
+    }
+
+    #[test]
+    fn renders_the_board_after_a_tick() {
+        let mut game = Game::from_ascii(".....\n.@o..\n.....").unwrap();
+        game.food_count = 0;
+        game.foods.clear();
+        game.tick();
+        assert_eq!(game.render_ascii(), ".....\n@o...\n.....");
+    }
+
+    #[test]
+    fn ignores_trailing_blank_lines() {
+        let text = format!("{}\n\n\r\n", BOARD);
+        assert_eq!(Game::from_ascii(&text).unwrap().render_ascii(), BOARD);
+    }
+
+    #[test]
+    fn rejects_bad_boards() {
+        let line = |text: &str| match Game::from_ascii(text) {
+            Err(GameError::InvalidLevel { line, .. }) => line,
+            other => panic!("{:?} for {:?}", other.map(|g| g.render_ascii()), text),
+        };
+        assert_eq!(line("..\n@o\n..."), 3);
+        assert_eq!(line("@.\n.x"), 2);
+        assert_eq!(line("@.\n.@"), 2);
+        assert_eq!(line("...\n..."), 1);
+        assert_eq!(line("@..\n..o"), 2);
+        assert_eq!(line("@.O\n..."), 1);
+        assert_eq!(line("@.2\n..."), 1);
+        assert!(matches!(Game::from_ascii(""), Err(GameError::InvalidDimensions { .. })));
+    }
+}
@@ -0,0 +1,217 @@
+// src/bench.rs
+
+// Headless benchmark: runs the core for a fixed number of ticks per
+// scenario and board size, with no window and no real-time clock, and
+// reports ticks per second and heap allocations per tick. Meant to be run
+// before and after changes to the hot paths (the snake's body, collision
+// checks, picking free cells for food) with the same arguments:
+//
+//     cargo run --release --bin snake-bench -- --ticks 1000000
+//
+// The "long" scenario is the big-board target: a 1000x1000 board with a
+// snake of 100k segments, which should tick in well under a microsecond
+// without allocating.
+//
+// The criterion benches in benches/ticks.rs cover the same ground with
+// statistics but without the allocation counts.
+
+// This code will only be compiled for native targets, not for wasm32.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use snake_game::bench_hooks::{cycle_direction, long_snake};
+use snake_game::{Controller, Direction, Game, GameConfig, GameError};
+
+// Passes everything to the system allocator, counting calls and bytes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    // A growing Vec reallocates, that counts as an allocation too
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const BOARD_SIZES: [i32; 3] = [20, 50, 100];
+
+// The long scenario's board, whatever --sizes says, and its snake
+const LONG_BOARD: i32 = 1000;
+const LONG_SNAKE: usize = 100_000;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Runs the game without a window and measures the ticks")]
+struct Args {
+    #[arg(long, default_value_t = 100_000, help = "Ticks per scenario and board size")]
+    ticks: u64,
+    #[arg(long, default_value_t = 1, help = "Random seed, the same one gives the same games")]
+    seed: u64,
+    #[arg(long, value_delimiter = ',', help = "Board sizes to run, e.g. 20,50 (square boards)")]
+    sizes: Vec<i32>,
+    #[arg(long, help = "Only run scenarios whose name contains this")]
+    only: Option<String>,
+}
+
+// How the snakes are steered
+#[derive(Debug, Clone, Copy)]
+enum Scenario {
+    // Fixed turns on a board without walls: lots of ticks without dying
+    Scripted,
+    // The player's snake steered by an AI
+    Autopilot(Controller),
+    // A greedy player against a pathfinder opponent
+    Versus,
+    // A very long snake going round a cycle through every cell, it never dies
+    Long,
+}
+
+const SCENARIOS: [Scenario; 5] = [
+    Scenario::Scripted,
+    Scenario::Autopilot(Controller::Greedy),
+    Scenario::Autopilot(Controller::Pathfinder),
+    Scenario::Versus,
+    Scenario::Long,
+];
+
+impl Scenario {
+    fn name(self) -> &'static str {
+        match self {
+            Scenario::Scripted => "scripted",
+            Scenario::Autopilot(Controller::Greedy) => "greedy",
+            Scenario::Autopilot(Controller::Pathfinder) => "pathfinder",
+            Scenario::Autopilot(Controller::Chaser) => "chaser",
+            Scenario::Versus => "versus",
+            Scenario::Long => "long",
+        }
+    }
+
+    fn game(self, size: i32, seed: u64) -> Result<Game, GameError> {
+        if let Scenario::Long = self {
+            return long_snake(size, LONG_SNAKE, seed);
+        }
+        let config = GameConfig {
+            width: size,
+            height: size,
+            seed: Some(seed),
+            wrap_walls: matches!(self, Scenario::Scripted),
+            opponents: match self {
+                Scenario::Versus => vec![Some(Controller::Pathfinder)],
+                _ => Vec::new(),
+            },
+            ..GameConfig::default()
+        };
+        let mut game = Game::from_config(&config)?;
+        game.autopilot = match self {
+            Scenario::Scripted => None,
+            Scenario::Autopilot(controller) => Some(controller),
+            Scenario::Versus => Some(Controller::Greedy),
+            Scenario::Long => None,
+        };
+        game.start_game();
+        Ok(game)
+    }
+}
+
+// A turn every few ticks, the gaps varying so the snake doesn't settle
+// into a loop
+fn scripted_turn(tick: u64) -> Option<Direction> {
+    const TURNS: [Direction; 4] = [Direction::Right, Direction::Down, Direction::Left, Direction::Up];
+    tick.is_multiple_of(7).then(|| TURNS[(tick / 7 % 4) as usize])
+}
+
+struct Report {
+    ticks: u64,
+    rounds: u64,
+    elapsed: Duration,
+    allocations: u64,
+    bytes: u64,
+}
+
+// Ticks the game as fast as it goes, starting a new round whenever one
+// ends. Events are drained every tick like a frontend would.
+fn run(scenario: Scenario, size: i32, seed: u64, ticks: u64) -> Result<Report, GameError> {
+    let mut game = scenario.game(size, seed)?;
+    let mut rounds = 1;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for tick in 0..ticks {
+        if game.game_over {
+            game.start_game();
+            rounds += 1;
+        }
+        let turn = match scenario {
+            Scenario::Scripted => scripted_turn(tick),
+            Scenario::Long => Some(cycle_direction(&game, game.snake.body[0])).filter(|&d| d != game.snake.direction),
+            _ => None,
+        };
+        if let Some(direction) = turn {
+            game.queue_turn(direction);
+        }
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    Ok(Report {
+        ticks,
+        rounds,
+        elapsed: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    })
+}
+
+fn main() -> Result<(), GameError> {
+    let args = Args::parse();
+    let sizes = if args.sizes.is_empty() { BOARD_SIZES.to_vec() } else { args.sizes };
+    if cfg!(debug_assertions) {
+        eprintln!("note: this is a debug build, use --release for numbers worth comparing");
+    }
+    println!(
+        "{:<12} {:>9} {:>10} {:>7} {:>9} {:>12} {:>11} {:>10}",
+        "scenario", "board", "ticks", "rounds", "secs", "ticks/s", "allocs/tick", "bytes/tick"
+    );
+    for scenario in SCENARIOS {
+        if args.only.as_deref().is_some_and(|only| !scenario.name().contains(only)) {
+            continue;
+        }
+        let sizes = if let Scenario::Long = scenario { &[LONG_BOARD][..] } else { &sizes };
+        for &size in sizes {
+            let report = run(scenario, size, args.seed, args.ticks)?;
+            let secs = report.elapsed.as_secs_f64();
+            let ticks = report.ticks.max(1) as f64;
+            println!(
+                "{:<12} {:>9} {:>10} {:>7} {:>9.3} {:>12.0} {:>11.2} {:>10.1}",
+                scenario.name(),
+                format!("{}x{}", size, size),
+                report.ticks,
+                report.rounds,
+                secs,
+                ticks / secs,
+                report.allocations as f64 / ticks,
+                report.bytes as f64 / ticks,
+            );
+        }
+    }
+    Ok(())
+}
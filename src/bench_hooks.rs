@@ -0,0 +1,157 @@
+// src/bench_hooks.rs
+
+// What the criterion benches (benches/) and snake-bench need from inside the
+// crate: deterministic fixtures, the same game for the same arguments on
+// every run, and counters of the work the hot paths do. Time varies from
+// one machine to the next but the counters don't, so a change that makes
+// the pathfinder search twice as many cells shows up anywhere.
+//
+// Only compiled with the `bench-hooks` feature, the counters cost an atomic
+// add per tick, food and searched cell.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{Body, Controller, Direction, FoodKind, Game, GameConfig, GameError, Point};
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+static FOOD_SPAWNED: AtomicU64 = AtomicU64::new(0);
+static PATHS_SEARCHED: AtomicU64 = AtomicU64::new(0);
+static CELLS_SEARCHED: AtomicU64 = AtomicU64::new(0);
+
+// Totals since the last `reset_counters`, over every game of the process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub ticks: u64,
+    pub food_spawned: u64,
+    // Pathfinder searches, and the cells they took off the queue
+    pub paths_searched: u64,
+    pub cells_searched: u64,
+}
+
+pub fn counters() -> Counters {
+    Counters {
+        ticks: TICKS.load(Ordering::Relaxed),
+        food_spawned: FOOD_SPAWNED.load(Ordering::Relaxed),
+        paths_searched: PATHS_SEARCHED.load(Ordering::Relaxed),
+        cells_searched: CELLS_SEARCHED.load(Ordering::Relaxed),
+    }
+}
+
+pub fn reset_counters() {
+    for counter in [&TICKS, &FOOD_SPAWNED, &PATHS_SEARCHED, &CELLS_SEARCHED] {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn count_tick() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_food_spawned() {
+    FOOD_SPAWNED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_path_searched() {
+    PATHS_SEARCHED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_cell_searched() {
+    CELLS_SEARCHED.fetch_add(1, Ordering::Relaxed);
+}
+
+// A started game on a square board, nothing else changed
+pub fn started(size: i32, seed: u64) -> Result<Game, GameError> {
+    let config = GameConfig { width: size, height: size, seed: Some(seed), ..GameConfig::default() };
+    let mut game = Game::from_config(&config)?;
+    game.start_game();
+    Ok(game)
+}
+
+// A game `controller` has played for `ticks` ticks, starting over whenever
+// it died, for a board in the state an actual round leaves it in
+pub fn played(size: i32, controller: Controller, ticks: u64, seed: u64) -> Result<Game, GameError> {
+    let mut game = started(size, seed)?;
+    game.autopilot = Some(controller);
+    for _ in 0..ticks {
+        if game.game_over {
+            game.start_game();
+        }
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    if game.game_over {
+        game.start_game();
+    }
+    Ok(game)
+}
+
+// The direction to take from `p` on a cycle through every cell of a board
+// with an even height: right and left along the rows, leaving column 0 to
+// go back up to the top
+pub fn cycle_direction(game: &Game, p: Point) -> Direction {
+    match (p.x, p.y % 2) {
+        (0, _) if p.y > 0 => Direction::Up,
+        (0, _) => Direction::Right,
+        (x, 0) if x < game.width - 1 => Direction::Right,
+        (_, 0) => Direction::Down,
+        (1, _) if p.y < game.height - 1 => Direction::Down,
+        _ => Direction::Left,
+    }
+}
+
+pub fn next_on_cycle(game: &Game, p: Point) -> Point {
+    match cycle_direction(game, p) {
+        Direction::Up => Point { x: p.x, y: p.y - 1 },
+        Direction::Down => Point { x: p.x, y: p.y + 1 },
+        Direction::Left => Point { x: p.x - 1, y: p.y },
+        Direction::Right => Point { x: p.x + 1, y: p.y },
+    }
+}
+
+// Queues the turn that keeps the snake on the cycle, call before each tick
+pub fn steer_on_cycle(game: &mut Game) {
+    let direction = cycle_direction(game, game.snake.body[0]);
+    if direction != game.snake.direction {
+        game.queue_turn(direction);
+    }
+}
+
+// A started game whose snake lies along the cycle with `length` segments,
+// so it can go round forever with `steer_on_cycle`. `size` must be even.
+pub fn long_snake(size: i32, length: usize, seed: u64) -> Result<Game, GameError> {
+    let mut game = started(size, seed)?;
+    let cells = size as usize * size as usize;
+    if size % 2 != 0 {
+        return Err(GameError::InvalidDimensions { width: size, height: size });
+    }
+    if length == 0 || length >= cells {
+        return Err(GameError::InvalidSnakeLength(length));
+    }
+    // The cells leading up to the head along the cycle, head first
+    let mut body = Body::from([Point { x: 1, y: 0 }]);
+    while body.len() < length {
+        body.push_front(next_on_cycle(&game, body[0]));
+    }
+    game.snake.direction = cycle_direction(&game, body.get(1).copied().unwrap_or(body[0]));
+    game.snake.body = body;
+    // Food can't share a cell with the snake
+    let body = &game.snake.body;
+    game.foods.retain(|food| !body.contains(&food.pos));
+    game.refresh_board();
+    Ok(game)
+}
+
+// A board with only `free` cells left, everything else under the snake and
+// no food, for timing food placement when there's hardly any room
+pub fn near_full(size: i32, free: usize, seed: u64) -> Result<Game, GameError> {
+    let cells = size as usize * size as usize;
+    let mut game = long_snake(size, cells.saturating_sub(free), seed)?;
+    game.foods.clear();
+    game.refresh_board();
+    Ok(game)
+}
+
+// Puts one food on a random free cell, like eating or a round starting does
+pub fn spawn_food(game: &mut Game, kind: FoodKind) -> Result<(), GameError> {
+    game.spawn_food(kind, None)
+}
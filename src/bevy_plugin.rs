@@ -0,0 +1,108 @@
+// src/bevy_plugin.rs
+
+// The game as a Bevy plugin, only compiled with the `bevy` feature. It adds
+// the game as the `SnakeGame` resource and ticks it on Bevy's clock, turns
+// key presses into actions through the `SnakeKeyMap` resource, and forwards
+// every `GameEvent` as a `SnakeEvent`. Drawing is left to the app, read
+// `SnakeGame` in a system that runs after `SnakeSystems::Tick`:
+//
+//     App::new()
+//         .add_plugins(DefaultPlugins)
+//         .add_plugins(SnakePlugin::new(&GameConfig::default())?)
+//         .add_systems(Update, draw_snake.after(SnakeSystems::Tick))
+//
+// Other input (gamepads, touch, UI buttons) can send `SnakeAction` events.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use bevy::input::keyboard::KeyCode;
+use bevy::input::ButtonInput;
+use bevy::prelude::*;
+
+use crate::{Game, GameConfig, GameError, GameEvent, InputAction, KeyMap};
+
+#[derive(Resource, Debug, Clone)]
+pub struct SnakeGame(pub Game);
+
+// Keys for each action, the same names as everywhere else (see `KeyMap`)
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SnakeKeyMap(pub KeyMap);
+
+// Every event the game emitted, in order, during the tick system
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeEvent(pub GameEvent);
+
+// An action from any input the app handles itself
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct SnakeAction(pub InputAction);
+
+// Keys and `SnakeAction`s are applied before the game ticks, in `Update`
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnakeSystems {
+    Input,
+    Tick,
+}
+
+pub struct SnakePlugin {
+    game: Game,
+}
+
+impl SnakePlugin {
+    pub fn new(config: &GameConfig) -> Result<SnakePlugin, GameError> {
+        Ok(SnakePlugin { game: Game::from_config(config)? })
+    }
+
+    // For a game set up some other way, e.g. from a level
+    pub fn from_game(game: Game) -> SnakePlugin {
+        SnakePlugin { game }
+    }
+}
+
+impl Plugin for SnakePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SnakeGame(self.game.clone()))
+            .init_resource::<SnakeKeyMap>()
+            .add_event::<SnakeEvent>()
+            .add_event::<SnakeAction>()
+            .configure_sets(Update, (SnakeSystems::Input, SnakeSystems::Tick).chain())
+            .add_systems(
+                Update,
+                (
+                    keyboard_input.run_if(resource_exists::<ButtonInput<KeyCode>>),
+                    apply_actions,
+                )
+                    .chain()
+                    .in_set(SnakeSystems::Input),
+            )
+            .add_systems(Update, tick.in_set(SnakeSystems::Tick));
+    }
+}
+
+// The key map's name for a key: ArrowUp -> "arrowup", KeyW -> "w",
+// Digit1 -> "1"
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key).to_lowercase();
+    match name.strip_prefix("key").or_else(|| name.strip_prefix("digit")) {
+        Some(rest) if !rest.is_empty() => rest.to_string(),
+        _ => name,
+    }
+}
+
+fn keyboard_input(keys: Res<ButtonInput<KeyCode>>, key_map: Res<SnakeKeyMap>, mut actions: EventWriter<SnakeAction>) {
+    for &key in keys.get_just_pressed() {
+        if let Some(action) = key_map.0.action_for(&key_name(key)) {
+            actions.write(SnakeAction(action));
+        }
+    }
+}
+
+fn apply_actions(mut game: ResMut<SnakeGame>, mut actions: EventReader<SnakeAction>) {
+    for action in actions.read() {
+        game.0.handle_action(action.0);
+    }
+}
+
+fn tick(time: Res<Time>, mut game: ResMut<SnakeGame>, mut events: EventWriter<SnakeEvent>) {
+    game.0.advance(time.delta());
+    events.write_batch(game.0.drain_events().map(SnakeEvent));
+}
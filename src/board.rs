@@ -0,0 +1,381 @@
+// src/board.rs
+
+// Which cells are taken, kept up to date as the game changes so the hot
+// paths never search the bodies, walls or food: a collision check is a bit
+// test and picking a free cell for food walks a tree of counts. A tick
+// costs the same on a 1000x1000 board with a snake of 100k segments as on
+// a small one.
+//
+// The `Game` fields stay the source of truth. The game updates the index
+// as it moves the snakes and places food, and rebuilds it when a round
+// starts, a snapshot is restored or custom rules had their say. Code that
+// changes the snakes, food or walls by hand calls `Game::refresh_board`.
+//
+// Every update also marks the cell dirty, for renderers redrawing only what
+// changed (see `Game::take_dirty_cells`). A rebuild marks the whole board.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Game, Point};
+
+// One bit per cell, row-major
+#[derive(Clone, Default)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn reset(&mut self, cells: usize) {
+        self.0.clear();
+        self.0.resize(cells.div_ceil(64), 0);
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize, on: bool) {
+        if on {
+            self.0[i / 64] |= 1 << (i % 64);
+        } else {
+            self.0[i / 64] &= !(1 << (i % 64));
+        }
+    }
+}
+
+// The free cells: a bit per cell plus a Fenwick tree counting the free
+// cells of each 64-cell word, so the n-th free cell is found in
+// O(log cells) instead of by listing them all
+#[derive(Clone, Default)]
+struct FreeCells {
+    bits: Bits,
+    // 1-based, `counts[i]` sums the words of the range ending at word i
+    counts: Vec<u32>,
+    len: usize,
+}
+
+impl FreeCells {
+    fn rebuild(&mut self) {
+        let words = self.bits.0.len();
+        self.counts.clear();
+        self.counts.resize(words + 1, 0);
+        for w in 1..=words {
+            self.counts[w] += self.bits.0[w - 1].count_ones();
+            let parent = w + (w & w.wrapping_neg());
+            if parent <= words {
+                self.counts[parent] += self.counts[w];
+            }
+        }
+        self.len = self.bits.0.iter().map(|w| w.count_ones() as usize).sum();
+    }
+
+    fn set(&mut self, i: usize, free: bool) {
+        if self.bits.get(i) == free {
+            return;
+        }
+        self.bits.set(i, free);
+        let mut w = i / 64 + 1;
+        while w < self.counts.len() {
+            if free {
+                self.counts[w] += 1;
+            } else {
+                self.counts[w] -= 1;
+            }
+            w += w & w.wrapping_neg();
+        }
+        if free {
+            self.len += 1;
+        } else {
+            self.len -= 1;
+        }
+    }
+
+    // Cell index of the free cell `n` (0-based, row-major order)
+    fn nth(&self, mut n: usize) -> Option<usize> {
+        if n >= self.len {
+            return None;
+        }
+        // Down the tree to the word holding it
+        let words = self.counts.len() - 1;
+        let mut w = 0;
+        let mut step = if words == 0 { 0 } else { 1 << words.ilog2() };
+        while step > 0 {
+            if w + step <= words && (self.counts[w + step] as usize) <= n {
+                w += step;
+                n -= self.counts[w] as usize;
+            }
+            step /= 2;
+        }
+        // Then its bit within the word
+        let mut word = self.bits.0[w];
+        for _ in 0..n {
+            word &= word - 1;
+        }
+        Some(w * 64 + word.trailing_zeros() as usize)
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Board {
+    width: i32,
+    height: i32,
+    snakes: Bits,
+    walls: Bits,
+    portals: Bits,
+    food: Bits,
+    free: FreeCells,
+    // Cells changed since `take_dirty`, each listed once
+    dirty: Bits,
+    dirty_cells: Vec<usize>,
+    // Everything changed, the list is left empty
+    all_dirty: bool,
+}
+
+// A million bits says nothing, the counts do
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Board")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("free", &self.free.len)
+            .finish()
+    }
+}
+
+impl Board {
+    // The index for everything on `game` right now, reusing this one's memory
+    pub(crate) fn rebuild(&mut self, game: &Game) {
+        self.width = game.width.max(0);
+        self.height = game.height.max(0);
+        let cells = self.width as usize * self.height as usize;
+        for bits in [&mut self.snakes, &mut self.walls, &mut self.portals, &mut self.food, &mut self.dirty] {
+            bits.reset(cells);
+        }
+        for index in 0..game.snake_count() {
+            for &p in game.snake_body(index) {
+                if let Some(i) = self.index(p) {
+                    self.snakes.set(i, true);
+                }
+            }
+        }
+        for &p in &game.level.walls {
+            if let Some(i) = self.index(p) {
+                self.walls.set(i, true);
+            }
+        }
+        for &(a, b) in &game.level.portals {
+            for p in [a, b] {
+                if let Some(i) = self.index(p) {
+                    self.portals.set(i, true);
+                }
+            }
+        }
+        for food in &game.foods {
+            if let Some(i) = self.index(food.pos) {
+                self.food.set(i, true);
+            }
+        }
+        // Whole words at a time, then the bits past the last cell cleared
+        self.free.bits.0.clear();
+        self.free.bits.0.extend(
+            (0..self.snakes.0.len()).map(|w| !(self.snakes.0[w] | self.walls.0[w] | self.portals.0[w] | self.food.0[w])),
+        );
+        if let Some(last) = self.free.bits.0.last_mut() {
+            if !cells.is_multiple_of(64) {
+                *last &= (1 << (cells % 64)) - 1;
+            }
+        }
+        self.free.rebuild();
+        self.dirty_cells.clear();
+        self.all_dirty = true;
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        let inside = p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height;
+        inside.then(|| p.y as usize * self.width as usize + p.x as usize)
+    }
+
+    fn point(&self, i: usize) -> Point {
+        let width = self.width as usize;
+        Point { x: (i % width) as i32, y: (i / width) as i32 }
+    }
+
+    fn update(&mut self, i: usize) {
+        let taken = self.snakes.get(i) || self.walls.get(i) || self.portals.get(i) || self.food.get(i);
+        self.free.set(i, !taken);
+        self.mark(i);
+    }
+
+    fn mark(&mut self, i: usize) {
+        if self.all_dirty || self.dirty.get(i) {
+            return;
+        }
+        self.dirty.set(i, true);
+        self.dirty_cells.push(i);
+        // Past a quarter of the board a full redraw is as cheap, and a game
+        // nobody draws doesn't pile up cells forever
+        let cells = self.width as usize * self.height as usize;
+        if self.dirty_cells.len() > cells / 4 {
+            self.dirty.reset(cells);
+            self.dirty_cells.clear();
+            self.all_dirty = true;
+        }
+    }
+
+    // For changes the index doesn't see, like a head becoming a body segment
+    pub(crate) fn mark_dirty(&mut self, p: Point) {
+        if let Some(i) = self.index(p) {
+            self.mark(i);
+        }
+    }
+
+    // The cells changed since the last call, `None` when it's all of them
+    pub(crate) fn take_dirty(&mut self) -> Option<Vec<Point>> {
+        if self.all_dirty {
+            self.all_dirty = false;
+            return None;
+        }
+        for &i in &self.dirty_cells {
+            self.dirty.set(i, false);
+        }
+        let cells = self.dirty_cells.iter().map(|&i| self.point(i)).collect();
+        self.dirty_cells.clear();
+        Some(cells)
+    }
+
+    pub(crate) fn set_snake(&mut self, p: Point, on: bool) {
+        if let Some(i) = self.index(p) {
+            self.snakes.set(i, on);
+            self.update(i);
+        }
+    }
+
+    pub(crate) fn set_wall(&mut self, p: Point, on: bool) {
+        if let Some(i) = self.index(p) {
+            self.walls.set(i, on);
+            self.update(i);
+        }
+    }
+
+    pub(crate) fn set_food(&mut self, p: Point, on: bool) {
+        if let Some(i) = self.index(p) {
+            self.food.set(i, on);
+            self.update(i);
+        }
+    }
+
+    // Off-board cells count as nothing, callers check the bounds themselves
+    pub(crate) fn is_snake(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.snakes.get(i))
+    }
+
+    pub(crate) fn is_wall(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.walls.get(i))
+    }
+
+    pub(crate) fn is_food(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.food.get(i))
+    }
+
+    // On the board with no snake, wall, portal or food on it
+    pub(crate) fn is_free(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.free.bits.get(i))
+    }
+
+    // One bit per cell in row-major order, set under any snake
+    pub(crate) fn snake_bits(&self) -> &[u64] {
+        &self.snakes.0
+    }
+
+    pub(crate) fn free_count(&self) -> usize {
+        self.free.len
+    }
+
+    // Free cell `n` counting in row-major order (left to right, top to
+    // bottom), the order food spawning has always drawn from
+    pub(crate) fn nth_free(&self, n: usize) -> Option<Point> {
+        self.free.nth(n).map(|i| self.point(i))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::{Controller, GameConfig, Level};
+
+    // Free cells listed the slow way, straight from the game's fields
+    fn brute_force(game: &Game) -> Vec<Point> {
+        let taken = |p: Point| {
+            (0..game.snake_count()).any(|index| game.snake_body(index).contains(&p))
+                || game.level.walls.contains(&p)
+                || game.level.portals.iter().any(|&(a, b)| a == p || b == p)
+                || game.foods.iter().any(|f| f.pos == p)
+        };
+        (0..game.height).flat_map(|y| (0..game.width).map(move |x| Point { x, y })).filter(|&p| !taken(p)).collect()
+    }
+
+    fn check(game: &Game) {
+        let free = brute_force(game);
+        assert_eq!(game.board.free_count(), free.len());
+        for (n, &p) in free.iter().enumerate() {
+            assert_eq!(game.board.nth_free(n), Some(p));
+        }
+        assert_eq!(game.board.nth_free(free.len()), None);
+        for y in 0..game.height {
+            for x in 0..game.width {
+                let p = Point { x, y };
+                assert_eq!(game.board.is_free(p), free.contains(&p), "{:?}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_match_random_updates() {
+        // Sizes around the 64-cell words, including a lone partial one
+        for cells in [1, 63, 64, 65, 200, 1000] {
+            let mut rng = ChaCha8Rng::seed_from_u64(cells as u64);
+            let mut free = FreeCells::default();
+            free.bits.reset(cells);
+            free.rebuild();
+            let mut expected = vec![false; cells];
+            for _ in 0..2000 {
+                let i = rng.gen_range(0..cells);
+                let on = rng.gen_bool(0.6);
+                free.set(i, on);
+                expected[i] = on;
+                let cells_free: Vec<usize> = (0..cells).filter(|&i| expected[i]).collect();
+                assert_eq!(free.len, cells_free.len());
+                let n = rng.gen_range(0..=cells_free.len());
+                assert_eq!(free.nth(n), cells_free.get(n).copied(), "cells {} n {}", cells, n);
+            }
+            // A rebuild from the bits gives the same tree
+            let counts = free.counts.clone();
+            free.rebuild();
+            assert_eq!(free.counts, counts);
+        }
+    }
+
+    #[test]
+    fn follows_the_game() {
+        let mut level = Level::parse("#....a....\n..........\n...##.....\n.....@....\n....a.....\n......#...\n...#......").unwrap();
+        level.spawn.length = 3;
+        let config = GameConfig { seed: Some(7), food_count: 3, opponents: vec![Some(Controller::Greedy)], ..GameConfig::default() };
+        let mut game = Game::from_level_with_config(&level, &config).unwrap();
+        game.start_game();
+        check(&game);
+        for _ in 0..200 {
+            if game.game_over {
+                game.start_game();
+            }
+            if let Some(direction) = Controller::Pathfinder.decide(&game, 0) {
+                game.queue_turn(direction);
+            }
+            game.tick();
+            check(&game);
+        }
+    }
+}
@@ -0,0 +1,455 @@
+// src/body.rs
+
+// A snake's body, head first: a ring buffer of up to `INLINE` cells stored
+// in place, moving to the heap only when the snake outgrows it. Most rounds
+// never get there, so ticking, cloning a game for a search and the
+// simulator's millions of short games don't allocate for the bodies, and
+// the cells sit next to the rest of the snake.
+//
+// Offers the `VecDeque` methods the game uses and serializes the same way,
+// as a plain list of points, so saves and replays didn't change.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::{fmt, ops, slice};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Point;
+
+// Cells kept in place, 256 bytes
+const INLINE: usize = 32;
+
+const ORIGIN: Point = Point { x: 0, y: 0 };
+
+pub struct Body(Storage);
+
+// Being bigger than a `VecDeque` is the point
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+enum Storage {
+    // `len` cells from `start` on, wrapping round the end of `cells`
+    Inline { cells: [Point; INLINE], start: usize, len: usize },
+    // Stays there once spilled, until the body is replaced
+    Heap(VecDeque<Point>),
+}
+
+impl Body {
+    pub const fn new() -> Body {
+        Body(Storage::Inline { cells: [ORIGIN; INLINE], start: 0, len: 0 })
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Storage::Inline { len, .. } => *len,
+            Storage::Heap(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Whether the cells moved to the heap
+    pub fn spilled(&self) -> bool {
+        matches!(self.0, Storage::Heap(_))
+    }
+
+    pub fn get(&self, i: usize) -> Option<&Point> {
+        match &self.0 {
+            Storage::Inline { cells, start, len } => (i < *len).then(|| &cells[(start + i) % INLINE]),
+            Storage::Heap(cells) => cells.get(i),
+        }
+    }
+
+    pub fn front(&self) -> Option<&Point> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&Point> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        let (a, b) = self.as_slices();
+        a.contains(p) || b.contains(p)
+    }
+
+    // The cells in order as two slices, like `VecDeque::as_slices`
+    pub fn as_slices(&self) -> (&[Point], &[Point]) {
+        match &self.0 {
+            Storage::Inline { cells, start, len } if start + len <= INLINE => (&cells[*start..start + len], &[]),
+            Storage::Inline { cells, start, len } => (&cells[*start..], &cells[..start + len - INLINE]),
+            Storage::Heap(cells) => cells.as_slices(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        let (front, back) = self.as_slices();
+        Iter { front: front.iter(), back: back.iter() }
+    }
+
+    // Onto the heap, with room to grow
+    fn spill(&mut self) -> &mut VecDeque<Point> {
+        if let Storage::Inline { .. } = self.0 {
+            let mut cells = VecDeque::with_capacity(INLINE * 2);
+            cells.extend(self.iter().copied());
+            self.0 = Storage::Heap(cells);
+        }
+        match &mut self.0 {
+            Storage::Heap(cells) => cells,
+            Storage::Inline { .. } => unreachable!(),
+        }
+    }
+
+    pub fn push_front(&mut self, p: Point) {
+        match &mut self.0 {
+            Storage::Inline { cells, start, len } if *len < INLINE => {
+                *start = (*start + INLINE - 1) % INLINE;
+                cells[*start] = p;
+                *len += 1;
+            }
+            _ => self.spill().push_front(p),
+        }
+    }
+
+    pub fn push_back(&mut self, p: Point) {
+        match &mut self.0 {
+            Storage::Inline { cells, start, len } if *len < INLINE => {
+                cells[(*start + *len) % INLINE] = p;
+                *len += 1;
+            }
+            _ => self.spill().push_back(p),
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<Point> {
+        match &mut self.0 {
+            Storage::Inline { cells, start, len } => {
+                if *len == 0 {
+                    return None;
+                }
+                let p = cells[*start];
+                *start = (*start + 1) % INLINE;
+                *len -= 1;
+                Some(p)
+            }
+            Storage::Heap(cells) => cells.pop_front(),
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<Point> {
+        match &mut self.0 {
+            Storage::Inline { cells, start, len } => {
+                if *len == 0 {
+                    return None;
+                }
+                *len -= 1;
+                Some(cells[(*start + *len) % INLINE])
+            }
+            Storage::Heap(cells) => cells.pop_back(),
+        }
+    }
+
+    pub fn truncate(&mut self, n: usize) {
+        match &mut self.0 {
+            Storage::Inline { len, .. } => *len = (*len).min(n),
+            Storage::Heap(cells) => cells.truncate(n),
+        }
+    }
+
+    // Keeps the heap storage, if any, for refilling a body of the same size
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+}
+
+// Refilling a spilled body reuses its memory, as restoring snapshots of a
+// long snake does every time
+impl Clone for Body {
+    fn clone(&self) -> Body {
+        Body(self.0.clone())
+    }
+
+    fn clone_from(&mut self, source: &Body) {
+        match (&mut self.0, &source.0) {
+            (Storage::Heap(cells), Storage::Heap(from)) => cells.clone_from(from),
+            _ => *self = source.clone(),
+        }
+    }
+}
+
+impl Default for Body {
+    fn default() -> Body {
+        Body::new()
+    }
+}
+
+impl ops::Index<usize> for Body {
+    type Output = Point;
+
+    fn index(&self, i: usize) -> &Point {
+        self.get(i).expect("body index out of bounds")
+    }
+}
+
+impl PartialEq for Body {
+    fn eq(&self, other: &Body) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Body {}
+
+// Same as `VecDeque`'s, the state hash didn't change either
+impl Hash for Body {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|p| p.hash(state));
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<Point> for Body {
+    fn extend<I: IntoIterator<Item = Point>>(&mut self, cells: I) {
+        cells.into_iter().for_each(|p| self.push_back(p));
+    }
+}
+
+impl FromIterator<Point> for Body {
+    fn from_iter<I: IntoIterator<Item = Point>>(cells: I) -> Body {
+        let mut body = Body::new();
+        body.extend(cells);
+        body
+    }
+}
+
+impl From<Vec<Point>> for Body {
+    fn from(cells: Vec<Point>) -> Body {
+        if cells.len() > INLINE {
+            return Body(Storage::Heap(cells.into()));
+        }
+        cells.into_iter().collect()
+    }
+}
+
+impl<const N: usize> From<[Point; N]> for Body {
+    fn from(cells: [Point; N]) -> Body {
+        cells.into_iter().collect()
+    }
+}
+
+impl Serialize for Body {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Body, D::Error> {
+        Vec::<Point>::deserialize(deserializer).map(Body::from)
+    }
+}
+
+#[derive(Clone)]
+pub struct Iter<'a> {
+    front: slice::Iter<'a, Point>,
+    back: slice::Iter<'a, Point>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Point;
+
+    fn next(&mut self) -> Option<&'a Point> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+
+    // Straight to the cell, `skip` goes through here
+    fn nth(&mut self, n: usize) -> Option<&'a Point> {
+        let in_front = self.front.len();
+        if n < in_front {
+            return self.front.nth(n);
+        }
+        self.front = [].iter();
+        self.back.nth(n - in_front)
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a Body {
+    type Item = &'a Point;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+pub struct IntoIter(Body);
+
+impl Iterator for IntoIter {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Point> {
+        self.0.pop_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for Body {
+    type Item = Point;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn p(i: usize) -> Point {
+        Point { x: i as i32, y: -(i as i32) }
+    }
+
+    // Everything a reader of the body can see, against the reference
+    fn check(body: &Body, reference: &VecDeque<Point>) {
+        assert_eq!(body.len(), reference.len());
+        assert!(body.iter().eq(reference.iter()));
+        assert!(body.iter().rev().eq(reference.iter().rev()));
+        assert_eq!((body.front(), body.back()), (reference.front(), reference.back()));
+        for (i, cell) in reference.iter().enumerate() {
+            assert_eq!(body.get(i), Some(cell));
+            assert_eq!(body.iter().nth(i), Some(cell));
+        }
+        assert_eq!(body.get(reference.len()), None);
+        let (a, b) = body.as_slices();
+        assert!(a.iter().chain(b).eq(reference.iter()));
+    }
+
+    #[test]
+    fn grows_past_inline() {
+        let mut body = Body::new();
+        let mut reference = VecDeque::new();
+        for i in 0..INLINE * 3 {
+            body.push_front(p(i));
+            reference.push_front(p(i));
+            assert_eq!(body.spilled(), i >= INLINE, "{} cells", i + 1);
+            check(&body, &reference);
+        }
+        // Shrinking again stays on the heap
+        body.truncate(4);
+        reference.truncate(4);
+        assert!(body.spilled());
+        check(&body, &reference);
+    }
+
+    #[test]
+    fn head_wraps_round_the_buffer() {
+        // A moving snake: new head in front, tail off the back, so `start`
+        // goes round the inline cells several times without spilling
+        let mut body: Body = (0..5).map(p).collect();
+        let mut reference: VecDeque<Point> = (0..5).map(p).collect();
+        for i in 5..INLINE * 4 {
+            body.push_front(p(i));
+            reference.push_front(p(i));
+            assert_eq!(body.pop_back(), reference.pop_back());
+            check(&body, &reference);
+        }
+        assert!(!body.spilled());
+
+        // And the other way round
+        for i in 0..INLINE * 4 {
+            body.push_back(p(i));
+            reference.push_back(p(i));
+            assert_eq!(body.pop_front(), reference.pop_front());
+            check(&body, &reference);
+        }
+        assert!(!body.spilled());
+    }
+
+    #[test]
+    fn clear_then_reuse() {
+        for length in [INLINE / 2, INLINE, INLINE * 2] {
+            let mut body: Body = (0..length).map(p).collect();
+            body.clear();
+            check(&body, &VecDeque::new());
+            assert_eq!((body.pop_front(), body.pop_back()), (None, None));
+
+            let reference: VecDeque<Point> = (100..103).map(p).collect();
+            body.extend(reference.iter().copied());
+            check(&body, &reference);
+            assert_eq!(body.spilled(), length > INLINE, "a cleared body keeps its storage");
+        }
+    }
+
+    #[test]
+    fn matches_vec_deque_on_random_updates() {
+        let mut spilled = 0;
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut body = Body::new();
+            let mut reference = VecDeque::new();
+            for step in 0..2000 {
+                // Leaning towards growth, so it spills some way into a run
+                match rng.gen_range(0..10) {
+                    0..=2 => {
+                        body.push_front(p(step));
+                        reference.push_front(p(step));
+                    }
+                    3..=5 => {
+                        body.push_back(p(step));
+                        reference.push_back(p(step));
+                    }
+                    6 | 7 => assert_eq!(body.pop_back(), reference.pop_back()),
+                    8 => assert_eq!(body.pop_front(), reference.pop_front()),
+                    _ => {
+                        let n = rng.gen_range(0..=reference.len());
+                        body.truncate(n);
+                        reference.truncate(n);
+                    }
+                }
+                check(&body, &reference);
+            }
+            spilled += usize::from(body.spilled());
+            let cloned = body.clone();
+            assert_eq!(cloned, body);
+            assert!(cloned.into_iter().eq(reference.iter().copied()));
+        }
+        assert!(spilled > 0, "no run got past the inline cells");
+    }
+}
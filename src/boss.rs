@@ -0,0 +1,121 @@
+// src/boss.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Controller, Direction, Game, GameEvent, Point, SnakeSpawn};
+
+// Boss encounters, off unless `Game::boss` (or the config's `boss`) is set:
+// each time the score passes a multiple of `every`, a long opponent steered
+// by `Controller::Chaser` comes in as far from the player as it fits and
+// hunts them for `ticks` ticks. Running into it ends the round like any
+// other snake, getting it to crash ends the encounter early. Either way it
+// leaves the board afterwards and play goes on as before.
+//
+// The boss is a regular opponent while it's there, the last one, so the
+// frontends draw it and the replays record its turns without knowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BossConfig {
+    // Points between encounters
+    pub every: u32,
+    // How long each one lasts
+    pub ticks: u64,
+    // The boss's length, a few times the player's to make it hard to get around
+    pub length: usize,
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        BossConfig { every: 10, ticks: 60, length: 8 }
+    }
+}
+
+// The encounter going on, see `Game::boss_fight`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BossFight {
+    // The boss's snake index
+    pub snake: usize,
+    // Tick it leaves at
+    pub until: u64,
+}
+
+impl Game {
+    pub fn boss_fight(&self) -> Option<BossFight> {
+        self.boss_fight
+    }
+
+    // Ticks left before the boss leaves, 0 without one
+    pub fn boss_ticks_left(&self) -> u64 {
+        self.boss_fight.map_or(0, |fight| fight.until.saturating_sub(self.tick_count()))
+    }
+
+    // Runs at the end of every tick: sends the boss off once its time is up
+    // or it crashed, and brings the next one when the score calls for it
+    pub(crate) fn update_boss(&mut self) {
+        if let Some(fight) = self.boss_fight {
+            let trapped = !self.snake_alive(fight.snake);
+            if trapped || self.tick_count() >= fight.until {
+                self.remove_boss();
+                self.events.push(GameEvent::BossLeft { snake: fight.snake, trapped });
+            }
+            return;
+        }
+        let Some(config) = self.boss.filter(|config| config.every > 0) else { return };
+        if self.score < (self.bosses + 1) * config.every {
+            return;
+        }
+        // A board too full for it skips this one
+        self.bosses = self.score / config.every;
+        let Some(spawn) = self.boss_spawn(config.length) else { return };
+        let Ok(snake) = self.add_opponent(spawn, Some(Controller::Chaser)) else { return };
+        self.boss_fight = Some(BossFight { snake, until: self.tick_count() + config.ticks });
+        self.events.push(GameEvent::BossAppeared { snake, ticks: config.ticks });
+    }
+
+    // Takes the boss off the board, e.g. before a new round
+    pub(crate) fn remove_boss(&mut self) {
+        let Some(fight) = self.boss_fight.take() else { return };
+        if fight.snake == 0 || fight.snake > self.opponents.len() {
+            return;
+        }
+        let boss = self.opponents.remove(fight.snake - 1);
+        for &segment in &boss.snake.body {
+            self.board.set_snake(segment, false);
+        }
+        self.last_moves.truncate(self.snake_count());
+    }
+
+    // Whether snake `index` is the boss, e.g. to keep it off the scores
+    pub fn is_boss(&self, index: usize) -> bool {
+        self.boss_fight.is_some_and(|fight| fight.snake == index)
+    }
+
+    // The free straight spawn of `length` segments whose head is farthest
+    // from the player's, heading back towards them. First found wins ties,
+    // so the same board always gets the same boss.
+    fn boss_spawn(&self, length: usize) -> Option<SnakeSpawn> {
+        let player = self.snake.body.front().copied()?;
+        // A boss longer than the board can't spawn anywhere, don't lay one
+        // out per cell to find that out
+        let probe = SnakeSpawn { head: player, direction: Direction::Right, length };
+        probe.check_length(self.width, self.height).ok()?;
+        let distance = |p: Point| (player.x - p.x).abs() + (player.y - p.y).abs();
+        let fits = |spawn: &SnakeSpawn| {
+            spawn.body().iter().all(|&p| {
+                p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height && self.is_free(p) && distance(p) > 2
+            })
+        };
+        let mut best: Option<SnakeSpawn> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let head = Point { x, y };
+                let direction = if x < player.x { Direction::Right } else { Direction::Left };
+                let spawn = SnakeSpawn { head, direction, length };
+                if best.is_none_or(|best| distance(head) > distance(best.head)) && fits(&spawn) {
+                    best = Some(spawn);
+                }
+            }
+        }
+        best
+    }
+}
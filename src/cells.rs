@@ -0,0 +1,108 @@
+// src/cells.rs
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{FoodKind, Game, Point};
+
+// What occupies a board cell, from a renderer's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Empty,
+    SnakeHead,
+    SnakeBody,
+    // Head or body of another snake, with the snake's index
+    OpponentHead(usize),
+    OpponentBody(usize),
+    Food(FoodKind),
+    Obstacle,
+    Portal,
+}
+
+impl CellKind {
+    // One character per cell for `Game::render_ascii`, walls and the
+    // player's head written like in level files
+    pub fn symbol(self) -> char {
+        match self {
+            CellKind::Empty => '.',
+            CellKind::SnakeHead => '@',
+            CellKind::SnakeBody => 'o',
+            CellKind::OpponentHead(index) => char::from_digit(index as u32 % 10, 10).unwrap_or('&'),
+            CellKind::OpponentBody(_) => '+',
+            CellKind::Food(FoodKind::Apple) => '*',
+            CellKind::Food(FoodKind::Bonus) => '$',
+            CellKind::Food(FoodKind::SlowDown) => '%',
+            CellKind::Obstacle => '#',
+            CellKind::Portal => 'O',
+        }
+    }
+}
+
+impl Game {
+    // Every cell of the board in row-major order (left to right, top to
+    // bottom), so a renderer can draw everything with a single loop.
+    pub fn cells(&self) -> impl Iterator<Item = (Point, CellKind)> {
+        let width = self.width;
+        let grid = self.cell_grid();
+        grid.into_iter().enumerate().map(move |(i, kind)| {
+            let i = i as i32;
+            (Point { x: i % width, y: i / width }, kind)
+        })
+    }
+
+    pub fn cell_at(&self, p: Point) -> CellKind {
+        if p.x < 0 || p.x >= self.width || p.y < 0 || p.y >= self.height {
+            return CellKind::Obstacle;
+        }
+        if self.snake.body.front() == Some(&p) {
+            CellKind::SnakeHead
+        } else if self.board.is_snake(p) && self.snake.body.contains(&p) {
+            CellKind::SnakeBody
+        } else if let Some(index) = self.snake_at(p) {
+            if self.snake_body(index).front() == Some(&p) {
+                CellKind::OpponentHead(index)
+            } else {
+                CellKind::OpponentBody(index)
+            }
+        } else if let Some(food) = self.foods.iter().find(|f| f.pos == p) {
+            CellKind::Food(food.kind)
+        } else if self.board.is_wall(p) {
+            CellKind::Obstacle
+        } else if self.level.portals.iter().any(|(a, b)| *a == p || *b == p) {
+            CellKind::Portal
+        } else {
+            CellKind::Empty
+        }
+    }
+
+    // Builds the whole board in one pass over each collection instead of
+    // looking every cell up separately. Later layers win, so the snake is
+    // drawn on top of the portal it's passing through.
+    fn cell_grid(&self) -> Vec<CellKind> {
+        let mut grid = vec![CellKind::Empty; (self.width * self.height) as usize];
+        let index = |p: &Point| (p.y * self.width + p.x) as usize;
+
+        for (a, b) in &self.level.portals {
+            grid[index(a)] = CellKind::Portal;
+            grid[index(b)] = CellKind::Portal;
+        }
+        for wall in &self.level.walls {
+            grid[index(wall)] = CellKind::Obstacle;
+        }
+        for food in &self.foods {
+            grid[index(&food.pos)] = CellKind::Food(food.kind);
+        }
+        for (i, opponent) in self.opponents.iter().enumerate() {
+            for (n, segment) in opponent.snake.body.iter().enumerate() {
+                grid[index(segment)] = if n == 0 { CellKind::OpponentHead(i + 1) } else { CellKind::OpponentBody(i + 1) };
+            }
+        }
+        for segment in self.snake.body.iter().skip(1) {
+            grid[index(segment)] = CellKind::SnakeBody;
+        }
+        if let Some(head) = self.snake.body.front() {
+            grid[index(head)] = CellKind::SnakeHead;
+        }
+        grid
+    }
+}
@@ -0,0 +1,102 @@
+// src/cheats.rs
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::food::{BONUS_TTL, SLOW_DOWN_TTL};
+use crate::{Food, FoodKind, Game, GameError, Point};
+
+// A change made to a round by hand, for trying things out and reproducing
+// bugs (the `console` feature types them in). Rounds they were used in are
+// flagged: `RunStats::cheats` counts them and the replay carries them, so
+// it still plays back the same and whoever checks a score can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cheat {
+    // Food of `kind` on the free cell `at`, bonus items and power-ups
+    // expiring as usual
+    SpawnFood { at: Point, kind: FoodKind },
+    // The snake grows this many segments over the next ticks
+    Grow { segments: u32 },
+    // Runs at this speed level whatever the score, 0 goes back to the score's
+    SpeedLevel { level: u32 },
+    // Moves the whole snake, its shape kept, so its head is on `to`
+    Teleport { to: Point },
+}
+
+// A cheat and the tick count when it was used, it takes effect right away
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheatInput {
+    pub tick: u64,
+    pub cheat: Cheat,
+}
+
+impl Game {
+    // Only while a round is running, and not on replays, they play their
+    // own cheats back
+    pub fn cheat(&mut self, cheat: Cheat) -> Result<(), GameError> {
+        if self.is_replay() || !self.game_started || self.game_over {
+            return Err(GameError::InvalidCommand("no round running".to_string()));
+        }
+        self.apply_cheat(cheat)?;
+        self.cheats.push(CheatInput { tick: self.tick_count(), cheat });
+        Ok(())
+    }
+
+    // Cheats used this round, oldest first
+    pub fn cheats(&self) -> &[CheatInput] {
+        &self.cheats
+    }
+
+    pub(crate) fn apply_cheat(&mut self, cheat: Cheat) -> Result<(), GameError> {
+        match cheat {
+            Cheat::SpawnFood { at, kind } => {
+                if !self.on_board(at) {
+                    return Err(GameError::SpawnOutOfBounds(at));
+                }
+                if !self.is_free(at) {
+                    return Err(GameError::SpawnOnObstacle(at));
+                }
+                let ttl = match kind {
+                    FoodKind::Apple => None,
+                    FoodKind::Bonus => Some(BONUS_TTL),
+                    FoodKind::SlowDown => Some(SLOW_DOWN_TTL),
+                };
+                self.foods.push(Food { pos: at, kind, ttl });
+                self.board.set_food(at, true);
+            }
+            Cheat::Grow { segments } => self.growing = self.growing.saturating_add(segments),
+            Cheat::SpeedLevel { level } => self.forced_level = (level > 0).then_some(level),
+            Cheat::Teleport { to } => self.teleport(to)?,
+        }
+        // The last tick can't be redone from where the snake was
+        self.plain_move = None;
+        self.stats.cheats += 1;
+        Ok(())
+    }
+
+    fn on_board(&self, p: Point) -> bool {
+        p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height
+    }
+
+    // Food under the snake's new place is gone, the apples are topped up
+    fn teleport(&mut self, to: Point) -> Result<(), GameError> {
+        let head = self.snake.body[0];
+        let (dx, dy) = (to.x - head.x, to.y - head.y);
+        let body: Vec<Point> = self.snake.body.iter().map(|p| Point { x: p.x + dx, y: p.y + dy }).collect();
+        for &p in &body {
+            if !self.on_board(p) {
+                return Err(GameError::SpawnOutOfBounds(p));
+            }
+            let taken = self.board.is_snake(p) && !self.snake.body.contains(&p);
+            if self.board.is_wall(p) || taken {
+                return Err(GameError::SpawnOnObstacle(p));
+            }
+        }
+        self.foods.retain(|food| !body.contains(&food.pos));
+        self.snake.body = body.into();
+        self.last_moves.clear();
+        self.refresh_board();
+        self.replenish_apples()
+    }
+}
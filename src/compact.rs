@@ -0,0 +1,396 @@
+// src/compact.rs
+
+use alloc::vec::Vec;
+use core::time::Duration;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Body, BossFight, CheatInput, Controller, DeathCause, Direction, Food, Game, Opponent, Point, ReplayInput, RunStats,
+    SnakeSpawn,
+};
+
+// Bumped whenever the fields or their meaning change, `fits` turns away
+// states of another version. States from before it was kept read as 0.
+const COMPACT_VERSION: u8 = 3;
+
+// A smaller `Snapshot`: each body is its head plus the directions it moved
+// in, run-length encoded, so a 1000-segment snake that turned ten times
+// takes a dozen numbers instead of a thousand points. A bitset of the cells
+// under the snakes comes along, so a search algorithm can check for
+// collisions on a stored state without restoring it. Like a snapshot it
+// keeps the RNG's position and the round's inputs, a restored round goes on
+// (and replays) as the original did.
+//
+// Decoding follows the moves back from the head, through wrapping walls and
+// portals, so a state only restores into the game it was taken from (or one
+// built from the same level). Segments that didn't get there by moving
+// (custom rules putting a snake somewhere) are stored as they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactState {
+    #[serde(default)]
+    pub(crate) version: u8,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    // `Game::level_fingerprint` of the game it was taken from
+    pub(crate) level_fingerprint: u64,
+    // One bit per cell in row-major order, set under any snake
+    pub(crate) occupancy: Vec<u64>,
+    pub(crate) snake: CompactBody,
+    pub(crate) foods: Vec<Food>,
+    pub(crate) score: u32,
+    pub(crate) game_over: bool,
+    pub(crate) game_started: bool,
+    pub(crate) paused: bool,
+    pub(crate) boosting: bool,
+    pub(crate) pending_turn: Option<Direction>,
+    pub(crate) slow_ticks: u32,
+    pub(crate) accumulator: Duration,
+    pub(crate) death_cause: Option<DeathCause>,
+    pub(crate) tick_count: u64,
+    pub(crate) game_time: Duration,
+    pub(crate) opponents: Vec<CompactOpponent>,
+    pub(crate) stats: RunStats,
+    pub(crate) boss_fight: Option<BossFight>,
+    pub(crate) bosses: u32,
+    pub(crate) rng_seed: [u8; 32],
+    pub(crate) rng_word_pos: u128,
+    pub(crate) round_seed: u64,
+    pub(crate) recording: Vec<ReplayInput>,
+    pub(crate) cheats: Vec<CheatInput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CompactBody {
+    head: Option<Point>, // `None` for dead opponents
+    direction: Direction,
+    // `count << 2 | direction` for `count` segments, each one a move behind
+    // the one before. A count of 0 takes the next segment from `jumps`.
+    runs: Vec<u32>,
+    jumps: Vec<Point>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CompactOpponent {
+    body: CompactBody,
+    spawn: SnakeSpawn,
+    score: u32,
+    alive: bool,
+    controller: Option<Controller>,
+    death_cause: Option<DeathCause>,
+    pending_turn: Option<Direction>,
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl CompactState {
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn snake_len(&self) -> usize {
+        self.snake.len
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // Where the player's snake's head was
+    pub fn head(&self) -> Option<Point> {
+        self.snake.head
+    }
+
+    // Whether any snake was on `p`, false off the board
+    pub fn is_snake(&self, p: Point) -> bool {
+        if p.x < 0 || p.x >= self.width || p.y < 0 || p.y >= self.height {
+            return false;
+        }
+        let i = p.y as usize * self.width as usize + p.x as usize;
+        self.occupancy.get(i / 64).is_some_and(|word| word & (1 << (i % 64)) != 0)
+    }
+
+    // Whether it was taken from a game with `game`'s level by this version,
+    // which `restore_compact` needs
+    pub fn fits(&self, game: &Game) -> bool {
+        self.version == COMPACT_VERSION
+            && self.width == game.width
+            && self.height == game.height
+            && self.level_fingerprint == game.level_fingerprint()
+    }
+}
+
+impl Game {
+    // The state as a `CompactState`, see there
+    pub fn compact_state(&self) -> CompactState {
+        span!(TRACE, "compact_state");
+        CompactState {
+            version: COMPACT_VERSION,
+            width: self.width,
+            height: self.height,
+            level_fingerprint: self.level_fingerprint(),
+            occupancy: self.board.snake_bits().to_vec(),
+            snake: self.compact_body(&self.snake.body, self.snake.direction),
+            foods: self.foods.clone(),
+            score: self.score,
+            game_over: self.game_over,
+            game_started: self.game_started,
+            paused: self.paused,
+            boosting: self.boosting,
+            pending_turn: self.pending_turn,
+            slow_ticks: self.slow_ticks,
+            accumulator: self.accumulator,
+            death_cause: self.death_cause,
+            tick_count: self.tick_count,
+            game_time: self.game_time,
+            opponents: self
+                .opponents
+                .iter()
+                .map(|o| CompactOpponent {
+                    body: self.compact_body(&o.snake.body, o.snake.direction),
+                    spawn: o.spawn,
+                    score: o.score,
+                    alive: o.alive,
+                    controller: o.controller,
+                    death_cause: o.death_cause,
+                    pending_turn: o.pending_turn,
+                })
+                .collect(),
+            stats: self.stats,
+            boss_fight: self.boss_fight,
+            bosses: self.bosses,
+            rng_seed: self.rng.get_seed(),
+            rng_word_pos: self.rng.get_word_pos(),
+            round_seed: self.round_seed,
+            recording: self.recording.clone(),
+            cheats: self.cheats.clone(),
+        }
+    }
+
+    // Rewinds to a compact state taken from this game, like `restore`,
+    // random sequence and round recording included. Opponents missing from
+    // the game are added back.
+    pub fn restore_compact(&mut self, state: &CompactState) {
+        span!(TRACE, "restore_compact");
+        let mut body = core::mem::take(&mut self.snake.body);
+        self.expand_body(&state.snake, &mut body);
+        self.snake.body = body;
+        self.snake.direction = state.snake.direction;
+        self.foods.clone_from(&state.foods);
+        self.score = state.score;
+        self.game_over = state.game_over;
+        self.game_started = state.game_started;
+        self.paused = state.paused;
+        self.boosting = state.boosting;
+        self.pending_turn = state.pending_turn;
+        self.slow_ticks = state.slow_ticks;
+        self.accumulator = state.accumulator;
+        self.death_cause = state.death_cause;
+        self.tick_count = state.tick_count;
+        self.game_time = state.game_time;
+        self.stats = state.stats;
+        self.boss_fight = state.boss_fight;
+        self.bosses = state.bosses;
+        self.rng = ChaCha8Rng::from_seed(state.rng_seed);
+        self.rng.set_word_pos(state.rng_word_pos);
+        self.round_seed = state.round_seed;
+        self.recording.clone_from(&state.recording);
+        self.cheats.clone_from(&state.cheats);
+
+        self.opponents.truncate(state.opponents.len());
+        for (i, compact) in state.opponents.iter().enumerate() {
+            if i == self.opponents.len() {
+                self.opponents.push(Opponent::new(compact.spawn, compact.controller));
+            }
+            let mut body = core::mem::take(&mut self.opponents[i].snake.body);
+            self.expand_body(&compact.body, &mut body);
+            let opponent = &mut self.opponents[i];
+            opponent.snake.body = body;
+            opponent.snake.direction = compact.body.direction;
+            opponent.spawn = compact.spawn;
+            opponent.score = compact.score;
+            opponent.alive = compact.alive;
+            opponent.controller = compact.controller;
+            opponent.death_cause = compact.death_cause;
+            opponent.pending_turn = compact.pending_turn;
+        }
+        self.settle_restored();
+    }
+
+    // The cell a segment on `p` came from by moving `direction`, undoing
+    // `step`: a segment on a portal came out of it, having stepped into the
+    // other end
+    fn behind(&self, p: Point, direction: Direction) -> Option<Point> {
+        let p = self.portal_exit(p).unwrap_or(p);
+        let mut q = match direction {
+            Direction::Up => Point { x: p.x, y: p.y + 1 },
+            Direction::Down => Point { x: p.x, y: p.y - 1 },
+            Direction::Left => Point { x: p.x + 1, y: p.y },
+            Direction::Right => Point { x: p.x - 1, y: p.y },
+        };
+        if q.x < 0 || q.x >= self.width || q.y < 0 || q.y >= self.height {
+            if !self.wrap_walls {
+                return None;
+            }
+            q.x = q.x.rem_euclid(self.width);
+            q.y = q.y.rem_euclid(self.height);
+        }
+        Some(q)
+    }
+
+    fn compact_body(&self, body: &Body, direction: Direction) -> CompactBody {
+        let mut compact = CompactBody {
+            head: body.front().copied(),
+            direction,
+            runs: Vec::new(),
+            jumps: Vec::new(),
+            len: body.len(),
+        };
+        for (&p, &next) in body.iter().zip(body.iter().skip(1)) {
+            let moved = DIRECTIONS.iter().position(|&d| self.behind(p, d) == Some(next));
+            match (moved, compact.runs.last_mut()) {
+                (Some(d), Some(run)) if *run != 0 && *run & 3 == d as u32 && *run >> 2 < u32::MAX >> 2 => *run += 4,
+                (Some(d), _) => compact.runs.push(4 | d as u32),
+                (None, _) => {
+                    compact.runs.push(0);
+                    compact.jumps.push(next);
+                }
+            }
+        }
+        compact
+    }
+
+    fn expand_body(&self, compact: &CompactBody, body: &mut Body) {
+        body.clear();
+        let Some(mut p) = compact.head else { return };
+        body.push_back(p);
+        let mut jumps = compact.jumps.iter();
+        for &run in &compact.runs {
+            if run == 0 {
+                let Some(&next) = jumps.next() else { return };
+                p = next;
+                body.push_back(p);
+                continue;
+            }
+            for _ in 0..run >> 2 {
+                // Only from a state of another board
+                let Some(next) = self.behind(p, DIRECTIONS[(run & 3) as usize]) else { return };
+                p = next;
+                body.push_back(p);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    use crate::{BossConfig, GameConfig, Level};
+
+    fn game(seed: u64, wrap_walls: bool) -> Game {
+        let mut level = Level::parse("....a.....\n..........\n..##......\n....@.....\n.......a..\n..........").unwrap();
+        level.spawn.length = 4;
+        let config = GameConfig {
+            seed: Some(seed),
+            wrap_walls,
+            food_count: 4,
+            opponents: vec![Some(Controller::Greedy), Some(Controller::Pathfinder)],
+            boss: Some(BossConfig { every: 2, ticks: 15, length: 4 }),
+            ..GameConfig::default()
+        };
+        Game::from_level_with_config(&level, &config).unwrap()
+    }
+
+    #[test]
+    fn restores_what_it_stored() {
+        let mut bosses = 0;
+        for (seed, wrap_walls) in [(1, false), (2, true), (3, true)] {
+            let mut original = game(seed, wrap_walls);
+            original.start_game();
+            for tick in 0..300 {
+                if original.game_over {
+                    original.start_game();
+                }
+                if let Some(direction) = Controller::Pathfinder.decide(&original, 0) {
+                    original.queue_turn(direction);
+                }
+                original.tick();
+                if tick % 10 != 0 {
+                    continue;
+                }
+
+                let state = original.compact_state();
+                let state: CompactState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+                for y in 0..original.height {
+                    for x in 0..original.width {
+                        let p = Point { x, y };
+                        let snake = (0..original.snake_count()).any(|i| original.snake_body(i).contains(&p));
+                        assert_eq!(state.is_snake(p), snake, "{:?}", p);
+                    }
+                }
+
+                // Into a game that lost an opponent and is somewhere else
+                let mut restored = game(seed + 100, wrap_walls);
+                restored.opponents.pop();
+                restored.start_game();
+                assert!(state.fits(&restored));
+                restored.restore_compact(&state);
+                assert_eq!(restored.render_ascii(), original.render_ascii(), "seed {} tick {}", seed, tick);
+                for i in 0..original.snake_count() {
+                    assert_eq!(restored.snake_body(i), original.snake_body(i));
+                }
+                assert_eq!(restored.compact_state(), state);
+                assert_eq!((restored.score, restored.tick_count()), (original.score, original.tick_count()));
+                assert_eq!(restored.boss_fight(), original.boss_fight());
+                assert_eq!(restored.state_hash(), original.state_hash());
+                bosses += usize::from(original.boss_fight().is_some());
+
+                // and goes on the same way, replay included
+                let mut ahead = original.clone();
+                for _ in 0..20 {
+                    for game in [&mut ahead, &mut restored] {
+                        if let Some(direction) = Controller::Pathfinder.decide(game, 0) {
+                            game.queue_turn(direction);
+                        }
+                        game.tick();
+                    }
+                    assert_eq!(restored.state_hash(), ahead.state_hash(), "seed {} tick {}", seed, tick);
+                }
+                assert_eq!(restored.replay(), ahead.replay());
+            }
+        }
+        assert!(bosses > 0, "no state had a boss in it");
+    }
+
+    #[test]
+    fn turns_away_other_versions() {
+        let game = game(1, false);
+        let mut state = game.compact_state();
+        assert!(state.fits(&game));
+        state.version = 0;
+        assert!(!state.fits(&game));
+    }
+
+    #[test]
+    fn turns_away_other_levels() {
+        let original = game(1, false);
+        let (state, snapshot) = (original.compact_state(), original.snapshot());
+        assert!(state.fits(&original) && snapshot.fits(&original));
+        let wrapping = game(1, true);
+        assert!(!state.fits(&wrapping) && !snapshot.fits(&wrapping));
+
+        // Same size, a wall moved to a free cell
+        let mut level = (*original.level).clone();
+        level.walls[0] = Point { x: 9, y: 5 };
+        let walled = Game::from_level_with_config(&level, &GameConfig { seed: Some(1), ..GameConfig::default() }).unwrap();
+        assert!(!state.fits(&walled) && !snapshot.fits(&walled));
+    }
+}
@@ -0,0 +1,77 @@
+// src/config.rs
+
+use serde::{Deserialize, Serialize};
+
+use alloc::vec::Vec;
+
+use crate::{AfkMode, BossConfig, Controller, SpeedCurve, ThemePreset, DEFAULT_COUNTDOWN_SECS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all_fields = "camelCase")]
+pub enum GameMode {
+    // Play until the snake crashes
+    #[default]
+    Classic,
+    // Score as much as possible before the in-game clock runs out
+    Timed { limit_secs: u32 },
+}
+
+// Everything that can be tuned when creating a game. Missing fields take
+// their default, so `{ "width": 30 }` is a valid config on its own.
+// Field names are camelCase because JS options objects are the main source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GameConfig {
+    pub width: i32,
+    pub height: i32,
+    // `None` picks a random seed
+    pub seed: Option<u64>,
+    pub mode: GameMode,
+    // Leaving the board on one side comes back in on the other
+    pub wrap_walls: bool,
+    // Apples kept on the board at all times
+    pub food_count: usize,
+    pub speed_curve: SpeedCurve,
+    pub snake_length: usize,
+    // One entry per extra snake: the AI steering it, or `None` for a snake
+    // steered with `queue_turn_for` (a second player)
+    pub opponents: Vec<Option<Controller>>,
+    pub theme: ThemePreset,
+    // Seconds counted down before the snake starts moving, 0 for none
+    pub countdown_secs: u32,
+    // No flashing or collapsing death animation, see `Game::snake_opacity`
+    pub reduced_motion: bool,
+    // Fraction of a tick (0.0 to 1.0) after it runs during which a turn
+    // still counts for it, see `Game::queue_turn`. 0 turns it off.
+    pub input_grace: f32,
+    // Ticks without input before a snake steered by a player counts as
+    // away, in games with more than one snake, 0 for never. See `AfkMode`
+    // for what it does then.
+    pub afk_ticks: u64,
+    pub afk_mode: AfkMode,
+    // Boss encounters at score milestones, `None` for none, see boss.rs
+    pub boss: Option<BossConfig>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            width: 20,
+            height: 20,
+            seed: None,
+            mode: GameMode::Classic,
+            wrap_walls: false,
+            food_count: 1,
+            speed_curve: SpeedCurve::default(),
+            snake_length: 1,
+            opponents: Vec::new(),
+            theme: ThemePreset::Classic,
+            countdown_secs: DEFAULT_COUNTDOWN_SECS,
+            reduced_motion: false,
+            input_grace: 0.0,
+            afk_ticks: 0,
+            afk_mode: AfkMode::Straight,
+            boss: None,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// src/console.rs
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Cheat, FoodKind, Game, GameError, Point};
+
+// What `help` prints, one command per line
+pub const HELP: &str = "spawn food X Y [apple|bonus|slow]  food on a free cell
+grow N                             grow by N segments
+speed LEVEL                        run at LEVEL, 0 for the score's
+teleport X Y                       move the head to X Y
+seed?                              the round's seed
+help                               this list";
+
+// A line typed into the debug console, see `Command::parse`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Cheat(Cheat),
+    // Prints the round's seed, to play it again with --seed
+    Seed,
+    Help,
+}
+
+impl Command {
+    // Words separated by spaces, case doesn't matter. See `HELP`.
+    pub fn parse(line: &str) -> Result<Command, GameError> {
+        let lower = line.to_lowercase();
+        let words: Vec<&str> = lower.split_whitespace().collect();
+        let command = match words.as_slice() {
+            ["spawn", "food", x, y] => Cheat::SpawnFood { at: point(x, y)?, kind: FoodKind::Apple },
+            ["spawn", "food", x, y, kind] => Cheat::SpawnFood { at: point(x, y)?, kind: food_kind(kind)? },
+            ["grow", n] => Cheat::Grow { segments: number(n)? },
+            ["speed", level] => Cheat::SpeedLevel { level: number(level)? },
+            ["teleport", x, y] => Cheat::Teleport { to: point(x, y)? },
+            ["seed?"] | ["seed"] => return Ok(Command::Seed),
+            ["help"] | ["?"] => return Ok(Command::Help),
+            [] => return Err(GameError::InvalidCommand("empty command".to_string())),
+            _ => return Err(GameError::InvalidCommand(format!("unknown command '{}', try help", line.trim()))),
+        };
+        Ok(Command::Cheat(command))
+    }
+}
+
+fn number<T: core::str::FromStr>(word: &str) -> Result<T, GameError> {
+    word.parse().map_err(|_| GameError::InvalidCommand(format!("'{}' is not a number", word)))
+}
+
+fn point(x: &str, y: &str) -> Result<Point, GameError> {
+    Ok(Point { x: number(x)?, y: number(y)? })
+}
+
+fn food_kind(word: &str) -> Result<FoodKind, GameError> {
+    match word {
+        "apple" => Ok(FoodKind::Apple),
+        "bonus" => Ok(FoodKind::Bonus),
+        "slow" | "slowdown" => Ok(FoodKind::SlowDown),
+        _ => Err(GameError::InvalidCommand(format!("unknown food '{}', expected apple, bonus or slow", word))),
+    }
+}
+
+// Parses and runs a line on `game`, returning what to print back. Cheats
+// are recorded like with `Game::cheat`, so the round is flagged.
+pub fn run(game: &mut Game, line: &str) -> Result<String, GameError> {
+    match Command::parse(line)? {
+        Command::Cheat(cheat) => {
+            game.cheat(cheat)?;
+            Ok("ok".to_string())
+        }
+        Command::Seed => Ok(format!("seed {}", game.round_seed())),
+        Command::Help => Ok(HELP.to_string()),
+    }
+}
@@ -0,0 +1,325 @@
+// src/editor.rs
+
+// Level editor in the terminal. Walls, portals and the spawn point are
+// painted on a grid and saved in the level text format (see `Level`), ready
+// for `--level` in the other frontends:
+//
+//     cargo run --bin snake-editor -- levels/maze.txt --width 30 --height 20
+//
+// An existing file is opened as it is, otherwise a new empty level of the
+// given size is started. Open cells the snake can't reach are shaded while
+// editing, and saving only goes through if `Game::from_level` accepts the
+// level and reading the saved text back gives the same level.
+
+// This code will only be compiled for native targets, not for wasm32.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use snake_game::{Direction, Game, Level, Point, Rgb, Theme};
+
+// How long to wait for a key before drawing again
+const FRAME: Duration = Duration::from_millis(50);
+
+// Portals are lettered 'a' to 'z' in the file
+const MAX_PORTALS: usize = 26;
+
+// Open cells the snake can't reach
+const UNREACHABLE: Rgb = Rgb::new(90, 40, 40);
+
+fn color(rgb: Rgb) -> Color {
+    Color::Rgb(rgb.r, rgb.g, rgb.b)
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Paints levels for the snake game in the terminal")]
+struct Args {
+    #[arg(value_name = "FILE", help = "Level file to open, created on the first save if it doesn't exist")]
+    path: PathBuf,
+    #[arg(long, default_value_t = 20, help = "Width of a new level")]
+    width: i32,
+    #[arg(long, default_value_t = 20, help = "Height of a new level")]
+    height: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Wall,
+    // Two presses: the first end, then the other one
+    Portal,
+    // Pressing on the spawn turns it around clockwise
+    Spawn,
+    Erase,
+}
+
+const TOOLS: [Tool; 4] = [Tool::Wall, Tool::Portal, Tool::Spawn, Tool::Erase];
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Wall => "Wall",
+            Tool::Portal => "Portal",
+            Tool::Spawn => "Spawn",
+            Tool::Erase => "Erase",
+        }
+    }
+}
+
+fn turn_clockwise(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+struct Editor {
+    level: Level,
+    path: PathBuf,
+    cursor: Point,
+    tool: Tool,
+    portal_start: Option<Point>, // First end of the portal being placed
+    modified: bool,
+    status: String,
+    quit_warned: bool, // Asked once to save before quitting
+    quit: bool,
+}
+
+impl Editor {
+    // Takes the wall or portal off a cell, both ends for a portal
+    fn clear(&mut self, p: Point) {
+        self.level.walls.retain(|&wall| wall != p);
+        self.level.portals.retain(|&(a, b)| a != p && b != p);
+    }
+
+    fn paint(&mut self) {
+        let p = self.cursor;
+        let level = &mut self.level;
+        if p == level.spawn.head && self.tool != Tool::Spawn {
+            self.status = "The spawn stays, move it elsewhere first".to_string();
+            return;
+        }
+        match self.tool {
+            Tool::Wall => {
+                self.clear(p);
+                self.level.walls.push(p);
+                // Kept in reading order, the order `Level::parse` gives
+                self.level.walls.sort_by_key(|wall| (wall.y, wall.x));
+            }
+            Tool::Erase => self.clear(p),
+            Tool::Spawn if p == level.spawn.head => level.spawn.direction = turn_clockwise(level.spawn.direction),
+            Tool::Spawn => {
+                self.clear(p);
+                self.level.spawn.head = p;
+            }
+            Tool::Portal => match self.portal_start.take() {
+                None if level.portals.len() >= MAX_PORTALS => {
+                    self.status = format!("At most {} portals", MAX_PORTALS);
+                    return;
+                }
+                None => {
+                    self.clear(p);
+                    self.portal_start = Some(p);
+                    self.status = "Now the other end".to_string();
+                    return;
+                }
+                Some(start) if start == p => {
+                    self.status.clear();
+                    return;
+                }
+                Some(start) => {
+                    self.clear(p);
+                    self.level.portals.push((start, p));
+                }
+            },
+        }
+        self.status.clear();
+        self.modified = true;
+        self.quit_warned = false;
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = Game::from_level_with_seed(&self.level, 0) {
+            self.status = format!("Not saved: {}", e);
+            return;
+        }
+        let text = format!("{}\n", self.level);
+        if Level::parse(&text).as_ref() != Ok(&self.level) {
+            self.status = "Not saved: the level doesn't read back the same".to_string();
+            return;
+        }
+        if let Err(e) = std::fs::write(&self.path, text) {
+            self.status = format!("Not saved: {}", e);
+            return;
+        }
+        self.modified = false;
+        self.status = match self.level.unreachable_cells().len() {
+            0 => format!("Saved {}", self.path.display()),
+            n => format!("Saved {}, but {} open cells can't be reached", self.path.display(), n),
+        };
+    }
+
+    fn key_pressed(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let move_by = |dx: i32, dy: i32, cursor: Point, level: &Level| Point {
+            x: (cursor.x + dx).clamp(0, level.width - 1),
+            y: (cursor.y + dy).clamp(0, level.height - 1),
+        };
+        match code {
+            KeyCode::Up => self.cursor = move_by(0, -1, self.cursor, &self.level),
+            KeyCode::Down => self.cursor = move_by(0, 1, self.cursor, &self.level),
+            KeyCode::Left => self.cursor = move_by(-1, 0, self.cursor, &self.level),
+            KeyCode::Right => self.cursor = move_by(1, 0, self.cursor, &self.level),
+            KeyCode::Char(' ') | KeyCode::Enter => self.paint(),
+            KeyCode::Char(c @ '1'..='4') => self.select(TOOLS[c as usize - '1' as usize]),
+            KeyCode::Tab => {
+                let index = TOOLS.iter().position(|&t| t == self.tool).unwrap_or(0);
+                self.select(TOOLS[(index + 1) % TOOLS.len()]);
+            }
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.modified && !self.quit_warned {
+                    self.quit_warned = true;
+                    self.status = "Unsaved changes, Q again to quit anyway".to_string();
+                } else {
+                    self.quit = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn select(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.portal_start = None;
+        self.status.clear();
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let theme = Theme::default();
+        let level = &self.level;
+        let text = Style::new().fg(color(theme.text)).bg(color(theme.background));
+        frame.render_widget(Block::new().style(text), frame.area());
+
+        // Two columns per cell, plus the border, the tools line and the
+        // status and help lines
+        let (columns, rows) = (level.width as u16 * 2 + 2, level.height as u16 + 2);
+        if frame.area().width < columns || frame.area().height < rows + 3 {
+            let message = format!("Make the terminal at least {}x{}", columns, rows + 3);
+            let [area] = Layout::vertical([Constraint::Length(1)]).flex(Flex::Center).areas(frame.area());
+            frame.render_widget(Paragraph::new(message).style(text).alignment(Alignment::Center), area);
+            return;
+        }
+        let [tools, board, status, help] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(rows), Constraint::Length(1), Constraint::Length(1)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+
+        let mut spans = vec![Span::styled(
+            format!("{}{}  ", self.path.display(), if self.modified { " *" } else { "" }),
+            text.add_modifier(Modifier::BOLD),
+        )];
+        for (i, &tool) in TOOLS.iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, tool.name());
+            let style = if tool == self.tool { text.add_modifier(Modifier::REVERSED) } else { text };
+            spans.push(Span::styled(label, style));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)).alignment(Alignment::Center), tools);
+
+        let unreachable = level.unreachable_cells();
+        let lines: Vec<Line> = (0..level.height)
+            .map(|y| {
+                let spans = (0..level.width).map(|x| {
+                    let p = Point { x, y };
+                    let portal = level.portals.iter().position(|&(a, b)| a == p || b == p);
+                    let (cell, style) = if p == level.spawn.head {
+                        let arrow = match level.spawn.direction {
+                            Direction::Up => "▲ ",
+                            Direction::Down => "▼ ",
+                            Direction::Left => "◀ ",
+                            Direction::Right => "▶ ",
+                        };
+                        (arrow.to_string(), text.fg(color(theme.head)))
+                    } else if level.walls.contains(&p) {
+                        ("██".to_string(), text.fg(color(theme.obstacle)))
+                    } else if let Some(i) = portal {
+                        (format!("{} ", (b'a' + i as u8) as char), text.fg(color(theme.background)).bg(color(theme.portal)))
+                    } else if self.portal_start == Some(p) {
+                        ("? ".to_string(), text.bg(color(theme.portal)))
+                    } else if unreachable.contains(&p) {
+                        ("  ".to_string(), text.bg(color(UNREACHABLE)))
+                    } else {
+                        ("  ".to_string(), text)
+                    };
+                    let style = if p == self.cursor { style.add_modifier(Modifier::REVERSED) } else { style };
+                    Span::styled(cell, style)
+                });
+                Line::from(spans.collect::<Vec<_>>())
+            })
+            .collect();
+        let title = format!(" {}x{}  ({}, {}) ", level.width, level.height, self.cursor.x, self.cursor.y);
+        let [board] = Layout::horizontal([Constraint::Length(columns)]).flex(Flex::Center).areas(board);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title).style(text)), board);
+
+        frame.render_widget(Paragraph::new(self.status.as_str()).style(text).alignment(Alignment::Center), status);
+        let keys = "Arrows move, Space paints, 1-4/Tab tool, S saves, Q quits";
+        frame.render_widget(Paragraph::new(keys).style(text.add_modifier(Modifier::DIM)).alignment(Alignment::Center), help);
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, mut editor: Editor) -> Result<(), Box<dyn Error>> {
+    while !editor.quit {
+        terminal.draw(|frame| editor.draw(frame))?;
+        if event::poll(FRAME)? {
+            // Some terminals report releases too, only presses count
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    editor.key_pressed(key.code, key.modifiers);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let level = match std::fs::read_to_string(&args.path) {
+        Ok(text) => Level::parse(&text).map_err(|e| format!("{}: {}", args.path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if args.width <= 0 || args.height <= 0 {
+                return Err(format!("invalid board dimensions {}x{}", args.width, args.height).into());
+            }
+            Level::empty(args.width, args.height)
+        }
+        Err(e) => return Err(format!("could not read {}: {}", args.path.display(), e).into()),
+    };
+    let editor = Editor {
+        cursor: level.spawn.head,
+        level,
+        path: args.path,
+        tool: Tool::Wall,
+        portal_start: None,
+        modified: false,
+        status: String::new(),
+        quit_warned: false,
+        quit: false,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, editor);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,135 @@
+// src/embedded.rs
+
+// Draws the board on any `embedded-graphics` display (SSD1306 OLEDs,
+// ST7735 or ILI9341 LCDs, e-paper...), to run the game on a microcontroller.
+// Only compiled with the `embedded` feature, which doesn't need `std`. Each
+// cell is a `cell_size` square from `origin`, and the whole board goes out
+// in a single `fill_contiguous` call, which most display drivers turn into
+// one transfer instead of a rectangle per cell.
+//
+// Color displays get the theme's colors:
+//
+//     BoardRenderer::fit(&game, display.bounding_box().size).draw(&game, &mut display)?;
+//
+// Any other display (monochrome OLEDs, grayscale) picks a color per cell:
+//
+//     renderer.draw_with(&game, &mut display, |kind| match kind {
+//         CellKind::Empty => BinaryColor::Off,
+//         _ => BinaryColor::On,
+//     })?;
+//
+// `draw_changes` and `draw_changes_with` only redraw the cells that changed
+// since the last call, for displays too slow to take the whole board on
+// every tick.
+//
+// Text is left to the app, embedded-graphics' fonts can draw the score next
+// to the board. See examples/embedded_sim.rs for a whole game loop.
+
+use alloc::vec::Vec;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::{DrawTarget, Point as DisplayPoint, Size};
+use embedded_graphics::primitives::Rectangle;
+
+use crate::{CellKind, Game, GameStatus, Point, Rgb};
+
+impl From<Rgb> for Rgb888 {
+    fn from(rgb: Rgb) -> Rgb888 {
+        Rgb888::new(rgb.r, rgb.g, rgb.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardRenderer {
+    pub cell_size: u32, // Pixels per side of a cell
+    pub origin: DisplayPoint, // Top-left corner of the board on the display
+}
+
+impl BoardRenderer {
+    pub fn new(cell_size: u32) -> BoardRenderer {
+        BoardRenderer { cell_size: cell_size.max(1), origin: DisplayPoint::zero() }
+    }
+
+    // The largest cells that fit the board on a display of `size`, centered
+    pub fn fit(game: &Game, size: Size) -> BoardRenderer {
+        let cell_size = (size.width / game.width.max(1) as u32).min(size.height / game.height.max(1) as u32).max(1);
+        let board = Size::new(game.width as u32 * cell_size, game.height as u32 * cell_size);
+        let origin = DisplayPoint::new(
+            (size.width.saturating_sub(board.width) / 2) as i32,
+            (size.height.saturating_sub(board.height) / 2) as i32,
+        );
+        BoardRenderer { cell_size, origin }
+    }
+
+    // The part of the display the board covers
+    pub fn bounding_box(&self, game: &Game) -> Rectangle {
+        Rectangle::new(self.origin, Size::new(game.width as u32 * self.cell_size, game.height as u32 * self.cell_size))
+    }
+
+    // With the theme's colors, for displays whose colors convert from Rgb888
+    // (Rgb565, Rgb888, Bgr565...)
+    pub fn draw<D>(&self, game: &Game, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        D::Color: From<Rgb888>,
+    {
+        let theme = &game.theme;
+        self.draw_with(game, target, |kind| Rgb888::from(theme.cell_color(kind).unwrap_or(theme.background)).into())
+    }
+
+    // With `color` picking each cell's color. Segments the death animation
+    // has already taken off a snake are drawn as empty cells.
+    pub fn draw_with<D, F>(&self, game: &Game, target: &mut D, color: F) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        F: Fn(CellKind) -> D::Color,
+    {
+        let mut kinds: Vec<CellKind> = game.cells().map(|(_, kind)| kind).collect();
+        for index in 0..game.snake_count() {
+            for p in game.snake_body(index).iter().skip(game.visible_segments(index)) {
+                kinds[(p.y * game.width + p.x) as usize] = CellKind::Empty;
+            }
+        }
+        let colors: Vec<D::Color> = kinds.into_iter().map(color).collect();
+
+        let area = self.bounding_box(game);
+        let (colors, width, cell) = (&colors, game.width as usize, self.cell_size as usize);
+        let pixels = (0..area.size.height as usize)
+            .flat_map(|y| (0..area.size.width as usize).map(move |x| colors[y / cell * width + x / cell]));
+        target.fill_contiguous(&area, pixels)
+    }
+
+    // Like `draw`, but only the cells that changed since the last call (see
+    // `Game::take_dirty_cells`). The first call, and every call outside a
+    // running round (countdown, pause, death animation, game over), draws
+    // the whole board.
+    pub fn draw_changes<D>(&self, game: &mut Game, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        D::Color: From<Rgb888>,
+    {
+        let theme = game.theme;
+        self.draw_changes_with(game, target, |kind| Rgb888::from(theme.cell_color(kind).unwrap_or(theme.background)).into())
+    }
+
+    // Like `draw_with`, but only the cells that changed, see `draw_changes`
+    pub fn draw_changes_with<D, F>(&self, game: &mut Game, target: &mut D, color: F) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        F: Fn(CellKind) -> D::Color,
+    {
+        let dirty = game.take_dirty_cells();
+        let Some(cells) = dirty.filter(|_| game.status() == GameStatus::Running) else {
+            return self.draw_with(game, target, color);
+        };
+        for p in cells {
+            target.fill_solid(&self.cell_area(p), color(game.cell_at(p)))?;
+        }
+        Ok(())
+    }
+
+    // The part of the display a board cell covers
+    fn cell_area(&self, p: Point) -> Rectangle {
+        let size = self.cell_size as i32;
+        Rectangle::new(self.origin + DisplayPoint::new(p.x * size, p.y * size), Size::new_equal(self.cell_size))
+    }
+}
@@ -0,0 +1,92 @@
+// src/error.rs
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::Point;
+
+// Everything that can go wrong while building a game or loading a level.
+// Returned instead of constructing an unusable game or looping forever.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    // The board must be at least 1x1
+    InvalidDimensions { width: i32, height: i32 },
+    // The snake would start inside a wall
+    SpawnOnObstacle(Point),
+    // Part of the snake would start outside the board
+    SpawnOutOfBounds(Point),
+    // The snake needs at least one segment
+    InvalidSnakeLength(usize),
+    // Every cell is taken, so food cannot be placed
+    NoFreeCell,
+    // The level text could not be parsed
+    InvalidLevel { line: usize, reason: String },
+    // Saving or loading persistent data failed
+    Storage(String),
+    // A replay could not be decoded
+    InvalidReplay(String),
+    // A custom rules script could not be loaded
+    Script(String),
+    // A message from another program (e.g. Protocol Buffers) doesn't make
+    // a valid game value
+    InvalidMessage(String),
+    // A peer-to-peer connection couldn't be set up or broke down
+    WebRtc(String),
+    // A debug console command or cheat couldn't be run
+    InvalidCommand(String),
+    // The speed curve would tick too fast (or never stop ticking), see
+    // `SpeedCurve::validate`
+    InvalidSpeedCurve(String),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::InvalidDimensions { width, height } => {
+                write!(f, "invalid board dimensions {}x{}", width, height)
+            }
+            GameError::SpawnOnObstacle(p) => {
+                write!(f, "snake spawn at ({}, {}) is blocked by an obstacle", p.x, p.y)
+            }
+            GameError::SpawnOutOfBounds(p) => {
+                write!(f, "snake spawn segment at ({}, {}) is outside the board", p.x, p.y)
+            }
+            GameError::InvalidSnakeLength(len) => write!(f, "invalid snake length {}", len),
+            GameError::NoFreeCell => write!(f, "no free cell left to spawn food"),
+            GameError::InvalidLevel { line, reason } => {
+                write!(f, "invalid level at line {}: {}", line, reason)
+            }
+            GameError::Storage(reason) => write!(f, "storage error: {}", reason),
+            GameError::InvalidReplay(reason) => write!(f, "invalid replay: {}", reason),
+            GameError::Script(reason) => write!(f, "rules script error: {}", reason),
+            GameError::InvalidMessage(reason) => write!(f, "invalid message: {}", reason),
+            GameError::WebRtc(reason) => write!(f, "WebRTC error: {}", reason),
+            GameError::InvalidCommand(reason) => write!(f, "invalid command: {}", reason),
+            GameError::InvalidSpeedCurve(reason) => write!(f, "invalid speed curve: {}", reason),
+        }
+    }
+}
+
+impl GameError {
+    // Stable identifier for frontends that react to specific errors
+    // (the WASM build hands it to JS as `error.code`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::InvalidDimensions { .. } => "INVALID_DIMENSIONS",
+            GameError::SpawnOnObstacle(_) => "SPAWN_ON_OBSTACLE",
+            GameError::SpawnOutOfBounds(_) => "SPAWN_OUT_OF_BOUNDS",
+            GameError::InvalidSnakeLength(_) => "INVALID_SNAKE_LENGTH",
+            GameError::NoFreeCell => "NO_FREE_CELL",
+            GameError::InvalidLevel { .. } => "INVALID_LEVEL",
+            GameError::Storage(_) => "STORAGE",
+            GameError::InvalidReplay(_) => "INVALID_REPLAY",
+            GameError::Script(_) => "SCRIPT",
+            GameError::InvalidMessage(_) => "INVALID_MESSAGE",
+            GameError::WebRtc(_) => "WEBRTC",
+            GameError::InvalidCommand(_) => "INVALID_COMMAND",
+            GameError::InvalidSpeedCurve(_) => "INVALID_SPEED_CURVE",
+        }
+    }
+}
+
+impl core::error::Error for GameError {}
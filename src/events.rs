@@ -0,0 +1,131 @@
+// src/events.rs
+
+use alloc::string::String;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, FoodKind, Language, Point};
+
+// Why a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum DeathCause {
+    // Ran off the edge of the board at `at` (the cell it tried to enter)
+    Wall { at: Point },
+    // Ran into a level wall
+    Obstacle { at: Point },
+    // Bit its own body, `segment` counts from the head (1 = neck)
+    SelfCollision { at: Point, segment: usize },
+    // The snake covers every free cell, there's nowhere left to put food
+    BoardFull,
+    // The clock ran out in timed mode
+    TimeExpired,
+    // Ran into the body of another snake (`snake` is its index, 0 = player)
+    Opponent { at: Point, snake: usize },
+    // Custom rules ended the round, see `Rules`
+    Rules,
+}
+
+// Short sentences for game over screens, e.g. "Hit the wall at (20, 4)".
+// Always English, see `message` for other languages.
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(Language::English))
+    }
+}
+
+impl DeathCause {
+    // The translation key of the sentence, see `Language`. Its placeholders
+    // are `{x}` and `{y}` for the cell, `{segment}` and `{snake}`.
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            DeathCause::Wall { .. } => "death-wall",
+            DeathCause::Obstacle { .. } => "death-obstacle",
+            DeathCause::SelfCollision { .. } => "death-self",
+            DeathCause::BoardFull => "death-board-full",
+            DeathCause::TimeExpired => "death-time-up",
+            DeathCause::Opponent { .. } => "death-opponent",
+            DeathCause::Rules => "death-rules",
+        }
+    }
+
+    pub fn message(&self, language: Language) -> String {
+        let (at, segment, snake) = match *self {
+            DeathCause::Wall { at } | DeathCause::Obstacle { at } => (Some(at), 0, 0),
+            DeathCause::SelfCollision { at, segment } => (Some(at), segment, 0),
+            DeathCause::Opponent { at, snake } => (Some(at), 0, snake),
+            DeathCause::BoardFull | DeathCause::TimeExpired | DeathCause::Rules => (None, 0, 0),
+        };
+        let at = at.unwrap_or(Point { x: 0, y: 0 });
+        language.format(self.message_key(), &[("x", &at.x), ("y", &at.y), ("segment", &segment), ("snake", &snake)])
+    }
+}
+
+// Something noteworthy that happened during a tick. Events pile up in the
+// game until a frontend drains them (to play sounds, animate, notify JS...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    Started,
+    // Seconds left before the snake starts moving, 0 as it starts
+    Countdown { remaining: u32 },
+    FoodEaten { at: Point, kind: FoodKind, score: u32 },
+    PowerUpCollected { at: Point, kind: FoodKind, duration_ticks: u32 },
+    // A bonus item or power-up disappeared before being picked up
+    FoodExpired { at: Point, kind: FoodKind },
+    // The score reached a new speed level
+    LevelUp { level: u32 },
+    // The player's snake changed direction
+    Turned { direction: Direction },
+    // The player turned away from a fatal cell (`at`, which may be off the
+    // board) at the last moment, going fast, see `NEAR_MISS_INTERVAL`
+    NearMiss { at: Point },
+    GameOver { cause: DeathCause, score: u32 },
+    // An opponent (`snake` >= 1) ate something, `score` is its new score
+    SnakeAte { snake: usize, at: Point, kind: FoodKind, score: u32 },
+    // An opponent died, the game goes on
+    SnakeDied { snake: usize, cause: DeathCause },
+    // A boss came in as snake `snake` for `ticks` ticks, see boss.rs
+    BossAppeared { snake: usize, ticks: u64 },
+    // It left, `trapped` when it crashed before its time was up
+    BossLeft { snake: usize, trapped: bool },
+}
+
+// What a batch of ticks did, see `Game::tick_n`. The events themselves
+// stay queued for `drain_events` as usual, these are their counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickSummary {
+    // Ticks that actually ran, fewer than asked when the round ended, the
+    // game was paused or a replay ran out of recording
+    pub ticks: u64,
+    // Food the player's snake ate, power-ups included
+    pub food_eaten: u32,
+    pub food_expired: u32,
+    pub level_ups: u32,
+    pub turns: u32,
+    // Opponents that died during the batch
+    pub snakes_died: u32,
+    // The player's score after the batch
+    pub score: u32,
+    // Set when the round ended during the batch
+    pub game_over: Option<DeathCause>,
+}
+
+impl TickSummary {
+    pub(crate) fn count(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::FoodEaten { .. } | GameEvent::PowerUpCollected { .. } => self.food_eaten += 1,
+            GameEvent::FoodExpired { .. } => self.food_expired += 1,
+            GameEvent::LevelUp { .. } => self.level_ups += 1,
+            GameEvent::Turned { .. } => self.turns += 1,
+            GameEvent::SnakeDied { .. } => self.snakes_died += 1,
+            GameEvent::GameOver { cause, .. } => self.game_over = Some(cause),
+            GameEvent::Started
+            | GameEvent::Countdown { .. }
+            | GameEvent::NearMiss { .. }
+            | GameEvent::SnakeAte { .. }
+            | GameEvent::BossAppeared { .. }
+            | GameEvent::BossLeft { .. } => {}
+        }
+    }
+}
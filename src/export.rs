@@ -0,0 +1,83 @@
+// src/export.rs
+
+// Replays as animated PNGs that play in any browser or image viewer, drawn
+// off-screen with `Game::render_rgba`. Only compiled with the `apng`
+// feature, which the native app turns on for its `--export` option and the
+// replay viewer's export key.
+
+use std::io::Write;
+use std::string::ToString;
+use std::time::Duration;
+use std::vec::Vec;
+
+use crate::{GameError, Replay, Theme, ThemePreset};
+
+// Frames shorter than this are merged with the next ones, browsers play
+// faster frames at a fraction of the speed
+const MIN_FRAME: Duration = Duration::from_millis(20);
+
+// The last frame stays up this long before the animation starts over
+const LAST_FRAME: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    pub cell_px: u32, // Pixels per board cell
+    pub speed: f32, // 1.0 plays at the recorded speed
+    pub theme: ThemePreset, // Replays don't record one
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions { cell_px: 16, speed: 1.0, theme: ThemePreset::Classic }
+    }
+}
+
+fn encoding_error(e: png::EncodingError) -> GameError {
+    GameError::Storage(e.to_string())
+}
+
+// The tick each frame shows and how long it stays up. The header needs
+// the number of frames, so the replay is played once without drawing.
+fn frame_plan(replay: &Replay, speed: f32) -> Result<Vec<(u64, Duration)>, GameError> {
+    let mut game = replay.game()?;
+    let mut plan = Vec::new();
+    loop {
+        let tick = game.tick_count();
+        if game.replay_finished() {
+            plan.push((tick, LAST_FRAME));
+            return Ok(plan);
+        }
+        let mut shown = Duration::ZERO;
+        while shown < MIN_FRAME && !game.replay_finished() {
+            shown += game.tick_interval().div_f32(speed);
+            game.tick();
+            game.drain_events().for_each(drop);
+        }
+        plan.push((tick, shown));
+    }
+}
+
+// Plays the replay and writes every frame, looping forever
+pub fn export_apng<W: Write>(replay: &Replay, options: &ExportOptions, out: W) -> Result<(), GameError> {
+    let speed = options.speed.max(0.01);
+    let plan = frame_plan(replay, speed)?;
+    let mut game = replay.game()?;
+    game.theme = Theme::preset(options.theme);
+    let (width, height) = game.frame_size(options.cell_px);
+
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(plan.len() as u32, 0).map_err(encoding_error)?;
+    let mut writer = encoder.write_header().map_err(encoding_error)?;
+    for (tick, shown) in plan {
+        while game.tick_count() < tick {
+            game.tick();
+            game.drain_events().for_each(drop);
+        }
+        let ms = shown.as_millis().min(u16::MAX as u128) as u16;
+        writer.set_frame_delay(ms, 1000).map_err(encoding_error)?;
+        writer.write_image_data(&game.render_rgba(options.cell_px)).map_err(encoding_error)?;
+    }
+    writer.finish().map_err(encoding_error)
+}
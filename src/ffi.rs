@@ -0,0 +1,149 @@
+// src/ffi.rs
+
+// A C API so C, C++, Unity (P/Invoke), Unreal and anything else that can
+// load a shared library can embed the engine. Only compiled with the `ffi`
+// feature, the matching header is include/snake_game.h (see build.rs). A
+// host creates a game, feeds it inputs and elapsed time every frame and
+// reads the state back as JSON, the same JSON the server sends:
+//
+//     SnakeGame *game = snake_game_new("{\"width\": 30, \"height\": 20}");
+//     snake_game_queue_input(game, SnakeInput_Confirm); // Starts it
+//     snake_game_queue_input(game, SnakeInput_Up);
+//     snake_game_tick(game, delta_seconds);
+//     const char *state = snake_game_state_json(game);
+//     snake_game_free(game);
+//
+// The functions are unsafe the way any C API is: a handle is either NULL or
+// comes from `snake_game_new` and wasn't freed yet, and strings are
+// NUL-terminated UTF-8. NULL handles are fine, nothing happens. A handle can
+// move between threads but must not be used from two at once.
+
+// The safety rules are the ones above, for every function
+#![allow(clippy::missing_safety_doc)]
+
+use core::ffi::{c_char, CStr};
+use core::time::Duration;
+use std::boxed::Box;
+use std::ffi::CString;
+
+use crate::{Direction, Game, GameConfig, InputAction};
+
+// What the C side holds on to. The last state JSON is kept here so the
+// pointer handed out stays valid until the next call.
+pub struct SnakeGame {
+    game: Game,
+    state_json: CString,
+}
+
+// The actions of `InputAction`, flattened for C. They're passed as plain
+// `uint32_t`s, a C enum can hold any value and a Rust one can't.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnakeInput {
+    Up = 0,
+    Down = 1,
+    Left = 2,
+    Right = 3,
+    Pause = 4,
+    Restart = 5,
+    Boost = 6,
+    Confirm = 7,
+}
+
+impl SnakeInput {
+    const ALL: [SnakeInput; 8] = [
+        SnakeInput::Up,
+        SnakeInput::Down,
+        SnakeInput::Left,
+        SnakeInput::Right,
+        SnakeInput::Pause,
+        SnakeInput::Restart,
+        SnakeInput::Boost,
+        SnakeInput::Confirm,
+    ];
+
+    fn from_raw(value: u32) -> Option<SnakeInput> {
+        SnakeInput::ALL.into_iter().find(|&input| input as u32 == value)
+    }
+}
+
+impl From<SnakeInput> for InputAction {
+    fn from(input: SnakeInput) -> InputAction {
+        match input {
+            SnakeInput::Up => InputAction::Turn(Direction::Up),
+            SnakeInput::Down => InputAction::Turn(Direction::Down),
+            SnakeInput::Left => InputAction::Turn(Direction::Left),
+            SnakeInput::Right => InputAction::Turn(Direction::Right),
+            SnakeInput::Pause => InputAction::Pause,
+            SnakeInput::Restart => InputAction::Restart,
+            SnakeInput::Boost => InputAction::Boost,
+            SnakeInput::Confirm => InputAction::Confirm,
+        }
+    }
+}
+
+// A game from a `GameConfig` as JSON, NULL or "" for the defaults, waiting
+// on the title screen for `SnakeInput_Confirm`. Returns NULL if the JSON
+// doesn't parse or the config is invalid, e.g. a speed curve with a zero
+// tick interval (see `SpeedCurve::validate`).
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_new(config_json: *const c_char) -> *mut SnakeGame {
+    let config = if config_json.is_null() {
+        GameConfig::default()
+    } else {
+        match CStr::from_ptr(config_json).to_str() {
+            Ok(text) if text.trim().is_empty() => GameConfig::default(),
+            Ok(text) => match serde_json::from_str(text) {
+                Ok(config) => config,
+                Err(_) => return core::ptr::null_mut(),
+            },
+            Err(_) => return core::ptr::null_mut(),
+        }
+    };
+    match Game::from_config(&config) {
+        Ok(game) => Box::into_raw(Box::new(SnakeGame { game, state_json: CString::default() })),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+// Moves the game on by the time since the last call, countdown included.
+// Returns how many ticks were played. The events are dropped, the host sees
+// what happened in the state.
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_tick(game: *mut SnakeGame, elapsed_seconds: f64) -> u32 {
+    let Some(handle) = game.as_mut() else { return 0 };
+    // Negative or NaN times from the host count as no time
+    let elapsed = Duration::try_from_secs_f64(elapsed_seconds).unwrap_or(Duration::ZERO);
+    let ticks = handle.game.advance(elapsed);
+    handle.game.drain_events().for_each(drop);
+    ticks
+}
+
+// Applies a `SnakeInput` right away, turns take effect on the next tick.
+// Returns false, doing nothing, for a NULL handle or a value that isn't one.
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_queue_input(game: *mut SnakeGame, input: u32) -> bool {
+    let (Some(handle), Some(input)) = (game.as_mut(), SnakeInput::from_raw(input)) else { return false };
+    handle.game.handle_action(input.into());
+    true
+}
+
+// The state as JSON (see `GameState`). The string belongs to the game and
+// stays valid until the next call with the same handle, copy it to keep it.
+// Returns NULL for a NULL handle.
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_state_json(game: *mut SnakeGame) -> *const c_char {
+    let Some(handle) = game.as_mut() else { return core::ptr::null() };
+    let json = serde_json::to_string(&handle.game.state()).expect("the state serializes to JSON");
+    // JSON escapes control characters, so there's no NUL inside
+    handle.state_json = CString::new(json).expect("JSON has no NUL bytes");
+    handle.state_json.as_ptr()
+}
+
+// Frees a game, the handle and its state JSON can't be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_free(game: *mut SnakeGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
@@ -0,0 +1,49 @@
+// src/food.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::Point;
+
+// Chance (1 in N) that eating an apple also drops a bonus item
+pub(crate) const BONUS_CHANCE: u32 = 5;
+// Ticks a bonus item stays on the board before disappearing
+pub(crate) const BONUS_TTL: u32 = 40;
+// Chance (1 in N) that eating an apple also drops a slow-down power-up
+pub(crate) const SLOW_DOWN_CHANCE: u32 = 8;
+// Ticks a slow-down power-up stays on the board
+pub(crate) const SLOW_DOWN_TTL: u32 = 60;
+// Ticks the slow-down effect lasts once picked up
+pub(crate) const SLOW_DOWN_DURATION: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FoodKind {
+    // Always on the board, worth one point
+    Apple,
+    // Worth more but expires after a while
+    Bonus,
+    // Power-up: slows the game down for a while instead of growing the snake
+    SlowDown,
+}
+
+impl FoodKind {
+    pub fn points(self) -> u32 {
+        match self {
+            FoodKind::Apple => 1,
+            FoodKind::Bonus => 3,
+            FoodKind::SlowDown => 0,
+        }
+    }
+
+    // Power-ups give an effect instead of making the snake longer
+    pub fn is_power_up(self) -> bool {
+        matches!(self, FoodKind::SlowDown)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Food {
+    pub pos: Point,
+    pub kind: FoodKind,
+    // Ticks left before it disappears, `None` for food that stays
+    pub ttl: Option<u32>,
+}
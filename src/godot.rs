@@ -0,0 +1,523 @@
+// src/godot.rs
+
+// The game as a Godot node, through GDExtension. Only compiled with the
+// `godot` feature:
+//
+//     cargo build --release --lib --no-default-features --features godot
+//
+// then copy target/release/libsnake_game.so (snake_game.dll on Windows,
+// libsnake_game.dylib on macOS) into the project, next to a
+// snake_game.gdextension file for Godot 4.5 or later:
+//
+//     [configuration]
+//     entry_symbol = "gdext_rust_init"
+//     compatibility_minimum = 4.5
+//
+//     [libraries]
+//     linux.x86_64 = "res://bin/libsnake_game.so"
+//     windows.x86_64 = "res://bin/snake_game.dll"
+//     macos = "res://bin/libsnake_game.dylib"
+//
+// This adds a `SnakeGame` node. It plays the whole game in `_process`,
+// reads the ui_up/ui_down/ui_left/ui_right, ui_accept (start, confirm) and
+// ui_cancel (pause) actions, and sends a signal for every `GameEvent`.
+// GDScript only draws it:
+//
+//     func _ready():
+//         $SnakeGame.food_eaten.connect(func(at, kind, score): $Crunch.play())
+//         $SnakeGame.game_over.connect(func(cause, message, score): show_score(score))
+//
+//     func _draw():
+//         for cell in $SnakeGame.interpolated_body(0):
+//             draw_rect(Rect2(cell * 16, Vector2(16, 16)), Color.GREEN)
+//
+// Cells are `Vector2i`s counted from the top left, directions unit vectors
+// (`Vector2i.UP`...). Names of food kinds and death causes are the ones in
+// the state JSON ("Apple", "Wall"...). Pausing the scene tree stops the game
+// too.
+
+use core::time::Duration;
+// The crate is no_std, the code gdext generates expects the std prelude
+use std::format;
+use std::prelude::rust_2021::*;
+
+use godot::classes::{INode, InputEvent, Node};
+use godot::prelude::*;
+
+use crate::{Controller, DeathCause, Direction, Food, FoodKind, Game, GameConfig, GameEvent, InputAction, Level, Point, Replay};
+
+struct SnakeExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for SnakeExtension {}
+
+fn cell(p: Point) -> Vector2i {
+    Vector2i::new(p.x, p.y)
+}
+
+fn cells<'a>(points: impl IntoIterator<Item = &'a Point>) -> Array<Vector2i> {
+    points.into_iter().map(|&p| cell(p)).collect()
+}
+
+fn direction_vector(direction: Direction) -> Vector2i {
+    match direction {
+        Direction::Up => Vector2i::UP,
+        Direction::Down => Vector2i::DOWN,
+        Direction::Left => Vector2i::LEFT,
+        Direction::Right => Vector2i::RIGHT,
+    }
+}
+
+fn kind_name(kind: FoodKind) -> GString {
+    GString::from(&format!("{:?}", kind))
+}
+
+fn cause_name(cause: DeathCause) -> GString {
+    GString::from(match cause {
+        DeathCause::Wall { .. } => "Wall",
+        DeathCause::Obstacle { .. } => "Obstacle",
+        DeathCause::SelfCollision { .. } => "SelfCollision",
+        DeathCause::BoardFull => "BoardFull",
+        DeathCause::TimeExpired => "TimeExpired",
+        DeathCause::Opponent { .. } => "Opponent",
+        DeathCause::Rules => "Rules",
+    })
+}
+
+// `{ position, kind, ttl }`, `ttl` being -1 for food that stays
+fn food_dictionary(food: &Food) -> VarDictionary {
+    let mut dictionary = VarDictionary::new();
+    dictionary.set("position", cell(food.pos));
+    dictionary.set("kind", kind_name(food.kind));
+    dictionary.set("ttl", food.ttl.map_or(-1, |t| t as i64));
+    dictionary
+}
+
+// The exported properties set up the game when the node enters the tree,
+// `new_game` applies changes made later on
+#[derive(GodotClass)]
+#[class(base = Node, rename = SnakeGame)]
+pub struct SnakeNode {
+    #[export]
+    width: i32,
+    #[export]
+    height: i32,
+    // The same seed gives the same food every round, 0 picks one each time
+    #[export]
+    seed: i64,
+    #[export]
+    wrap_walls: bool,
+    // Starts playing right away instead of waiting for ui_accept
+    #[export]
+    autostart: bool,
+    // Off to feed every input through `handle_action` or `turn`
+    #[export]
+    use_ui_actions: bool,
+    game: Game,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for SnakeNode {
+    fn init(base: Base<Node>) -> SnakeNode {
+        let config = GameConfig::default();
+        SnakeNode {
+            width: config.width,
+            height: config.height,
+            seed: 0,
+            wrap_walls: config.wrap_walls,
+            autostart: false,
+            use_ui_actions: true,
+            game: Game::from_config(&config).expect("the default config is valid"),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.new_game();
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.game.advance(Duration::try_from_secs_f64(delta).unwrap_or(Duration::ZERO));
+        self.emit_events();
+    }
+
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        if !self.use_ui_actions {
+            return;
+        }
+        let action = [
+            ("ui_up", InputAction::Turn(Direction::Up)),
+            ("ui_down", InputAction::Turn(Direction::Down)),
+            ("ui_left", InputAction::Turn(Direction::Left)),
+            ("ui_right", InputAction::Turn(Direction::Right)),
+            ("ui_accept", InputAction::Confirm),
+            ("ui_cancel", InputAction::Pause),
+        ]
+        .into_iter()
+        .find(|&(name, _)| event.is_action_pressed(name));
+        if let Some((_, action)) = action {
+            self.game.handle_action(action);
+            self.emit_events();
+            if let Some(mut viewport) = self.base().get_viewport() {
+                viewport.set_input_as_handled();
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl SnakeNode {
+    #[signal]
+    fn started();
+
+    // Seconds left before the snake starts moving, 0 as it starts
+    #[signal]
+    fn countdown(remaining: i64);
+
+    #[signal]
+    fn food_eaten(at: Vector2i, kind: GString, score: i64);
+
+    #[signal]
+    fn power_up_collected(at: Vector2i, kind: GString, duration_ticks: i64);
+
+    // A bonus item or power-up disappeared before being picked up
+    #[signal]
+    fn food_expired(at: Vector2i, kind: GString);
+
+    #[signal]
+    fn level_up(level: i64);
+
+    #[signal]
+    fn turned(direction: Vector2i);
+
+    // The player turned away from a fatal cell at the last moment, going fast
+    #[signal]
+    fn near_miss(at: Vector2i);
+
+    // `message` is the sentence for the game over screen, in English
+    #[signal]
+    fn game_over(cause: GString, message: GString, score: i64);
+
+    // An opponent (`snake` >= 1) ate something, `score` is its new score
+    #[signal]
+    fn snake_ate(snake: i64, at: Vector2i, kind: GString, score: i64);
+
+    // An opponent died, the game goes on
+    #[signal]
+    fn snake_died(snake: i64, cause: GString);
+
+    // A boss came in as snake `snake`, it leaves after `ticks` ticks
+    #[signal]
+    fn boss_appeared(snake: i64, ticks: i64);
+
+    // `trapped` when it crashed before its time was up
+    #[signal]
+    fn boss_left(snake: i64, trapped: bool);
+
+    // The signals go out after the game is done changing, so handlers can
+    // call back into the node
+    fn emit_events(&mut self) {
+        let events: Vec<GameEvent> = self.game.drain_events().collect();
+        for event in events {
+            match event {
+                GameEvent::Started => self.signals().started().emit(),
+                GameEvent::Countdown { remaining } => self.signals().countdown().emit(remaining as i64),
+                GameEvent::FoodEaten { at, kind, score } => {
+                    self.signals().food_eaten().emit(cell(at), &kind_name(kind), score as i64)
+                }
+                GameEvent::PowerUpCollected { at, kind, duration_ticks } => {
+                    self.signals().power_up_collected().emit(cell(at), &kind_name(kind), duration_ticks as i64)
+                }
+                GameEvent::FoodExpired { at, kind } => self.signals().food_expired().emit(cell(at), &kind_name(kind)),
+                GameEvent::LevelUp { level } => self.signals().level_up().emit(level as i64),
+                GameEvent::Turned { direction } => self.signals().turned().emit(direction_vector(direction)),
+                GameEvent::NearMiss { at } => self.signals().near_miss().emit(cell(at)),
+                GameEvent::GameOver { cause, score } => {
+                    let message = GString::from(&cause.to_string());
+                    self.signals().game_over().emit(&cause_name(cause), &message, score as i64)
+                }
+                GameEvent::SnakeAte { snake, at, kind, score } => {
+                    self.signals().snake_ate().emit(snake as i64, cell(at), &kind_name(kind), score as i64)
+                }
+                GameEvent::SnakeDied { snake, cause } => self.signals().snake_died().emit(snake as i64, &cause_name(cause)),
+                GameEvent::BossAppeared { snake, ticks } => self.signals().boss_appeared().emit(snake as i64, ticks as i64),
+                GameEvent::BossLeft { snake, trapped } => self.signals().boss_left().emit(snake as i64, trapped),
+            }
+        }
+    }
+
+    fn set_game(&mut self, mut game: Game) {
+        if self.autostart {
+            game.start_game();
+        }
+        self.game = game;
+        self.emit_events();
+    }
+
+    // A new game from the exported properties. Keeps the current one and
+    // returns false if they don't make a valid board.
+    #[func]
+    fn new_game(&mut self) -> bool {
+        let config = GameConfig {
+            width: self.width,
+            height: self.height,
+            seed: Some(self.seed as u64).filter(|&seed| seed != 0),
+            wrap_walls: self.wrap_walls,
+            ..GameConfig::default()
+        };
+        self.configure_with(&config)
+    }
+
+    // A new game from a `GameConfig` as JSON, the same JSON as the server
+    // and the C API take, for what the properties don't cover (mode, speed,
+    // food...). Returns false if it isn't valid.
+    #[func]
+    fn configure(&mut self, config_json: GString) -> bool {
+        match serde_json::from_str::<GameConfig>(&config_json.to_string()) {
+            Ok(config) => self.configure_with(&config),
+            Err(e) => {
+                godot_error!("SnakeGame: invalid config: {}", e);
+                false
+            }
+        }
+    }
+
+    fn configure_with(&mut self, config: &GameConfig) -> bool {
+        match Game::from_config(config) {
+            Ok(game) => {
+                self.set_game(game);
+                true
+            }
+            Err(e) => {
+                godot_error!("SnakeGame: {}", e);
+                false
+            }
+        }
+    }
+
+    // A new game on a level in the text format of the level editor
+    #[func]
+    fn load_level(&mut self, text: GString) -> bool {
+        match Level::parse(&text.to_string()).and_then(|level| Game::from_level(&level)) {
+            Ok(game) => {
+                self.set_game(game);
+                true
+            }
+            Err(e) => {
+                godot_error!("SnakeGame: {}", e);
+                false
+            }
+        }
+    }
+
+    // Turns the player's snake, `direction` being one of the unit vectors
+    #[func]
+    fn turn(&mut self, direction: Vector2i) {
+        let direction = match (direction.x, direction.y) {
+            (0, -1) => Direction::Up,
+            (0, 1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            (1, 0) => Direction::Right,
+            _ => return,
+        };
+        self.game.handle_action(InputAction::Turn(direction));
+        self.emit_events();
+    }
+
+    // Takes an action by name ("up", "pause", "restart", "boost",
+    // "confirm"...) or key name ("ArrowUp", "w", "p"...). Returns false for
+    // names the game doesn't use.
+    #[func]
+    fn handle_action(&mut self, name: GString) -> bool {
+        match InputAction::from_key(&name.to_string()) {
+            Some(action) => {
+                self.game.handle_action(action);
+                self.emit_events();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[func]
+    fn start(&mut self) {
+        self.game.start_game();
+        self.emit_events();
+    }
+
+    #[func]
+    fn pause(&mut self) {
+        self.game.pause();
+    }
+
+    #[func]
+    fn resume(&mut self) {
+        self.game.resume();
+    }
+
+    // The current (or last finished) round as a URL-safe base64 string
+    #[func]
+    fn export_replay(&self) -> GString {
+        GString::from(&self.game.replay().to_base64())
+    }
+
+    // Plays an exported replay in place of the game, player input is ignored
+    #[func]
+    fn import_replay(&mut self, data: GString) -> bool {
+        match Replay::from_base64(&data.to_string()).and_then(|replay| replay.game()) {
+            Ok(game) => {
+                self.game = game;
+                self.emit_events();
+                true
+            }
+            Err(e) => {
+                godot_error!("SnakeGame: {}", e);
+                false
+            }
+        }
+    }
+
+    // Everything at once, the same JSON as the server sends
+    #[func]
+    fn state_json(&self) -> GString {
+        GString::from(&serde_json::to_string(&self.game.state()).expect("the state serializes to JSON"))
+    }
+
+    #[func]
+    fn board_size(&self) -> Vector2i {
+        Vector2i::new(self.game.width, self.game.height)
+    }
+
+    #[func]
+    fn score(&self) -> i64 {
+        self.game.score as i64
+    }
+
+    #[func]
+    fn is_started(&self) -> bool {
+        self.game.game_started
+    }
+
+    #[func]
+    fn is_paused(&self) -> bool {
+        self.game.paused
+    }
+
+    #[func]
+    fn is_game_over(&self) -> bool {
+        self.game.game_over
+    }
+
+    // Why the game ended as a sentence ("Hit the wall at (20, 4)"), empty
+    // while it goes on
+    #[func]
+    fn death_message(&self) -> GString {
+        self.game.death_cause.map(|c| GString::from(&c.to_string())).unwrap_or_default()
+    }
+
+    #[func]
+    fn tick_count(&self) -> i64 {
+        self.game.tick_count() as i64
+    }
+
+    #[func]
+    fn speed_level(&self) -> i64 {
+        self.game.speed_level() as i64
+    }
+
+    // The number to show before the snake starts moving, 0 after
+    #[func]
+    fn countdown_left(&self) -> i64 {
+        self.game.countdown().unwrap_or(0) as i64
+    }
+
+    // Seconds of game time left in timed mode, -1 otherwise
+    #[func]
+    fn time_left(&self) -> f64 {
+        self.game.time_left().map_or(-1.0, |t| t.as_secs_f64())
+    }
+
+    // 0.0 right after a tick up to 1.0 just before the next one
+    #[func]
+    fn tick_progress(&self) -> f64 {
+        self.game.tick_progress() as f64
+    }
+
+    #[func]
+    fn direction(&self) -> Vector2i {
+        direction_vector(self.game.snake.direction)
+    }
+
+    // Snake 0 is the player's, opponents are numbered from 1
+    #[func]
+    fn snake_count(&self) -> i64 {
+        self.game.snake_count() as i64
+    }
+
+    // Head first, empty for dead opponents
+    #[func]
+    fn body(&self, snake: i64) -> Array<Vector2i> {
+        cells(self.game.snake_body(snake as usize))
+    }
+
+    // Fractional cells part way between the last two ticks, for smooth
+    // movement
+    #[func]
+    fn interpolated_body(&self, snake: i64) -> PackedVector2Array {
+        self.game.interpolated_body(snake as usize).into_iter().map(|(x, y)| Vector2::new(x, y)).collect()
+    }
+
+    // How many segments of a snake to draw, fewer while the death
+    // animation plays
+    #[func]
+    fn visible_segments(&self, snake: i64) -> i64 {
+        self.game.visible_segments(snake as usize) as i64
+    }
+
+    #[func]
+    fn snake_score(&self, snake: i64) -> i64 {
+        self.game.snake_score(snake as usize) as i64
+    }
+
+    #[func]
+    fn is_snake_alive(&self, snake: i64) -> bool {
+        self.game.snake_alive(snake as usize)
+    }
+
+    // Whether the snake's player is away, see `afkTicks` in the `configure` JSON
+    #[func]
+    fn is_snake_afk(&self, snake: i64) -> bool {
+        self.game.is_afk(snake as usize)
+    }
+
+    // Adds an AI opponent wherever there's room, returns its index or -1
+    #[func]
+    fn add_opponent(&mut self) -> i64 {
+        match self.game.add_opponent_auto(Some(Controller::Pathfinder)) {
+            Ok(index) => index as i64,
+            Err(e) => {
+                godot_error!("SnakeGame: {}", e);
+                -1
+            }
+        }
+    }
+
+    // `{ position, kind, ttl }` for each, `ttl` being -1 for food that stays
+    #[func]
+    fn foods(&self) -> Array<VarDictionary> {
+        self.game.foods.iter().map(food_dictionary).collect()
+    }
+
+    // Level walls
+    #[func]
+    fn obstacles(&self) -> Array<Vector2i> {
+        cells(&self.game.level.walls)
+    }
+
+    // Linked pairs, one after the other
+    #[func]
+    fn portals(&self) -> Array<Vector2i> {
+        self.game.level.portals.iter().flat_map(|&(a, b)| [cell(a), cell(b)]).collect()
+    }
+}
@@ -0,0 +1,170 @@
+// src/grpc.rs
+
+// The gRPC API of snake-server (proto/snake_remote.proto), only with the
+// `grpc` feature:
+//
+//     cargo run --bin snake-server --features grpc -- --port 8080 --grpc-port 50051
+//
+// It serves the same games as the REST API, on its own port, from a tokio
+// runtime on a thread of its own. The games stay on the server's clock and
+// in the same table, so a bot can create a game over gRPC while a web
+// page watches it over Server-Sent Events. Errors are statuses with the
+// REST error code in their `error-code` metadata.
+
+use std::error::Error;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+
+use snake_game::{proto, Controller, GameConfig, GameError, GameState, InputAction};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Request, Response, Status};
+
+use super::{
+    add_game, api_error, apply_input, build_game, key_action, lock_session, too_many_streams, ApiError, Games, Slot, MAX_STREAMS,
+};
+
+mod remote {
+    include!(concat!(env!("OUT_DIR"), "/snake_remote.rs"));
+}
+
+use remote::remote_play_server::{RemotePlay, RemotePlayServer};
+use remote::{MatchRequest, MatchResult, SendInputRequest, StreamStateRequest};
+
+// RunMatch stops games still going after this many ticks, whatever the
+// request asks for. A pathfinder on a full-size board takes a while per
+// tick, this keeps a match to seconds.
+const MAX_MATCH_TICKS: u64 = 50_000;
+const DEFAULT_MATCH_TICKS: u64 = 10_000;
+// Matches played at once, each keeps a core busy. More get turned away.
+const MAX_MATCHES: usize = 4;
+
+fn status(e: ApiError) -> Status {
+    let code = match e.error {
+        "NOT_FOUND" => Code::NotFound,
+        "TOO_MANY_GAMES" | "TOO_MANY_STREAMS" | "TOO_MANY_MATCHES" => Code::ResourceExhausted,
+        _ => Code::InvalidArgument,
+    };
+    let mut status = Status::new(code, e.message);
+    status.metadata_mut().insert("error-code", MetadataValue::from_static(e.error));
+    status
+}
+
+// A message that doesn't convert to the core type
+fn invalid(e: GameError) -> Status {
+    status(api_error(e.code(), e))
+}
+
+struct Service {
+    games: Arc<Games>,
+    matches: Arc<Semaphore>, // See `MAX_MATCHES`
+}
+
+// Sends the state every time it changes. The clock signals frames with a
+// Condvar, so this runs on a thread rather than in the runtime.
+fn watch(games: &Games, id: u64, sender: mpsc::Sender<Result<proto::GameState, Status>>) {
+    let mut last_state: Option<GameState> = None;
+    loop {
+        let state = match games.next_frame(id) {
+            Some(session) => lock_session(&session).game.state(),
+            None => return,
+        };
+        if last_state.as_ref() == Some(&state) {
+            if sender.is_closed() {
+                return;
+            }
+            continue;
+        }
+        if sender.blocking_send(Ok(state.clone().into())).is_err() {
+            return;
+        }
+        last_state = Some(state);
+    }
+}
+
+// Plays the way `simulate` does, keeping the game for the replay
+fn play_match(config: &GameConfig, controller: Controller, max_ticks: u64) -> Result<MatchResult, ApiError> {
+    let mut game = build_game(config)?;
+    game.autopilot = Some(controller);
+    game.start_game();
+    while !game.game_over && game.tick_count() < max_ticks {
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    Ok(MatchResult { seed: game.round_seed(), state: Some(game.state().into()), replay: game.replay().to_base64() })
+}
+
+#[tonic::async_trait]
+impl RemotePlay for Service {
+    async fn create_game(&self, request: Request<proto::GameConfig>) -> Result<Response<proto::Created>, Status> {
+        let config = GameConfig::try_from(request.into_inner()).map_err(invalid)?;
+        let (id, state) = add_game(&self.games, &config).map_err(status)?;
+        Ok(Response::new(proto::Created { id, state: Some(state.into()) }))
+    }
+
+    type StreamStateStream = ReceiverStream<Result<proto::GameState, Status>>;
+
+    async fn stream_state(&self, request: Request<StreamStateRequest>) -> Result<Response<Self::StreamStateStream>, Status> {
+        let id = request.into_inner().game_id;
+        if self.games.session(id).is_none() {
+            return Err(status(api_error("NOT_FOUND", format!("no game {}", id))));
+        }
+        let slot = Slot::take(&self.games.streams, MAX_STREAMS).ok_or_else(|| status(too_many_streams()))?;
+        let (sender, receiver) = mpsc::channel(4);
+        let games = Arc::clone(&self.games);
+        thread::spawn(move || {
+            watch(&games, id, sender);
+            drop(slot);
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn send_input(&self, request: Request<SendInputRequest>) -> Result<Response<proto::GameState>, Status> {
+        let SendInputRequest { game_id, input } = request.into_inner();
+        let action = match input {
+            Some(proto::Input { input: Some(proto::input::Input::Key(key)) }) => key_action(&key).map_err(status)?,
+            input => InputAction::try_from(input.unwrap_or_default()).map_err(invalid)?,
+        };
+        let state = apply_input(&self.games, game_id, action).map_err(status)?;
+        Ok(Response::new(state.into()))
+    }
+
+    async fn run_match(&self, request: Request<MatchRequest>) -> Result<Response<MatchResult>, Status> {
+        let MatchRequest { config, controller, max_ticks } = request.into_inner();
+        let config = GameConfig::try_from(config.unwrap_or_default()).map_err(invalid)?;
+        let controller = match proto::Controller::try_from(controller) {
+            Ok(proto::Controller::Unspecified) => Controller::Pathfinder,
+            Ok(controller) => Controller::try_from(controller).map_err(invalid)?,
+            Err(_) => return Err(invalid(GameError::InvalidMessage(format!("unknown controller {}", controller)))),
+        };
+        let max_ticks = max_ticks.unwrap_or(DEFAULT_MATCH_TICKS).min(MAX_MATCH_TICKS);
+        let permit = Arc::clone(&self.matches)
+            .try_acquire_owned()
+            .map_err(|_| status(api_error("TOO_MANY_MATCHES", "too many matches running, try again later")))?;
+        // A long game keeps a core busy, away from the runtime's threads
+        let result = tokio::task::spawn_blocking(move || {
+            let result = play_match(&config, controller, max_ticks);
+            drop(permit);
+            result
+        })
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        result.map(Response::new).map_err(status)
+    }
+}
+
+// Runs until the listener fails
+pub fn serve(games: Arc<Games>, listener: TcpListener) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    runtime.block_on(async {
+        listener.set_nonblocking(true)?;
+        let incoming = TcpListenerStream::new(tokio::net::TcpListener::from_std(listener)?);
+        tonic::transport::Server::builder()
+            .add_service(RemotePlayServer::new(Service { games, matches: Arc::new(Semaphore::new(MAX_MATCHES)) }))
+            .serve_with_incoming(incoming)
+            .await?;
+        Ok(())
+    })
+}
@@ -0,0 +1,59 @@
+// src/heatmap.rs
+
+use crate::{Game, Point};
+
+// Where the player's head has been this round, one count per cell and tick,
+// for heatmaps on the game over screen or over a replay. Only kept with
+// `Game::track_visits` set (games built by `Replay::game` have it), a
+// simulator running millions of games has no use for it.
+//
+// The counts start over with each round. Restoring a snapshot doesn't
+// rewind them, they keep counting from the branch.
+impl Game {
+    // Ticks the head spent on each cell in row-major order (left to right,
+    // top to bottom), the spawn counting as one. Empty when visits aren't
+    // tracked, setting `track_visits` takes effect from the next round.
+    pub fn visits(&self) -> &[u32] {
+        &self.visits
+    }
+
+    // Ticks the head spent on `p`, 0 off the board or when not tracked
+    pub fn visit_count(&self, p: Point) -> u32 {
+        self.visit_index(p).map_or(0, |i| self.visits[i])
+    }
+
+    // The highest count, for scaling the colors
+    pub fn max_visits(&self) -> u32 {
+        self.visits.iter().copied().max().unwrap_or(0)
+    }
+
+    pub(crate) fn reset_visits(&mut self) {
+        self.visits.clear();
+        if self.track_visits {
+            self.visits.resize((self.width * self.height) as usize, 0);
+            if let Some(&head) = self.snake.body.front() {
+                self.count_visit(head);
+            }
+        }
+    }
+
+    pub(crate) fn count_visit(&mut self, p: Point) {
+        if let Some(i) = self.visit_index(p) {
+            self.visits[i] = self.visits[i].saturating_add(1);
+        }
+    }
+
+    // A late turn took the head somewhere else after all
+    pub(crate) fn uncount_visit(&mut self, p: Point) {
+        if let Some(i) = self.visit_index(p) {
+            self.visits[i] = self.visits[i].saturating_sub(1);
+        }
+    }
+
+    // Where `p` is in `visits`, `None` off the board or when not tracked
+    fn visit_index(&self, p: Point) -> Option<usize> {
+        let on_board = p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height;
+        let i = (p.y * self.width + p.x) as usize;
+        (on_board && i < self.visits.len()).then_some(i)
+    }
+}
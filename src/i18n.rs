@@ -0,0 +1,336 @@
+// src/i18n.rs
+
+// Text shown to players, as one key -> string table per language. Strings
+// may have `{name}` placeholders, filled in by `Language::format`. A key
+// missing from a language falls back to English, and to the key itself if
+// English doesn't have it either, so a typo shows up on screen instead of
+// blank text.
+
+use alloc::string::String;
+use core::fmt::{self, Write};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Portuguese, // Brazilian
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Portuguese];
+
+    // Each language's name in that language, for the language picker
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Portuguese => "Português",
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => EN,
+            Language::Portuguese => PT_BR,
+        }
+    }
+
+    pub fn text(self, key: &str) -> &str {
+        lookup(self.table(), key).or_else(|| lookup(EN, key)).unwrap_or(key)
+    }
+
+    // The text with its placeholders filled in. Placeholders without a
+    // matching argument are left as they are.
+    pub fn format(self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut text = String::new();
+        let mut rest = self.text(key);
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else { break };
+            text.push_str(&rest[..start]);
+            let name = &rest[start + 1..start + len];
+            match args.iter().find(|(arg, _)| *arg == name) {
+                // Writing to a String can't fail
+                Some((_, value)) => {
+                    let _ = write!(text, "{}", value);
+                }
+                None => text.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+        text
+    }
+}
+
+fn lookup(table: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+}
+
+const EN: &[(&str, &str)] = &[
+    // Menu
+    ("menu-title", "Snake"),
+    ("menu-resume", "Resume"),
+    ("menu-play", "Play"),
+    ("menu-tutorial", "Tutorial"),
+    ("menu-mode", "Mode: < {mode} >"),
+    ("attract-prompt", "Press any key"),
+    ("menu-settings", "Settings"),
+    ("menu-high-scores", "High scores"),
+    ("menu-replays", "Replays"),
+    ("menu-quit", "Quit"),
+    ("mode-classic", "Classic"),
+    ("mode-timed", "Timed {secs}s"),
+    // Settings
+    ("settings-title", "Settings"),
+    ("setting-board", "Board: < {width}x{height} >"),
+    ("setting-speed", "Speed: < {value} >"),
+    ("setting-input-grace", "Late turn grace: < {value} >"),
+    ("speed-slow", "Slow"),
+    ("speed-normal", "Normal"),
+    ("speed-fast", "Fast"),
+    ("speed-custom", "Custom"),
+    ("setting-wrap-walls", "Wrap walls: < {value} >"),
+    ("setting-players", "Players: < {value} >"),
+    ("setting-sound", "Sound: < {value} >"),
+    ("setting-effects-volume", "Effects: < {value}% >"),
+    ("setting-music-volume", "Music: < {value}% >"),
+    ("setting-theme", "Theme: < {value} >"),
+    ("theme-classic", "Classic"),
+    ("theme-neon", "Neon"),
+    ("theme-colorblind-safe", "Colorblind safe"),
+    ("setting-pattern", "Background: < {value} >"),
+    ("setting-skin", "Skin: < {value} >"),
+    ("skin-classic", "Classic"),
+    ("skin-forest", "Forest"),
+    ("skin-coral", "Coral"),
+    ("skin-ocean", "Ocean"),
+    ("skin-tiger", "Tiger"),
+    ("skin-rainbow", "Rainbow"),
+    ("skin-unlocked", "New skin: {skin}!"),
+    ("pattern-plain", "Plain"),
+    ("pattern-grid", "Grid"),
+    ("pattern-checkerboard", "Checkerboard"),
+    ("setting-screen-effects", "Screen effects: < {value} >"),
+    ("setting-effect-intensity", "Effect strength: < {value}% >"),
+    ("setting-near-miss", "Near-miss slow motion: < {value} >"),
+    ("setting-reduced-motion", "Reduced motion: < {value} >"),
+    ("setting-minimap", "Minimap: < {value} >"),
+    ("setting-bosses", "Bosses: < {value} >"),
+    ("setting-language", "Language: < {value} >"),
+    ("setting-controls", "Controls"),
+    ("on", "on"),
+    ("off", "off"),
+    ("back", "Back"),
+    // Controls
+    ("controls-title", "Controls"),
+    ("press-a-key", "press a key..."),
+    ("reset-defaults", "Reset to defaults"),
+    ("action-up", "Up"),
+    ("action-down", "Down"),
+    ("action-left", "Left"),
+    ("action-right", "Right"),
+    ("action-pause", "Pause"),
+    ("action-restart", "Restart"),
+    ("action-boost", "Boost"),
+    ("action-confirm", "Confirm"),
+    // High scores and replays
+    ("high-scores-title", "High scores"),
+    ("no-scores", "No scores yet"),
+    ("name-entry-title", "New high score!"),
+    ("name-entry-score", "Score: {score}"),
+    ("name-entry-name", "Name: {name}_"),
+    ("replays-title", "Replays"),
+    ("no-replays", "No replays yet"),
+    ("replay-last-game", "Last game  {score} pts (E to save)"),
+    ("replay-saved", "{date}  {score} pts"),
+    // Terminal version
+    ("setting-opponent", "Opponent: < {value} >"),
+    ("opponent-none", "none"),
+    ("opponent-greedy", "greedy AI"),
+    ("opponent-pathfinder", "pathfinder AI"),
+    ("opponent-chaser", "chaser AI"),
+    ("terminal-too-small", "Make the terminal at least {width}x{height}"),
+    // Hints at the bottom of the pages
+    ("hint-menu", "Arrows to move, Enter to pick"),
+    ("hint-settings", "Left/right to change, Esc to go back"),
+    ("hint-binding", "Press the new key, Esc to cancel"),
+    ("hint-controls", "Enter to add a key, Delete to clear"),
+    ("hint-name-entry", "Type your name, Enter to save"),
+    ("hint-replays", "Enter to watch, Delete to remove"),
+    ("hint-back", "Esc to go back"),
+    // In game
+    ("hud-score", "Score {score}  Best {best}"),
+    ("hud-player-2", "P2 {score}"),
+    ("hud-speed", "Length {length}  Lv {level} x{multiplier}"),
+    ("hud-time-left", "{time} left"),
+    ("hud-slow", "Slow {secs}s"),
+    ("hud-boost", "Boost"),
+    ("hud-afk", "P{player} away"),
+    ("paused-message", "Paused\nP to resume, Q for menu"),
+    ("game-over", "Game Over"),
+    ("game-over-message", "{headline}!\nScore: {score}\n{stats}\nSPACE to restart, Esc for menu"),
+    ("game-over-stats", "{apples} apples, {turns} turns, {near_misses} close calls"),
+    ("save-replay-hint", "E to save the replay"),
+    ("heatmap-hint", "H to see where you went"),
+    ("confirm-leave", "Leave this game?\nIt stays paused for later\nEnter to leave, Esc to stay"),
+    ("confirm-quit", "Quit in the middle of a game?\nIt's saved for next time\nEnter to quit, Esc to stay"),
+    ("replay-finished", "Replay finished\nEsc to go back"),
+    ("playback-paused", "Paused"),
+    ("playback-playing", "Playing"),
+    ("playback-status", "{state} {speed}x  tick {position}/{length}"),
+    ("playback-keys", "Space pause, Left/Right seek, ,/. step, Up/Down speed, X export, H heatmap, A analysis"),
+    ("analysis-summary", "{risky} risky moves, {missed} longer ways to food (+{lost} ticks)"),
+    // Death causes, see `DeathCause::message_key`
+    ("death-wall", "Hit the wall at ({x}, {y})"),
+    ("death-obstacle", "Hit an obstacle at ({x}, {y})"),
+    ("death-self", "Bit itself at segment {segment}"),
+    ("death-board-full", "Filled the whole board"),
+    ("death-time-up", "Time's up"),
+    ("death-opponent", "Ran into opponent {snake} at ({x}, {y})"),
+    ("death-rules", "Ended by the custom rules"),
+    // Tutorial prompts, see src/tutorial.json
+    ("tutorial-turn-up", "Step {step}: turn up with the Up arrow or W"),
+    ("tutorial-turn-left", "Step {step}: now turn left with the Left arrow or A"),
+    ("tutorial-eat", "Step {step}: eat the apples to grow ({done}/{target})"),
+    ("tutorial-avoid-wall", "Step {step}: steer clear of the wall ({done}/{target})"),
+    ("tutorial-done", "You're ready! SPACE to play, Esc for menu"),
+    ("hud-chat-votes", "Chat: up {up}, down {down}, left {left}, right {right}"),
+    ("hud-chat-offline", "Chat offline"),
+    ("hud-boss", "Boss leaves in {ticks}"),
+    ("presence-menu", "In the menus"),
+    ("presence-score", "Score {score}"),
+    ("presence-paused", "Paused, score {score}"),
+    ("presence-game-over", "Game over, score {score}"),
+    ("presence-tutorial", "Learning to play"),
+    ("presence-watching", "Watching a replay"),
+];
+
+const PT_BR: &[(&str, &str)] = &[
+    ("menu-title", "Cobrinha"),
+    ("menu-resume", "Continuar"),
+    ("menu-play", "Jogar"),
+    ("menu-tutorial", "Tutorial"),
+    ("menu-mode", "Modo: < {mode} >"),
+    ("attract-prompt", "Aperte qualquer tecla"),
+    ("menu-settings", "Configurações"),
+    ("menu-high-scores", "Recordes"),
+    ("menu-replays", "Replays"),
+    ("menu-quit", "Sair"),
+    ("mode-classic", "Clássico"),
+    ("mode-timed", "Contra o tempo {secs}s"),
+    ("settings-title", "Configurações"),
+    ("setting-board", "Tabuleiro: < {width}x{height} >"),
+    ("setting-speed", "Velocidade: < {value} >"),
+    ("setting-input-grace", "Tolerância para curvas atrasadas: < {value} >"),
+    ("speed-slow", "Lenta"),
+    ("speed-normal", "Normal"),
+    ("speed-fast", "Rápida"),
+    ("speed-custom", "Personalizada"),
+    ("setting-wrap-walls", "Atravessar bordas: < {value} >"),
+    ("setting-players", "Jogadores: < {value} >"),
+    ("setting-sound", "Som: < {value} >"),
+    ("setting-effects-volume", "Efeitos: < {value}% >"),
+    ("setting-music-volume", "Música: < {value}% >"),
+    ("setting-theme", "Tema: < {value} >"),
+    ("theme-classic", "Clássico"),
+    ("theme-neon", "Neon"),
+    ("theme-colorblind-safe", "Para daltônicos"),
+    ("setting-pattern", "Fundo: < {value} >"),
+    ("setting-skin", "Visual: < {value} >"),
+    ("skin-classic", "Clássico"),
+    ("skin-forest", "Floresta"),
+    ("skin-coral", "Coral"),
+    ("skin-ocean", "Oceano"),
+    ("skin-tiger", "Tigre"),
+    ("skin-rainbow", "Arco-íris"),
+    ("skin-unlocked", "Novo visual: {skin}!"),
+    ("pattern-plain", "Liso"),
+    ("pattern-grid", "Grade"),
+    ("pattern-checkerboard", "Xadrez"),
+    ("setting-screen-effects", "Efeitos de tela: < {value} >"),
+    ("setting-effect-intensity", "Intensidade dos efeitos: < {value}% >"),
+    ("setting-near-miss", "Câmera lenta em quase batidas: < {value} >"),
+    ("setting-reduced-motion", "Movimento reduzido: < {value} >"),
+    ("setting-minimap", "Minimapa: < {value} >"),
+    ("setting-bosses", "Chefões: < {value} >"),
+    ("setting-language", "Idioma: < {value} >"),
+    ("setting-controls", "Controles"),
+    ("on", "sim"),
+    ("off", "não"),
+    ("back", "Voltar"),
+    ("controls-title", "Controles"),
+    ("press-a-key", "aperte uma tecla..."),
+    ("reset-defaults", "Restaurar padrões"),
+    ("action-up", "Cima"),
+    ("action-down", "Baixo"),
+    ("action-left", "Esquerda"),
+    ("action-right", "Direita"),
+    ("action-pause", "Pausar"),
+    ("action-restart", "Reiniciar"),
+    ("action-boost", "Acelerar"),
+    ("action-confirm", "Confirmar"),
+    ("high-scores-title", "Recordes"),
+    ("no-scores", "Nenhum recorde ainda"),
+    ("name-entry-title", "Novo recorde!"),
+    ("name-entry-score", "Pontos: {score}"),
+    ("name-entry-name", "Nome: {name}_"),
+    ("replays-title", "Replays"),
+    ("no-replays", "Nenhum replay ainda"),
+    ("replay-last-game", "Último jogo  {score} pts (E para salvar)"),
+    ("replay-saved", "{date}  {score} pts"),
+    ("setting-opponent", "Oponente: < {value} >"),
+    ("opponent-none", "nenhum"),
+    ("opponent-greedy", "IA gulosa"),
+    ("opponent-pathfinder", "IA que busca caminho"),
+    ("opponent-chaser", "IA caçadora"),
+    ("terminal-too-small", "Aumente o terminal para pelo menos {width}x{height}"),
+    ("hint-menu", "Setas para mover, Enter para escolher"),
+    ("hint-settings", "Esquerda/direita para mudar, Esc para voltar"),
+    ("hint-binding", "Aperte a nova tecla, Esc para cancelar"),
+    ("hint-controls", "Enter adiciona uma tecla, Delete limpa"),
+    ("hint-name-entry", "Digite seu nome, Enter para salvar"),
+    ("hint-replays", "Enter para assistir, Delete para apagar"),
+    ("hint-back", "Esc para voltar"),
+    ("hud-score", "Pontos {score}  Recorde {best}"),
+    ("hud-player-2", "J2 {score}"),
+    ("hud-speed", "Tamanho {length}  Nv {level} x{multiplier}"),
+    ("hud-time-left", "faltam {time}"),
+    ("hud-slow", "Lento {secs}s"),
+    ("hud-boost", "Turbo"),
+    ("hud-afk", "J{player} ausente"),
+    ("paused-message", "Pausado\nP para continuar, Q para o menu"),
+    ("game-over", "Fim de jogo"),
+    ("game-over-message", "{headline}!\nPontos: {score}\n{stats}\nESPAÇO para recomeçar, Esc para o menu"),
+    ("game-over-stats", "{apples} maçãs, {turns} curvas, {near_misses} quase batidas"),
+    ("save-replay-hint", "E para salvar o replay"),
+    ("heatmap-hint", "H para ver por onde você passou"),
+    ("confirm-leave", "Sair desta partida?\nEla fica pausada para depois\nEnter para sair, Esc para ficar"),
+    ("confirm-quit", "Fechar no meio de uma partida?\nEla fica salva para a próxima vez\nEnter para fechar, Esc para ficar"),
+    ("replay-finished", "Fim do replay\nEsc para voltar"),
+    ("playback-paused", "Pausado"),
+    ("playback-playing", "Reproduzindo"),
+    ("playback-status", "{state} {speed}x  tick {position}/{length}"),
+    ("playback-keys", "Espaço pausa, Esquerda/Direita avança, ,/. passo, Cima/Baixo velocidade, X exporta, H mapa de calor, A análise"),
+    ("analysis-summary", "{risky} movimentos arriscados, {missed} caminhos mais longos até a comida (+{lost} ticks)"),
+    ("death-wall", "Bateu na parede em ({x}, {y})"),
+    ("death-obstacle", "Bateu num obstáculo em ({x}, {y})"),
+    ("death-self", "Mordeu o próprio corpo no segmento {segment}"),
+    ("death-board-full", "Encheu o tabuleiro inteiro"),
+    ("death-time-up", "Acabou o tempo"),
+    ("death-opponent", "Bateu no oponente {snake} em ({x}, {y})"),
+    ("death-rules", "Encerrado pelas regras personalizadas"),
+    ("tutorial-turn-up", "Passo {step}: vire para cima com a seta para cima ou W"),
+    ("tutorial-turn-left", "Passo {step}: agora vire à esquerda com a seta para a esquerda ou A"),
+    ("tutorial-eat", "Passo {step}: coma as maçãs para crescer ({done}/{target})"),
+    ("tutorial-avoid-wall", "Passo {step}: desvie da parede ({done}/{target})"),
+    ("tutorial-done", "Pronto! ESPAÇO para jogar, Esc para o menu"),
+    ("hud-chat-votes", "Chat: cima {up}, baixo {down}, esquerda {left}, direita {right}"),
+    ("hud-chat-offline", "Chat desconectado"),
+    ("hud-boss", "O chefão sai em {ticks}"),
+    ("presence-menu", "Nos menus"),
+    ("presence-score", "Pontos: {score}"),
+    ("presence-paused", "Pausado, pontos: {score}"),
+    ("presence-game-over", "Fim de jogo, pontos: {score}"),
+    ("presence-tutorial", "Aprendendo a jogar"),
+    ("presence-watching", "Assistindo a um replay"),
+];
@@ -1,11 +1,11 @@
 // src/index.js
 
 // Import the wasm module and the structs we exposed
-import init, { Game, Direction } from '../pkg/snake_game.js';
+import init, { Game, FoodKind } from '../pkg/snake_game.js';
 
 async function run() {
     // Initialize the WebAssembly module
-    const wasm = await init();
+    await init();
 
     const GRID_SIZE = 20; // 20x20 grid
     const PIXEL_SCALE = 20; // Each grid cell will be 20x20 pixels
@@ -20,71 +20,106 @@ async function run() {
     // Note the `new` keyword, as we defined a constructor in wasm_bindgen
     const game = new Game(GRID_SIZE, GRID_SIZE);
 
-    // Map key codes to our Rust enum directions
+    // Follow the system's reduced motion setting, also when it changes
+    const reducedMotion = window.matchMedia('(prefers-reduced-motion: reduce)');
+    game.set_reduced_motion(reducedMotion.matches);
+    reducedMotion.addEventListener('change', () => game.set_reduced_motion(reducedMotion.matches));
+
+    // Key names are mapped to actions in Rust (arrows, WASD, space, P...)
     document.addEventListener('keydown', (event) => {
-        switch (event.key) {
-            case 'ArrowUp':
-                game.change_snake_direction(Direction.Up);
-                break;
-            case 'ArrowDown':
-                game.change_snake_direction(Direction.Down);
-                break;
-            case 'ArrowLeft':
-                game.change_snake_direction(Direction.Left);
-                break;
-            case 'ArrowRight':
-                game.change_snake_direction(Direction.Right);
-                break;
-            case ' ': // Spacebar
-                if (!game.game_started() || game.game_over()) {
-                    game.start_game();
-                }
-                break;
+        if (game.queue_input_str(event.key)) {
+            event.preventDefault();
+        }
+    });
+
+    // Swipes on the canvas steer the snake, a tap starts/resumes the game
+    canvas.addEventListener('touchstart', (event) => {
+        const touch = event.changedTouches[0];
+        game.touch_start(touch.clientX, touch.clientY);
+        event.preventDefault();
+    }, { passive: false });
+    canvas.addEventListener('touchmove', (event) => {
+        const touch = event.changedTouches[0];
+        game.touch_move(touch.clientX, touch.clientY);
+        event.preventDefault();
+    }, { passive: false });
+    canvas.addEventListener('touchend', (event) => {
+        const touch = event.changedTouches[0];
+        if (!game.touch_end(touch.clientX, touch.clientY) && (!game.game_started() || game.game_over() || game.paused())) {
+            game.queue_input_str('Enter');
         }
     });
+    canvas.addEventListener('touchcancel', () => game.touch_cancel());
 
     function draw() {
+        // Colors come from the Rust side so every frontend looks the same
+        const theme = game.theme();
+
         // Clear the canvas
-        ctx.clearRect(0, 0, canvas.width, canvas.height);
+        ctx.fillStyle = theme.background;
+        ctx.fillRect(0, 0, canvas.width, canvas.height);
+
+        // Draw the walls and portals, both come as flat Int32Arrays
+        const obstacles = game.obstacles_flat();
+        ctx.fillStyle = theme.obstacle;
+        for (let i = 0; i < obstacles.length; i += 2) {
+            ctx.fillRect(obstacles[i] * PIXEL_SCALE, obstacles[i + 1] * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
+        }
 
-        // Draw the food
-        // The `food()` getter returns a copy of the Point struct
-        const food = game.food();
-        ctx.fillStyle = 'red';
-        ctx.fillRect(food.x * PIXEL_SCALE, food.y * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
+        const portals = game.portals_flat();
+        ctx.fillStyle = theme.portal;
+        for (let i = 0; i < portals.length; i += 2) {
+            ctx.fillRect(portals[i] * PIXEL_SCALE, portals[i + 1] * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
+        }
+
+        // Draw the food, [x, y, kind, ttl] per item
+        const FOOD_COLORS = {
+            [FoodKind.Apple]: theme.apple,
+            [FoodKind.Bonus]: theme.bonus,
+            [FoodKind.SlowDown]: theme.slowDown,
+        };
+        const foods = game.foods_flat();
+        for (let i = 0; i < foods.length; i += 4) {
+            ctx.fillStyle = FOOD_COLORS[foods[i + 2]];
+            ctx.fillRect(foods[i] * PIXEL_SCALE, foods[i + 1] * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
+        }
 
         // Draw the snake
-        // To read the snake's body, we get the pointer and length, THEN the memory
-        const snakeBodyPtr = game.get_body_ptr();
-        const snakeBodyLen = game.get_body_len();
-        const wasmMemory = new Uint8Array(wasm.memory.buffer);
-        
-        // Each Point (x, y) has 2 * i32 = 8 bytes.
-        const snakeCells = new Int32Array(wasmMemory.buffer, snakeBodyPtr, snakeBodyLen * 2);
-
-        ctx.fillStyle = 'lime';
+        // The body comes as a copied Int32Array of interleaved x/y pairs
+        const snakeCells = game.body_coords();
+        const snakeBodyLen = snakeCells.length / 2;
+
+        // Below 1 while the snake fades out on death with reduced motion
+        ctx.globalAlpha = game.snake_opacity(0);
         for (let i = 0; i < snakeBodyLen; i++) {
+            ctx.fillStyle = game.segment_color(i);
             const x = snakeCells[i * 2];
             const y = snakeCells[i * 2 + 1];
             ctx.fillRect(x * PIXEL_SCALE, y * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
         }
+        ctx.globalAlpha = 1;
 
         // Draw score
-        ctx.fillStyle = 'white';
+        ctx.fillStyle = theme.text;
         ctx.font = '16px Arial';
         ctx.textAlign = 'right'; // Align text to the right
         ctx.fillText(`Score: ${game.score()}`, canvas.width - 10, 25); // Position at top-right
 
-        // Draw start/game over message
-        if (!game.game_started() || game.game_over()) {
+        // Draw start/game over/pause message, or the countdown
+        const countdown = game.countdown();
+        if (!game.game_started() || game.game_over() || game.paused() || countdown !== undefined) {
             let message;
-            if (!game.game_started()) {
+            if (countdown !== undefined) {
+                message = `${countdown}`;
+            } else if (!game.game_started()) {
                 message = "Press SPACE to Start";
+            } else if (game.paused()) {
+                message = "Paused\nPress P to Resume";
             } else {
-                message = `Game Over! Score: ${game.score()}\nPress SPACE to Restart`;
+                message = `${game.death_message() ?? 'Game Over'}!\nScore: ${game.score()}\nPress SPACE to Restart`;
             }
 
-            ctx.fillStyle = 'white';
+            ctx.fillStyle = theme.text;
             ctx.font = '30px Arial';
             ctx.textAlign = 'center';
             ctx.textBaseline = 'middle';
@@ -99,18 +134,21 @@ async function run() {
         }
     }
 
-    function gameLoop() {
-        // Only tick if the game is started and not over
-        if (game.game_started() && !game.game_over()) {
-            game.tick();
+    let lastFrame = null;
+
+    function gameLoop(timestamp) {
+        // The Rust clock runs as many ticks as fit in the elapsed time,
+        // so the speed doesn't depend on the display refresh rate
+        if (lastFrame !== null) {
+            game.advance(timestamp - lastFrame);
         }
+        lastFrame = timestamp;
+
         // Draw the new state
         draw();
 
         // Call the next frame
-        setTimeout(() => {
-            requestAnimationFrame(gameLoop);
-        }, 1000 / 10); // Controls game speed (10 fps)
+        requestAnimationFrame(gameLoop);
     }
 
     // Start the game loop
@@ -0,0 +1,78 @@
+// src/input.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::Direction;
+
+// Everything a player can ask the game to do. Frontends only decide which
+// key/button maps to which action, what the action means lives in the core.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputAction {
+    // Steer the snake
+    Turn(Direction),
+    // Toggle pause on a running game
+    Pause,
+    // Start over right away, even in the middle of a game
+    Restart,
+    // Toggle double speed
+    Boost,
+    // Start a new game from the title/game over screen, or resume when paused
+    Confirm,
+}
+
+impl InputAction {
+    // Maps common key names (`KeyboardEvent.key` values, WASD, plain words)
+    // to actions, ignoring case. Every frontend that receives keys as strings
+    // shares this table instead of writing its own.
+    pub fn from_key(key: &str) -> Option<InputAction> {
+        const KEYS: &[(&str, InputAction)] = &[
+            ("arrowup", InputAction::Turn(Direction::Up)),
+            ("w", InputAction::Turn(Direction::Up)),
+            ("up", InputAction::Turn(Direction::Up)),
+            ("arrowdown", InputAction::Turn(Direction::Down)),
+            ("s", InputAction::Turn(Direction::Down)),
+            ("down", InputAction::Turn(Direction::Down)),
+            ("arrowleft", InputAction::Turn(Direction::Left)),
+            ("a", InputAction::Turn(Direction::Left)),
+            ("left", InputAction::Turn(Direction::Left)),
+            ("arrowright", InputAction::Turn(Direction::Right)),
+            ("d", InputAction::Turn(Direction::Right)),
+            ("right", InputAction::Turn(Direction::Right)),
+            ("p", InputAction::Pause),
+            ("escape", InputAction::Pause),
+            ("pause", InputAction::Pause),
+            ("r", InputAction::Restart),
+            ("restart", InputAction::Restart),
+            ("b", InputAction::Boost),
+            ("boost", InputAction::Boost),
+            (" ", InputAction::Confirm),
+            ("space", InputAction::Confirm),
+            ("enter", InputAction::Confirm),
+        ];
+        KEYS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|&(_, action)| action)
+    }
+}
+
+// How far (0.0 to 1.0) a stick must be pushed before it counts
+pub const DEFAULT_STICK_DEAD_ZONE: f64 = 0.5;
+
+impl Direction {
+    // Turns an analog stick position into a direction, the same way for
+    // every frontend: nothing inside the dead zone, otherwise the axis the
+    // stick is pushed furthest along. `y` grows downwards, like screen
+    // coordinates.
+    pub fn from_stick(x: f64, y: f64, dead_zone: f64) -> Option<Direction> {
+        if x.abs().max(y.abs()) < dead_zone {
+            return None;
+        }
+        Some(if x.abs() > y.abs() {
+            if x > 0.0 { Direction::Right } else { Direction::Left }
+        } else if y > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        })
+    }
+}
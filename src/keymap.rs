@@ -0,0 +1,105 @@
+// src/keymap.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, InputAction};
+
+// The actions a key can be bound to, in the order a controls screen lists them
+pub const BINDABLE_ACTIONS: [InputAction; 8] = [
+    InputAction::Turn(Direction::Up),
+    InputAction::Turn(Direction::Down),
+    InputAction::Turn(Direction::Left),
+    InputAction::Turn(Direction::Right),
+    InputAction::Pause,
+    InputAction::Restart,
+    InputAction::Boost,
+    InputAction::Confirm,
+];
+
+// Player-chosen keys for each action, saved with the settings. Keys are
+// lowercase `KeyboardEvent.key` style names ("arrowup", "w", "escape",
+// "enter") and "space" for the space bar; frontends translate their own key
+// codes to these names before looking them up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct KeyMap {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    pub pause: Vec<String>,
+    pub restart: Vec<String>,
+    pub boost: Vec<String>,
+    pub confirm: Vec<String>,
+}
+
+impl Default for KeyMap {
+    // Arrows and WASD both steer
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        KeyMap {
+            up: keys(&["arrowup", "w"]),
+            down: keys(&["arrowdown", "s"]),
+            left: keys(&["arrowleft", "a"]),
+            right: keys(&["arrowright", "d"]),
+            pause: keys(&["p", "escape"]),
+            restart: keys(&["r"]),
+            boost: keys(&["b"]),
+            confirm: vec!["space".to_string()],
+        }
+    }
+}
+
+impl KeyMap {
+    pub fn keys(&self, action: InputAction) -> &[String] {
+        match action {
+            InputAction::Turn(Direction::Up) => &self.up,
+            InputAction::Turn(Direction::Down) => &self.down,
+            InputAction::Turn(Direction::Left) => &self.left,
+            InputAction::Turn(Direction::Right) => &self.right,
+            InputAction::Pause => &self.pause,
+            InputAction::Restart => &self.restart,
+            InputAction::Boost => &self.boost,
+            InputAction::Confirm => &self.confirm,
+        }
+    }
+
+    fn keys_mut(&mut self, action: InputAction) -> &mut Vec<String> {
+        match action {
+            InputAction::Turn(Direction::Up) => &mut self.up,
+            InputAction::Turn(Direction::Down) => &mut self.down,
+            InputAction::Turn(Direction::Left) => &mut self.left,
+            InputAction::Turn(Direction::Right) => &mut self.right,
+            InputAction::Pause => &mut self.pause,
+            InputAction::Restart => &mut self.restart,
+            InputAction::Boost => &mut self.boost,
+            InputAction::Confirm => &mut self.confirm,
+        }
+    }
+
+    // The action bound to a key, ignoring case
+    pub fn action_for(&self, key: &str) -> Option<InputAction> {
+        BINDABLE_ACTIONS
+            .iter()
+            .copied()
+            .find(|&action| self.keys(action).iter().any(|k| k.eq_ignore_ascii_case(key)))
+    }
+
+    // Adds a key to an action. A key does one thing only, so it's taken
+    // away from whatever action had it before.
+    pub fn bind(&mut self, action: InputAction, key: &str) {
+        let key = key.to_ascii_lowercase();
+        for other in BINDABLE_ACTIONS {
+            self.keys_mut(other).retain(|k| *k != key);
+        }
+        self.keys_mut(action).push(key);
+    }
+
+    // Removes every key from an action
+    pub fn clear(&mut self, action: InputAction) {
+        self.keys_mut(action).clear();
+    }
+}
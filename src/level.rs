@@ -0,0 +1,256 @@
+// src/level.rs
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Direction, GameError, Point, SnakeSpawn};
+
+// A level is a fixed board layout: its size, the walls on it and where the
+// snake starts. Levels are written as plain text, one character per cell:
+//
+//   '.' or ' '  empty cell
+//   '#'         wall
+//   '@'         snake spawn facing Right (at most one spawn)
+//   '^' 'v' '<' '>'  snake spawn facing Up/Down/Left/Right
+//   'a'..='z'   portal, each letter used exactly twice to link both ends
+//
+// The spawn length isn't part of the text, it can be set on `spawn` after parsing.
+// Every line must have the same length, blank lines at the end are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Level {
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub spawn: SnakeSpawn,
+}
+
+impl Level {
+    // An empty level with no walls, the snake starts at the center
+    pub fn empty(width: i32, height: i32) -> Level {
+        Level {
+            width,
+            height,
+            walls: Vec::new(),
+            portals: Vec::new(),
+            spawn: SnakeSpawn::centered(width, height),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Level, GameError> {
+        let mut walls = Vec::new();
+        // Ends found so far for each letter, in order
+        let mut portal_ends: [Vec<Point>; 26] = Default::default();
+        let mut spawn: Option<(Point, Direction)> = None;
+        let mut width = None;
+        let mut height = 0;
+
+        // An editor's final newlines aren't rows
+        let text = text.trim_end_matches(['\n', '\r']);
+        for (y, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let line_width = line.chars().count() as i32;
+            match width {
+                None => width = Some(line_width),
+                Some(w) if w != line_width => {
+                    return Err(GameError::InvalidLevel {
+                        line: y + 1,
+                        reason: format!("expected {} columns, found {}", w, line_width),
+                    });
+                }
+                _ => (),
+            }
+
+            for (x, c) in line.chars().enumerate() {
+                let p = Point { x: x as i32, y: y as i32 };
+                match c {
+                    '.' | ' ' => (),
+                    '#' => walls.push(p),
+                    '@' | '^' | 'v' | '<' | '>' => {
+                        if spawn.is_some() {
+                            return Err(GameError::InvalidLevel {
+                                line: y + 1,
+                                reason: "more than one spawn point".to_string(),
+                            });
+                        }
+                        let direction = match c {
+                            '^' => Direction::Up,
+                            'v' => Direction::Down,
+                            '<' => Direction::Left,
+                            _ => Direction::Right,
+                        };
+                        spawn = Some((p, direction));
+                    }
+                    'a'..='z' => {
+                        let ends = &mut portal_ends[(c as u8 - b'a') as usize];
+                        if ends.len() == 2 {
+                            return Err(GameError::InvalidLevel {
+                                line: y + 1,
+                                reason: format!("portal '{}' has more than two ends", c),
+                            });
+                        }
+                        ends.push(p);
+                    }
+                    other => {
+                        return Err(GameError::InvalidLevel {
+                            line: y + 1,
+                            reason: format!("unknown cell character '{}'", other),
+                        });
+                    }
+                }
+            }
+            height += 1;
+        }
+
+        let width = width.unwrap_or(0);
+        if width <= 0 || height <= 0 {
+            return Err(GameError::InvalidDimensions { width, height });
+        }
+
+        let mut portals = Vec::new();
+        for (letter, ends) in (b'a'..=b'z').zip(portal_ends.iter()) {
+            match ends.as_slice() {
+                [] => (),
+                [a, b] => portals.push((*a, *b)),
+                _ => {
+                    return Err(GameError::InvalidLevel {
+                        line: ends[0].y as usize + 1,
+                        reason: format!("portal '{}' has only one end", letter as char),
+                    });
+                }
+            }
+        }
+
+        let spawn = match spawn {
+            Some((head, direction)) => SnakeSpawn { head, direction, length: 1 },
+            None => SnakeSpawn::centered(width, height),
+        };
+
+        Ok(Level { width, height, walls, portals, spawn })
+    }
+
+    // Open cells the snake can never get to from its spawn, moving between
+    // neighbours and through portals (and without wrapping around the
+    // edges). Food can still land there, so a level should have none.
+    pub fn unreachable_cells(&self) -> Vec<Point> {
+        let inside = |p: Point| p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height;
+        let index = |p: Point| (p.y * self.width + p.x) as usize;
+        let mut reached = vec![false; (self.width * self.height).max(0) as usize];
+        let mut queue = VecDeque::new();
+        if inside(self.spawn.head) && !self.walls.contains(&self.spawn.head) {
+            reached[index(self.spawn.head)] = true;
+            queue.push_back(self.spawn.head);
+        }
+
+        while let Some(p) = queue.pop_front() {
+            let exits = self.portals.iter().filter_map(|&(a, b)| {
+                if p == a {
+                    Some(b)
+                } else if p == b {
+                    Some(a)
+                } else {
+                    None
+                }
+            });
+            let neighbours = [(0, -1), (0, 1), (-1, 0), (1, 0)].map(|(dx, dy)| Point { x: p.x + dx, y: p.y + dy });
+            for next in neighbours.into_iter().chain(exits) {
+                if inside(next) && !reached[index(next)] && !self.walls.contains(&next) {
+                    reached[index(next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .filter(|&p| !reached[index(p)] && !self.walls.contains(&p))
+            .collect()
+    }
+}
+
+// The same text `Level::parse` reads, portals lettered in order from 'a'.
+// The spawn length isn't written.
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.width {
+                let p = Point { x, y };
+                let portal = self.portals.iter().position(|&(a, b)| a == p || b == p);
+                let c = if p == self.spawn.head {
+                    match self.spawn.direction {
+                        Direction::Up => '^',
+                        Direction::Down => 'v',
+                        Direction::Left => '<',
+                        Direction::Right => '@',
+                    }
+                } else if self.walls.contains(&p) {
+                    '#'
+                } else if let Some(i) = portal.filter(|&i| i < 26) {
+                    (b'a' + i as u8) as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+##########
+#a.......#
+#...>....#
+#.##..##.#
+#.......a#
+##########";
+
+    #[test]
+    fn parses_walls_portals_and_spawn() {
+        let level = Level::parse(LEVEL).unwrap();
+        assert_eq!((level.width, level.height), (10, 6));
+        assert_eq!(level.walls.len(), 32);
+        assert_eq!(level.portals, [(Point { x: 1, y: 1 }, Point { x: 8, y: 4 })]);
+        assert_eq!(level.spawn, SnakeSpawn { head: Point { x: 4, y: 2 }, direction: Direction::Right, length: 1 });
+        assert_eq!(level.to_string().replace('@', ">"), LEVEL);
+        assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+    }
+
+    #[test]
+    fn ignores_trailing_blank_lines() {
+        let level = Level::parse(LEVEL).unwrap();
+        assert_eq!(Level::parse(&format!("{}\n", LEVEL)).unwrap(), level);
+        assert_eq!(Level::parse(&format!("{}\r\n\r\n\n", LEVEL)).unwrap(), level);
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        let error = |text: &str| match Level::parse(text) {
+            Err(GameError::InvalidLevel { line, reason }) => (line, reason),
+            other => panic!("{:?} for {:?}", other, text),
+        };
+        assert_eq!(error("...\n....\n..."), (2, "expected 3 columns, found 4".to_string()));
+        assert_eq!(error("...\n.\n\n..."), (2, "expected 3 columns, found 1".to_string()));
+        assert_eq!(error("..?\n..."), (1, "unknown cell character '?'".to_string()));
+        assert_eq!(error("@..\n..^"), (2, "more than one spawn point".to_string()));
+        assert_eq!(error("a.a\n..a"), (2, "portal 'a' has more than two ends".to_string()));
+        assert_eq!(error("...\n.b."), (2, "portal 'b' has only one end".to_string()));
+    }
+
+    #[test]
+    fn rejects_an_empty_level() {
+        assert_eq!(Level::parse(""), Err(GameError::InvalidDimensions { width: 0, height: 0 }));
+        assert_eq!(Level::parse("\n\n"), Err(GameError::InvalidDimensions { width: 0, height: 0 }));
+    }
+}
@@ -1,13 +1,129 @@
 // src/lib.rs
 
+// The core logic only needs `alloc`, so it also runs on microcontrollers and
+// bare WASM runtimes. Anything that needs the standard library (OS random
+// seeds, file storage) sits behind the default `std` feature, the
+// wasm-bindgen API behind `wasm`.
+#![no_std]
+
+extern crate alloc;
+// The macroquad web build goes without `std` (see macroquad_main.rs), but
+// the cdylib still needs std's allocator and panic handler
+#[cfg(any(feature = "std", feature = "macroquad"))]
+extern crate std;
+
 // Common imports for both native and WASM
-use rand::Rng;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use board::Board;
+use food::{BONUS_CHANCE, BONUS_TTL, SLOW_DOWN_CHANCE, SLOW_DOWN_DURATION, SLOW_DOWN_TTL};
+use replay::Playback;
+
+// A `tracing` span over the rest of the block with the `tracing` feature,
+// nothing without it: `span!(TRACE, "tick", tick = self.tick_count);`
+macro_rules! span {
+    ($level:ident, $($span:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($span)+).entered();
+    };
+}
+
+mod afk;
+mod ai;
+mod analysis;
+mod ascii;
+mod board;
+mod body;
+mod boss;
+mod cells;
+mod cheats;
+mod compact;
+mod config;
+mod error;
+mod events;
+mod food;
+mod hash;
+mod heatmap;
+mod i18n;
+mod input;
+mod keymap;
+mod level;
+mod lockstep;
+mod opponent;
+mod persist;
+mod raster;
+mod replay;
+mod rules;
+mod session_log;
+mod sim;
+mod skin;
+mod snapshot;
+mod sound;
+mod speed;
+mod sprite;
+mod state;
+mod stats;
+mod theme;
+mod transition;
+mod tutorial;
+mod votes;
+
+pub use afk::AfkMode;
+pub use ai::Controller;
+pub use analysis::{MissedPath, ReplayReport, RiskyMove, ScorePoint, SpeedPoint, RISKY_EXITS};
+pub use body::Body;
+pub use boss::{BossConfig, BossFight};
+pub use cells::CellKind;
+pub use cheats::{Cheat, CheatInput};
+pub use compact::CompactState;
+pub use config::{GameConfig, GameMode};
+pub use error::GameError;
+pub use events::{DeathCause, GameEvent, TickSummary};
+pub use food::{Food, FoodKind};
+pub use i18n::Language;
+pub use input::{InputAction, DEFAULT_STICK_DEAD_ZONE};
+pub use keymap::{KeyMap, BINDABLE_ACTIONS};
+pub use level::Level;
+pub use lockstep::{Lockstep, LockstepMessage, LockstepStatus, DEFAULT_INPUT_DELAY, HASH_INTERVAL};
+pub use opponent::Opponent;
+#[cfg(feature = "std")]
+pub use persist::FileStorage;
+pub use persist::{
+    HighScore, HighScores, Persistence, SavedReplay, Settings, Storage, MAX_HIGH_SCORES, MAX_NAME_LEN, MAX_SAVED_REPLAYS,
+};
+pub use replay::{Replay, ReplayInput, ReplayPlayer};
+pub use rules::Rules;
+#[cfg(feature = "std")]
+pub use session_log::SessionLog;
+pub use session_log::SessionRecord;
+pub use sim::{simulate, simulate_batch, simulate_many, SimResult, SimStats};
+pub use skin::{Achievement, Skin, SkinPattern, Skins};
+pub use snapshot::Snapshot;
+pub use stats::{RunStats, IDLE_AFTER_TICKS, NEAR_MISS_INTERVAL};
+pub use sound::{tones_to_wav, SoundEffect, Tone, MUSIC_LOOP};
+pub use speed::{SpeedCurve, MIN_TICK_INTERVAL};
+pub use sprite::{atlas_rgba, Sprite, Tile, TILE_SIZE};
+pub use state::{GameState, GameStatus, OpponentState, SnakeState};
+pub use theme::{BoardPattern, Rgb, Theme, ThemePreset};
+pub use transition::{DEATH_ANIMATION, DEFAULT_COUNTDOWN_SECS};
+pub use tutorial::{StepGoal, StepStatus, Tutorial, TutorialRun, TutorialStep};
+pub use votes::{parse_vote, VoteTally, VOTE_DIRECTIONS};
+
+// After a long stall (e.g. a backgrounded tab) only this many ticks are
+// caught up, instead of fast-forwarding the snake into a wall.
+const MAX_CATCH_UP_TICKS: u32 = 5;
 
 // Structs and Enums for the core game logic.
 // These are public so they can be used by the native executable.
 // The `Clone`, `Copy`, `PartialEq`, and `Debug` traits are useful for both targets.
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -15,32 +131,41 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Snake {
-    pub body: Vec<Point>,
+    pub body: Body, // Head first
     pub direction: Direction,
 }
 
 impl Snake {
     pub fn new(start_pos: Point, direction: Direction) -> Snake {
         Snake {
-            body: vec![start_pos],
+            body: Body::from([start_pos]),
             direction,
         }
     }
 
+    pub fn from_spawn(spawn: &SnakeSpawn) -> Snake {
+        Snake {
+            body: spawn.body().into(),
+            direction: spawn.direction,
+        }
+    }
+
     pub fn change_direction(&mut self, new_direction: Direction) {
-        let is_opposite = match (&self.direction, new_direction) {
-            (Direction::Up, Direction::Down) | (Direction::Down, Direction::Up) => true,
-            (Direction::Left, Direction::Right) | (Direction::Right, Direction::Left) => true,
-            _ => false,
-        };
+        let is_opposite = matches!(
+            (&self.direction, new_direction),
+            (Direction::Up, Direction::Down)
+                | (Direction::Down, Direction::Up)
+                | (Direction::Left, Direction::Right)
+                | (Direction::Right, Direction::Left)
+        );
 
         if !is_opposite {
             self.direction = new_direction;
@@ -48,195 +173,1215 @@ impl Snake {
     }
 }
 
+// How a snake moved on the last tick, enough to tell where each segment
+// was before it (see `Game::interpolated_body`) without copying the bodies
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LastMove {
+    Stayed,
+    // `tail` is the cell it left, `None` when it grew instead
+    Moved { tail: Option<Point> },
+}
+
+// A tick where the player's snake only moved from `from`, heading
+// `direction` before that tick's turn: nothing eaten, nobody died. A late
+// turn can still redo it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PlainMove {
+    from: Point,
+    direction: Direction,
+}
+
+// Where and how the snake starts: the head position, the direction it faces
+// and how many segments it has. The rest of the body trails behind the head.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SnakeSpawn {
+    pub head: Point,
+    pub direction: Direction,
+    pub length: usize,
+}
+
+impl SnakeSpawn {
+    // The classic start: a single segment at the center facing Right
+    pub fn centered(width: i32, height: i32) -> SnakeSpawn {
+        SnakeSpawn {
+            head: Point { x: width / 2, y: height / 2 },
+            direction: Direction::Right,
+            length: 1,
+        }
+    }
+
+    // Whether the snake could fit on a `width` x `height` board at all. Checked
+    // before `body`, which would lay out any length it's given.
+    pub(crate) fn check_length(&self, width: i32, height: i32) -> Result<(), GameError> {
+        let cells = width.max(0) as u64 * height.max(0) as u64;
+        if self.length == 0 || self.length as u64 > cells {
+            return Err(GameError::InvalidSnakeLength(self.length));
+        }
+        Ok(())
+    }
+
+    // Body segments, head first, laid out opposite to the facing direction
+    pub fn body(&self) -> Vec<Point> {
+        let (dx, dy) = match self.direction {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (1, 0),
+            Direction::Right => (-1, 0),
+        };
+        (0..i32::try_from(self.length).unwrap_or(i32::MAX))
+            .map(|i| Point { x: self.head.x + dx * i, y: self.head.y + dy * i })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Game {
     pub width: i32,
     pub height: i32,
     pub snake: Snake,
-    pub food: Point,
+    pub foods: Vec<Food>, // Apples, bonus items and power-ups on the board
+    pub food_count: usize, // Apples kept on the board at all times
+    pub mode: GameMode, // Classic or timed
+    pub wrap_walls: bool, // The board edges wrap around instead of killing
+    // Walls and portals (entering one end exits through the other), shared
+    // with every game built from the same `Arc<Level>` and copied on the
+    // first change, see `from_shared_level`. The level's size and spawn are
+    // copied into the fields above.
+    pub level: Arc<Level>,
+    pub spawn: SnakeSpawn, // Where and how the snake starts
     pub score: u32,
     pub game_over: bool,
     pub game_started: bool, // New field
-    rng: rand::rngs::ThreadRng, // Random number generator
+    pub paused: bool, // Ticks are ignored while paused
+    pub speed: SpeedCurve, // Tick interval for each speed level
+    pub boosting: bool, // Runs at double speed while set
+    pub opponents: Vec<Opponent>, // Other snakes on the board, see `add_opponent`
+    pub autopilot: Option<Controller>, // AI steering the player's snake
+    pub theme: Theme, // Colors the frontends draw with
+    pub countdown_secs: u32, // Length of the countdown before a round, 0 for none
+    pub reduced_motion: bool, // Dying snakes fade out instead of flashing
+    pub track_visits: bool, // Count where the head goes, see `visits()`
+    pub input_grace: f32, // Fraction of a tick a late turn still counts for it, see `queue_turn`
+    pub afk_ticks: u64, // Ticks without input before a snake is away, 0 for never, see afk.rs
+    pub afk_mode: AfkMode, // How snakes whose player is away keep going
+    pub boss: Option<BossConfig>, // Boss encounters at score milestones, see boss.rs
+    pub rules: Option<Arc<dyn Rules>>, // Custom rules (mods), see `Rules`
+    pending_turn: Option<Direction>, // Turn to apply on the next tick
+    late_turn: Option<Direction>, // Turn for the last tick, see `queue_turn`
+    plain_move: Option<PlainMove>, // The player's last tick, while a late turn may still redo it
+    last_input: u64, // Tick of the player's last input, see afk.rs
+    growing: u32, // Segments the snake still grows by, see cheats.rs
+    forced_level: Option<u32>, // Speed level set by a cheat instead of the score's
+    boss_fight: Option<BossFight>, // The boss on the board, if any
+    bosses: u32, // Score milestones that brought a boss this round
+    slow_ticks: u32, // Ticks left on the slow-down power-up
+    accumulator: Duration, // Time not yet consumed by a tick
+    countdown_left: Duration, // Real time before the snake starts moving
+    dying_left: Duration, // Real time left on the death animation
+    last_moves: Vec<LastMove>, // How every snake moved on the last tick, for `interpolated_body`
+    pub death_cause: Option<DeathCause>, // Why the last game ended
+    events: Vec<GameEvent>, // Not yet drained by a frontend
+    tick_count: u64, // Ticks run since the game started
+    game_time: Duration, // Sum of the intervals of those ticks
+    seed: u64, // Seed the RNG was created from
+    rng: ChaCha8Rng, // Owned, seedable random number generator
+    round_seed: u64, // Seed the current round started from, drawn from `rng`
+    recording: Vec<ReplayInput>, // Inputs of the current round, see `replay()`
+    cheats: Vec<CheatInput>, // Cheats used this round, see cheats.rs
+    stats: RunStats, // The player's current (or last) round, see `run_stats()`
+    visits: Vec<u32>, // Per cell, see heatmap.rs
+    playback: Option<Playback>, // Set when this game plays a replay back
+    board: Board, // Which cells are taken, see board.rs
+}
+
+// `Game` holds no thread-bound state, so it can be moved to or shared with
+// other threads (simulators, servers, AI workers). Checked at compile time.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Game>();
+};
+
+// ChaCha8 gives the same sequence on every platform (native and wasm32),
+// so a seed always reproduces the same game.
+#[cfg(feature = "std")]
+fn random_seed() -> u64 {
+    use rand::RngCore;
+    rand::rngs::OsRng.next_u64()
+}
+
+// Without `std` there is no entropy source, use `Game::from_level_with_seed`
+// or `Game::reseed` to vary the games.
+#[cfg(not(feature = "std"))]
+fn random_seed() -> u64 {
+    0
 }
 
 // Core game logic, platform-agnostic
 impl Game {
-    pub fn new(width: i32, height: i32) -> Game {
-        let start_pos = Point { x: width / 2, y: height / 2 };
-        let snake = Snake::new(start_pos, Direction::Right);
-        let rng = rand::thread_rng();
+    pub fn new(width: i32, height: i32) -> Result<Game, GameError> {
+        Game::from_level(&Level::empty(width, height))
+    }
+
+    pub fn from_level(level: &Level) -> Result<Game, GameError> {
+        Game::from_level_with_seed(level, random_seed())
+    }
+
+    // Same seed and same inputs always give the same game
+    pub fn from_level_with_seed(level: &Level, seed: u64) -> Result<Game, GameError> {
+        Game::from_shared_level_with_seed(Arc::new(level.clone()), seed)
+    }
+
+    // Like `from_level_with_seed` without copying the level: the games keep
+    // a reference to it instead of their own walls and portals, so a server
+    // hosting hundreds of rooms on one map holds it once. A game that adds
+    // or removes a wall gets its own copy then, the others don't see it.
+    pub fn from_shared_level_with_seed(level: Arc<Level>, seed: u64) -> Result<Game, GameError> {
+        if level.width <= 0 || level.height <= 0 {
+            return Err(GameError::InvalidDimensions {
+                width: level.width,
+                height: level.height,
+            });
+        }
+
+        level.spawn.check_length(level.width, level.height)?;
+        // Every segment, not just the head, has to fit on the board
+        for segment in level.spawn.body() {
+            if segment.x < 0 || segment.x >= level.width || segment.y < 0 || segment.y >= level.height {
+                return Err(GameError::SpawnOutOfBounds(segment));
+            }
+            if level.walls.contains(&segment) {
+                return Err(GameError::SpawnOnObstacle(segment));
+            }
+        }
+
+        let snake = Snake::from_spawn(&level.spawn);
+        let rng = ChaCha8Rng::seed_from_u64(seed);
 
         let mut game = Game {
-            width,
-            height,
+            width: level.width,
+            height: level.height,
             snake,
-            food: Point { x: 0, y: 0 }, // Temporary position
+            foods: Vec::new(),
+            food_count: 1,
+            mode: GameMode::Classic,
+            wrap_walls: false,
+            spawn: level.spawn,
+            level,
             score: 0,
             game_over: false,
             game_started: false, // Initialize as false
+            paused: false,
+            speed: SpeedCurve::default(),
+            boosting: false,
+            opponents: Vec::new(),
+            autopilot: None,
+            theme: Theme::default(),
+            countdown_secs: DEFAULT_COUNTDOWN_SECS,
+            reduced_motion: false,
+            track_visits: false,
+            input_grace: 0.0,
+            afk_ticks: 0,
+            afk_mode: AfkMode::Straight,
+            boss: None,
+            rules: None,
+            pending_turn: None,
+            late_turn: None,
+            plain_move: None,
+            last_input: 0,
+            growing: 0,
+            forced_level: None,
+            boss_fight: None,
+            bosses: 0,
+            slow_ticks: 0,
+            accumulator: Duration::ZERO,
+            countdown_left: Duration::ZERO,
+            dying_left: Duration::ZERO,
+            last_moves: Vec::new(),
+            death_cause: None,
+            events: Vec::new(),
+            tick_count: 0,
+            game_time: Duration::ZERO,
+            seed,
             rng,
+            round_seed: seed,
+            recording: Vec::new(),
+            cheats: Vec::new(),
+            stats: RunStats::default(),
+            visits: Vec::new(),
+            playback: None,
+            board: Board::default(),
         };
-        game.spawn_food();
-        game
-    }
-
-    // Now uses the `rand` crate
-    fn spawn_food(&mut self) {
-        loop {
-            let x = self.rng.gen_range(0..self.width);
-            let y = self.rng.gen_range(0..self.height);
-            let new_food_pos = Point { x, y };
-            if !self.snake.body.iter().any(|p| *p == new_food_pos) {
-                self.food = new_food_pos;
+        game.refresh_board();
+        game.replenish_apples()?;
+        Ok(game)
+    }
+
+    // A game on an empty board set up from a config
+    pub fn from_config(config: &GameConfig) -> Result<Game, GameError> {
+        let mut level = Level::empty(config.width, config.height);
+        level.spawn.length = config.snake_length;
+        Game::from_level_with_config(&level, config)
+    }
+
+    // A level with the rules (mode, speed, food...) taken from a config. The
+    // config's width, height and snake length are ignored, the level has its own.
+    pub fn from_level_with_config(level: &Level, config: &GameConfig) -> Result<Game, GameError> {
+        Game::from_shared_level(Arc::new(level.clone()), config)
+    }
+
+    // `from_level_with_config` for a level shared between games, see
+    // `from_shared_level_with_seed`. Every config-built game comes through
+    // here, so this is where its speed curve gets checked.
+    pub fn from_shared_level(level: Arc<Level>, config: &GameConfig) -> Result<Game, GameError> {
+        config.speed_curve.validate()?;
+        let seed = config.seed.unwrap_or_else(random_seed);
+        let mut game = Game::from_shared_level_with_seed(level, seed)?;
+        game.mode = config.mode;
+        game.wrap_walls = config.wrap_walls;
+        game.food_count = config.food_count;
+        game.speed = config.speed_curve;
+        game.theme = Theme::preset(config.theme);
+        game.countdown_secs = config.countdown_secs;
+        game.reduced_motion = config.reduced_motion;
+        game.input_grace = config.input_grace.clamp(0.0, 1.0);
+        game.afk_ticks = config.afk_ticks;
+        game.afk_mode = config.afk_mode;
+        game.boss = config.boss;
+        game.replenish_apples()?;
+        for &controller in &config.opponents {
+            game.add_opponent_auto(controller)?;
+        }
+        Ok(game)
+    }
+
+    // Whether `p` is on the board with no snake, wall, portal or food on it
+    pub fn is_free(&self, p: Point) -> bool {
+        self.board.is_free(p)
+    }
+
+    // Rebuilds the index of taken cells (see board.rs) from the snakes,
+    // food, walls and portals. The game keeps it up to date by itself, this
+    // is only needed after changing those fields by hand.
+    pub fn refresh_board(&mut self) {
+        let mut board = core::mem::take(&mut self.board);
+        board.rebuild(self);
+        self.board = board;
+    }
+
+    // Puts a wall on a free cell, returns whether it did
+    pub fn add_obstacle(&mut self, p: Point) -> bool {
+        let added = self.is_free(p);
+        if added {
+            Arc::make_mut(&mut self.level).walls.push(p);
+            self.board.set_wall(p, true);
+        }
+        added
+    }
+
+    // Clears the walls off a cell, returns whether there was one
+    pub fn remove_obstacle(&mut self, p: Point) -> bool {
+        if !self.board.is_wall(p) {
+            return false;
+        }
+        Arc::make_mut(&mut self.level).walls.retain(|&wall| wall != p);
+        self.board.set_wall(p, false);
+        true
+    }
+
+    // The cells whose content changed since the last call (snakes moving,
+    // food eaten, placed or expired, walls added or removed), so a renderer
+    // can redraw just those. `None` when the whole board needs redrawing: on
+    // the first call, after a restart, a restore or custom rules, or when so
+    // much changed that a full redraw is as cheap. Only covers what's on the
+    // cells, the death animation and overlays are up to the renderer.
+    pub fn take_dirty_cells(&mut self) -> Option<Vec<Point>> {
+        self.board.take_dirty()
+    }
+
+    // Picks a random cell among the free ones, so it can never loop forever.
+    // Numbering them in row-major order, the way they used to be listed,
+    // keeps the games the same for a given seed.
+    fn spawn_food(&mut self, kind: FoodKind, ttl: Option<u32>) -> Result<(), GameError> {
+        span!(TRACE, "spawn_food", kind = ?kind);
+        let free = self.board.free_count();
+        if free == 0 {
+            return Err(GameError::NoFreeCell);
+        }
+        // Drawn as a u32, rand samples a usize range differently on 32-bit
+        // targets (wasm32) and the games would diverge from the native ones
+        let n = self.rng.gen_range(0..free as u32) as usize;
+        let pos = self.board.nth_free(n).ok_or(GameError::NoFreeCell)?;
+        let mut food = Food { pos, kind, ttl };
+        if let Some(rules) = self.rules.clone() {
+            let placed = rules.on_spawn_food(self, &mut food);
+            self.refresh_board();
+            if !placed || !self.is_free(food.pos) {
+                return Ok(());
+            }
+        }
+        self.foods.push(food);
+        self.board.set_food(food.pos, true);
+        #[cfg(feature = "bench-hooks")]
+        bench_hooks::count_food_spawned();
+        Ok(())
+    }
+
+    // Takes the food at `p` off the board
+    fn take_food(&mut self, p: Point) -> Option<Food> {
+        if !self.board.is_food(p) {
+            return None;
+        }
+        let food = self.foods.iter().position(|f| f.pos == p).map(|i| self.foods.remove(i));
+        self.board.set_food(p, false);
+        food
+    }
+
+    // Tops the apples back up to `food_count`. Only fails when there's no
+    // room at all for the missing apples and none is left on the board.
+    fn replenish_apples(&mut self) -> Result<(), GameError> {
+        let apples = self.foods.iter().filter(|f| f.kind == FoodKind::Apple).count();
+        for _ in apples..self.food_count.max(1) {
+            if let Err(e) = self.spawn_food(FoodKind::Apple, None) {
+                if !self.foods.iter().any(|f| f.kind == FoodKind::Apple) {
+                    return Err(e);
+                }
                 break;
             }
         }
+        Ok(())
+    }
+
+    // Counts down bonus items and power-ups, removing the expired ones
+    fn expire_food(&mut self) {
+        for food in &mut self.foods {
+            if let Some(ttl) = &mut food.ttl {
+                *ttl = ttl.saturating_sub(1);
+            }
+        }
+        let (events, board) = (&mut self.events, &mut self.board);
+        self.foods.retain(|food| {
+            let expired = food.ttl == Some(0);
+            if expired {
+                events.push(GameEvent::FoodExpired { at: food.pos, kind: food.kind });
+                board.set_food(food.pos, false);
+            }
+            !expired
+        });
+    }
+
+    fn eat(&mut self, food: Food) {
+        let level_before = self.speed_level();
+        self.score += food.kind.points();
+        if self.speed_level() > level_before {
+            self.events.push(GameEvent::LevelUp { level: self.speed_level() });
+        }
+        match food.kind {
+            FoodKind::Apple => {
+                self.events.push(GameEvent::FoodEaten { at: food.pos, kind: food.kind, score: self.score });
+                // The snake fills the whole board, nothing left to play for
+                if self.replenish_apples().is_err() {
+                    self.end_game(DeathCause::BoardFull);
+                    return;
+                }
+                // Extras are nice to have, no room for them is not an error
+                if self.rng.gen_ratio(1, BONUS_CHANCE) {
+                    let _ = self.spawn_food(FoodKind::Bonus, Some(BONUS_TTL));
+                }
+                if self.rng.gen_ratio(1, SLOW_DOWN_CHANCE) {
+                    let _ = self.spawn_food(FoodKind::SlowDown, Some(SLOW_DOWN_TTL));
+                }
+            }
+            FoodKind::Bonus => {
+                self.events.push(GameEvent::FoodEaten { at: food.pos, kind: food.kind, score: self.score });
+            }
+            FoodKind::SlowDown => {
+                self.slow_ticks = SLOW_DOWN_DURATION;
+                self.events.push(GameEvent::PowerUpCollected {
+                    at: food.pos,
+                    kind: food.kind,
+                    duration_ticks: SLOW_DOWN_DURATION,
+                });
+            }
+        }
+        if let Some(rules) = self.rules.clone() {
+            rules.on_food_eaten(self, food);
+            self.refresh_board();
+        }
     }
 
     pub fn tick(&mut self) {
-        // Only tick if the game is started and not over
-        if !self.game_started || self.game_over {
-            return;
+        span!(TRACE, "tick", tick = self.tick_count);
+        let tick_count = self.tick_count;
+        self.run_tick();
+        // Custom rules see every tick that ran, unless it ended the round
+        if self.tick_count > tick_count && !self.game_over {
+            if let Some(rules) = self.rules.clone() {
+                rules.on_tick(self);
+                self.refresh_board();
+            }
         }
+    }
 
-        let mut new_head = self.snake.body[0];
-        match self.snake.direction {
-            Direction::Up => new_head.y -= 1,
-            Direction::Down => new_head.y += 1,
-            Direction::Left => new_head.x -= 1,
-            Direction::Right => new_head.x += 1,
+    // Runs up to `n` ticks in one call, for fast-forwarding, AI rollouts and
+    // frontends across an FFI boundary that would rather not call `tick` n
+    // times. Stops early once a tick doesn't run (round over, paused, end of
+    // a replay). Same result as calling `tick` in a loop.
+    pub fn tick_n(&mut self, n: u64) -> TickSummary {
+        span!(DEBUG, "tick_n", n);
+        let queued = self.events.len();
+        let mut summary = TickSummary::default();
+        while summary.ticks < n {
+            let tick_count = self.tick_count;
+            self.tick();
+            if self.tick_count == tick_count {
+                break;
+            }
+            summary.ticks += 1;
         }
+        for event in &self.events[queued..] {
+            summary.count(event);
+        }
+        summary.score = self.score;
+        summary
+    }
 
-        // Wall collision
-        if new_head.x < 0 || new_head.x >= self.width || new_head.y < 0 || new_head.y >= self.height {
-            self.game_over = true;
+    fn run_tick(&mut self) {
+        // Only tick if the game is started, not over and not paused
+        if !self.game_started || self.game_over || self.paused {
             return;
         }
+        // Ticking by hand skips the countdown
+        self.countdown_left = Duration::ZERO;
+
+        // A replay feeds its recorded inputs and stops where the recording did
+        if let Some(playback) = &mut self.playback {
+            if self.tick_count >= playback.end {
+                return;
+            }
+            while let Some(input) = playback.inputs.get(playback.next).filter(|i| i.tick <= self.tick_count) {
+                match (input.snake, input.action) {
+                    (0, InputAction::Turn(direction)) if input.late => self.late_turn = Some(direction),
+                    (0, InputAction::Turn(direction)) => self.pending_turn = Some(direction),
+                    (0, InputAction::Boost) => self.boosting = !self.boosting,
+                    (index, InputAction::Turn(direction)) => {
+                        if let Some(opponent) = self.opponents.get_mut(index - 1) {
+                            opponent.pending_turn = Some(direction);
+                        }
+                    }
+                    _ => {}
+                }
+                playback.next += 1;
+            }
+            // Checked when recorded, a cheat failing here means the replay
+            // was tampered with and it plays on without it
+            let mut cheats = Vec::new();
+            while let Some(input) = playback.cheats.get(playback.next_cheat).filter(|c| c.tick <= self.tick_count) {
+                cheats.push(input.cheat);
+                playback.next_cheat += 1;
+            }
+            for cheat in cheats {
+                let _ = self.apply_cheat(cheat);
+            }
+        }
+        self.run_controllers();
+        // Settled before anything else moves, the last tick is redone as if
+        // the turn had come in time
+        let late_turn = self.late_turn.take();
+        if let Some(direction) = late_turn {
+            if !self.redo_last_move(direction) && self.pending_turn.is_none() {
+                self.pending_turn = Some(direction);
+            }
+        }
+        self.plain_move = None;
+
+        // Every tick that actually runs counts, including the one that ends the game
+        self.tick_count += 1;
+        self.game_time += self.tick_interval();
+        #[cfg(feature = "bench-hooks")]
+        bench_hooks::count_tick();
+        self.slow_ticks = self.slow_ticks.saturating_sub(1);
+        self.expire_food();
+
+        self.last_moves.clear();
+        self.last_moves.resize(self.snake_count(), LastMove::Stayed);
 
-        // Self collision
-        if self.snake.body.iter().skip(1).any(|p| *p == new_head) {
-            self.game_over = true;
+        // The turn is checked against the direction the snake actually moved
+        // in, so two quick turns within a tick can't reverse it into its neck
+        self.stats.count_tick(self.pending_turn.is_some() || late_turn.is_some());
+        let mut dodged = None;
+        let before = self.snake.direction;
+        if let Some(direction) = self.pending_turn.take() {
+            self.snake.change_direction(direction);
+            if self.snake.direction != before {
+                self.events.push(GameEvent::Turned { direction: self.snake.direction });
+                self.stats.turns += 1;
+                dodged = self.fatal_ahead(before).filter(|_| self.tick_interval() <= NEAR_MISS_INTERVAL);
+            }
+        }
+
+        let new_head = match self.step(self.snake.body[0], self.snake.direction) {
+            Ok(p) => p,
+            Err(cause) => {
+                self.end_game(cause);
+                return;
+            }
+        };
+
+        // Self, opponent and obstacle collision
+        if let Some(cause) = self.snake_hit(new_head, 0) {
+            self.end_game(cause);
+            return;
+        }
+        if self.board.is_wall(new_head) {
+            self.end_game(DeathCause::Obstacle { at: new_head });
             return;
         }
 
-        self.snake.body.insert(0, new_head);
+        if let Some(at) = dodged {
+            self.stats.near_misses += 1;
+            self.events.push(GameEvent::NearMiss { at });
+        }
 
-        if new_head == self.food {
-            self.score += 1;
-            self.spawn_food();
-        } else {
-            self.snake.body.pop();
+        // The old head is drawn as body from now on
+        let from = self.snake.body[0];
+        self.board.mark_dirty(from);
+        self.snake.body.push_front(new_head);
+        self.board.set_snake(new_head, true);
+        if self.track_visits {
+            self.count_visit(new_head);
+        }
+
+        let eaten = self.take_food(new_head);
+        // Only real food makes the snake longer, power-ups don't
+        let mut tail = None;
+        if eaten.is_none_or(|f| f.kind.is_power_up()) {
+            if self.growing > 0 {
+                self.growing -= 1;
+            } else {
+                tail = self.snake.body.pop_back();
+                tail.inspect(|&p| self.board.set_snake(p, false));
+            }
+        }
+        self.last_moves[0] = LastMove::Moved { tail };
+        // Kept whatever `input_grace` is, replays play late turns back without it
+        if eaten.is_none() {
+            self.plain_move = Some(PlainMove { from, direction: before });
+        }
+        self.stats.max_length = self.stats.max_length.max(self.snake.body.len());
+        if let Some(food) = eaten {
+            self.stats.count_eaten(food.kind);
+            self.eat(food);
+        }
+
+        if !self.game_over {
+            self.move_opponents();
+        }
+        if !self.game_over {
+            self.update_boss();
+        }
+
+        if self.time_left() == Some(Duration::ZERO) && !self.game_over {
+            self.end_game(DeathCause::TimeExpired);
+        }
+    }
+
+    // Game time left in timed mode, `None` in modes without a clock
+    pub fn time_left(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Classic => None,
+            GameMode::Timed { limit_secs } => {
+                Some(Duration::from_secs(limit_secs.into()).saturating_sub(self.game_time))
+            }
         }
     }
     
+    // Ends the round now, e.g. from custom rules. Does nothing once it's over.
+    pub fn end_game(&mut self, cause: DeathCause) {
+        if self.game_over {
+            return;
+        }
+        self.game_over = true;
+        self.death_cause = Some(cause);
+        self.stats.finished = true;
+        self.stats.score = self.score;
+        self.stats.length = self.snake.body.len();
+        self.start_death_animation(cause);
+        self.events.push(GameEvent::GameOver { cause, score: self.score });
+    }
+
+    // Hands over every event since the last call, oldest first
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    // Where a head at `from` ends up after one move: wrapped around the edge
+    // or out through a portal. Fails with `Wall` when it leaves the board.
+    pub(crate) fn step(&self, from: Point, direction: Direction) -> Result<Point, DeathCause> {
+        let mut p = from;
+        match direction {
+            Direction::Up => p.y -= 1,
+            Direction::Down => p.y += 1,
+            Direction::Left => p.x -= 1,
+            Direction::Right => p.x += 1,
+        }
+
+        // Wall collision, or coming back in on the other side
+        if p.x < 0 || p.x >= self.width || p.y < 0 || p.y >= self.height {
+            if !self.wrap_walls {
+                return Err(DeathCause::Wall { at: p });
+            }
+            p.x = p.x.rem_euclid(self.width);
+            p.y = p.y.rem_euclid(self.height);
+        }
+
+        // Stepping on a portal moves the head to the other end
+        Ok(self.portal_exit(p).unwrap_or(p))
+    }
+
+    // The cell the player's head would have died on going on in `direction`,
+    // off the board or on a wall or snake. Decided like a real move, by
+    // `snake_hit`, so its own tail counts: collisions are checked before the
+    // tail moves out of the way.
+    fn fatal_ahead(&self, direction: Direction) -> Option<Point> {
+        match self.step(self.snake.body[0], direction) {
+            Err(DeathCause::Wall { at }) => Some(at),
+            Err(_) => None,
+            Ok(p) => (self.board.is_wall(p) || self.snake_hit(p, 0).is_some()).then_some(p),
+        }
+    }
+
+    fn portal_exit(&self, p: Point) -> Option<Point> {
+        self.level.portals.iter().find_map(|&(a, b)| {
+            if a == p {
+                Some(b)
+            } else if b == p {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+
+    // The canonical game clock, only moves while the game is running
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // In-game time, each tick counts for the interval it ran at
+    pub fn elapsed(&self) -> Duration {
+        self.game_time
+    }
+
+    pub fn speed_level(&self) -> u32 {
+        self.forced_level.unwrap_or_else(|| self.speed.level_for_score(self.score))
+    }
+
+    // Ticks left on the slow-down power-up, 0 when it isn't active
+    pub fn slow_ticks_left(&self) -> u32 {
+        self.slow_ticks
+    }
+
+    // Time left on a power-up's effect at the current speed, `None` when it
+    // isn't active (or the kind isn't a power-up)
+    pub fn power_up_time_left(&self, kind: FoodKind) -> Option<Duration> {
+        match kind {
+            FoodKind::SlowDown if self.slow_ticks > 0 => Some(self.tick_interval() * self.slow_ticks),
+            _ => None,
+        }
+    }
+
+    pub fn snake_length(&self) -> usize {
+        self.snake.body.len()
+    }
+
+    // How much faster than the starting speed the game runs right now,
+    // counting levels, boost and slow-down. 1.0 at the start.
+    pub fn speed_multiplier(&self) -> f32 {
+        let interval = self.tick_interval().as_secs_f32();
+        if interval == 0.0 {
+            return 1.0;
+        }
+        self.speed.base_interval.as_secs_f32() / interval
+    }
+
+    // Time between two ticks at the current speed level
+    pub fn tick_interval(&self) -> Duration {
+        let mut interval = self.speed.interval_for_level(self.speed_level());
+        if self.slow_ticks > 0 {
+            interval = interval * 3 / 2;
+        }
+        if self.boosting {
+            interval /= 2;
+        }
+        interval
+    }
+
+    // Fixed-timestep clock: feed it the real time since the last call and it
+    // runs as many ticks as fit, keeping the remainder for the next call.
+    // The countdown and death animation run on it too. Returns the number of
+    // ticks that were run.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        // A frame, with the ticks it ran and the events they queued
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("advance", ticks = tracing::field::Empty, events = tracing::field::Empty).entered();
+        #[cfg(feature = "tracing")]
+        let queued = self.events.len();
+        if self.game_over {
+            self.dying_left = self.dying_left.saturating_sub(elapsed);
+        }
+        if !self.game_started || self.game_over || self.paused {
+            self.accumulator = Duration::ZERO;
+            return 0;
+        }
+        let elapsed = self.run_countdown(elapsed);
+        // A zero interval (a curve set by hand, see `SpeedCurve::validate`)
+        // would have the loop below tick forever
+        if self.tick_interval().is_zero() {
+            self.accumulator = Duration::ZERO;
+            return 0;
+        }
+
+        let max_backlog = self.tick_interval() * MAX_CATCH_UP_TICKS;
+        self.accumulator = (self.accumulator + elapsed).min(max_backlog);
+
+        let mut ticks = 0;
+        // The interval is re-read every time, eating food may change the level
+        while self.accumulator >= self.tick_interval() && !self.game_over {
+            self.accumulator -= self.tick_interval();
+            self.tick();
+            ticks += 1;
+        }
+        #[cfg(feature = "tracing")]
+        span.record("ticks", ticks).record("events", self.events.len().saturating_sub(queued));
+        ticks
+    }
+
+    // How far the clock is towards the next tick, from 0.0 (a tick just ran)
+    // to 1.0. Stays at 1.0 while the game isn't running.
+    pub fn tick_progress(&self) -> f32 {
+        if !self.game_started || self.game_over || self.paused || self.tick_interval().is_zero() {
+            return 1.0;
+        }
+        (self.accumulator.as_secs_f32() / self.tick_interval().as_secs_f32()).min(1.0)
+    }
+
+    // A snake's segments (head first) in fractional cells, part way between
+    // where they were before the last tick and where they are now, so
+    // frontends drawing faster than the game ticks can show smooth motion.
+    // Segments that jumped (wrapping walls, portals) are drawn where they are.
+    pub fn interpolated_body(&self, index: usize) -> Vec<(f32, f32)> {
+        let body = self.snake_body(index);
+        let last_move = self.last_moves.get(index).copied().unwrap_or(LastMove::Stayed);
+        let t = self.tick_progress();
+        body.iter()
+            .enumerate()
+            .map(|(i, p)| {
+                // Each segment was where the one behind it is now, the tail
+                // in the cell it left. A segment added by growing starts
+                // where the old tail was.
+                let from = match last_move {
+                    LastMove::Stayed => *p,
+                    LastMove::Moved { tail } => body.get(i + 1).copied().or(tail).unwrap_or(*p),
+                };
+                if (p.x - from.x).abs() + (p.y - from.y).abs() > 1 {
+                    return (p.x as f32, p.y as f32);
+                }
+                (from.x as f32 + (p.x - from.x) as f32 * t, from.y as f32 + (p.y - from.y) as f32 * t)
+            })
+            .collect()
+    }
+
     // This is a core logic function, not tied to wasm
     pub fn change_snake_direction(&mut self, direction: Direction) {
         self.snake.change_direction(direction);
     }
 
-    // New method to start/restart the game
-    pub fn start_game(&mut self) {
-        self.snake = Snake::new(
-            Point { x: self.width / 2, y: self.height / 2 },
-            Direction::Right,
-        );
-        self.score = 0;
-        self.game_over = false;
-        self.game_started = true;
-        self.spawn_food();
+    // Single entry point for player input, whatever device it came from
+    pub fn handle_action(&mut self, action: InputAction) {
+        self.note_input(0);
+        match action {
+            InputAction::Turn(direction) => self.queue_turn(direction),
+            InputAction::Pause => self.toggle_pause(),
+            InputAction::Restart => self.start_game(),
+            InputAction::Boost => {
+                if self.game_started && !self.game_over && self.playback.is_none() {
+                    self.record(0, action);
+                    self.boosting = !self.boosting;
+                }
+            }
+            // Waits for the death animation, so a held key can't skip it
+            InputAction::Confirm => {
+                if !self.game_started || self.status() == GameStatus::GameOver {
+                    self.start_game();
+                } else if self.paused {
+                    self.resume();
+                }
+            }
+        }
     }
-}
 
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 
-// This block of code will only be compiled for the wasm32 target
-#[cfg(target_arch = "wasm32")]
-pub mod wasm {
-    use super::*; // Import everything from the parent module
-    use wasm_bindgen::prelude::*;
+    // Seed the current round started from, the one its replay uses
+    pub fn round_seed(&self) -> u64 {
+        self.round_seed
+    }
 
-    // This is a wrapper around the main `Game` struct that will be exposed to JS.
-    #[wasm_bindgen(js_name = Game)]
-    pub struct WasmGame(Game);
+    // Restarts the random sequence, food placed from now on follows the new seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
 
-    #[wasm_bindgen(js_class = Game)]
-    impl WasmGame {
-        #[wasm_bindgen(constructor)]
-        pub fn new(width: i32, height: i32) -> WasmGame {
-            WasmGame(Game::new(width, height))
+    // Fingerprint of everything that decides how the game goes on: snake,
+    // food, score, clock and the position in the random sequence. Two games
+    // with the same seed and inputs have the same hash on every platform,
+    // so lockstep peers can compare it to detect a desync.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = hash::Fnv1a::new();
+        self.snake.body.hash(&mut hasher);
+        self.snake.direction.hash(&mut hasher);
+        self.foods.hash(&mut hasher);
+        self.score.hash(&mut hasher);
+        self.game_over.hash(&mut hasher);
+        self.boosting.hash(&mut hasher);
+        self.pending_turn.hash(&mut hasher);
+        self.slow_ticks.hash(&mut hasher);
+        self.boss_fight.hash(&mut hasher);
+        self.tick_count.hash(&mut hasher);
+        self.round_seed.hash(&mut hasher);
+        self.rng.get_word_pos().hash(&mut hasher);
+        for opponent in &self.opponents {
+            opponent.snake.body.hash(&mut hasher);
+            opponent.snake.direction.hash(&mut hasher);
+            opponent.score.hash(&mut hasher);
+            opponent.alive.hash(&mut hasher);
+            opponent.pending_turn.hash(&mut hasher);
         }
+        hasher.finish()
+    }
 
-        pub fn tick(&mut self) {
-            self.0.tick();
-        }
+    // Fingerprint of the board layout: size, walls, portals and whether the
+    // edges wrap. Snapshots and compact states only restore into a game with
+    // the same one.
+    pub(crate) fn level_fingerprint(&self) -> u64 {
+        let mut hasher = hash::Fnv1a::new();
+        self.width.hash(&mut hasher);
+        self.height.hash(&mut hasher);
+        self.level.walls.hash(&mut hasher);
+        self.level.portals.hash(&mut hasher);
+        self.wrap_walls.hash(&mut hasher);
+        hasher.finish()
+    }
 
-        #[wasm_bindgen(js_name = change_snake_direction)]
-        pub fn change_snake_direction(&mut self, direction: WasmDirection) {
-            self.0.change_snake_direction(direction.into());
+    // Captures the dynamic state so it can be branched from later
+    pub fn snapshot(&self) -> Snapshot {
+        span!(TRACE, "snapshot");
+        Snapshot {
+            body: self.snake.body.clone(),
+            direction: self.snake.direction,
+            foods: self.foods.clone(),
+            score: self.score,
+            game_over: self.game_over,
+            game_started: self.game_started,
+            paused: self.paused,
+            boosting: self.boosting,
+            pending_turn: self.pending_turn,
+            slow_ticks: self.slow_ticks,
+            accumulator: self.accumulator,
+            death_cause: self.death_cause,
+            tick_count: self.tick_count,
+            game_time: self.game_time,
+            opponents: self.opponents.clone(),
+            stats: self.stats,
+            boss_fight: self.boss_fight,
+            bosses: self.bosses,
+            rng_seed: self.rng.get_seed(),
+            rng_word_pos: self.rng.get_word_pos(),
+            round_seed: self.round_seed,
+            recording: self.recording.clone(),
+            cheats: self.cheats.clone(),
+            level_fingerprint: self.level_fingerprint(),
         }
+    }
 
-        // New method to expose to JS
-        #[wasm_bindgen(js_name = start_game)]
-        pub fn start_game(&mut self) {
-            self.0.start_game();
+    // Rewinds to a snapshot taken from this game, random sequence and
+    // round recording included, so it goes on as the original run did
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        span!(TRACE, "restore");
+        self.snake.body.clone_from(&snapshot.body);
+        self.snake.direction = snapshot.direction;
+        self.foods.clone_from(&snapshot.foods);
+        self.score = snapshot.score;
+        self.game_over = snapshot.game_over;
+        self.game_started = snapshot.game_started;
+        self.paused = snapshot.paused;
+        self.boosting = snapshot.boosting;
+        self.pending_turn = snapshot.pending_turn;
+        self.slow_ticks = snapshot.slow_ticks;
+        self.accumulator = snapshot.accumulator;
+        self.death_cause = snapshot.death_cause;
+        self.tick_count = snapshot.tick_count;
+        self.game_time = snapshot.game_time;
+        self.opponents.clone_from(&snapshot.opponents);
+        self.stats = snapshot.stats;
+        self.boss_fight = snapshot.boss_fight;
+        self.bosses = snapshot.bosses;
+        self.rng = ChaCha8Rng::from_seed(snapshot.rng_seed);
+        self.rng.set_word_pos(snapshot.rng_word_pos);
+        self.round_seed = snapshot.round_seed;
+        self.recording.clone_from(&snapshot.recording);
+        self.cheats.clone_from(&snapshot.cheats);
+        self.settle_restored();
+    }
+
+    // What's left to do once a restore has put the fields back
+    pub(crate) fn settle_restored(&mut self) {
+        self.last_moves.clear();
+        // The tick a late turn would redo was before the restore
+        self.late_turn = None;
+        self.plain_move = None;
+        self.reset_last_inputs();
+        // Snapshots don't carry what cheats left running, `stats` still counts them
+        self.growing = 0;
+        self.forced_level = None;
+        self.refresh_board();
+        self.countdown_left = Duration::ZERO;
+        self.dying_left = Duration::ZERO;
+    }
+
+    // Turns wait for the next tick, the last one queued within a tick wins.
+    // Ignored while a replay is playing, it does the steering.
+    //
+    // With `input_grace` set, a turn coming in that fraction of a tick after
+    // one ran counts for that tick instead, if the snake could have taken
+    // it there (see `redo_last_move`). Whether it was late is decided now,
+    // from the clock, and recorded; whether it could be taken is settled at
+    // the next tick from the board alone, so replays redo the same ticks.
+    // The clock only moves with `advance`: a game ticked by hand is always
+    // right after a tick and should leave `input_grace` at 0.
+    pub fn queue_turn(&mut self, direction: Direction) {
+        if self.playback.is_some() {
+            return;
+        }
+        self.note_input(0);
+        let late = self.plain_move.is_some()
+            && self.pending_turn.is_none()
+            && self.tick_progress() < self.input_grace;
+        if late && self.game_started && !self.game_over {
+            self.recording.push(ReplayInput { tick: self.tick_count, snake: 0, action: InputAction::Turn(direction), late });
+            self.late_turn = Some(direction);
+        } else {
+            self.record(0, InputAction::Turn(direction));
+            self.pending_turn = Some(direction);
         }
+    }
 
-        // Getters that return copies of data
-        pub fn width(&self) -> i32 { self.0.width }
-        pub fn height(&self) -> i32 { self.0.height }
-        pub fn food(&self) -> WasmPoint { self.0.food.into() }
-        pub fn score(&self) -> u32 { self.0.score }
-        #[wasm_bindgen(js_name = game_over)]
-        pub fn game_over(&self) -> bool { self.0.game_over }
-        #[wasm_bindgen(js_name = game_started)] // Expose new field
-        pub fn game_started(&self) -> bool { self.0.game_started }
+    // Takes back the player's last move and makes it in `direction`, when
+    // that move was plain (see `PlainMove`) and the turn would have been
+    // legal then: not back into the neck, onto a free cell. Returns false
+    // when it can't, the turn then waits for the next tick like any other.
+    fn redo_last_move(&mut self, direction: Direction) -> bool {
+        let Some(PlainMove { from, direction: before }) = self.plain_move else { return false };
+        if direction == self.snake.direction {
+            return true;
+        }
+        if direction == ai::opposite(before) {
+            return false;
+        }
+        let Ok(p) = self.step(from, direction) else { return false };
+        if !self.board.is_free(p) {
+            return false;
+        }
+        let Some(head) = self.snake.body.pop_front() else { return false };
+        self.board.set_snake(head, false);
+        self.snake.body.push_front(p);
+        self.board.set_snake(p, true);
+        if self.track_visits {
+            self.uncount_visit(head);
+            self.count_visit(p);
+        }
+        self.snake.direction = direction;
+        self.events.push(GameEvent::Turned { direction });
+        self.stats.turns += 1;
+        true
+    }
 
-        // Functions to get pointers for efficient memory reading from JS
-        #[wasm_bindgen(js_name = get_body_ptr)]
-        pub fn get_body_ptr(&self) -> *const Point {
-            self.0.snake.body.as_ptr()
+    // Only inputs during a round can change it, the rest isn't worth keeping
+    pub(crate) fn record(&mut self, snake: usize, action: InputAction) {
+        if self.game_started && !self.game_over {
+            self.recording.push(ReplayInput { tick: self.tick_count, snake, action, late: false });
         }
+    }
 
-        #[wasm_bindgen(js_name = get_body_len)]
-        pub fn get_body_len(&self) -> usize {
-            self.0.snake.body.len()
+    // The current (or last) round so far, see `Replay`
+    pub fn replay(&self) -> Replay {
+        Replay {
+            width: self.width,
+            height: self.height,
+            obstacles: self.level.walls.clone(),
+            portals: self.level.portals.clone(),
+            spawn: self.spawn,
+            mode: self.mode,
+            wrap_walls: self.wrap_walls,
+            food_count: self.food_count,
+            speed: self.speed,
+            // The boss comes back by itself at the same score
+            opponents: (1..self.snake_count())
+                .filter(|&index| !self.is_boss(index))
+                .map(|index| self.opponents[index - 1].spawn)
+                .collect(),
+            boss: self.boss,
+            seed: self.round_seed,
+            inputs: self.recording.clone(),
+            cheats: self.cheats.clone(),
+            ticks: self.tick_count,
+            score: self.score,
+            stats: self.stats,
         }
     }
 
-    // We need to create wasm-bindgen compatible versions of our enums and structs
-    // because the original ones are now pure Rust.
-    #[wasm_bindgen(js_name = Direction)]
-    #[derive(Clone, Copy)]
-    pub enum WasmDirection {
-        Up,
-        Down,
-        Left,
-        Right,
+    // How the player's current (or last) round went, see `RunStats`
+    pub fn run_stats(&self) -> RunStats {
+        self.stats
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.playback.is_some()
     }
 
-    // Conversion from WasmDirection to the core Direction
-    impl From<WasmDirection> for Direction {
-        fn from(d: WasmDirection) -> Self {
-            match d {
-                WasmDirection::Up => Direction::Up,
-                WasmDirection::Down => Direction::Down,
-                WasmDirection::Left => Direction::Left,
-                WasmDirection::Right => Direction::Right,
+    // Whether a replay has reached the end of its recording
+    pub fn replay_finished(&self) -> bool {
+        self.playback.as_ref().is_some_and(|p| self.game_over || self.tick_count >= p.end)
+    }
+
+    // New method to start/restart the game
+    pub fn start_game(&mut self) {
+        // Every round gets its own seed so it can be replayed on its own. A
+        // replay reuses the recorded one.
+        self.round_seed = match &mut self.playback {
+            Some(playback) => {
+                playback.next = 0;
+                playback.next_cheat = 0;
+                playback.seed
             }
+            None => self.rng.gen(),
+        };
+        self.rng = ChaCha8Rng::seed_from_u64(self.round_seed);
+        self.recording.clear();
+        self.cheats.clear();
+        self.snake = Snake::from_spawn(&self.spawn);
+        self.stats = RunStats { max_length: self.snake.body.len(), ..RunStats::default() };
+        self.reset_visits();
+        self.score = 0;
+        self.game_over = false;
+        self.game_started = true;
+        self.paused = false;
+        self.boosting = false;
+        self.pending_turn = None;
+        self.late_turn = None;
+        self.plain_move = None;
+        self.last_input = 0;
+        self.growing = 0;
+        self.forced_level = None;
+        self.slow_ticks = 0;
+        self.accumulator = Duration::ZERO;
+        self.dying_left = Duration::ZERO;
+        self.last_moves.clear();
+        self.tick_count = 0;
+        self.game_time = Duration::ZERO;
+        self.death_cause = None;
+        self.remove_boss();
+        self.bosses = 0;
+        for opponent in &mut self.opponents {
+            opponent.reset();
+        }
+        self.events.push(GameEvent::Started);
+        self.start_countdown();
+        self.foods.clear();
+        self.refresh_board();
+        // The spawn was validated on construction, so a lone snake always leaves room
+        if self.replenish_apples().is_err() {
+            self.end_game(DeathCause::BoardFull);
         }
     }
-    
-    #[wasm_bindgen(js_name = Point)]
-    #[derive(Clone, Copy)]
-    pub struct WasmPoint {
-        pub x: i32,
-        pub y: i32,
+
+    // Pausing only makes sense while a game is actually running
+    pub fn pause(&mut self) {
+        if self.game_started && !self.game_over {
+            self.paused = true;
+        }
     }
 
-    // Conversion from core Point to WasmPoint
-    impl From<Point> for WasmPoint {
-        fn from(p: Point) -> Self {
-            WasmPoint { x: p.x, y: p.y }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
         }
     }
-}
\ No newline at end of file
+}
+
+// This block of code will only be compiled for the wasm32 target
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub mod wasm;
+
+// The debug console's command parser, see `console::run`
+#[cfg(feature = "console")]
+pub mod console;
+
+// Fixtures and work counters for the benches, see `bench_hooks::counters`
+#[cfg(feature = "bench-hooks")]
+pub mod bench_hooks;
+
+// The game as a Bevy plugin, see `bevy_plugin::SnakePlugin`
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+
+// Draws the board on embedded-graphics displays, see `embedded::BoardRenderer`
+#[cfg(feature = "embedded")]
+pub mod embedded;
+
+// Replays as animated PNGs, see `export::export_apng`
+#[cfg(all(feature = "apng", not(target_arch = "wasm32")))]
+pub mod export;
+
+// The C API of the cdylib, see `ffi::snake_game_new`
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+
+// The cdylib as a Node.js addon, see `node::NodeGame`
+#[cfg(all(feature = "node", not(target_arch = "wasm32")))]
+pub mod node;
+
+// Custom rules written in Lua, see `lua::LuaRules`
+#[cfg(all(feature = "lua", not(target_arch = "wasm32")))]
+pub mod lua;
+
+// The game as a Godot node, see `godot::SnakeNode`
+#[cfg(all(feature = "godot", not(target_arch = "wasm32")))]
+pub mod godot;
+
+// Peer-to-peer games over WebRTC data channels, see `rtc::RtcPeer`
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+pub mod rtc;
+
+// Twitch chat voting on the snake's turns, see `twitch::TwitchPlays`
+#[cfg(all(feature = "twitch", not(target_arch = "wasm32")))]
+pub mod twitch;
+
+// Kotlin and Swift bindings through UniFFI, see `mobile::Game`
+#[cfg(all(feature = "uniffi", not(target_arch = "wasm32")))]
+pub mod mobile;
+#[cfg(all(feature = "uniffi", not(target_arch = "wasm32")))]
+uniffi::setup_scaffolding!();
+
+// Protocol Buffers types and conversions, see proto/snake_game.proto
+#[cfg(feature = "protobuf")]
+pub mod proto;
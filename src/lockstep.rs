@@ -0,0 +1,394 @@
+// src/lockstep.rs
+
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, Game, GameConfig, GameError};
+
+// Two players on two machines, each running the same game. Nothing but the
+// inputs goes over the wire: both peers build the game from the same config
+// and seed, and a tick only runs once both peers' turns for it are known,
+// so the games stay identical without a server deciding anything. The
+// transport (a WebRTC data channel in the browser and natively, see
+// `wasm::PeerGame` and `rtc::RtcPeer`) must deliver messages reliably and
+// in order.
+//
+// The host plays the first snake and picks the config, the guest plays the
+// opponent. A turn is played `input_delay` ticks after it was pressed, which
+// hides the round trip as long as it's shorter than that many ticks; a slow
+// peer stalls the game instead of letting it drift apart.
+
+// Bumped when the messages or the simulation change, peers with different
+// versions can't play
+const LOCKSTEP_VERSION: u8 = 2;
+
+// Ticks between a key press and the tick it's played on
+pub const DEFAULT_INPUT_DELAY: u64 = 3;
+
+// Ticks between two state hashes sent for desync detection
+pub const HASH_INTERVAL: u64 = 30;
+
+// Limits on what a `Start` can ask of a peer: every tick of delay is kept
+// in flight, and every cell is simulated on both machines
+pub const MAX_INPUT_DELAY: u64 = 64;
+pub const MAX_BOARD_SIZE: i32 = 200;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LockstepMessage {
+    // The host's first message: the game to play and the input delay
+    Start { config: GameConfig, input_delay: u64 },
+    // The host starts another round once the last one is over
+    Restart { round: u32 },
+    // The sender's turn for a tick, sent for every tick even with no turn
+    Inputs { round: u32, tick: u64, turn: Option<Direction> },
+    // `Game::state_hash` after a tick, every `HASH_INTERVAL` ticks
+    Hash { round: u32, tick: u64, hash: u64 },
+}
+
+impl LockstepMessage {
+    // A version byte followed by the postcard encoding, like `Replay::to_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from([LOCKSTEP_VERSION]);
+        // Encoding into a Vec can't fail for these types
+        bytes.extend(postcard::to_allocvec(self).unwrap_or_default());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<LockstepMessage, GameError> {
+        match bytes.split_first() {
+            Some((&LOCKSTEP_VERSION, rest)) => {
+                postcard::from_bytes(rest).map_err(|e| GameError::InvalidMessage(e.to_string()))
+            }
+            Some((version, _)) => {
+                Err(GameError::InvalidMessage(alloc::format!("unsupported lockstep version {}", version)))
+            }
+            None => Err(GameError::InvalidMessage("empty lockstep message".to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockstepStatus {
+    // Ticking along
+    Running,
+    // The next tick is due but the other peer's turn for it hasn't arrived
+    Waiting,
+    // The round is over, only the host can start another
+    GameOver,
+    // The peers' games differ since `tick`, playing on is pointless
+    Desynced { tick: u64 },
+}
+
+pub struct Lockstep {
+    pub game: Game,
+    config: GameConfig,
+    // Snake steered by this peer: 0 for the host, 1 for the guest
+    player: usize,
+    input_delay: u64,
+    round: u32,
+    local: BTreeMap<u64, Option<Direction>>,
+    remote: BTreeMap<u64, Option<Direction>>,
+    // First tick this peer hasn't sent its turn for yet
+    next_input: u64,
+    // The last turn pressed since the last input was sent
+    pending_turn: Option<Direction>,
+    accumulator: Duration,
+    // Hashes of this peer's game the other peer hasn't sent yet, and the
+    // other way round
+    local_hashes: BTreeMap<u64, u64>,
+    remote_hashes: BTreeMap<u64, u64>,
+    desynced_at: Option<u64>,
+    outbox: Vec<LockstepMessage>,
+    // The host's restart while this peer's round was still running, with
+    // the messages of the new round that came after it
+    held_restart: Option<(u32, Vec<LockstepMessage>)>,
+}
+
+impl Lockstep {
+    // Starts a session as the host, with one opponent for the guest. The
+    // seed is fixed here (a random one if the config has none) and the
+    // countdown dropped, the peers can't agree on when it would end, nor on
+    // which turns came in late for the grace window. The
+    // `Start` message for the guest is the first one in `take_messages`.
+    pub fn host(config: &GameConfig, input_delay: u64) -> Result<Lockstep, GameError> {
+        let mut config = GameConfig { opponents: vec![None], countdown_secs: 0, input_grace: 0.0, ..config.clone() };
+        check_limits(&config, input_delay)?;
+        let game = Game::from_config(&config)?;
+        config.seed = Some(game.seed());
+        let mut lockstep = Lockstep::new(game, config.clone(), 0, input_delay);
+        lockstep.outbox.push(LockstepMessage::Start { config, input_delay });
+        lockstep.start_round();
+        Ok(lockstep)
+    }
+
+    // Joins with the host's `Start` message, any other one is an error
+    pub fn join(start: &LockstepMessage) -> Result<Lockstep, GameError> {
+        let LockstepMessage::Start { config, input_delay } = start else {
+            return Err(GameError::InvalidMessage("expected a start message".to_string()));
+        };
+        if config.seed.is_none() || config.opponents.len() != 1 {
+            return Err(GameError::InvalidMessage("not a lockstep game".to_string()));
+        }
+        check_limits(config, *input_delay)?;
+        let game = Game::from_config(config)?;
+        let mut lockstep = Lockstep::new(game, config.clone(), 1, *input_delay);
+        lockstep.start_round();
+        Ok(lockstep)
+    }
+
+    fn new(game: Game, config: GameConfig, player: usize, input_delay: u64) -> Lockstep {
+        Lockstep {
+            game,
+            config,
+            player,
+            input_delay,
+            round: 0,
+            local: BTreeMap::new(),
+            remote: BTreeMap::new(),
+            next_input: 0,
+            pending_turn: None,
+            accumulator: Duration::ZERO,
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+            desynced_at: None,
+            outbox: Vec::new(),
+            held_restart: None,
+        }
+    }
+
+    // Both peers' rngs are in the same state, so they pick the same round seed
+    fn start_round(&mut self) {
+        self.game.start_game();
+        self.local.clear();
+        self.remote.clear();
+        self.local_hashes.clear();
+        self.remote_hashes.clear();
+        self.pending_turn = None;
+        self.accumulator = Duration::ZERO;
+        // Nobody could have pressed anything before the first ticks
+        for tick in 0..self.input_delay {
+            self.local.insert(tick, None);
+            self.remote.insert(tick, None);
+        }
+        self.next_input = self.input_delay;
+        self.send_inputs();
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.player == 0
+    }
+
+    // The snake this peer steers, for `Game::snake_body` and friends
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn input_delay(&self) -> u64 {
+        self.input_delay
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    // Played `input_delay` ticks from now, the last one pressed in a tick wins
+    pub fn turn(&mut self, direction: Direction) {
+        self.pending_turn = Some(direction);
+    }
+
+    // The host's restart, once the round is over. Does nothing for the guest.
+    pub fn restart(&mut self) {
+        if self.is_host() && self.game.game_over && self.desynced_at.is_none() {
+            self.round += 1;
+            self.outbox.push(LockstepMessage::Restart { round: self.round });
+            self.start_round();
+        }
+    }
+
+    pub fn status(&self) -> LockstepStatus {
+        if let Some(tick) = self.desynced_at {
+            LockstepStatus::Desynced { tick }
+        } else if self.game.game_over {
+            LockstepStatus::GameOver
+        } else if self.accumulator >= self.game.tick_interval() && !self.remote.contains_key(&self.game.tick_count()) {
+            LockstepStatus::Waiting
+        } else {
+            LockstepStatus::Running
+        }
+    }
+
+    // A message from the other peer
+    pub fn receive(&mut self, message: LockstepMessage) -> Result<(), GameError> {
+        match message {
+            LockstepMessage::Start { .. } => {
+                return Err(GameError::InvalidMessage("the game has already started".to_string()));
+            }
+            LockstepMessage::Restart { round } => {
+                if self.is_host() || self.held_restart.is_some() || round != self.round + 1 {
+                    return Err(GameError::InvalidMessage(alloc::format!("unexpected restart to round {}", round)));
+                }
+                // The host may have ended the round ticks before this peer
+                // got there, the round plays out first
+                self.held_restart = Some((round, Vec::new()));
+                self.apply_held_restart();
+            }
+            LockstepMessage::Inputs { round, .. } | LockstepMessage::Hash { round, .. }
+                if self.held_restart.as_ref().is_some_and(|(next, _)| *next == round) =>
+            {
+                if let Some((_, held)) = &mut self.held_restart {
+                    held.push(message);
+                }
+            }
+            // Whatever the other peer sent before it saw a restart is stale,
+            // and ticks no honest peer could have reached yet are dropped
+            // rather than piling up
+            LockstepMessage::Inputs { round, tick, turn } => {
+                let tick_count = self.game.tick_count();
+                if round == self.round && tick >= tick_count && tick <= self.max_remote_tick() {
+                    self.remote.insert(tick, turn);
+                }
+            }
+            LockstepMessage::Hash { round, tick, hash } => {
+                let compared = tick <= self.game.tick_count() && !self.local_hashes.contains_key(&tick);
+                if round == self.round && !compared && tick <= self.max_remote_tick() {
+                    self.remote_hashes.insert(tick, hash);
+                    self.compare_hashes();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The last tick the other peer can have sent its turn for. It runs tick
+    // `t` once it has this peer's turn for it, which is sent `input_delay`
+    // ticks ahead, and sends its own turns as far ahead again: up to twice
+    // the delay past this peer's tick.
+    fn max_remote_tick(&self) -> u64 {
+        self.game.tick_count().saturating_add(self.input_delay.saturating_mul(2)).saturating_add(1)
+    }
+
+    // Starts the round the host restarted to once this peer's is over too,
+    // then takes the new round's messages that came in meanwhile
+    fn apply_held_restart(&mut self) {
+        if !self.game.game_over {
+            return;
+        }
+        let Some((round, held)) = self.held_restart.take() else { return };
+        self.round = round;
+        self.start_round();
+        for message in held {
+            // Only inputs and hashes are held, those are never an error
+            let _ = self.receive(message);
+        }
+    }
+
+    // `receive` for a message straight off the transport
+    pub fn receive_bytes(&mut self, bytes: &[u8]) -> Result<(), GameError> {
+        self.receive(LockstepMessage::from_bytes(bytes)?)
+    }
+
+    // Messages to send to the other peer, in order
+    pub fn take_messages(&mut self) -> Vec<LockstepMessage> {
+        core::mem::take(&mut self.outbox)
+    }
+
+    // `take_messages`, encoded for the transport
+    pub fn take_bytes(&mut self) -> Vec<Vec<u8>> {
+        self.take_messages().iter().map(LockstepMessage::to_bytes).collect()
+    }
+
+    // Like `Game::advance`, except a tick waits for the other peer's turn.
+    // The clock doesn't run ahead while waiting, the game just slows down.
+    // Returns the number of ticks that were run.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        if self.game.game_over || self.desynced_at.is_some() {
+            // Still runs the death animation
+            self.game.advance(elapsed);
+            return 0;
+        }
+        self.send_inputs();
+        let max_backlog = self.game.tick_interval() * crate::MAX_CATCH_UP_TICKS;
+        self.accumulator = (self.accumulator + elapsed).min(max_backlog);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.game.tick_interval() && !self.game.game_over && self.desynced_at.is_none() {
+            let tick = self.game.tick_count();
+            if !self.remote.contains_key(&tick) {
+                // Not a whole tick ahead, or the game couldn't catch up
+                self.accumulator = self.accumulator.min(self.game.tick_interval());
+                break;
+            }
+            self.accumulator -= self.game.tick_interval();
+            self.run_tick(tick);
+            ticks += 1;
+        }
+        self.apply_held_restart();
+        ticks
+    }
+
+    // Both turns are applied in snake order, so the recordings (and replays)
+    // of both peers are the same
+    fn run_tick(&mut self, tick: u64) {
+        let local = self.local.remove(&tick).flatten();
+        let remote = self.remote.remove(&tick).flatten();
+        let turns = if self.is_host() { [local, remote] } else { [remote, local] };
+        for (index, turn) in turns.into_iter().enumerate() {
+            if let Some(direction) = turn {
+                self.game.queue_turn_for(index, direction);
+            }
+        }
+        self.game.tick();
+        let tick = self.game.tick_count();
+        if tick.is_multiple_of(HASH_INTERVAL) {
+            let hash = self.game.state_hash();
+            self.local_hashes.insert(tick, hash);
+            self.outbox.push(LockstepMessage::Hash { round: self.round, tick, hash });
+            self.compare_hashes();
+        }
+        self.send_inputs();
+    }
+
+    // Sends this peer's turns up to `input_delay` ticks ahead of the game
+    fn send_inputs(&mut self) {
+        while self.next_input <= self.game.tick_count().saturating_add(self.input_delay) {
+            let turn = self.pending_turn.take();
+            self.local.insert(self.next_input, turn);
+            self.outbox.push(LockstepMessage::Inputs { round: self.round, tick: self.next_input, turn });
+            self.next_input += 1;
+        }
+    }
+
+    fn compare_hashes(&mut self) {
+        let ticks: Vec<u64> = self.remote_hashes.keys().filter(|t| self.local_hashes.contains_key(t)).copied().collect();
+        for tick in ticks {
+            if self.local_hashes.remove(&tick) != self.remote_hashes.remove(&tick) && self.desynced_at.is_none() {
+                self.desynced_at = Some(tick);
+            }
+        }
+    }
+}
+
+// What both peers check before building the game, see `MAX_INPUT_DELAY`
+fn check_limits(config: &GameConfig, input_delay: u64) -> Result<(), GameError> {
+    if input_delay > MAX_INPUT_DELAY {
+        return Err(GameError::InvalidMessage(alloc::format!(
+            "input delay {} over {} ticks",
+            input_delay, MAX_INPUT_DELAY
+        )));
+    }
+    if config.width > MAX_BOARD_SIZE || config.height > MAX_BOARD_SIZE {
+        return Err(GameError::InvalidMessage(alloc::format!(
+            "board {}x{} over {} cells per side",
+            config.width, config.height, MAX_BOARD_SIZE
+        )));
+    }
+    Ok(())
+}
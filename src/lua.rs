@@ -0,0 +1,192 @@
+// src/lua.rs
+
+// Custom rules written in Lua, see `Rules`. Only compiled with the `lua`
+// feature. A script defines any of these global functions, the missing
+// ones change nothing:
+//
+//     -- After every tick of a running round
+//     function on_tick(game)
+//         if game:tick() % 50 == 0 then game:add_score(-1) end
+//     end
+//
+//     -- After the player's snake ate something
+//     function on_food_eaten(game, food)
+//         if food.kind == "Bonus" then game:add_obstacle(food.x, food.y + 1) end
+//         if game:score() >= 30 then game:end_game() end
+//     end
+//
+//     -- Before food goes on the board. Changing the table changes what
+//     -- appears, returning false keeps it off the board.
+//     function on_spawn_food(game, food)
+//         if food.kind == "SlowDown" then food.kind = "Bonus" end
+//     end
+//
+// `food` is `{ x, y, kind, ttl }`, `kind` being "Apple", "Bonus" or
+// "SlowDown" and `ttl` nil for food that stays. `game` has these methods,
+// with cells counted from 0 at the top left like everywhere else:
+//
+//     width() height() tick() score() length() direction() head() -> x, y
+//     set_score(n) add_score(n) is_free(x, y) foods() end_game()
+//     add_obstacle(x, y) remove_obstacle(x, y)   (false if nothing changed)
+//
+// Scripts only get the base, string, table, math and utf8 libraries: a
+// downloaded mod can't read or write files or run programs. An error in a
+// hook doesn't stop the game, the hook just does nothing that time and the
+// error is kept for the frontend, see `LuaRules::take_error`.
+
+use std::format;
+use std::path::Path;
+use std::string::{String, ToString};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use mlua::{Function, IntoLuaMulti, Lua, LuaOptions, StdLib, Table, UserData, UserDataMethods, Value};
+
+use crate::{DeathCause, Food, FoodKind, Game, GameError, Point, Rules};
+
+#[derive(Debug)]
+pub struct LuaRules {
+    lua: Lua,
+    error: Mutex<Option<String>>, // Last hook error, until taken
+}
+
+fn script_error(err: mlua::Error) -> GameError {
+    GameError::Script(err.to_string())
+}
+
+fn food_kind(name: &str) -> mlua::Result<FoodKind> {
+    match name {
+        "Apple" => Ok(FoodKind::Apple),
+        "Bonus" => Ok(FoodKind::Bonus),
+        "SlowDown" => Ok(FoodKind::SlowDown),
+        _ => Err(mlua::Error::runtime(format!("unknown food kind '{}', expected Apple, Bonus or SlowDown", name))),
+    }
+}
+
+fn food_table(lua: &Lua, food: Food) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+    table.set("x", food.pos.x)?;
+    table.set("y", food.pos.y)?;
+    table.set("kind", format!("{:?}", food.kind))?;
+    table.set("ttl", food.ttl)?;
+    Ok(table)
+}
+
+fn table_food(table: &Table) -> mlua::Result<Food> {
+    Ok(Food {
+        pos: Point { x: table.get("x")?, y: table.get("y")? },
+        kind: food_kind(&table.get::<String>("kind")?)?,
+        ttl: table.get("ttl")?,
+    })
+}
+
+// The `game` the hooks get, only valid while the hook runs
+impl UserData for Game {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("width", |_, game, ()| Ok(game.width));
+        methods.add_method("height", |_, game, ()| Ok(game.height));
+        methods.add_method("tick", |_, game, ()| Ok(game.tick_count()));
+        methods.add_method("score", |_, game, ()| Ok(game.score));
+        methods.add_method("length", |_, game, ()| Ok(game.snake.body.len()));
+        methods.add_method("direction", |_, game, ()| Ok(format!("{:?}", game.snake.direction)));
+        methods.add_method("head", |_, game, ()| Ok((game.snake.body[0].x, game.snake.body[0].y)));
+        methods.add_method("is_free", |_, game, (x, y): (i32, i32)| Ok(game.is_free(Point { x, y })));
+        methods.add_method("foods", |lua, game, ()| {
+            game.foods.iter().map(|&food| food_table(lua, food)).collect::<mlua::Result<Vec<Table>>>()
+        });
+        methods.add_method_mut("set_score", |_, game, score: u32| {
+            game.score = score;
+            Ok(())
+        });
+        // Negative to take points away, the score stops at 0
+        methods.add_method_mut("add_score", |_, game, points: i64| {
+            game.score = (game.score as i64 + points).clamp(0, u32::MAX as i64) as u32;
+            Ok(())
+        });
+        methods.add_method_mut("add_obstacle", |_, game, (x, y): (i32, i32)| Ok(game.add_obstacle(Point { x, y })));
+        methods.add_method_mut("remove_obstacle", |_, game, (x, y): (i32, i32)| {
+            Ok(game.remove_obstacle(Point { x, y }))
+        });
+        methods.add_method_mut("end_game", |_, game, ()| {
+            game.end_game(DeathCause::Rules);
+            Ok(())
+        });
+    }
+}
+
+impl LuaRules {
+    // Runs the script once to define its hooks. `name` shows up in error
+    // messages, usually the file name.
+    pub fn load(name: &str, source: &str) -> Result<LuaRules, GameError> {
+        let libs = StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8;
+        let lua = Lua::new_with(libs, LuaOptions::default()).map_err(script_error)?;
+        // The base library can still read files
+        for name in ["dofile", "loadfile"] {
+            lua.globals().raw_remove(name).map_err(script_error)?;
+        }
+        lua.load(source).set_name(format!("@{}", name)).exec().map_err(script_error)?;
+        Ok(LuaRules { lua, error: Mutex::new(None) })
+    }
+
+    pub fn from_file(path: &Path) -> Result<LuaRules, GameError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| GameError::Script(format!("could not read {}: {}", path.display(), e)))?;
+        LuaRules::load(&path.display().to_string(), &source)
+    }
+
+    // The last error a hook ran into since the previous call
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    // Calls the hook `name` with the game and `args`, if the script has it.
+    // `None` when it doesn't or when it failed.
+    fn call<A: IntoLuaMulti>(&self, name: &str, game: &mut Game, args: A) -> Option<Value> {
+        let hook = match self.lua.globals().get::<Option<Function>>(name) {
+            Ok(hook) => hook?,
+            Err(e) => return self.failed(e),
+        };
+        let result = self.lua.scope(|scope| {
+            let game = scope.create_userdata_ref_mut(game)?;
+            hook.call::<Value>((game, args))
+        });
+        result.or_else(|e| self.failed(e).ok_or(())).ok()
+    }
+
+    fn failed<T>(&self, err: mlua::Error) -> Option<T> {
+        *self.error.lock().unwrap_or_else(|e| e.into_inner()) = Some(err.to_string());
+        None
+    }
+}
+
+impl Rules for LuaRules {
+    fn on_tick(&self, game: &mut Game) {
+        self.call("on_tick", game, ());
+    }
+
+    fn on_food_eaten(&self, game: &mut Game, food: Food) {
+        match food_table(&self.lua, food) {
+            Ok(table) => self.call("on_food_eaten", game, table),
+            Err(e) => self.failed(e),
+        };
+    }
+
+    fn on_spawn_food(&self, game: &mut Game, food: &mut Food) -> bool {
+        let table = match food_table(&self.lua, *food) {
+            Ok(table) => table,
+            Err(e) => return self.failed(e).unwrap_or(true),
+        };
+        match self.call("on_spawn_food", game, &table) {
+            Some(Value::Boolean(false)) => false,
+            // The hook may have changed the table
+            Some(_) => match table_food(&table) {
+                Ok(changed) => {
+                    *food = changed;
+                    true
+                }
+                Err(e) => self.failed(e).unwrap_or(true),
+            },
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// src/macroquad_main.rs
+
+// Macroquad frontend, only built with the `macroquad` feature. The same
+// code runs as a native window and in the browser, so it can stand in for
+// both the ggez app and the JS renderer:
+//
+//     cargo run --bin snake-macroquad --features macroquad
+//     cargo build --bin snake-macroquad --release --target wasm32-unknown-unknown \
+//         --no-default-features --features macroquad
+//
+// The web build leaves out the default features: `wasm` and `std` both pull
+// in wasm-bindgen (the API and the browser's entropy source), and macroquad
+// loads the module with its own mq_js_bundle.js instead. Without `std` the
+// core has no entropy source, the seed comes from the clock here.
+
+use macroquad::audio::{load_sound_from_bytes, play_sound_once, Sound};
+use macroquad::prelude::*;
+
+use snake_game::{
+    atlas_rgba, CellKind, Direction, Game, GameConfig, GameStatus, InputAction, KeyMap, Rgb, SoundEffect, Sprite, Tile, TILE_SIZE,
+};
+
+const BOARD_SIZE: i32 = 20;
+const CELL_PIXELS: f32 = 24.0;
+const SAMPLE_RATE: u32 = 44_100;
+
+// A press that moves less than this is a tap, anything longer a swipe
+const SWIPE_PIXELS: f64 = 30.0;
+
+fn color(rgb: Rgb) -> Color {
+    Color::from_rgba(rgb.r, rgb.g, rgb.b, 255)
+}
+
+// The key map's name for a key, see `KeyMap`
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Up => "arrowup".to_string(),
+        KeyCode::Down => "arrowdown".to_string(),
+        KeyCode::Left => "arrowleft".to_string(),
+        KeyCode::Right => "arrowright".to_string(),
+        KeyCode::Enter | KeyCode::KpEnter => "enter".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        // Key1 -> "1"
+        _ => {
+            let name = format!("{:?}", key).to_lowercase();
+            match name.strip_prefix("key") {
+                Some(digit) if !digit.is_empty() => digit.to_string(),
+                _ => name,
+            }
+        }
+    }
+}
+
+fn window_conf() -> Conf {
+    Conf {
+        window_title: "Snake Game (macroquad)".to_string(),
+        window_width: (BOARD_SIZE as f32 * CELL_PIXELS) as i32,
+        window_height: (BOARD_SIZE as f32 * CELL_PIXELS) as i32,
+        high_dpi: true,
+        ..Default::default()
+    }
+}
+
+struct App {
+    game: Game,
+    keymap: KeyMap,
+    atlas: Texture2D,
+    sounds: Vec<(SoundEffect, Sound)>,
+    press: Option<Vec2>, // Where the mouse or a finger went down
+}
+
+impl App {
+    fn input(&mut self) {
+        for key in get_keys_pressed() {
+            if let Some(action) = self.keymap.action_for(&key_name(key)) {
+                self.game.handle_action(action);
+            }
+        }
+
+        // Touches come in as the mouse too. A swipe steers, a tap starts,
+        // restarts or resumes.
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.press = Some(mouse_position().into());
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            let Some(start) = self.press.take() else { return };
+            let moved = Vec2::from(mouse_position()) - start;
+            match Direction::from_stick(moved.x as f64, moved.y as f64, SWIPE_PIXELS) {
+                Some(direction) => self.game.queue_turn(direction),
+                None => self.game.handle_action(InputAction::Confirm),
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        self.game.advance(std::time::Duration::from_secs_f32(get_frame_time()));
+        let events: Vec<_> = self.game.drain_events().collect();
+        for effect in events.iter().filter_map(SoundEffect::for_event) {
+            if let Some((_, sound)) = self.sounds.iter().find(|(e, _)| *e == effect) {
+                play_sound_once(sound);
+            }
+        }
+    }
+
+    // Draws a tile over the cell at (x, y), which may be between cells
+    fn draw_sprite(&self, origin: Vec2, cell: f32, sprite: Sprite, x: f32, y: f32, tint: Color) {
+        let tile = TILE_SIZE as f32;
+        draw_texture_ex(
+            &self.atlas,
+            origin.x + x * cell,
+            origin.y + y * cell,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(vec2(cell, cell)),
+                source: Some(Rect::new(sprite.tile.index() as f32 * tile, 0.0, tile, tile)),
+                rotation: sprite.quarter_turns as f32 * std::f32::consts::FRAC_PI_2,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn draw(&self) {
+        let game = &self.game;
+        let theme = &game.theme;
+        clear_background(BLACK);
+
+        // The board as large as the window allows, centered
+        let cell = (screen_width() / game.width as f32).min(screen_height() / game.height as f32);
+        let (width, height) = (game.width as f32 * cell, game.height as f32 * cell);
+        let origin = vec2((screen_width() - width) / 2.0, (screen_height() - height) / 2.0);
+        draw_rectangle(origin.x, origin.y, width, height, color(theme.background));
+
+        // Walls and food as sprites, portals as plain squares. Snakes are
+        // drawn separately below so they can move smoothly between cells.
+        for (p, kind) in game.cells() {
+            let Some(fill) = theme.cell_color(kind) else { continue };
+            let (x, y) = (p.x as f32, p.y as f32);
+            let tile = match kind {
+                CellKind::Food(_) => Tile::Apple,
+                CellKind::Obstacle => Tile::Wall,
+                CellKind::Portal => {
+                    draw_rectangle(origin.x + x * cell, origin.y + y * cell, cell, cell, color(fill));
+                    continue;
+                }
+                _ => continue,
+            };
+            self.draw_sprite(origin, cell, Sprite { tile, quarter_turns: 0 }, x, y, color(fill));
+        }
+
+        // Tail first, so the head ends up on top
+        for index in 0..game.snake_count() {
+            let visible = game.visible_segments(index);
+            let opacity = game.snake_opacity(index);
+            let segments = game.interpolated_body(index).into_iter().zip(game.snake_sprites(index)).take(visible);
+            for (i, ((x, y), sprite)) in segments.enumerate().rev() {
+                let mut tint = color(game.segment_color(index, i));
+                tint.a *= opacity;
+                self.draw_sprite(origin, cell, sprite, x, y, tint);
+            }
+        }
+
+        let text_color = color(theme.text);
+        let score = format!("Score: {}", game.score);
+        let size = measure_text(&score, None, 20, 1.0);
+        draw_text(&score, origin.x + width - size.width - 10.0, origin.y + 25.0, 20.0, text_color);
+
+        // Same messages as the built-in canvas renderer
+        let message = match game.status() {
+            GameStatus::NotStarted => "Press SPACE to Start".to_string(),
+            GameStatus::Paused => "Paused\nPress P to Resume".to_string(),
+            GameStatus::GameOver => {
+                let headline = game.death_cause.map_or("Game Over".to_string(), |cause| cause.to_string());
+                format!("{}!\nScore: {}\nPress SPACE to Restart", headline, game.score)
+            }
+            GameStatus::Countdown => game.countdown().unwrap_or(0).to_string(),
+            GameStatus::Running | GameStatus::Dying => return,
+        };
+
+        // Dim the board behind the message
+        draw_rectangle(origin.x, origin.y, width, height, Color::new(0.0, 0.0, 0.0, 0.5));
+        let lines: Vec<&str> = message.lines().collect();
+        let line_height = 36.0;
+        let start_y = origin.y + height / 2.0 - (lines.len() as f32 - 1.0) * line_height / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            let size = measure_text(line, None, 30, 1.0);
+            let x = origin.x + (width - size.width) / 2.0;
+            draw_text(line, x, start_y + i as f32 * line_height + size.offset_y / 2.0, 30.0, text_color);
+        }
+    }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let seed = (macroquad::miniquad::date::now() * 1000.0) as u64;
+    let config = GameConfig { width: BOARD_SIZE, height: BOARD_SIZE, seed: Some(seed), ..GameConfig::default() };
+    let game = Game::from_config(&config).expect("the default board is valid");
+
+    let atlas = Texture2D::from_rgba8((TILE_SIZE as usize * Tile::ALL.len()) as u16, TILE_SIZE as u16, &atlas_rgba());
+    atlas.set_filter(FilterMode::Nearest);
+
+    // Audio trouble shouldn't stop the game, a sound that fails to load
+    // just doesn't play
+    let mut sounds = Vec::new();
+    for effect in SoundEffect::ALL {
+        if let Ok(sound) = load_sound_from_bytes(&effect.to_wav(SAMPLE_RATE)).await {
+            sounds.push((effect, sound));
+        }
+    }
+
+    let mut app = App { game, keymap: KeyMap::default(), atlas, sounds, press: None };
+    loop {
+        app.input();
+        app.update();
+        app.draw();
+        next_frame().await;
+    }
+}
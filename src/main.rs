@@ -4,135 +4,2992 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 use ggez::{
+    audio::{SoundData, SoundSource, Source},
     conf,
-    event::{self, EventHandler},
+    event::{self, EventHandler, MouseButton},
     graphics::{self, Color, Rect, Text},
-    input::keyboard::{KeyCode, KeyInput},
-    Context, ContextBuilder, GameResult,
+    input::gamepad::{
+        gilrs::{Axis, Button},
+        GamepadId,
+    },
+    input::keyboard::{KeyCode, KeyInput, KeyMods},
+    Context, ContextBuilder, GameError, GameResult,
 };
 
 // Import the core game logic from our library
-use snake_game::{Direction, Game};
+use snake_game::{
+    atlas_rgba, tones_to_wav, AfkMode, BoardPattern, BossConfig, CellKind, Controller, Direction, FoodKind, FileStorage, Game, GameConfig, GameEvent, GameMode, GameStatus, InputAction, KeyMap, Language, Level, Persistence, Replay, ReplayPlayer, ReplayReport, Rgb,
+    SavedReplay, SessionLog, SessionRecord, Settings, SkinPattern, Skins, SoundEffect, SpeedCurve, Sprite, StepStatus, Theme, Tile, Tutorial, TutorialRun, MAX_NAME_LEN, MUSIC_LOOP, TILE_SIZE, ThemePreset, BINDABLE_ACTIONS, DEFAULT_STICK_DEAD_ZONE,
+};
+use clap::Parser;
+use snake_game::export::{export_apng, ExportOptions};
+#[cfg(feature = "lua")]
+use snake_game::lua::LuaRules;
+#[cfg(feature = "lua")]
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "hot-reload")]
+use std::sync::mpsc;
+#[cfg(feature = "twitch")]
+use snake_game::twitch::{TwitchChat, TwitchPlays};
+#[cfg(feature = "discord")]
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use std::time::Duration;
+
+const PIXEL_SCALE: f32 = 20.0;
+const SAMPLE_RATE: u32 = 44_100;
+
+// Board sizes offered by the settings screen. Small boards are left out,
+// the menus need about 20 cells of width to fit.
+const BOARD_SIZES: [(i32, i32); 4] = [(20, 20), (25, 25), (30, 20), (30, 30)];
+
+// Starting speeds offered by the settings screen, as the tick interval in ms.
+// The names are for `--speed` and the `speed-*` translation keys.
+const SPEEDS: [(&str, u64); 3] = [("slow", 150), ("normal", 100), ("fast", 70)];
+
+// Late turn grace windows offered by the settings screen, as a fraction of
+// a tick, see `Game::queue_turn`
+const INPUT_GRACES: [f32; 4] = [0.0, 0.1, 0.2, 0.3];
+
+const THEMES: [ThemePreset; 3] = [ThemePreset::Classic, ThemePreset::Neon, ThemePreset::ColorblindSafe];
+
+const PATTERNS: [BoardPattern; 3] = [BoardPattern::Plain, BoardPattern::Grid, BoardPattern::Checkerboard];
+
+// Replay playback speeds, cycled with up/down while watching
+const REPLAY_SPEEDS: [f32; 3] = [0.5, 1.0, 4.0];
+
+// Ticks skipped by left/right while watching
+const SEEK_TICKS: u64 = 25;
+
+// Ticks without input before a player counts as away in two-player games,
+// about five seconds at the starting speed. Their snake then only turns to
+// stay alive, see `AfkMode::Safe`.
+const AFK_TICKS: u64 = 50;
+
+// Time without input on the menus before the AI plays a demo game on its
+// own, until a key is pressed
+const ATTRACT_AFTER: Duration = Duration::from_secs(30);
+
+// Modes offered by the menu, cycled with left/right
+const MODES: [GameMode; 3] = [
+    GameMode::Classic,
+    GameMode::Timed { limit_secs: 60 },
+    GameMode::Timed { limit_secs: 120 },
+];
+
+fn color(rgb: Rgb) -> Color {
+    Color::from_rgb(rgb.r, rgb.g, rgb.b)
+}
+
+fn speed_curve(base_ms: u64) -> SpeedCurve {
+    SpeedCurve {
+        base_interval: Duration::from_millis(base_ms),
+        min_interval: Duration::from_millis(base_ms / 2),
+        ..SpeedCurve::default()
+    }
+}
+
+// Screen coordinates that show `area` as large as the window allows,
+// centered and with its aspect ratio kept. The rest of the window shows
+// as bars on the sides or above and below.
+fn letterbox(area: Rect, (window_width, window_height): (f32, f32)) -> Rect {
+    let scale = (window_width / area.w).min(window_height / area.h);
+    let (width, height) = (window_width / scale, window_height / scale);
+    Rect::new(area.x - (width - area.w) / 2.0, area.y - (height - area.h) / 2.0, width, height)
+}
+
+// Minutes and seconds, e.g. 1:05
+fn clock_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// Time since the Unix epoch, for file names and replay dates
+fn unix_time() -> Duration {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default()
+}
+
+// Unix seconds as a UTC date, e.g. 2024-05-01 14:03. Uses the
+// days-to-civil algorithm from http://howardhinnant.github.io/date_algorithms.html
+fn date_time(secs: u64) -> String {
+    let minutes = secs % 86_400 / 60;
+    let z = secs / 86_400 + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+}
+
+// A 0.0 to 1.0 setting as a whole percentage, without the % sign
+fn percent(value: f32) -> u32 {
+    (value * 100.0).round() as u32
+}
+
+// Volumes and the effect intensity go from 0 to 100% in steps of 10%
+fn step_percent(value: f32, delta: isize) -> f32 {
+    ((value * 10.0).round() as isize + delta).clamp(0, 10) as f32 / 10.0
+}
+
+// Steps `index` by `delta` through a list of `len` options, wrapping around
+fn cycle(index: usize, len: usize, delta: isize) -> usize {
+    (index as isize + delta).rem_euclid(len as isize) as usize
+}
+
+// The key map's name for a key, see `KeyMap`
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Up => "arrowup".to_string(),
+        KeyCode::Down => "arrowdown".to_string(),
+        KeyCode::Left => "arrowleft".to_string(),
+        KeyCode::Right => "arrowright".to_string(),
+        KeyCode::Return | KeyCode::NumpadEnter => "enter".to_string(),
+        KeyCode::Back => "backspace".to_string(),
+        // Key1 -> "1"
+        _ => {
+            let name = format!("{:?}", key).to_lowercase();
+            match name.strip_prefix("key") {
+                Some(digit) if !digit.is_empty() => digit.to_string(),
+                _ => name,
+            }
+        }
+    }
+}
+
+fn direction_key(direction: Direction) -> KeyCode {
+    match direction {
+        Direction::Up => KeyCode::Up,
+        Direction::Down => KeyCode::Down,
+        Direction::Left => KeyCode::Left,
+        Direction::Right => KeyCode::Right,
+    }
+}
+
+// Translation keys, see `Language`
+fn action_key(action: InputAction) -> &'static str {
+    match action {
+        InputAction::Turn(Direction::Up) => "action-up",
+        InputAction::Turn(Direction::Down) => "action-down",
+        InputAction::Turn(Direction::Left) => "action-left",
+        InputAction::Turn(Direction::Right) => "action-right",
+        InputAction::Pause => "action-pause",
+        InputAction::Restart => "action-restart",
+        InputAction::Boost => "action-boost",
+        InputAction::Confirm => "action-confirm",
+    }
+}
+
+fn theme_key(theme: ThemePreset) -> &'static str {
+    match theme {
+        ThemePreset::Classic => "theme-classic",
+        ThemePreset::Neon => "theme-neon",
+        ThemePreset::ColorblindSafe => "theme-colorblind-safe",
+    }
+}
+
+fn pattern_key(pattern: BoardPattern) -> &'static str {
+    match pattern {
+        BoardPattern::Plain => "pattern-plain",
+        BoardPattern::Grid => "pattern-grid",
+        BoardPattern::Checkerboard => "pattern-checkerboard",
+    }
+}
+
+// The screens of the app. Everything but `Playing` is a page of text
+// navigated with the arrow keys, Enter to pick and Esc to go back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Menu,
+    Playing,
+    HighScores,
+    Settings,
+    Controls,
+    Replays,
+    Watching, // A replay from the Replays screen
+    Attract, // The AI playing by itself after a while on the menus
+    NameEntry, // After a game that made the high score table
+}
+
+// Asked over a game in progress before leaving it, answered with Enter/Y
+// or Esc/N
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirm {
+    Leave, // Esc, back to the menu
+    Quit, // Closing the window
+}
+
+impl Confirm {
+    fn message_key(self) -> &'static str {
+        match self {
+            Confirm::Leave => "confirm-leave",
+            Confirm::Quit => "confirm-quit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuItem {
+    Resume, // Only listed while there's an unfinished game
+    Play,
+    Tutorial,
+    Mode,
+    Settings,
+    HighScores,
+    Replays,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsItem {
+    BoardSize,
+    Speed,
+    InputGrace,
+    WrapWalls,
+    Bosses,
+    Players,
+    Sound,
+    EffectsVolume,
+    MusicVolume,
+    Theme,
+    Pattern,
+    Skin,
+    ScreenEffects,
+    EffectIntensity,
+    NearMiss,
+    ReducedMotion,
+    Minimap,
+    Language,
+    Controls,
+    Back,
+}
+
+const SETTINGS_ITEMS: [SettingsItem; 20] = [
+    SettingsItem::BoardSize,
+    SettingsItem::Speed,
+    SettingsItem::InputGrace,
+    SettingsItem::WrapWalls,
+    SettingsItem::Bosses,
+    SettingsItem::Players,
+    SettingsItem::Sound,
+    SettingsItem::EffectsVolume,
+    SettingsItem::MusicVolume,
+    SettingsItem::Theme,
+    SettingsItem::Pattern,
+    SettingsItem::Skin,
+    SettingsItem::ScreenEffects,
+    SettingsItem::EffectIntensity,
+    SettingsItem::NearMiss,
+    SettingsItem::ReducedMotion,
+    SettingsItem::Minimap,
+    SettingsItem::Language,
+    SettingsItem::Controls,
+    SettingsItem::Back,
+];
+
+// The core's sound effects rendered to WAV once at startup. Audio trouble
+// (no output device, a sound that won't decode) leaves the game silent
+// instead of stopping it.
+struct Sfx {
+    sources: Vec<(SoundEffect, Source)>,
+}
+
+impl Sfx {
+    fn new(ctx: &Context) -> Sfx {
+        let sources = SoundEffect::ALL
+            .iter()
+            .filter_map(|&effect| match Source::from_data(ctx, SoundData::from(effect.to_wav(SAMPLE_RATE))) {
+                Ok(source) => Some((effect, source)),
+                Err(e) => {
+                    eprintln!("could not load the {:?} sound: {}", effect, e);
+                    None
+                }
+            })
+            .collect();
+        Sfx { sources }
+    }
+
+    fn play(&mut self, ctx: &Context, effect: SoundEffect, volume: f32) {
+        let Some((_, source)) = self.sources.iter_mut().find(|(e, _)| *e == effect) else { return };
+        source.set_volume(volume);
+        if let Err(e) = source.play_detached(ctx) {
+            eprintln!("could not play the {:?} sound: {}", effect, e);
+        }
+    }
+}
+
+const SHAKE_SECS: f32 = 0.35;
+const SHAKE_PIXELS: f32 = 8.0;
+const FLASH_SECS: f32 = 0.3;
+const FLASH_ALPHA: f32 = 0.5;
+const PULSE_SECS: f32 = 0.15;
+const PULSE_ZOOM: f32 = 0.03;
+const FADE_SECS: f32 = 1.2;
+const FADE_ALPHA: f32 = 0.2;
+// The near-miss flourish: the game drops to SLOW_MO_SPEED and eases back
+// to full speed while the camera zooms in and back out
+const SLOW_MO_SECS: f32 = 0.8;
+const SLOW_MO_SPEED: f32 = 0.3;
+const SLOW_MO_ZOOM: f32 = 0.08;
+
+// Screen feedback: a shake and a red flash when the snake dies, a slight
+// zoom pulse when it eats. The shake and pulse move the camera over the
+// board, the HUD stays put. With reduced motion a death only gets a slow
+// red fade in and out, and eating gets nothing. A near miss slows the game
+// down for a moment, see `slow_motion`.
+#[derive(Default)]
+struct Juice {
+    // Seconds left on each effect
+    shake: f32,
+    flash: f32,
+    pulse: f32,
+    fade: f32,
+    slow_mo: f32,
+    time: f32, // Drives the shake wobble
+}
+
+impl Juice {
+    fn trigger(&mut self, event: &GameEvent, reduced_motion: bool) {
+        match event {
+            GameEvent::GameOver { .. } if reduced_motion => self.fade = FADE_SECS,
+            _ if reduced_motion => {}
+            GameEvent::GameOver { .. } => {
+                self.shake = SHAKE_SECS;
+                self.flash = FLASH_SECS;
+            }
+            GameEvent::FoodEaten { .. } => self.pulse = PULSE_SECS,
+            GameEvent::BossAppeared { .. } => self.shake = SHAKE_SECS,
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.shake = (self.shake - dt).max(0.0);
+        self.flash = (self.flash - dt).max(0.0);
+        self.pulse = (self.pulse - dt).max(0.0);
+        self.fade = (self.fade - dt).max(0.0);
+        self.slow_mo = (self.slow_mo - dt).max(0.0);
+    }
+
+    fn slow_motion(&mut self) {
+        self.slow_mo = SLOW_MO_SECS;
+    }
+
+    // How fast the game runs, 1.0 outside of slow motion
+    fn time_scale(&self) -> f32 {
+        1.0 - (1.0 - SLOW_MO_SPEED) * self.slow_mo / SLOW_MO_SECS
+    }
+
+    // Screen coordinates for the board layer, each effect fading out as
+    // its time runs down. Shrinking the visible area zooms in.
+    fn camera(&self, width: f32, height: f32, intensity: f32) -> Rect {
+        let shake = SHAKE_PIXELS * intensity * self.shake / SHAKE_SECS;
+        let (dx, dy) = (shake * (self.time * 90.0).sin(), shake * (self.time * 73.0).cos());
+        let zoom = 1.0 + intensity * (PULSE_ZOOM * self.pulse / PULSE_SECS + SLOW_MO_ZOOM * self.slow_mo / SLOW_MO_SECS);
+        let (w, h) = (width / zoom, height / zoom);
+        Rect::new((width - w) / 2.0 + dx, (height - h) / 2.0 + dy, w, h)
+    }
+
+    fn flash_alpha(&self, intensity: f32) -> f32 {
+        let flash = FLASH_ALPHA * self.flash / FLASH_SECS;
+        let fade = FADE_ALPHA * (std::f32::consts::PI * self.fade / FADE_SECS).sin();
+        intensity * flash.max(fade)
+    }
+}
+
+// Cells smaller than this many pixels on screen are hard to follow, the
+// camera shows part of the board around the head instead
+const MIN_CELL_PIXELS: f32 = 12.0;
+
+// Zoom levels on top of that, changed with +/-
+const ZOOMS: [f32; 4] = [1.0, 1.5, 2.0, 3.0];
+
+// How quickly the camera catches up with the head, higher is snappier
+const PAN_SPEED: f32 = 6.0;
+
+// Boards bigger than this open a window this size and scroll
+const MAX_WINDOW: (f32, f32) = (1280.0, 900.0);
+
+// The minimap's longest side, in screen coordinates
+const MINIMAP_SIZE: f32 = 100.0;
+
+// The part of the board on screen, in board coordinates like everything
+// drawn. It's the whole board unless that would make cells too small to
+// read or the player zoomed in, then it follows the head.
+#[derive(Default)]
+struct Camera {
+    center: (f32, f32),
+    view: (f32, f32), // Size of the part on screen
+    board: (f32, f32),
+    zoom: usize, // Index into ZOOMS
+}
+
+impl Camera {
+    // Sizes the view for the window, called every frame since the window
+    // or the board may have changed. Menus aren't zoomed.
+    fn fit(&mut self, window: (f32, f32), board: (f32, f32), zoomed: bool) {
+        let whole = (window.0 / board.0).min(window.1 / board.1);
+        let zoom = if zoomed { ZOOMS[self.zoom] } else { 1.0 };
+        let scale = whole.max(MIN_CELL_PIXELS / PIXEL_SCALE) * zoom;
+        self.view = ((window.0 / scale).min(board.0), (window.1 / scale).min(board.1));
+        self.board = board;
+        self.center = self.clamp(self.center);
+    }
+
+    // Pans toward the head, or jumps there when it's far away: a new game,
+    // wrapping around the edge or going through a portal
+    fn follow(&mut self, target: (f32, f32), dt: f32) {
+        let target = self.clamp(target);
+        let (dx, dy) = (target.0 - self.center.0, target.1 - self.center.1);
+        if dx.abs() > self.view.0 / 2.0 || dy.abs() > self.view.1 / 2.0 {
+            self.center = target;
+        } else {
+            let t = 1.0 - (-PAN_SPEED * dt).exp();
+            self.center = (self.center.0 + dx * t, self.center.1 + dy * t);
+        }
+    }
+
+    // Keeps the view inside the board
+    fn clamp(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (width, height) = self.view;
+        (x.clamp(width / 2.0, self.board.0 - width / 2.0), y.clamp(height / 2.0, self.board.1 - height / 2.0))
+    }
+
+    fn zoom(&mut self, delta: isize) {
+        self.zoom = self.zoom.saturating_add_signed(delta).min(ZOOMS.len() - 1);
+    }
+
+    fn area(&self) -> Rect {
+        Rect::new(self.center.0 - self.view.0 / 2.0, self.center.1 - self.view.1 / 2.0, self.view.0, self.view.1)
+    }
+
+    // Whether some of the board is off screen
+    fn following(&self) -> bool {
+        self.view.0 < self.board.0 || self.view.1 < self.board.1
+    }
+}
+
+// The looping background music. The core picks a tempo for the game, the
+// loop restarts at the new pitch whenever that changes.
+struct Music {
+    source: Option<Source>,
+    tempo: f32,
+}
+
+impl Music {
+    fn new(ctx: &Context) -> Music {
+        let source = match Source::from_data(ctx, SoundData::from(tones_to_wav(&MUSIC_LOOP, SAMPLE_RATE))) {
+            Ok(mut source) => {
+                source.set_repeat(true);
+                Some(source)
+            }
+            Err(e) => {
+                eprintln!("could not load the music: {}", e);
+                None
+            }
+        };
+        Music { source, tempo: 1.0 }
+    }
+
+    // Plays at `tempo`, or holds the music with `None`
+    fn update(&mut self, ctx: &Context, tempo: Option<f32>, volume: f32) {
+        let Some(source) = &mut self.source else { return };
+        source.set_volume(volume);
+        match tempo {
+            Some(tempo) if tempo != self.tempo || source.stopped() => {
+                self.tempo = tempo;
+                source.set_pitch(tempo);
+                if let Err(e) = source.play(ctx) {
+                    eprintln!("could not play the music: {}", e);
+                    self.source = None;
+                }
+            }
+            Some(_) if source.paused() => source.resume(),
+            Some(_) => {}
+            None if source.playing() => source.pause(),
+            None => {}
+        }
+    }
+}
+
+// A gamepad, kept in the order pads were first used
+struct Pad {
+    id: GamepadId,
+    stick: (f32, f32),
+    // Only a change of stick direction turns the snake, like a key press
+    stick_direction: Option<Direction>,
+}
+
+// Lines of output the debug console keeps, the newest last
+#[cfg(feature = "console")]
+const CONSOLE_LINES: usize = 10;
+
+// The backtick console (`console` feature): the line being typed and what
+// the last commands printed
+#[cfg(feature = "console")]
+#[derive(Default)]
+struct Console {
+    open: bool,
+    line: String,
+    log: Vec<String>,
+}
+
+#[cfg(feature = "console")]
+impl Console {
+    // Runs the typed line on `game`, logging it and the answer
+    fn submit(&mut self, game: &mut Game) {
+        let line = std::mem::take(&mut self.line);
+        if line.trim().is_empty() {
+            return;
+        }
+        let answer = snake_game::console::run(game, &line).unwrap_or_else(|e| e.to_string());
+        self.log.push(format!("> {}", line.trim()));
+        self.log.extend(answer.lines().map(str::to_string));
+        let excess = self.log.len().saturating_sub(CONSOLE_LINES);
+        self.log.drain(..excess);
+    }
+}
+
+// Discord application Rich Presence shows up as, set when building a
+// release (SNAKE_DISCORD_APP_ID=... cargo build) or with --discord-app-id
+#[cfg(feature = "discord")]
+const DISCORD_APP_ID: Option<&str> = option_env!("SNAKE_DISCORD_APP_ID");
+
+// Wait before trying Discord again after it wasn't there, and between two
+// updates (it drops updates sent faster than about one per 4 s)
+#[cfg(feature = "discord")]
+const PRESENCE_RETRY: Duration = Duration::from_secs(15);
+#[cfg(feature = "discord")]
+const PRESENCE_INTERVAL: Duration = Duration::from_secs(4);
+
+// What Discord shows under the player's name: what they're doing, how it's
+// going and since when, in unix seconds. See `AppState::activity`.
+#[cfg(feature = "discord")]
+#[derive(Debug, Clone, PartialEq)]
+struct Activity {
+    details: String,
+    state: String,
+    start: Option<i64>,
+}
+
+#[cfg(feature = "discord")]
+impl Activity {
+    // The start is worked out from the game clock every frame and wobbles
+    // by a second, that isn't worth an update
+    fn same_as(&self, other: &Activity) -> bool {
+        let start = match (self.start, other.start) {
+            (Some(a), Some(b)) => (a - b).abs() <= 2,
+            (a, b) => a == b,
+        };
+        self.details == other.details && self.state == other.state && start
+    }
+}
+
+// Discord Rich Presence (`discord` feature). When Discord isn't running, or
+// quits, nothing shows and the connection is tried again now and then.
+#[cfg(feature = "discord")]
+struct Presence {
+    client: DiscordIpcClient,
+    connected: bool,
+    shown: Option<Activity>,
+    wait: Duration,
+}
+
+#[cfg(feature = "discord")]
+impl Presence {
+    fn new(app_id: &str) -> Presence {
+        Presence { client: DiscordIpcClient::new(app_id), connected: false, shown: None, wait: Duration::ZERO }
+    }
+
+    // Sends `activity` if it isn't what's shown, `PRESENCE_INTERVAL` at
+    // most after the last update
+    fn update(&mut self, dt: Duration, activity: Activity) {
+        self.wait = self.wait.saturating_sub(dt);
+        if !self.wait.is_zero() || self.shown.as_ref().is_some_and(|shown| shown.same_as(&activity)) {
+            return;
+        }
+        if !self.connected && self.client.connect().is_err() {
+            self.wait = PRESENCE_RETRY;
+            return;
+        }
+        self.connected = true;
+        let mut payload = activity::Activity::new().details(activity.details.as_str());
+        if !activity.state.is_empty() {
+            payload = payload.state(activity.state.as_str());
+        }
+        if let Some(start) = activity.start {
+            payload = payload.timestamps(activity::Timestamps::new().start(start));
+        }
+        match self.client.set_activity(payload) {
+            Ok(()) => {
+                self.shown = Some(activity);
+                self.wait = PRESENCE_INTERVAL;
+            }
+            Err(_) => {
+                let _ = self.client.close();
+                self.connected = false;
+                self.shown = None;
+                self.wait = PRESENCE_RETRY;
+            }
+        }
+    }
+}
+
+// Watches settings.json and the --theme file (`hot-reload` feature). Their
+// directories are watched rather than the files, editors often save by
+// writing a new file in place of the old one.
+#[cfg(feature = "hot-reload")]
+struct HotReload {
+    _watcher: notify::RecommendedWatcher,
+    changes: mpsc::Receiver<notify::Result<notify::Event>>,
+    settings: PathBuf,
+    theme: Option<PathBuf>,
+    // Why the last change couldn't be applied, shown in the debug overlay
+    error: Option<String>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl HotReload {
+    fn new(settings: PathBuf, theme: Option<PathBuf>) -> notify::Result<HotReload> {
+        use notify::Watcher;
+        let settings = std::path::absolute(settings)?;
+        let theme = theme.map(std::path::absolute).transpose()?;
+        let (sender, changes) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for file in std::iter::once(&settings).chain(&theme) {
+            let dir = file.parent().unwrap_or(Path::new("."));
+            // The settings directory only exists once something was saved
+            std::fs::create_dir_all(dir)?;
+            watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+        }
+        Ok(HotReload { _watcher: watcher, changes, settings, theme, error: None })
+    }
+
+    // The watched files written since the last call
+    fn changed(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for event in self.changes.try_iter().flatten() {
+            if !event.kind.is_create() && !event.kind.is_modify() {
+                continue;
+            }
+            for path in event.paths {
+                let watched = path == self.settings || self.theme.as_ref() == Some(&path);
+                if watched && !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+        changed
+    }
+}
+
+// Command line options. They take precedence over settings.json, and end
+// up in it only if the settings screen saves.
+#[derive(Parser, Debug)]
+#[command(version, about = "Snake, the native version")]
+struct Args {
+    #[arg(long, help = "Board width in cells")]
+    width: Option<i32>,
+    #[arg(long, help = "Board height in cells")]
+    height: Option<i32>,
+    #[arg(long, value_parser = parse_speed, help = "Starting speed: slow, normal, fast or a tick interval in ms")]
+    speed: Option<u64>,
+    #[arg(long, help = "Random seed, every game of the session gets the same food")]
+    seed: Option<u64>,
+    #[arg(long, value_parser = parse_mode, help = "classic, timed (60 s) or timed:<seconds>")]
+    mode: Option<GameMode>,
+    #[arg(long, help = "Leaving the board comes back in on the other side")]
+    wrap: bool,
+    #[arg(long, value_name = "FILE", help = "Play on a level from a text file")]
+    level: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Draw with the colors of a JSON theme file")]
+    theme: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Watch a replay exported as base64 text")]
+    replay: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", requires = "replay", help = "Save the --replay as an animated PNG instead of watching it")]
+    export: Option<PathBuf>,
+    #[arg(long, default_value_t = 16, help = "Pixels per cell in the --export animation")]
+    export_scale: u32,
+    #[arg(long, default_value_t = 1.0, help = "Speed of the --export animation, 2 plays twice as fast")]
+    export_speed: f32,
+    #[arg(long, help = "Start in fullscreen")]
+    fullscreen: bool,
+    #[arg(long, value_name = "FILE", help = "Append a JSON line per finished game (settings, seed, result, stats) to a file")]
+    session_log: Option<PathBuf>,
+    #[cfg(feature = "lua")]
+    #[arg(long, value_name = "FILE", help = "Play with custom rules from a Lua script")]
+    rules: Option<PathBuf>,
+    #[cfg(feature = "discord")]
+    #[arg(long, value_name = "ID", help = "Discord application to show the game in Rich Presence as")]
+    discord_app_id: Option<String>,
+}
+
+impl Args {
+    fn apply(&self, config: &mut GameConfig) {
+        config.width = self.width.unwrap_or(config.width);
+        config.height = self.height.unwrap_or(config.height);
+        if let Some(ms) = self.speed {
+            config.speed_curve = speed_curve(ms);
+        }
+        config.wrap_walls |= self.wrap;
+    }
+
+    // A game shaped by the command line isn't the one that was autosaved
+    fn changes_game(&self) -> bool {
+        self.width.is_some()
+            || self.height.is_some()
+            || self.speed.is_some()
+            || self.seed.is_some()
+            || self.mode.is_some()
+            || self.wrap
+            || self.level.is_some()
+            || self.replay.is_some()
+            || self.has_rules()
+    }
+
+    #[cfg(feature = "lua")]
+    fn has_rules(&self) -> bool {
+        self.rules.is_some()
+    }
+
+    #[cfg(not(feature = "lua"))]
+    fn has_rules(&self) -> bool {
+        false
+    }
+}
+
+fn parse_speed(text: &str) -> Result<u64, String> {
+    if let Some(&(_, ms)) = SPEEDS.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)) {
+        return Ok(ms);
+    }
+    match text.parse() {
+        Ok(ms) if ms > 0 => Ok(ms),
+        _ => Err(format!("'{}' is not slow, normal, fast or a number of ms", text)),
+    }
+}
+
+fn parse_mode(text: &str) -> Result<GameMode, String> {
+    match text.to_ascii_lowercase().split_once(':') {
+        None if text.eq_ignore_ascii_case("classic") => Ok(GameMode::Classic),
+        None if text.eq_ignore_ascii_case("timed") => Ok(MODES[1]),
+        Some(("timed", secs)) => match secs.parse() {
+            Ok(limit_secs) if limit_secs > 0 => Ok(GameMode::Timed { limit_secs }),
+            _ => Err(format!("'{}' is not a number of seconds", secs)),
+        },
+        _ => Err(format!("'{}' is not classic, timed or timed:<seconds>", text)),
+    }
+}
+
+fn read_level(path: &Path) -> GameResult<Level> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| GameError::CustomError(format!("could not read {}: {}", path.display(), e)))?;
+    Level::parse(&text).map_err(|e| GameError::CustomError(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(feature = "lua")]
+fn read_rules(path: &Path) -> GameResult<LuaRules> {
+    LuaRules::from_file(path).map_err(|e| GameError::CustomError(e.to_string()))
+}
+
+fn read_theme(path: &Path) -> GameResult<Theme> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| GameError::CustomError(format!("could not read {}: {}", path.display(), e)))?;
+    Theme::from_json(&text).map_err(|e| GameError::CustomError(format!("{}: {}", path.display(), e)))
+}
+
+fn read_replay(path: &Path) -> GameResult<Replay> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| GameError::CustomError(format!("could not read {}: {}", path.display(), e)))?;
+    Replay::from_base64(&text).map_err(|e| GameError::CustomError(format!("{}: {}", path.display(), e)))
+}
+
+// What went wrong, without ggez's "Custom error" in front
+#[cfg(feature = "hot-reload")]
+fn error_reason(error: GameError) -> String {
+    match error {
+        GameError::CustomError(reason) => reason,
+        error => error.to_string(),
+    }
+}
+
+// Writes a replay as an animated PNG, see `snake_game::export`
+fn export_replay(replay: &Replay, options: &ExportOptions, path: &Path) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    export_apng(replay, options, std::io::BufWriter::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// What the command line fixed for the whole session, kept out of the
+// settings so it isn't saved
+struct Overrides {
+    seed: Option<u64>,
+    level: Option<Level>,
+    theme: Option<Theme>,
+    #[cfg(feature = "lua")]
+    rules: Option<Arc<LuaRules>>,
+}
+
+impl Overrides {
+    // A game with the settings' rules, on the level if there is one
+    fn build_game(&self, config: &GameConfig) -> GameResult<Game> {
+        let config = GameConfig {
+            seed: self.seed.or(config.seed),
+            afk_ticks: AFK_TICKS,
+            afk_mode: AfkMode::Safe,
+            ..config.clone()
+        };
+        let mut game = match &self.level {
+            Some(level) => Game::from_level_with_config(level, &config),
+            None => Game::from_config(&config),
+        }
+        .map_err(|e| GameError::CustomError(e.to_string()))?;
+        game.track_visits = true;
+        game.theme = self.theme(&config);
+        #[cfg(feature = "lua")]
+        {
+            game.rules = self.rules.clone().map(|rules| rules as Arc<dyn snake_game::Rules>);
+        }
+        Ok(game)
+    }
+
+    // The --theme file's colors, or the settings' preset
+    fn theme(&self, config: &GameConfig) -> Theme {
+        self.theme.unwrap_or(config.theme.into())
+    }
+
+    // Script errors go to the terminal, the round goes on without the hook
+    fn report_rules_errors(&self) {
+        #[cfg(feature = "lua")]
+        if let Some(error) = self.rules.as_ref().and_then(|rules| rules.take_error()) {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+// Struct to hold the application state for ggez
+struct AppState {
+    game: Game,
+    screen: Screen,
+    menu_index: usize,
+    mode: GameMode, // Picked in the menu, used by the next new game
+    settings_index: usize,
+    // Row of the controls screen; the actions come first, then Reset and Back
+    controls_index: usize,
+    // Waiting for the key to bind to the selected action
+    binding: bool,
+    // Settings slider following the mouse while the button is held
+    dragging: Option<SettingsItem>,
+    // Board size, speed, theme... saved to settings.json on every change
+    settings: Settings,
+    overrides: Overrides,
+    pads: Vec<Pad>,
+    sfx: Sfx,
+    music: Music,
+    juice: Juice,
+    camera: Camera,
+    fullscreen: bool, // Toggled with F11 or Alt+Enter
+    debug_overlay: bool, // F3: timing, seed, state hash and AI paths for bug reports
+    heatmap: bool, // H: where the head went, after a game or over a replay
+    report: Option<ReplayReport>, // A: the analysis of the replay being watched
+    screenshot_requested: bool, // F12, saved once the frame is drawn
+    confirm: Option<Confirm>, // Question shown over the paused game
+    quit_confirmed: bool, // The window closes without asking again
+    // Pixel art for snakes, food and walls, tinted with the theme colors
+    atlas: graphics::Image,
+    // Recording of the last finished game until it's saved, listed on the
+    // Replays screen above the saved ones
+    last_replay: Option<Replay>,
+    replays_index: usize,
+    // The replay on the Watching screen, `game` is kept for resuming
+    player: Option<ReplayPlayer>,
+    // Score waiting for a name on the NameEntry screen, and the name so far
+    pending_score: Option<u32>,
+    name: String,
+    // Row of the high score table to point out, the one just entered
+    highlight: Option<usize>,
+    // High scores and the autosave live in ggez's user data directory
+    persistence: Persistence<FileStorage>,
+    best_score: Option<u32>,
+    // --session-log, and when the current round started for its duration
+    session_log: Option<SessionLog>,
+    round_started: Duration,
+    // Menu > Tutorial, the step being played. Kept once finished for its
+    // last prompt, until the game after it starts.
+    tutorial: Option<TutorialRun>,
+    // The game on the Attract screen, and how long the menus went without
+    // input
+    demo: Option<Game>,
+    idle: Duration,
+    // The skins to pick from, the ones this player unlocked, and the ones
+    // the last round unlocked for the game over screen
+    skins: Skins,
+    unlocked_skins: Vec<String>,
+    new_skins: Vec<String>,
+    #[cfg(feature = "console")]
+    console: Console,
+    #[cfg(feature = "hot-reload")]
+    hot_reload: Option<HotReload>,
+    // `None` without a Discord application ID
+    #[cfg(feature = "discord")]
+    presence: Option<Presence>,
+    // The chat of the settings' `twitchChannel` steering the player
+    #[cfg(feature = "twitch")]
+    twitch: Option<TwitchPlays>,
+}
+
+impl AppState {
+    fn new(ctx: &mut Context, args: Args) -> GameResult<AppState> {
+        let persistence = Persistence::new(FileStorage::new(ctx.fs.user_data_dir()), "");
+        let mut settings = persistence.load_settings();
+        args.apply(&mut settings.config);
+        let overrides = Overrides {
+            seed: args.seed,
+            level: args.level.as_deref().map(read_level).transpose()?,
+            theme: args.theme.as_deref().map(read_theme).transpose()?,
+            #[cfg(feature = "lua")]
+            rules: args.rules.as_deref().map(read_rules).transpose()?.map(Arc::new),
+        };
+        // A hand-edited settings file may ask for a board the game can't use
+        let mut game = overrides
+            .build_game(&settings.config)
+            .or_else(|_| {
+                let config = &settings.config;
+                overrides.build_game(&GameConfig { theme: config.theme, reduced_motion: config.reduced_motion, ..GameConfig::default() })
+            })?;
+        let replay = args.replay.as_deref().map(read_replay).transpose()?;
+        let mut player = replay.clone().map(ReplayPlayer::new).transpose().map_err(|e| GameError::CustomError(e.to_string()))?;
+        if let Some(player) = &mut player {
+            player.set_reduced_motion(settings.config.reduced_motion);
+        }
+        if replay.is_none() && !args.changes_game() {
+            // Pick up where the last session left off, paused
+            if let Some(snapshot) = persistence.load_autosave(&game) {
+                game.restore(&snapshot);
+                game.pause();
+            }
+        }
+        let best_score = persistence.load_high_scores().best();
+        let unlocked_skins = persistence.load_unlocked_skins();
+        let mut state = AppState {
+            game,
+            screen: if player.is_some() { Screen::Watching } else { Screen::Menu },
+            menu_index: 0,
+            mode: args.mode.unwrap_or(MODES[0]),
+            settings_index: 0,
+            controls_index: 0,
+            binding: false,
+            dragging: None,
+            pads: Vec::new(),
+            sfx: Sfx::new(ctx),
+            music: Music::new(ctx),
+            juice: Juice::default(),
+            camera: Camera::default(),
+            fullscreen: false,
+            debug_overlay: false,
+            heatmap: false,
+            report: None,
+            screenshot_requested: false,
+            confirm: None,
+            quit_confirmed: false,
+            atlas: graphics::Image::from_pixels(
+                ctx,
+                &atlas_rgba(),
+                graphics::ImageFormat::Rgba8UnormSrgb,
+                TILE_SIZE * Tile::ALL.len() as u32,
+                TILE_SIZE,
+            ),
+            settings,
+            overrides,
+            last_replay: replay,
+            replays_index: 0,
+            player,
+            pending_score: None,
+            name: String::new(),
+            highlight: None,
+            persistence,
+            best_score,
+            session_log: args.session_log.clone().map(SessionLog::new),
+            round_started: unix_time(),
+            tutorial: None,
+            demo: None,
+            idle: Duration::ZERO,
+            skins: Skins::builtin(),
+            unlocked_skins,
+            new_skins: Vec::new(),
+            #[cfg(feature = "console")]
+            console: Console::default(),
+            #[cfg(feature = "hot-reload")]
+            hot_reload: None,
+            #[cfg(feature = "discord")]
+            presence: args.discord_app_id.as_deref().or(DISCORD_APP_ID).map(Presence::new),
+            #[cfg(feature = "twitch")]
+            twitch: None,
+        };
+        state.game.theme = state.theme();
+        #[cfg(feature = "twitch")]
+        if !state.settings.twitch_channel.trim().is_empty() {
+            let chat = TwitchChat::connect(&state.settings.twitch_channel);
+            state.twitch = Some(TwitchPlays::new(chat, state.settings.twitch_vote_ticks, 0));
+        }
+        #[cfg(feature = "hot-reload")]
+        {
+            state.hot_reload = HotReload::new(state.persistence.settings_path(), args.theme.clone())
+                .inspect_err(|e| eprintln!("could not watch the settings for changes: {}", e))
+                .ok();
+        }
+        let (width, height) = state.window_size();
+        ctx.gfx.set_drawable_size(width, height)?;
+        state.camera.fit((width, height), state.board_size(), false);
+        if args.fullscreen {
+            state.toggle_fullscreen(ctx)?;
+        }
+        Ok(state)
+    }
+
+    // Draws a tile over the cell at (x, y), which may be between cells
+    fn draw_sprite(&self, canvas: &mut graphics::Canvas, sprite: Sprite, x: f32, y: f32, tint: Color) {
+        let tiles = Tile::ALL.len() as f32;
+        let scale = PIXEL_SCALE / TILE_SIZE as f32;
+        canvas.draw(
+            &self.atlas,
+            graphics::DrawParam::new()
+                .src(Rect::new(sprite.tile.index() as f32 / tiles, 0.0, 1.0 / tiles, 1.0))
+                .dest([(x + 0.5) * PIXEL_SCALE, (y + 0.5) * PIXEL_SCALE])
+                .offset([0.5, 0.5])
+                .rotation(sprite.quarter_turns as f32 * std::f32::consts::FRAC_PI_2)
+                .scale([scale, scale])
+                .color(tint),
+        );
+    }
+
+    // Grid lines or checkerboard squares under the board, see `BoardPattern`
+    fn draw_pattern(&self, canvas: &mut graphics::Canvas) {
+        let game = self.shown_game();
+        let (width, height) = self.board_size();
+        let theme = &game.theme;
+        match self.settings.board_pattern {
+            BoardPattern::Plain => {}
+            BoardPattern::Grid => {
+                let line = color(theme.grid_color());
+                for x in 1..game.width {
+                    let dest = [x as f32 * PIXEL_SCALE, 0.0];
+                    canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(dest).scale([1.0, height]).color(line));
+                }
+                for y in 1..game.height {
+                    let dest = [0.0, y as f32 * PIXEL_SCALE];
+                    canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(dest).scale([width, 1.0]).color(line));
+                }
+            }
+            BoardPattern::Checkerboard => {
+                let square = color(theme.checker_color());
+                for y in 0..game.height {
+                    for x in (0..game.width).filter(|x| (x + y) % 2 == 1) {
+                        let dest = [x as f32 * PIXEL_SCALE, y as f32 * PIXEL_SCALE];
+                        let param = graphics::DrawParam::new().dest(dest).scale([PIXEL_SCALE, PIXEL_SCALE]).color(square);
+                        canvas.draw(&graphics::Quad, param);
+                    }
+                }
+            }
+        }
+    }
+
+    // The game on screen: the replay being watched, the demo, or the
+    // player's own
+    fn shown_game(&self) -> &Game {
+        match (&self.player, &self.demo, self.screen) {
+            (Some(player), _, Screen::Watching) => player.game(),
+            (_, Some(demo), Screen::Attract) => demo,
+            _ => &self.game,
+        }
+    }
+
+    // The pages that fall back to the attract mode when left alone
+    fn can_attract(&self) -> bool {
+        matches!(self.screen, Screen::Menu | Screen::HighScores | Screen::Settings | Screen::Replays)
+            || (self.screen == Screen::Controls && !self.binding)
+    }
+
+    // A single-player game on the player's board with the pathfinder
+    // steering, started right away
+    fn start_attract(&mut self) -> GameResult {
+        let config = GameConfig {
+            seed: None,
+            mode: GameMode::Classic,
+            opponents: Vec::new(),
+            countdown_secs: 0,
+            ..self.settings.config.clone()
+        };
+        let mut demo = Game::from_config(&config).map_err(|e| GameError::CustomError(e.to_string()))?;
+        demo.set_controller(0, Some(Controller::Pathfinder));
+        demo.theme = self.theme();
+        demo.start_game();
+        self.demo = Some(demo);
+        self.screen = Screen::Attract;
+        Ok(())
+    }
+
+    // Backtick opens the console over a game being played and pauses it.
+    // While it's open it takes every key, returning true.
+    #[cfg(feature = "console")]
+    fn console_key(&mut self, key: KeyCode) -> bool {
+        if !self.console.open {
+            if key != KeyCode::Grave || self.screen != Screen::Playing {
+                return false;
+            }
+            self.console.open = true;
+            self.game.pause();
+            return true;
+        }
+        match key {
+            KeyCode::Grave | KeyCode::Escape => self.console.open = false,
+            KeyCode::Back => {
+                self.console.line.pop();
+            }
+            KeyCode::Return | KeyCode::NumpadEnter => self.console.submit(&mut self.game),
+            _ => {}
+        }
+        true
+    }
+
+    // Any input starts the idle time over. Returns true when it ended the
+    // attract mode, back on the menu, the input is used up then.
+    fn wake(&mut self) -> bool {
+        self.idle = Duration::ZERO;
+        if self.screen != Screen::Attract {
+            return false;
+        }
+        self.demo = None;
+        self.screen = Screen::Menu;
+        self.menu_index = 0;
+        true
+    }
+
+    fn board_size(&self) -> (f32, f32) {
+        (self.shown_game().width as f32 * PIXEL_SCALE, self.shown_game().height as f32 * PIXEL_SCALE)
+    }
+
+    // The size of the screen in board coordinates: the whole board, or the
+    // part the camera shows. The window can be any size, `draw` scales this
+    // to fit.
+    fn screen_size(&self) -> (f32, f32) {
+        self.camera.view
+    }
+
+    // The window a board asks for, big boards scroll instead
+    fn window_size(&self) -> (f32, f32) {
+        let (width, height) = self.board_size();
+        (width.min(MAX_WINDOW.0), height.min(MAX_WINDOW.1))
+    }
+
+    // Leaving fullscreen goes back to a window fitting the board
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) -> GameResult {
+        self.fullscreen = !self.fullscreen;
+        if self.fullscreen {
+            ctx.gfx.set_fullscreen(conf::FullscreenType::Desktop)
+        } else {
+            ctx.gfx.set_fullscreen(conf::FullscreenType::Windowed)?;
+            let (width, height) = self.window_size();
+            ctx.gfx.set_drawable_size(width, height)
+        }
+    }
+
+    // A game that was started, not finished and isn't a replay
+    fn can_resume(&self) -> bool {
+        self.game.game_started && !self.game.game_over && !self.game.is_replay()
+    }
+
+    fn menu_items(&self) -> Vec<MenuItem> {
+        let mut items = Vec::new();
+        if self.can_resume() {
+            items.push(MenuItem::Resume);
+        }
+        items.extend([
+            MenuItem::Play,
+            MenuItem::Tutorial,
+            MenuItem::Mode,
+            MenuItem::Settings,
+            MenuItem::HighScores,
+            MenuItem::Replays,
+            MenuItem::Quit,
+        ]);
+        items
+    }
+
+    fn menu_label(&self, item: MenuItem) -> String {
+        match item {
+            MenuItem::Resume => self.tr("menu-resume").to_string(),
+            MenuItem::Play => self.tr("menu-play").to_string(),
+            MenuItem::Tutorial => self.tr("menu-tutorial").to_string(),
+            MenuItem::Mode => self.trf("menu-mode", &[("mode", &self.mode_name(self.mode))]),
+            MenuItem::Settings => self.tr("menu-settings").to_string(),
+            MenuItem::HighScores => self.tr("menu-high-scores").to_string(),
+            MenuItem::Replays => self.tr("menu-replays").to_string(),
+            MenuItem::Quit => self.tr("menu-quit").to_string(),
+        }
+    }
+
+    // Text in the language picked in the settings
+    fn tr<'a>(&self, key: &'a str) -> &'a str {
+        self.settings.language.text(key)
+    }
+
+    fn trf(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        self.settings.language.format(key, args)
+    }
+
+    fn on_off(&self, on: bool) -> &'static str {
+        self.tr(if on { "on" } else { "off" })
+    }
+
+    fn mode_name(&self, mode: GameMode) -> String {
+        match mode {
+            GameMode::Classic => self.tr("mode-classic").to_string(),
+            GameMode::Timed { limit_secs } => self.trf("mode-timed", &[("secs", &limit_secs)]),
+        }
+    }
+
+    // A mode from the command line that isn't in the menu starts over at Classic
+    fn cycle_mode(&mut self, delta: isize) {
+        let current = MODES.iter().position(|&m| m == self.mode).unwrap_or(MODES.len() - 1);
+        self.mode = MODES[cycle(current, MODES.len(), delta)];
+    }
+
+    fn new_game(&mut self) -> GameResult {
+        let config = GameConfig { mode: self.mode, ..self.settings.config.clone() };
+        self.game = self.build_game(&config)?;
+        self.game.start_game();
+        self.screen = Screen::Playing;
+        self.tutorial = None;
+        self.new_skins.clear();
+        Ok(())
+    }
+
+    // The theme colors with the player's skin on top. A --theme file's own
+    // skin shows through the classic one.
+    fn theme(&self) -> Theme {
+        let mut theme = self.overrides.theme(&self.settings.config);
+        match self.skins.pattern(&self.settings.skin, &self.unlocked_skins) {
+            SkinPattern::Solid => {}
+            pattern => theme.skin = pattern,
+        }
+        theme
+    }
+
+    fn build_game(&self, config: &GameConfig) -> GameResult<Game> {
+        let mut game = self.overrides.build_game(config)?;
+        game.theme = self.theme();
+        Ok(game)
+    }
+
+    // The current tutorial step's game, started. After the last step it's
+    // the board of a regular game, waiting for Space under the last prompt.
+    fn tutorial_step(&mut self) -> GameResult {
+        let Some(tutorial) = &self.tutorial else { return Ok(()) };
+        match tutorial.current() {
+            Some(step) => {
+                self.game = step.game(&self.settings.config).map_err(|e| GameError::CustomError(e.to_string()))?;
+                self.game.track_visits = true;
+                self.game.theme = self.theme();
+                self.game.start_game();
+            }
+            None => self.game = self.build_game(&GameConfig { mode: self.mode, ..self.settings.config.clone() })?,
+        }
+        self.screen = Screen::Playing;
+        Ok(())
+    }
+
+    // Tutorial rounds don't count: no replay, high score, session log or
+    // autosave. Dying shows the game over screen, restarting plays the
+    // step again.
+    fn update_tutorial(&mut self, ctx: &mut Context, events: &[GameEvent]) -> GameResult {
+        for event in events {
+            self.feedback(ctx, event);
+        }
+        let Some(tutorial) = &mut self.tutorial else { return Ok(()) };
+        if tutorial.update(&self.game, events) == StepStatus::Done {
+            self.tutorial_step()?;
+        }
+        Ok(())
+    }
+
+    // The Replays screen lists the unsaved last game first, then the saved ones
+    fn replay_rows(&self) -> Vec<(String, Option<usize>)> {
+        let mut rows = Vec::new();
+        if let Some(replay) = &self.last_replay {
+            rows.push((self.trf("replay-last-game", &[("score", &replay.score)]), None));
+        }
+        for (i, saved) in self.persistence.load_replays().iter().enumerate() {
+            rows.push((self.trf("replay-saved", &[("date", &date_time(saved.saved_at)), ("score", &saved.score)]), Some(i)));
+        }
+        rows
+    }
+
+    fn replays_key(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        let rows = self.replay_rows();
+        if rows.is_empty() {
+            if matches!(key, KeyCode::Escape | KeyCode::Back | KeyCode::Return | KeyCode::NumpadEnter) {
+                self.screen = Screen::Menu;
+            }
+            return Ok(());
+        }
+        self.replays_index = self.replays_index.min(rows.len() - 1);
+        let saved = rows[self.replays_index].1;
+        match key {
+            KeyCode::Up | KeyCode::W => self.replays_index = cycle(self.replays_index, rows.len(), -1),
+            KeyCode::Down | KeyCode::S => self.replays_index = cycle(self.replays_index, rows.len(), 1),
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space => {
+                let replay = match (saved, &self.last_replay) {
+                    (Some(index), _) => self.persistence.load_replays()[index].replay(),
+                    (None, Some(replay)) => Ok(replay.clone()),
+                    (None, None) => return Ok(()),
+                };
+                let mut player = replay.and_then(ReplayPlayer::new).map_err(|e| GameError::CustomError(e.to_string()))?;
+                player.set_reduced_motion(self.settings.config.reduced_motion);
+                self.game.pause();
+                self.player = Some(player);
+                self.report = None;
+                self.screen = Screen::Watching;
+            }
+            KeyCode::E if saved.is_none() => self.save_replay(ctx),
+            KeyCode::Delete => {
+                if let Some(index) = saved {
+                    if let Err(e) = self.persistence.delete_replay(index) {
+                        eprintln!("could not delete the replay: {}", e);
+                    }
+                }
+            }
+            KeyCode::Escape | KeyCode::Back => self.screen = Screen::Menu,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Moves the last game to the saved replays, and exports it as a text
+    // file that `--replay` can play
+    fn save_replay(&mut self, ctx: &Context) {
+        let Some(replay) = self.last_replay.take() else { return };
+        let now = unix_time();
+        if let Err(e) = self.persistence.save_replay(SavedReplay::new(&replay, now.as_secs())) {
+            eprintln!("could not save the replay: {}", e);
+        }
+        let dir = ctx.fs.user_data_dir().join("replays");
+        let path = dir.join(format!("snake-{}.txt", now.as_millis()));
+        match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, replay.to_base64())) {
+            Ok(()) => println!("Saved {}", path.display()),
+            Err(e) => eprintln!("could not export the replay: {}", e),
+        }
+    }
+
+    // Adds the round that just ended to the --session-log file, if any
+    fn log_session(&mut self) {
+        let Some(log) = &mut self.session_log else { return };
+        let started = self.round_started;
+        let record = SessionRecord::new(&self.game, started.as_secs(), unix_time().saturating_sub(started));
+        if let Err(e) = log.append(&record) {
+            eprintln!("could not write to {}: {}", log.path().display(), e);
+        }
+    }
+
+    // Space pauses, left/right seek, comma/period step one tick back or
+    // forward, up/down change the speed, X exports and Esc goes back to
+    // the list, H shows the heatmap
+    fn watching_key(&mut self, ctx: &Context, key: KeyCode) -> GameResult {
+        let Some(player) = &mut self.player else { return Ok(()) };
+        let position = player.position();
+        let seek = match key {
+            KeyCode::Space | KeyCode::P | KeyCode::Return | KeyCode::NumpadEnter => {
+                player.paused = !player.paused;
+                None
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let current = REPLAY_SPEEDS.iter().position(|&s| s == player.speed).unwrap_or(1);
+                player.speed = REPLAY_SPEEDS[cycle(current, REPLAY_SPEEDS.len(), if key == KeyCode::Up { 1 } else { -1 })];
+                None
+            }
+            KeyCode::Period => {
+                player.step();
+                None
+            }
+            KeyCode::Comma => {
+                player.paused = true;
+                Some(position.saturating_sub(1))
+            }
+            KeyCode::Left => Some(position.saturating_sub(SEEK_TICKS)),
+            KeyCode::Right => Some(position + SEEK_TICKS),
+            KeyCode::H => {
+                self.heatmap = !self.heatmap;
+                None
+            }
+            // Plays the whole replay through once more, quick next to watching it
+            KeyCode::A => {
+                self.report = match self.report {
+                    Some(_) => None,
+                    None => Some(player.replay().analyze().map_err(|e| GameError::CustomError(e.to_string()))?),
+                };
+                None
+            }
+            KeyCode::Home => Some(0),
+            KeyCode::End => Some(player.length()),
+            // The whole replay as an animated PNG, at the speed it's being
+            // watched. Long replays take a while, so it's written on the side.
+            KeyCode::X => {
+                let options = ExportOptions { speed: player.speed, theme: self.settings.config.theme, ..ExportOptions::default() };
+                let replay = player.replay().clone();
+                let dir = ctx.fs.user_data_dir().join("exports");
+                let path = dir.join(format!("snake-{}.png", unix_time().as_millis()));
+                std::thread::spawn(move || {
+                    let result = std::fs::create_dir_all(&dir).map_err(|e| e.to_string());
+                    match result.and_then(|_| export_replay(&replay, &options, &path)) {
+                        Ok(()) => println!("Saved {}", path.display()),
+                        Err(e) => eprintln!("could not export the replay: {}", e),
+                    }
+                });
+                None
+            }
+            KeyCode::Escape | KeyCode::Back => {
+                self.player = None;
+                self.report = None;
+                self.screen = Screen::Replays;
+                None
+            }
+            _ => None,
+        };
+        if let (Some(tick), Some(player)) = (seek, &mut self.player) {
+            player.seek(tick).map_err(|e| GameError::CustomError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn pause_in_background(&mut self) {
+        if self.screen == Screen::Playing {
+            self.game.pause();
+        }
+        if let Some(player) = &mut self.player {
+            player.paused = true;
+        }
+    }
+
+    fn open_menu(&mut self) {
+        // Not before a new high score got its name
+        if self.pending_score.is_some() {
+            return;
+        }
+        self.game.pause();
+        self.screen = Screen::Menu;
+        self.menu_index = 0;
+    }
+
+    fn menu_key(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        let items = self.menu_items();
+        self.menu_index = self.menu_index.min(items.len() - 1);
+        let item = items[self.menu_index];
+        match key {
+            KeyCode::Up | KeyCode::W => self.menu_index = (self.menu_index + items.len() - 1) % items.len(),
+            KeyCode::Down | KeyCode::S => self.menu_index = (self.menu_index + 1) % items.len(),
+            KeyCode::Left | KeyCode::A if item == MenuItem::Mode => self.cycle_mode(-1),
+            KeyCode::Right | KeyCode::D if item == MenuItem::Mode => self.cycle_mode(1),
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space => match item {
+                MenuItem::Resume => {
+                    self.game.resume();
+                    self.screen = Screen::Playing;
+                }
+                MenuItem::Play => self.new_game()?,
+                MenuItem::Tutorial => {
+                    self.tutorial = Some(TutorialRun::new(Tutorial::builtin()));
+                    self.tutorial_step()?;
+                }
+                MenuItem::Mode => self.cycle_mode(1),
+                MenuItem::Settings => self.screen = Screen::Settings,
+                MenuItem::HighScores => {
+                    self.highlight = None;
+                    self.screen = Screen::HighScores;
+                }
+                MenuItem::Replays => self.screen = Screen::Replays,
+                MenuItem::Quit => ctx.request_quit(),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn settings_label(&self, item: SettingsItem) -> String {
+        let config = &self.settings.config;
+        match item {
+            SettingsItem::BoardSize => self.trf("setting-board", &[("width", &config.width), ("height", &config.height)]),
+            SettingsItem::Speed => {
+                let base_ms = config.speed_curve.base_interval.as_millis() as u64;
+                let key = SPEEDS.iter().find(|s| s.1 == base_ms).map_or("speed-custom".to_string(), |s| format!("speed-{}", s.0));
+                self.trf("setting-speed", &[("value", &self.tr(&key))])
+            }
+            SettingsItem::InputGrace => {
+                let value = match percent(config.input_grace) {
+                    0 => self.on_off(false).to_string(),
+                    grace => format!("{}%", grace),
+                };
+                self.trf("setting-input-grace", &[("value", &value)])
+            }
+            SettingsItem::WrapWalls => self.trf("setting-wrap-walls", &[("value", &self.on_off(config.wrap_walls))]),
+            SettingsItem::Bosses => self.trf("setting-bosses", &[("value", &self.on_off(config.boss.is_some()))]),
+            SettingsItem::Players => self.trf("setting-players", &[("value", &self.players())]),
+            SettingsItem::Sound => self.trf("setting-sound", &[("value", &self.on_off(!self.settings.muted))]),
+            SettingsItem::EffectsVolume => self.trf("setting-effects-volume", &[("value", &percent(self.settings.volume))]),
+            SettingsItem::MusicVolume => self.trf("setting-music-volume", &[("value", &percent(self.settings.music_volume))]),
+            SettingsItem::Theme => self.trf("setting-theme", &[("value", &self.tr(theme_key(config.theme)))]),
+            SettingsItem::Pattern => {
+                self.trf("setting-pattern", &[("value", &self.tr(pattern_key(self.settings.board_pattern)))])
+            }
+            SettingsItem::Skin => {
+                let skin = self.skins.available(&self.unlocked_skins).find(|skin| skin.id == self.settings.skin);
+                let name = skin.map_or("skin-classic", |skin| skin.name.as_str());
+                self.trf("setting-skin", &[("value", &self.tr(name))])
+            }
+            SettingsItem::ScreenEffects => {
+                self.trf("setting-screen-effects", &[("value", &self.on_off(self.settings.screen_effects))])
+            }
+            SettingsItem::EffectIntensity => {
+                self.trf("setting-effect-intensity", &[("value", &percent(self.settings.effect_intensity))])
+            }
+            SettingsItem::NearMiss => {
+                self.trf("setting-near-miss", &[("value", &self.on_off(self.settings.near_miss_slow_motion))])
+            }
+            SettingsItem::ReducedMotion => {
+                self.trf("setting-reduced-motion", &[("value", &self.on_off(config.reduced_motion))])
+            }
+            SettingsItem::Minimap => self.trf("setting-minimap", &[("value", &self.on_off(self.settings.minimap))]),
+            SettingsItem::Language => self.trf("setting-language", &[("value", &self.settings.language.name())]),
+            SettingsItem::Controls => self.tr("setting-controls").to_string(),
+            SettingsItem::Back => self.tr("back").to_string(),
+        }
+    }
+
+    // Moves a setting one step left (-1) or right (1) through its options
+    fn change_setting(&mut self, ctx: &mut Context, item: SettingsItem, delta: isize) -> GameResult {
+        let config = &mut self.settings.config;
+        match item {
+            SettingsItem::BoardSize => {
+                let current = BOARD_SIZES.iter().position(|&s| s == (config.width, config.height));
+                let (width, height) = BOARD_SIZES[current.map_or(0, |i| cycle(i, BOARD_SIZES.len(), delta))];
+                config.width = width;
+                config.height = height;
+            }
+            SettingsItem::Speed => {
+                let base_ms = config.speed_curve.base_interval.as_millis() as u64;
+                let current = SPEEDS.iter().position(|s| s.1 == base_ms);
+                config.speed_curve = speed_curve(SPEEDS[current.map_or(1, |i| cycle(i, SPEEDS.len(), delta))].1);
+            }
+            SettingsItem::InputGrace => {
+                let current = INPUT_GRACES.iter().position(|&g| g == config.input_grace).unwrap_or(0);
+                config.input_grace = INPUT_GRACES[cycle(current, INPUT_GRACES.len(), delta)];
+            }
+            SettingsItem::WrapWalls => config.wrap_walls = !config.wrap_walls,
+            SettingsItem::Bosses => config.boss = config.boss.xor(Some(BossConfig::default())),
+            SettingsItem::ReducedMotion => config.reduced_motion = !config.reduced_motion,
+            // One or two players, AI snakes from a shared settings file stay
+            SettingsItem::Players => {
+                let players = self.players();
+                let config = &mut self.settings.config;
+                config.opponents.retain(|c| c.is_some());
+                if players == 1 {
+                    config.opponents.push(None);
+                }
+            }
+            SettingsItem::Sound => self.settings.muted = !self.settings.muted,
+            SettingsItem::EffectsVolume => self.settings.volume = step_percent(self.settings.volume, delta),
+            SettingsItem::MusicVolume => self.settings.music_volume = step_percent(self.settings.music_volume, delta),
+            SettingsItem::ScreenEffects => self.settings.screen_effects = !self.settings.screen_effects,
+            SettingsItem::NearMiss => self.settings.near_miss_slow_motion = !self.settings.near_miss_slow_motion,
+            SettingsItem::Minimap => self.settings.minimap = !self.settings.minimap,
+            SettingsItem::Language => {
+                let current = Language::ALL.iter().position(|&l| l == self.settings.language).unwrap_or(0);
+                self.settings.language = Language::ALL[cycle(current, Language::ALL.len(), delta)];
+            }
+            SettingsItem::EffectIntensity => {
+                self.settings.effect_intensity = step_percent(self.settings.effect_intensity, delta)
+            }
+            SettingsItem::Theme => {
+                let current = THEMES.iter().position(|&t| t == config.theme).unwrap_or(0);
+                config.theme = THEMES[cycle(current, THEMES.len(), delta)];
+            }
+            SettingsItem::Pattern => {
+                let current = PATTERNS.iter().position(|&p| p == self.settings.board_pattern).unwrap_or(0);
+                self.settings.board_pattern = PATTERNS[cycle(current, PATTERNS.len(), delta)];
+            }
+            SettingsItem::Skin => {
+                let available: Vec<&str> = self.skins.available(&self.unlocked_skins).map(|skin| skin.id.as_str()).collect();
+                let current = available.iter().position(|&id| id == self.settings.skin).unwrap_or(0);
+                self.settings.skin = available[cycle(current, available.len(), delta)].to_string();
+            }
+            SettingsItem::Controls | SettingsItem::Back => return Ok(()),
+        }
+        self.apply_settings(ctx, item)
+    }
+
+    // Saves the settings and applies them right away. The theme and audio
+    // don't touch the rules, anything else starts over with a fresh game.
+    fn apply_settings(&mut self, ctx: &mut Context, changed: SettingsItem) -> GameResult {
+        self.save_settings();
+        match changed {
+            SettingsItem::Theme | SettingsItem::Skin => self.game.theme = self.theme(),
+            SettingsItem::InputGrace => self.game.input_grace = self.settings.config.input_grace,
+            SettingsItem::ReducedMotion => {
+                let reduced = self.settings.config.reduced_motion;
+                self.game.reduced_motion = reduced;
+                if let Some(player) = &mut self.player {
+                    player.set_reduced_motion(reduced);
+                }
+            }
+            SettingsItem::Sound
+            | SettingsItem::EffectsVolume
+            | SettingsItem::MusicVolume
+            | SettingsItem::Pattern
+            | SettingsItem::ScreenEffects
+            | SettingsItem::EffectIntensity
+            | SettingsItem::NearMiss
+            | SettingsItem::Minimap
+            | SettingsItem::Language
+            | SettingsItem::Controls
+            | SettingsItem::Back => {}
+            _ => {
+                self.game = self.build_game(&self.settings.config)?;
+                self.tutorial = None;
+                if !self.fullscreen {
+                    let (width, height) = self.window_size();
+                    ctx.gfx.set_drawable_size(width, height)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn settings_key(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        let item = SETTINGS_ITEMS[self.settings_index];
+        match key {
+            KeyCode::Up | KeyCode::W => self.settings_index = cycle(self.settings_index, SETTINGS_ITEMS.len(), -1),
+            KeyCode::Down | KeyCode::S => self.settings_index = cycle(self.settings_index, SETTINGS_ITEMS.len(), 1),
+            KeyCode::Left | KeyCode::A => self.change_setting(ctx, item, -1)?,
+            KeyCode::Right | KeyCode::D => self.change_setting(ctx, item, 1)?,
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space => match item {
+                SettingsItem::Controls => {
+                    self.controls_index = 0;
+                    self.screen = Screen::Controls;
+                }
+                SettingsItem::Back => self.screen = Screen::Menu,
+                _ => self.change_setting(ctx, item, 1)?,
+            },
+            KeyCode::Escape | KeyCode::Back => self.screen = Screen::Menu,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Where a setting sits between its lowest and highest option, for the
+    // ones shown as sliders
+    fn slider_value(&self, item: SettingsItem) -> Option<f32> {
+        match item {
+            SettingsItem::Speed => {
+                let base_ms = self.settings.config.speed_curve.base_interval.as_millis() as u64;
+                let index = SPEEDS.iter().position(|s| s.1 == base_ms)?;
+                Some(index as f32 / (SPEEDS.len() - 1) as f32)
+            }
+            SettingsItem::EffectsVolume => Some(self.settings.volume),
+            SettingsItem::MusicVolume => Some(self.settings.music_volume),
+            SettingsItem::EffectIntensity => Some(self.settings.effect_intensity),
+            _ => None,
+        }
+    }
+
+    // Sets a slider from a point along it, 0.0 to 1.0
+    fn set_slider(&mut self, ctx: &mut Context, item: SettingsItem, value: f32) -> GameResult {
+        let value = value.clamp(0.0, 1.0);
+        if self.slider_value(item).is_some_and(|old| (old - value).abs() < 0.05) {
+            return Ok(());
+        }
+        let percent = (value * 10.0).round() / 10.0;
+        match item {
+            SettingsItem::Speed => {
+                let ms = SPEEDS[(value * (SPEEDS.len() - 1) as f32).round() as usize].1;
+                if self.settings.config.speed_curve.base_interval == Duration::from_millis(ms) {
+                    return Ok(());
+                }
+                self.settings.config.speed_curve = speed_curve(ms);
+            }
+            SettingsItem::EffectsVolume => self.settings.volume = percent,
+            SettingsItem::MusicVolume => self.settings.music_volume = percent,
+            SettingsItem::EffectIntensity => self.settings.effect_intensity = percent,
+            _ => return Ok(()),
+        }
+        self.apply_settings(ctx, item)
+    }
+
+    // Left and right end of the settings sliders
+    fn slider_track(&self) -> (f32, f32) {
+        let (screen_width, _) = self.screen_size();
+        (screen_width * 0.25, screen_width * 0.75)
+    }
+
+    // A point in the window in screen coordinates, undoing `letterbox`
+    fn window_to_screen(&self, ctx: &Context, x: f32, y: f32) -> (f32, f32) {
+        let (width, height) = self.screen_size();
+        let window = ctx.gfx.drawable_size();
+        let shown = letterbox(Rect::new(0.0, 0.0, width, height), window);
+        (shown.x + x / window.0 * shown.w, shown.y + y / window.1 * shown.h)
+    }
+
+    // The page line under a point in screen coordinates
+    fn page_line_at(&self, y: f32) -> Option<usize> {
+        let (_, lines, _) = self.page();
+        let (top, line_height) = self.page_layout(lines.len());
+        let line = ((y - top) / line_height).floor();
+        (line >= 0.0 && (line as usize) < lines.len()).then_some(line as usize)
+    }
+
+    // Top of the first line and the line height of a page's lines. Long
+    // pages squeeze together to stay above the hint.
+    fn page_layout(&self, lines: usize) -> (f32, f32) {
+        let (_, screen_height) = self.screen_size();
+        let top = 110.0;
+        (top, ((screen_height - top - 40.0) / lines.max(1) as f32).min(30.0))
+    }
+
+    // Moves the selection of the current page to a line, if it has one
+    fn select_line(&mut self, line: usize) {
+        match self.screen {
+            Screen::Menu => self.menu_index = line,
+            Screen::Settings => self.settings_index = line,
+            Screen::Controls if !self.binding => self.controls_index = line,
+            Screen::Replays => self.replays_index = line,
+            _ => {}
+        }
+    }
+
+    // A left click picks the line under the cursor like Enter. On the
+    // settings page it changes the value instead: sliders jump to the
+    // cursor and follow it while the button is held, other settings go
+    // back a step when clicked left of the middle.
+    fn click(&mut self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
+        let Some(line) = self.page_line_at(y) else { return Ok(()) };
+        self.select_line(line);
+        if self.screen == Screen::Settings {
+            let item = SETTINGS_ITEMS[line];
+            if self.slider_value(item).is_some() {
+                self.dragging = Some(item);
+                let (left, right) = self.slider_track();
+                return self.set_slider(ctx, item, (x - left) / (right - left));
+            }
+            let (screen_width, _) = self.screen_size();
+            if x < screen_width / 2.0 && !matches!(item, SettingsItem::Controls | SettingsItem::Back) {
+                return self.change_setting(ctx, item, -1);
+            }
+        }
+        self.key_pressed(ctx, KeyCode::Return)
+    }
+
+    // The player plus every extra snake without an AI
+    fn players(&self) -> usize {
+        1 + self.settings.config.opponents.iter().filter(|c| c.is_none()).count()
+    }
+
+    fn pad_index(&mut self, id: GamepadId) -> usize {
+        match self.pads.iter().position(|pad| pad.id == id) {
+            Some(index) => index,
+            None => {
+                self.pads.push(Pad { id, stick: (0.0, 0.0), stick_direction: None });
+                self.pads.len() - 1
+            }
+        }
+    }
+
+    // The snake a pad steers. Pads are handed out from the last human
+    // player backwards, so in a two-player game the first pad goes to
+    // player 2 and the keyboard keeps player 1.
+    fn pad_turn(&mut self, pad: usize, direction: Direction) {
+        let humans: Vec<usize> = (0..self.game.snake_count()).filter(|&i| self.game.controller(i).is_none()).collect();
+        if let Some(&snake) = humans.iter().rev().cycle().nth(pad) {
+            self.game.queue_turn_for(snake, direction);
+        }
+    }
+
+    // Pad input on a menu page acts like the matching key
+    fn pad_menu_key(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        if self.binding {
+            return Ok(());
+        }
+        self.key_pressed(ctx, key)
+    }
+
+    fn key_pressed(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        match self.screen {
+            Screen::Menu => self.menu_key(ctx, key)?,
+            Screen::Playing => self.playing_key(ctx, key),
+            Screen::Settings => self.settings_key(ctx, key)?,
+            Screen::Controls => self.controls_key(key),
+            Screen::NameEntry => self.name_entry_key(key),
+            Screen::Replays => self.replays_key(ctx, key)?,
+            Screen::Watching => self.watching_key(ctx, key)?,
+            _ => {
+                if matches!(key, KeyCode::Escape | KeyCode::Back | KeyCode::Return | KeyCode::NumpadEnter) {
+                    self.screen = Screen::Menu;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Letters come in through `text_input_event`, this handles the rest
+    fn name_entry_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Back => {
+                self.name.pop();
+            }
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Escape => self.save_high_score(),
+            _ => {}
+        }
+    }
+
+    // Puts the pending score in the table and shows it. The name is kept
+    // for next time.
+    fn save_high_score(&mut self) {
+        let Some(score) = self.pending_score.take() else { return };
+        let name = self.name.trim().to_string();
+        match self.persistence.record_score(&name, score) {
+            Ok(rank) => self.highlight = rank,
+            Err(e) => eprintln!("could not save high scores: {}", e),
+        }
+        self.best_score = self.persistence.load_high_scores().best();
+        if name != self.settings.player_name {
+            self.settings.player_name = name;
+            self.save_settings();
+        }
+        self.screen = Screen::HighScores;
+    }
+
+    // The mode, score and time of the round being played, following its
+    // events as they change them, or what else is on screen
+    #[cfg(feature = "discord")]
+    fn activity(&self) -> Activity {
+        let game = &self.game;
+        let score: [(&str, &dyn std::fmt::Display); 1] = [("score", &game.score)];
+        let (details, state, start) = match self.screen {
+            Screen::Playing if self.tutorial.is_some() => (self.tr("presence-tutorial").to_string(), String::new(), None),
+            Screen::Playing | Screen::NameEntry if game.game_started => {
+                let (state, start) = match game.status() {
+                    GameStatus::Paused => (self.trf("presence-paused", &score), None),
+                    GameStatus::Dying | GameStatus::GameOver => (self.trf("presence-game-over", &score), None),
+                    _ => (self.trf("presence-score", &score), Some(unix_time().saturating_sub(game.elapsed()).as_secs() as i64)),
+                };
+                (self.mode_name(game.mode), state, start)
+            }
+            Screen::Watching => (self.tr("presence-watching").to_string(), String::new(), None),
+            _ => (self.tr("presence-menu").to_string(), String::new(), None),
+        };
+        Activity { details, state, start }
+    }
+
+    // Applies what changed in the watched files, a file that doesn't load
+    // leaves everything as it was
+    #[cfg(feature = "hot-reload")]
+    fn reload(&mut self) {
+        let Some(hot_reload) = &self.hot_reload else { return };
+        let theme = hot_reload.theme.clone();
+        for path in hot_reload.changed() {
+            let result = match theme.as_ref() == Some(&path) {
+                true => self.reload_theme(&path),
+                false => self.reload_settings(&path),
+            };
+            let error = result.err().map(|reason| format!("{}: {}", path.display(), reason));
+            if let Some(error) = &error {
+                eprintln!("could not reload {}", error);
+            }
+            if let Some(hot_reload) = &mut self.hot_reload {
+                hot_reload.error = error;
+            }
+        }
+    }
+
+    // Colors, audio, controls and language take effect right away. The
+    // speed curve too, unless a round is running: its replay has one curve
+    // from start to end. The board and rules come with the next game.
+    #[cfg(feature = "hot-reload")]
+    fn reload_settings(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let settings = Settings::from_json(&text).map_err(|e| e.to_string())?;
+        // Our own saves come back through the watcher too
+        if settings == self.settings {
+            return Ok(());
+        }
+        // The next game is built from them, it had better work
+        self.overrides.build_game(&settings.config).map_err(error_reason)?;
+        self.settings = settings;
+        let theme = self.theme();
+        let config = &self.settings.config;
+        self.game.theme = theme;
+        self.game.reduced_motion = config.reduced_motion;
+        self.game.input_grace = config.input_grace;
+        if !self.game.game_started || self.game.game_over {
+            self.game.speed = config.speed_curve;
+        }
+        if let Some(demo) = &mut self.demo {
+            demo.theme = theme;
+        }
+        Ok(())
+    }
 
-const GRID_SIZE: (i32, i32) = (20, 20);
-const PIXEL_SCALE: f32 = 20.0;
-const FPS: u32 = 10;
+    #[cfg(feature = "hot-reload")]
+    fn reload_theme(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let theme = Theme::from_json(&text).map_err(|e| e.to_string())?;
+        self.overrides.theme = Some(theme);
+        let theme = self.theme();
+        self.game.theme = theme;
+        if let Some(demo) = &mut self.demo {
+            demo.theme = theme;
+        }
+        Ok(())
+    }
 
-// Struct to hold the application state for ggez
-struct AppState {
-    game: Game,
-}
+    #[cfg(feature = "hot-reload")]
+    fn reload_error(&self) -> Option<&str> {
+        self.hot_reload.as_ref()?.error.as_deref()
+    }
 
-impl AppState {
-    fn new(_ctx: &mut Context) -> AppState {
-        AppState {
-            game: Game::new(GRID_SIZE.0, GRID_SIZE.1),
+    #[cfg(not(feature = "hot-reload"))]
+    fn reload_error(&self) -> Option<&str> {
+        None
+    }
+
+    fn save_settings(&mut self) {
+        if let Err(e) = self.persistence.save_settings(&self.settings) {
+            eprintln!("could not save settings: {}", e);
         }
     }
-}
 
-// ggez's event handler implementation
-impl EventHandler for AppState {
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        // The game logic is ticked based on the desired FPS
-        while ctx.time.check_update_time(FPS) {
-            // Only tick if the game is started and not over
-            if self.game.game_started && !self.game.game_over {
-                self.game.tick();
+    fn controls_key(&mut self, key: KeyCode) {
+        let rows = BINDABLE_ACTIONS.len() + 2;
+        let action = BINDABLE_ACTIONS.get(self.controls_index).copied();
+        if self.binding {
+            // Esc backs out instead of being bound
+            if let (Some(action), false) = (action, key == KeyCode::Escape) {
+                self.settings.keymap.bind(action, &key_name(key));
+                self.save_settings();
             }
+            self.binding = false;
+            return;
+        }
+        match (key, action) {
+            (KeyCode::Up | KeyCode::W, _) => self.controls_index = cycle(self.controls_index, rows, -1),
+            (KeyCode::Down | KeyCode::S, _) => self.controls_index = cycle(self.controls_index, rows, 1),
+            (KeyCode::Delete | KeyCode::Back, Some(action)) => {
+                self.settings.keymap.clear(action);
+                self.save_settings();
+            }
+            (KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space, Some(_)) => self.binding = true,
+            (KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space, None) => {
+                if self.controls_index == BINDABLE_ACTIONS.len() {
+                    self.settings.keymap = KeyMap::default();
+                    self.save_settings();
+                } else {
+                    self.screen = Screen::Settings;
+                }
+            }
+            (KeyCode::Escape | KeyCode::Back, _) => self.screen = Screen::Settings,
+            _ => {}
         }
-        Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(51, 51, 51));
-
-        // Draw the food
-        let food = self.game.food;
-        let food_rect = Rect::new(
-            food.x as f32 * PIXEL_SCALE,
-            food.y as f32 * PIXEL_SCALE,
-            PIXEL_SCALE,
-            PIXEL_SCALE,
+    fn playing_key(&mut self, ctx: &mut Context, key: KeyCode) {
+        if let Some(confirm) = self.confirm {
+            self.confirm_key(ctx, confirm, key);
+            return;
+        }
+        // Esc asks before leaving a run, pausing it in the meantime
+        if key == KeyCode::Escape && self.can_resume() {
+            self.ask(Confirm::Leave);
+            return;
+        }
+        // Q leaves from the pause screen only, so a stray key can't end a run.
+        // A finished game, or the one waiting after the tutorial, goes back
+        // to the menu with Esc too.
+        let finished = self.game.game_over;
+        if (key == KeyCode::Q && self.game.paused) || (key == KeyCode::Escape && (finished || !self.game.game_started)) {
+            self.open_menu();
+            return;
+        }
+        // A high score is about to be named, a restart would lose it
+        if self.pending_score.is_some() {
+            return;
+        }
+
+        // Keys are only mapped to actions here, the game decides what they do
+        match self.settings.keymap.action_for(&key_name(key)) {
+            Some(action) => self.game.handle_action(action),
+            // M mutes unless it was bound to something else
+            None if key == KeyCode::M => {
+                self.settings.muted = !self.settings.muted;
+                self.save_settings();
+            }
+            // E saves the replay of a finished game, the same way, and H
+            // shows its heatmap
+            None if key == KeyCode::E && finished => self.save_replay(ctx),
+            None if key == KeyCode::H && finished => self.heatmap = !self.heatmap,
+            None => {}
+        }
+    }
+
+    fn ask(&mut self, confirm: Confirm) {
+        self.game.pause();
+        self.confirm = Some(confirm);
+    }
+
+    // Saying no leaves the game paused, so it doesn't start again under
+    // the player's hands
+    fn confirm_key(&mut self, ctx: &mut Context, confirm: Confirm, key: KeyCode) {
+        match (key, confirm) {
+            (KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Y, Confirm::Leave) => {
+                self.confirm = None;
+                self.open_menu();
+            }
+            (KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Y, Confirm::Quit) => {
+                self.confirm = None;
+                self.quit_confirmed = true;
+                ctx.request_quit();
+            }
+            (KeyCode::Escape | KeyCode::Back | KeyCode::N, _) => self.confirm = None,
+            _ => {}
+        }
+    }
+
+    // Lines of text for the non-game screens, with the highlighted line's
+    // index if the screen has a selection
+    fn page(&self) -> (String, Vec<String>, Option<usize>) {
+        match self.screen {
+            Screen::Menu => {
+                let items = self.menu_items();
+                let lines = items.iter().map(|&item| self.menu_label(item)).collect();
+                (self.tr("menu-title").to_string(), lines, Some(self.menu_index.min(items.len() - 1)))
+            }
+            Screen::HighScores => {
+                let scores = self.persistence.load_high_scores();
+                let mut lines: Vec<String> = scores
+                    .entries()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let name = if entry.name.is_empty() { "---" } else { &entry.name };
+                        format!("{:>2}. {}  {}", i + 1, name, entry.score)
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    lines.push(self.tr("no-scores").to_string());
+                }
+                (self.tr("high-scores-title").to_string(), lines, self.highlight)
+            }
+            Screen::NameEntry => {
+                let lines = vec![
+                    self.trf("name-entry-score", &[("score", &self.pending_score.unwrap_or(0))]),
+                    self.trf("name-entry-name", &[("name", &self.name)]),
+                ];
+                (self.tr("name-entry-title").to_string(), lines, None)
+            }
+            Screen::Settings => {
+                let lines = SETTINGS_ITEMS.iter().map(|&item| self.settings_label(item)).collect();
+                (self.tr("settings-title").to_string(), lines, Some(self.settings_index))
+            }
+            Screen::Controls => {
+                let mut lines: Vec<String> = BINDABLE_ACTIONS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &action)| {
+                        let keys = if self.binding && i == self.controls_index {
+                            self.tr("press-a-key").to_string()
+                        } else if self.settings.keymap.keys(action).is_empty() {
+                            "-".to_string()
+                        } else {
+                            self.settings.keymap.keys(action).join(", ")
+                        };
+                        format!("{}: {}", self.tr(action_key(action)), keys)
+                    })
+                    .collect();
+                lines.push(self.tr("reset-defaults").to_string());
+                lines.push(self.tr("back").to_string());
+                (self.tr("controls-title").to_string(), lines, Some(self.controls_index))
+            }
+            Screen::Replays => {
+                let mut lines: Vec<String> = self.replay_rows().into_iter().map(|(line, _)| line).collect();
+                let selected = (!lines.is_empty()).then(|| self.replays_index.min(lines.len() - 1));
+                if lines.is_empty() {
+                    lines.push(self.tr("no-replays").to_string());
+                }
+                (self.tr("replays-title").to_string(), lines, selected)
+            }
+            Screen::Playing | Screen::Watching | Screen::Attract => (String::new(), Vec::new(), None),
+        }
+    }
+
+    // Along the top edge: score on the left, length and speed in the middle,
+    // the clock on the right. Active power-ups go on a second row.
+    fn draw_hud(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let (screen_width, screen_height) = self.screen_size();
+        let game = self.shown_game();
+
+        let mut score = self.trf("hud-score", &[("score", &game.score), ("best", &self.best_score.unwrap_or(0))]);
+        if game.snake_count() > 1 && !game.is_boss(1) {
+            score.push_str("  ");
+            score.push_str(&self.trf("hud-player-2", &[("score", &game.snake_score(1))]));
+        }
+        for index in (0..game.snake_count()).filter(|&i| game.is_afk(i)) {
+            score.push_str("  ");
+            score.push_str(&self.trf("hud-afk", &[("player", &(index + 1))]));
+        }
+        let speed = self.trf(
+            "hud-speed",
+            &[
+                ("length", &game.snake_length()),
+                ("level", &game.speed_level()),
+                ("multiplier", &format!("{:.1}", game.speed_multiplier())),
+            ],
         );
-        canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(food_rect.point()).scale(food_rect.size()).color(Color::RED));
-
-        // Draw the snake
-        for segment in &self.game.snake.body {
-            let snake_rect = Rect::new(
-                segment.x as f32 * PIXEL_SCALE,
-                segment.y as f32 * PIXEL_SCALE,
-                PIXEL_SCALE,
-                PIXEL_SCALE,
+        let clock = match game.time_left() {
+            Some(left) => self.trf("hud-time-left", &[("time", &clock_time(left))]),
+            None => clock_time(game.elapsed()),
+        };
+        let mut power_ups = Vec::new();
+        if let Some(left) = game.power_up_time_left(FoodKind::SlowDown) {
+            power_ups.push(self.trf("hud-slow", &[("secs", &format!("{:.1}", left.as_secs_f32()))]));
+        }
+        if game.boosting {
+            power_ups.push(self.tr("hud-boost").to_string());
+        }
+        if game.boss_fight().is_some() {
+            power_ups.push(self.trf("hud-boss", &[("ticks", &game.boss_ticks_left())]));
+        }
+        #[cfg(feature = "twitch")]
+        if let (Some(twitch), Screen::Playing) = (&self.twitch, self.screen) {
+            power_ups.push(match twitch.chat.error() {
+                Some(_) => self.tr("hud-chat-offline").to_string(),
+                None => {
+                    let [up, down, left, right] = twitch.tally.counts();
+                    self.trf("hud-chat-votes", &[("up", &up), ("down", &down), ("left", &left), ("right", &right)])
+                }
+            });
+        }
+
+        let rows = [(score, 0.0, 8.0), (speed, 0.5, 8.0), (clock, 1.0, 8.0), (power_ups.join("  "), 0.0, 26.0)];
+        for (line, align, y) in rows {
+            let mut text = Text::new(line);
+            text.set_scale(14.0);
+            let width = text.measure(ctx)?.x;
+            // `align` places the text from the left (0.0) to the right edge (1.0)
+            let x = 8.0 + (screen_width - 16.0 - width) * align;
+            canvas.draw(&text, graphics::DrawParam::new().color(color(self.shown_game().theme.text)).dest([x, y]));
+        }
+
+        // The tutorial's prompt along the bottom, out of the way of the
+        // game over message, or what ends the attract mode
+        let prompt = match (&self.tutorial, self.screen) {
+            (_, Screen::Attract) => Some(self.tr("attract-prompt").to_string()),
+            (Some(tutorial), Screen::Playing) => Some(tutorial.prompt(self.settings.language)),
+            _ => None,
+        };
+        if let Some(prompt) = prompt {
+            let mut text = Text::new(prompt);
+            text.set_scale(16.0);
+            let size = text.measure(ctx)?;
+            let dest = [(screen_width - size.x).max(0.0) / 2.0, screen_height - size.y - 8.0];
+            canvas.draw(&text, graphics::DrawParam::new().color(color(self.shown_game().theme.text)).dest(dest));
+        }
+        Ok(())
+    }
+
+    // Cells tinted by how long the head spent on them, under the snakes
+    fn draw_heatmap(&self, canvas: &mut graphics::Canvas) {
+        let game = self.shown_game();
+        let max = game.max_visits().max(1) as f32;
+        let Color { r, g, b, .. } = color(game.theme.head);
+        for (i, &count) in game.visits().iter().enumerate().filter(|&(_, &count)| count > 0) {
+            let (x, y) = ((i as i32 % game.width) as f32, (i as i32 / game.width) as f32);
+            let alpha = 0.15 + 0.6 * count as f32 / max;
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest([x * PIXEL_SCALE, y * PIXEL_SCALE])
+                    .scale([PIXEL_SCALE, PIXEL_SCALE])
+                    .color(Color::new(r, g, b, alpha)),
             );
-            canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(snake_rect.point()).scale(snake_rect.size()).color(Color::from_rgb(0, 255, 0)));
         }
+    }
+
+    // Over a replay: red corners on the cells of the risky moves so far,
+    // and while the player takes the long way to some food, the shorter
+    // one as dots in the food's color
+    fn draw_report(&self, canvas: &mut graphics::Canvas, report: &ReplayReport) {
+        let game = self.shown_game();
+        let position = game.tick_count();
+        let mark = PIXEL_SCALE * 0.25;
+        for risky in report.risky_moves.iter().take_while(|r| r.tick <= position) {
+            let (x, y) = (risky.at.x as f32 * PIXEL_SCALE, risky.at.y as f32 * PIXEL_SCALE);
+            for (dx, dy) in [(0.0, 0.0), (PIXEL_SCALE - mark, 0.0), (0.0, PIXEL_SCALE - mark), (PIXEL_SCALE - mark, PIXEL_SCALE - mark)] {
+                canvas.draw(
+                    &graphics::Quad,
+                    graphics::DrawParam::new().dest([x + dx, y + dy]).scale([mark, mark]).color(Color::new(0.9, 0.2, 0.2, 0.7)),
+                );
+            }
+        }
+        let dot = PIXEL_SCALE * 0.3;
+        for missed in report.missed_paths.iter().filter(|m| m.since <= position && position < m.eaten) {
+            let fill = game.theme.cell_color(CellKind::Food(missed.kind)).unwrap_or(game.theme.apple);
+            let Color { r, g, b, .. } = color(fill);
+            for p in &missed.path {
+                let dest = [(p.x as f32 + 0.5) * PIXEL_SCALE - dot / 2.0, (p.y as f32 + 0.5) * PIXEL_SCALE - dot / 2.0];
+                canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(dest).scale([dot, dot]).color(Color::new(r, g, b, 0.6)));
+            }
+        }
+    }
+
+    // The route each AI snake is heading for, as dots in its color
+    fn draw_ai_paths(&self, canvas: &mut graphics::Canvas) {
+        let game = self.shown_game();
+        let theme = &game.theme;
+        for index in (0..game.snake_count()).filter(|&i| game.controller(i).is_some()) {
+            let Color { r, g, b, .. } = color(if index == 0 { theme.head } else { theme.opponent_head });
+            let dot = PIXEL_SCALE * 0.3;
+            for p in game.ai_path(index) {
+                let dest = [(p.x as f32 + 0.5) * PIXEL_SCALE - dot / 2.0, (p.y as f32 + 0.5) * PIXEL_SCALE - dot / 2.0];
+                canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(dest).scale([dot, dot]).color(Color::new(r, g, b, 0.6)));
+            }
+        }
+    }
 
-        // Draw score
-        let score_text = Text::new(format!("Score: {}", self.game.score));
+    // Bottom left corner: what a bug report or a desync hunt needs
+    fn draw_debug_info(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let (_, screen_height) = self.screen_size();
+        let game = self.shown_game();
+        let lines: Vec<String> = [
+            format!("FPS {:.0}", ctx.time.fps()),
+            format!("Tick {} at {:.1}/s", game.tick_count(), 1.0 / game.tick_interval().as_secs_f32()),
+            format!("Seed {} (round {})", game.seed(), game.round_seed()),
+            format!("Hash {:016x}", game.state_hash()),
+        ]
+        .into_iter()
+        .chain(self.reload_error().map(|error| format!("Reload failed: {}", error)))
+        .collect();
+        let mut text = Text::new(lines.join("\n"));
+        text.set_scale(14.0);
+        let size = text.measure(ctx)?;
+        let dest = [6.0, screen_height - size.y - 6.0];
         canvas.draw(
-            &score_text,
+            &graphics::Quad,
             graphics::DrawParam::new()
-                .color(Color::WHITE)
-                .dest(ggez::mint::Point2 { x: 10.0, y: 10.0 }),
+                .dest([2.0, dest[1] - 4.0])
+                .scale([size.x + 8.0, size.y + 8.0])
+                .color(Color::new(0.0, 0.0, 0.0, 0.6)),
+        );
+        canvas.draw(&text, graphics::DrawParam::new().color(Color::WHITE).dest(dest));
+        Ok(())
+    }
+
+    // The console's log and the line being typed, across the top
+    #[cfg(feature = "console")]
+    fn draw_console(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let (screen_width, _) = self.screen_size();
+        let mut lines = self.console.log.clone();
+        lines.push(format!("> {}_", self.console.line));
+        let mut text = Text::new(lines.join("\n"));
+        text.set_scale(14.0);
+        let size = text.measure(ctx)?;
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new().scale([screen_width, size.y + 12.0]).color(Color::new(0.0, 0.0, 0.0, 0.75)),
         );
+        canvas.draw(&text, graphics::DrawParam::new().color(Color::WHITE).dest([6.0, 6.0]));
+        Ok(())
+    }
 
-        // Draw start/game over message
-        if !self.game.game_started || self.game.game_over {
-            let message = if !self.game.game_started {
-                "Press SPACE to Start".to_string()
-            } else {
-                format!("Game Over! Score: {}\nPress SPACE to Restart", self.game.score)
+    // The whole board shrunk into the top right corner, with the part on
+    // screen outlined
+    fn draw_minimap(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let game = self.shown_game();
+        let (screen_width, _) = self.screen_size();
+        let (board_width, board_height) = self.board_size();
+        let scale = (MINIMAP_SIZE / board_width).min(MINIMAP_SIZE / board_height);
+        let map = Rect::new(screen_width - board_width * scale - 8.0, 44.0, board_width * scale, board_height * scale);
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new().dest(map.point()).scale(map.size()).color(Color::new(0.0, 0.0, 0.0, 0.6)),
+        );
+        let cell = (PIXEL_SCALE * scale).max(1.0);
+        for (p, kind) in game.cells() {
+            let Some(fill) = game.theme.cell_color(kind) else { continue };
+            let dest = [map.x + p.x as f32 * PIXEL_SCALE * scale, map.y + p.y as f32 * PIXEL_SCALE * scale];
+            canvas.draw(&graphics::Quad, graphics::DrawParam::new().dest(dest).scale([cell, cell]).color(color(fill)));
+        }
+        let area = self.camera.area();
+        let outline = Rect::new(map.x + area.x * scale, map.y + area.y * scale, area.w * scale, area.h * scale);
+        let mesh = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(1.0), outline, color(game.theme.text))?;
+        canvas.draw(&mesh, graphics::DrawParam::new());
+        Ok(())
+    }
+
+    // Progress bar along the bottom edge while watching a replay, with the
+    // playback state and the keys above it
+    fn draw_playback(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, player: &ReplayPlayer) -> GameResult {
+        let (screen_width, screen_height) = self.screen_size();
+        let theme = player.game().theme;
+        let progress = (player.position() as f32 / player.length().max(1) as f32).min(1.0);
+        let bar = Rect::new(0.0, screen_height - 6.0, screen_width, 6.0);
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new().dest(bar.point()).scale(bar.size()).color(Color::new(0.0, 0.0, 0.0, 0.6)),
+        );
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new().dest(bar.point()).scale([bar.w * progress, bar.h]).color(color(theme.head)),
+        );
+
+        let state = self.tr(if player.paused { "playback-paused" } else { "playback-playing" });
+        let status = self.trf(
+            "playback-status",
+            &[("state", &state), ("speed", &player.speed), ("position", &player.position()), ("length", &player.length())],
+        );
+        let mut lines = vec![status];
+        if let Some(report) = &self.report {
+            lines.push(self.trf(
+                "analysis-summary",
+                &[("risky", &report.risky_moves.len()), ("missed", &report.missed_paths.len()), ("lost", &report.ticks_lost())],
+            ));
+        }
+        lines.push(self.tr("playback-keys").to_string());
+        let mut text = Text::new(lines.join("\n"));
+        text.set_scale(14.0);
+        let mut size = text.measure(ctx)?;
+        if size.x > screen_width - 12.0 {
+            text.set_scale(14.0 * (screen_width - 12.0) / size.x);
+            size = text.measure(ctx)?;
+        }
+        canvas.draw(&text, graphics::DrawParam::new().color(color(theme.text)).dest([6.0, bar.y - size.y - 4.0]));
+        Ok(())
+    }
+
+    // Sound and screen effects for a game event
+    fn feedback(&mut self, ctx: &mut Context, event: &GameEvent) {
+        if let Some(effect) = SoundEffect::for_event(event).filter(|_| !self.settings.muted) {
+            self.sfx.play(ctx, effect, self.settings.volume);
+        }
+        if self.settings.screen_effects {
+            self.juice.trigger(event, self.settings.config.reduced_motion);
+        }
+        // Its own toggle, it changes how the game plays for a moment. Not
+        // over replays, they'd no longer play at the speed picked.
+        let slow_motion = self.settings.near_miss_slow_motion && !self.settings.config.reduced_motion;
+        if slow_motion && self.screen == Screen::Playing && matches!(event, GameEvent::NearMiss { .. }) {
+            self.juice.slow_motion();
+        }
+    }
+
+    // Saves the frame as a PNG in the screenshots directory, next to a text
+    // dump of the board that's easier to attach to a bug report. The frame
+    // is drawn again at the board's own size rather than read back from the
+    // window.
+    fn save_screenshot(&self, ctx: &mut Context) -> GameResult {
+        let (width, height) = self.screen_size();
+        let (width_px, height_px) = (width as u32, height as u32);
+        // Reading pixels back needs rows of a multiple of 256 bytes, so the
+        // image is padded on the right and cropped when saving
+        let padded_px = width_px.next_multiple_of(64);
+        let image = graphics::Image::new_canvas_image(ctx, graphics::ImageFormat::Rgba8UnormSrgb, padded_px, height_px, 1);
+        let mut canvas = graphics::Canvas::from_image(ctx, image.clone(), Color::BLACK);
+        let view = |area: Rect| {
+            let scale = width / area.w;
+            Rect::new(area.x, area.y, padded_px as f32 / scale, height / scale)
+        };
+        self.draw_frame(ctx, &mut canvas, &view)?;
+        canvas.finish(ctx)?;
+        let pixels = image.to_pixels(ctx)?;
+
+        let dir = ctx.fs.user_data_dir().join("screenshots");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("snake-{}.png", unix_time().as_millis()));
+
+        let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        let mut encoder = png::Encoder::new(file, width_px, height_px);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| GameError::CustomError(e.to_string()))?;
+        let row = (width_px * 4) as usize;
+        let cropped: Vec<u8> = pixels.chunks(padded_px as usize * 4).flat_map(|r| &r[..row]).copied().collect();
+        writer.write_image_data(&cropped).map_err(|e| GameError::CustomError(e.to_string()))?;
+        writer.finish().map_err(|e| GameError::CustomError(e.to_string()))?;
+
+        let game = self.shown_game();
+        let mut dump = format!(
+            "Score {}, tick {}\nSeed {} (round {}), hash {:016x}\n",
+            game.score,
+            game.tick_count(),
+            game.seed(),
+            game.round_seed(),
+            game.state_hash()
+        );
+        if let Some(cause) = game.death_cause {
+            dump.push_str(&format!("{}\n", cause));
+        }
+        dump.push('\n');
+        dump.push_str(&game.render_ascii());
+        dump.push('\n');
+        std::fs::write(path.with_extension("txt"), dump)?;
+        println!("Saved {}", path.display());
+        Ok(())
+    }
+
+    // Everything on screen. `view` maps the part of the board to show to
+    // screen coordinates, `letterbox` for the window.
+    fn draw_frame(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, view: &dyn Fn(Rect) -> Rect) -> GameResult {
+        let game = self.shown_game();
+        let theme = game.theme;
+        let (screen_width, screen_height) = self.screen_size();
+        let board = Rect::new(0.0, 0.0, screen_width, screen_height);
+        canvas.set_screen_coordinates(view(board));
+        canvas.draw(&graphics::Quad, graphics::DrawParam::new().scale(board.size()).color(color(theme.background)));
+        if !matches!(self.screen, Screen::Playing | Screen::Watching | Screen::Attract) {
+            self.draw_page(ctx, canvas)?;
+            return Ok(());
+        }
+
+        // Draw the board: walls and food as sprites, portals as plain
+        // quads. Snakes are drawn separately below so they can move
+        // smoothly between cells.
+        let intensity = self.settings.effect_intensity;
+        let mut world = self.juice.camera(screen_width, screen_height, intensity);
+        world.translate(self.camera.area().point());
+        canvas.set_screen_coordinates(view(world));
+        self.draw_pattern(canvas);
+        canvas.set_sampler(graphics::Sampler::nearest_clamp());
+        for (cell, kind) in game.cells() {
+            let Some(fill) = theme.cell_color(kind) else { continue };
+            let (x, y) = (cell.x as f32, cell.y as f32);
+            let tile = match kind {
+                CellKind::Food(_) => Tile::Apple,
+                CellKind::Obstacle => Tile::Wall,
+                CellKind::Portal => {
+                    let cell_rect = Rect::new(x * PIXEL_SCALE, y * PIXEL_SCALE, PIXEL_SCALE, PIXEL_SCALE);
+                    canvas.draw(
+                        &graphics::Quad,
+                        graphics::DrawParam::new().dest(cell_rect.point()).scale(cell_rect.size()).color(color(fill)),
+                    );
+                    continue;
+                }
+                _ => continue,
             };
+            self.draw_sprite(canvas, Sprite { tile, quarter_turns: 0 }, x, y, color(fill));
+        }
+
+        if self.heatmap && (game.game_over || self.screen == Screen::Watching) {
+            self.draw_heatmap(canvas);
+        }
+        if let (Some(report), Screen::Watching) = (&self.report, self.screen) {
+            self.draw_report(canvas, report);
+        }
+
+        // Tail first, so the head ends up on top
+        for index in 0..game.snake_count() {
+            let sprites = game.snake_sprites(index);
+            let visible = game.visible_segments(index);
+            let opacity = game.snake_opacity(index);
+            let segments = game.interpolated_body(index).into_iter().zip(sprites).take(visible);
+            for (i, ((x, y), sprite)) in segments.enumerate().rev() {
+                let mut tint = color(game.segment_color(index, i));
+                tint.a *= opacity;
+                self.draw_sprite(canvas, sprite, x, y, tint);
+            }
+        }
+        if self.debug_overlay {
+            self.draw_ai_paths(canvas);
+        }
+        canvas.set_sampler(graphics::Sampler::default());
+        canvas.set_screen_coordinates(view(board));
+
+        let flash = self.juice.flash_alpha(intensity);
+        if flash > 0.0 {
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new().scale([screen_width, screen_height]).color(Color::new(1.0, 0.0, 0.0, flash)),
+            );
+        }
+
+        self.draw_hud(ctx, canvas)?;
+        if self.settings.minimap && self.camera.following() {
+            self.draw_minimap(ctx, canvas)?;
+        }
+
+        // Draw game over/pause message, or the countdown
+        let confirm = self.confirm.filter(|_| self.screen == Screen::Playing);
+        let message = if let Some(confirm) = confirm {
+            Some(self.tr(confirm.message_key()).to_string())
+        } else if game.status() == GameStatus::Dying {
+            None
+        } else if let Some(count) = game.countdown() {
+            Some(count.to_string())
+        } else if game.replay_finished() {
+            Some(self.tr("replay-finished").to_string())
+        } else if game.game_over && !self.heatmap {
+            let language = self.settings.language;
+            let headline = game.death_cause.map_or(self.tr("game-over").to_string(), |cause| cause.message(language));
+            let stats = game.run_stats();
+            let stats = self.trf(
+                "game-over-stats",
+                &[("apples", &stats.apples), ("turns", &stats.turns), ("near_misses", &stats.near_misses)],
+            );
+            let mut message =
+                self.trf("game-over-message", &[("headline", &headline), ("score", &game.score), ("stats", &stats)]);
+            for id in self.new_skins.iter().filter(|_| self.screen == Screen::Playing) {
+                let name = self.skins.get(id).map_or(id.as_str(), |skin| self.tr(&skin.name));
+                message.push('\n');
+                message.push_str(&self.trf("skin-unlocked", &[("skin", &name)]));
+            }
+            if self.last_replay.is_some() && self.tutorial.is_none() {
+                message.push('\n');
+                message.push_str(self.tr("save-replay-hint"));
+            }
+            message.push('\n');
+            message.push_str(self.tr("heatmap-hint"));
+            Some(message)
+        } else if game.paused {
+            Some(self.tr("paused-message").to_string())
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            // Dim the board behind the message
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .scale([screen_width, screen_height])
+                    .color(Color::new(0.0, 0.0, 0.0, 0.5)),
+            );
+
+            // Long lines shrink to fit small boards
             let mut text = Text::new(message);
             text.set_scale(30.0);
-            let screen_width = GRID_SIZE.0 as f32 * PIXEL_SCALE;
-            let screen_height = GRID_SIZE.1 as f32 * PIXEL_SCALE;
-            let text_dimensions = text.measure(ctx)?;
+            let mut text_dimensions = text.measure(ctx)?;
+            if text_dimensions.x > screen_width - 20.0 {
+                text.set_scale(30.0 * (screen_width - 20.0) / text_dimensions.x);
+                text_dimensions = text.measure(ctx)?;
+            }
             let x = (screen_width - text_dimensions.x) / 2.0;
             let y = (screen_height - text_dimensions.y) / 2.0;
 
             canvas.draw(
                 &text,
                 graphics::DrawParam::new()
-                    .color(Color::WHITE)
+                    .color(color(theme.text))
                     .dest(ggez::mint::Point2 { x, y }),
             );
         }
 
-        canvas.finish(ctx)
+        if let (Some(player), Screen::Watching) = (&self.player, self.screen) {
+            self.draw_playback(ctx, canvas, player)?;
+        }
+        if self.debug_overlay {
+            self.draw_debug_info(ctx, canvas)?;
+        }
+        #[cfg(feature = "console")]
+        if self.console.open && self.screen == Screen::Playing {
+            self.draw_console(ctx, canvas)?;
+        }
+        Ok(())
     }
 
-    // The new key_down_event signature
-    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
-        if let Some(keycode) = input.keycode {
-            match keycode {
-                KeyCode::Up => self.game.change_snake_direction(Direction::Up),
-                KeyCode::Down => self.game.change_snake_direction(Direction::Down),
-                KeyCode::Left => self.game.change_snake_direction(Direction::Left),
-                KeyCode::Right => self.game.change_snake_direction(Direction::Right),
-                KeyCode::Space => {
-                    if !self.game.game_started || self.game.game_over {
-                        self.game.start_game();
+    fn draw_page(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let theme = self.shown_game().theme;
+        let (title, lines, selected) = self.page();
+        let (screen_width, screen_height) = self.screen_size();
+
+        let mut title = Text::new(title);
+        title.set_scale(40.0);
+        let width = title.measure(ctx)?.x;
+        canvas.draw(
+            &title,
+            graphics::DrawParam::new()
+                .color(color(theme.head))
+                .dest(ggez::mint::Point2 { x: (screen_width - width) / 2.0, y: 40.0 }),
+        );
+
+        let (top, line_height) = self.page_layout(lines.len());
+        for (i, line) in lines.into_iter().enumerate() {
+            // Sliders get a track under their line, with a knob at the value
+            let slider = SETTINGS_ITEMS.get(i).filter(|_| self.screen == Screen::Settings);
+            if let Some(value) = slider.and_then(|&item| self.slider_value(item)) {
+                let (left, right) = self.slider_track();
+                let y = top + (i as f32 + 0.85) * line_height;
+                let track = graphics::DrawParam::new().dest([left, y]).scale([right - left, 2.0]);
+                canvas.draw(&graphics::Quad, track.color(color(theme.grid_color())));
+                let knob = graphics::DrawParam::new().dest([left + (right - left) * value - 3.0, y - 2.0]).scale([6.0, 6.0]);
+                canvas.draw(&graphics::Quad, knob.color(color(theme.head)));
+            }
+            let highlighted = selected == Some(i);
+            let mut text = Text::new(if highlighted { format!("> {} <", line) } else { line });
+            text.set_scale(line_height * 0.8);
+            let width = text.measure(ctx)?.x;
+            canvas.draw(
+                &text,
+                graphics::DrawParam::new()
+                    .color(color(if highlighted { theme.head } else { theme.text }))
+                    .dest(ggez::mint::Point2 { x: (screen_width - width) / 2.0, y: top + i as f32 * line_height }),
+            );
+        }
+
+        let hint = match self.screen {
+            Screen::Menu => "hint-menu",
+            Screen::Settings => "hint-settings",
+            Screen::Controls if self.binding => "hint-binding",
+            Screen::Controls => "hint-controls",
+            Screen::NameEntry => "hint-name-entry",
+            Screen::Replays if !self.replay_rows().is_empty() => "hint-replays",
+            _ => "hint-back",
+        };
+        let mut hint = Text::new(self.tr(hint));
+        hint.set_scale(16.0);
+        let width = hint.measure(ctx)?.x;
+        canvas.draw(
+            &hint,
+            graphics::DrawParam::new()
+                .color(color(theme.text))
+                .dest(ggez::mint::Point2 { x: (screen_width - width) / 2.0, y: screen_height - 30.0 }),
+        );
+        Ok(())
+    }
+}
+
+// ggez's event handler implementation
+impl EventHandler for AppState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        #[cfg(feature = "hot-reload")]
+        self.reload();
+        #[cfg(feature = "discord")]
+        if self.presence.is_some() {
+            let activity = self.activity();
+            if let Some(presence) = &mut self.presence {
+                presence.update(ctx.time.delta(), activity);
+            }
+        }
+        let running = self.screen == Screen::Playing && self.game.game_started && !self.game.game_over && !self.game.paused;
+        let tempo = Some(self.game.music_tempo()).filter(|_| running && !self.settings.muted);
+        self.music.update(ctx, tempo, self.settings.music_volume);
+        self.juice.update(ctx.time.delta().as_secs_f32());
+        if let Some(&(x, y)) = self.shown_game().interpolated_body(0).first() {
+            let head = ((x + 0.5) * PIXEL_SCALE, (y + 0.5) * PIXEL_SCALE);
+            self.camera.follow(head, ctx.time.delta().as_secs_f32());
+        }
+        // The demo starts over once it's lost, every round a new seed
+        if let (Some(demo), Screen::Attract) = (&mut self.demo, self.screen) {
+            demo.advance(ctx.time.delta());
+            demo.drain_events().for_each(drop);
+            if demo.status() == GameStatus::GameOver {
+                demo.start_game();
+            }
+            return Ok(());
+        }
+        if self.can_attract() {
+            self.idle += ctx.time.delta();
+            if self.idle >= ATTRACT_AFTER {
+                return self.start_attract();
+            }
+        }
+        if let (Some(player), Screen::Watching) = (&mut self.player, self.screen) {
+            player.advance(ctx.time.delta());
+            let events: Vec<GameEvent> = player.drain_events().collect();
+            for event in &events {
+                self.feedback(ctx, event);
+            }
+            return Ok(());
+        }
+        if self.screen != Screen::Playing {
+            return Ok(());
+        }
+        // Chat votes turn the snake like a key press would, not in the tutorial
+        #[cfg(feature = "twitch")]
+        if let Some(twitch) = self.twitch.as_mut().filter(|_| self.tutorial.is_none()) {
+            twitch.update(&mut self.game);
+        }
+        // The core clock decides how many ticks fit in the elapsed frame time
+        self.game.advance(ctx.time.delta().mul_f32(self.juice.time_scale()));
+        self.overrides.report_rules_errors();
+        let events: Vec<GameEvent> = self.game.drain_events().collect();
+        // The first regular game after the tutorial
+        if self.tutorial.as_ref().is_some_and(|t| t.finished()) && events.contains(&GameEvent::Started) {
+            self.tutorial = None;
+        }
+        if self.tutorial.is_some() {
+            return self.update_tutorial(ctx, &events);
+        }
+        for event in events {
+            self.feedback(ctx, &event);
+            if event == GameEvent::Started {
+                self.round_started = unix_time();
+            }
+            if let GameEvent::GameOver { score, .. } = event {
+                self.last_replay = Some(self.game.replay());
+                self.log_session();
+                // A finished game is not worth resuming
+                if let Err(e) = self.persistence.clear_autosave() {
+                    eprintln!("could not clear the autosave: {}", e);
+                }
+                // The name is asked for once the death animation is over.
+                // Rounds with cheats don't make the table or unlock skins.
+                let stats = self.game.run_stats();
+                let cheated = stats.cheats > 0;
+                if !cheated && self.persistence.load_high_scores().rank(score).is_some() {
+                    self.pending_score = Some(score);
+                    self.name = self.settings.player_name.clone();
+                }
+                if !cheated {
+                    match self.persistence.unlock_skins(&self.skins, &stats) {
+                        Ok(new) => {
+                            self.unlocked_skins.extend(new.iter().cloned());
+                            self.new_skins = new;
+                        }
+                        Err(e) => eprintln!("could not save the unlocked skins: {}", e),
                     }
                 }
-                _ => (),
             }
         }
+        if self.pending_score.is_some() && self.game.status() == GameStatus::GameOver {
+            self.screen = Screen::NameEntry;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let window = ctx.gfx.drawable_size();
+        let zoomed = matches!(self.screen, Screen::Playing | Screen::Watching | Screen::Attract);
+        self.camera.fit(window, self.board_size(), zoomed);
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
+        self.draw_frame(ctx, &mut canvas, &|area| letterbox(area, window))?;
+        canvas.finish(ctx)?;
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            if let Err(e) = self.save_screenshot(ctx) {
+                eprintln!("could not save the screenshot: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    // The new key_down_event signature
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+        let Some(key) = input.keycode else { return Ok(()) };
+        if self.wake() {
+            return Ok(());
+        }
+        #[cfg(feature = "console")]
+        if self.console_key(key) {
+            return Ok(());
+        }
+        let alt_enter = matches!(key, KeyCode::Return | KeyCode::NumpadEnter) && input.mods.contains(KeyMods::ALT);
+        if key == KeyCode::F11 || alt_enter {
+            return self.toggle_fullscreen(ctx);
+        }
+        if key == KeyCode::F3 {
+            self.debug_overlay = !self.debug_overlay;
+            return Ok(());
+        }
+        if key == KeyCode::F12 {
+            self.screenshot_requested = true;
+            return Ok(());
+        }
+        if matches!(self.screen, Screen::Playing | Screen::Watching) {
+            let zoom = match key {
+                KeyCode::Equals | KeyCode::Plus | KeyCode::NumpadAdd => Some(1),
+                KeyCode::Minus | KeyCode::NumpadSubtract => Some(-1),
+                _ => None,
+            };
+            if let Some(delta) = zoom {
+                self.camera.zoom(delta);
+                return Ok(());
+            }
+        }
+        self.key_pressed(ctx, key)
+    }
+
+    // D-pad turns, Start pauses, A confirms (restarts after a game over),
+    // B boosts and Select leaves a paused or finished game for the menu
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) -> GameResult {
+        if self.wake() {
+            return Ok(());
+        }
+        let pad = self.pad_index(id);
+        let direction = match btn {
+            Button::DPadUp => Some(Direction::Up),
+            Button::DPadDown => Some(Direction::Down),
+            Button::DPadLeft => Some(Direction::Left),
+            Button::DPadRight => Some(Direction::Right),
+            _ => None,
+        };
+        if self.screen != Screen::Playing || self.confirm.is_some() {
+            let key = match (btn, direction) {
+                (_, Some(direction)) => direction_key(direction),
+                (Button::South | Button::Start, _) => KeyCode::Return,
+                (Button::East | Button::Select, _) => KeyCode::Escape,
+                _ => return Ok(()),
+            };
+            return self.pad_menu_key(ctx, key);
+        }
+        match (btn, direction) {
+            (_, Some(direction)) => self.pad_turn(pad, direction),
+            (Button::Start, _) => self.game.handle_action(InputAction::Pause),
+            (Button::South, _) => self.game.handle_action(InputAction::Confirm),
+            (Button::East, _) => self.game.handle_action(InputAction::Boost),
+            (Button::Select, _) if self.game.paused || self.game.game_over => {
+                self.open_menu()
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) -> GameResult {
+        let index = self.pad_index(id);
+        let pad = &mut self.pads[index];
+        match axis {
+            Axis::LeftStickX => pad.stick.0 = value,
+            // gilrs' y axis points up
+            Axis::LeftStickY => pad.stick.1 = -value,
+            _ => return Ok(()),
+        }
+        let direction = Direction::from_stick(pad.stick.0 as f64, pad.stick.1 as f64, DEFAULT_STICK_DEAD_ZONE);
+        if direction == pad.stick_direction {
+            return Ok(());
+        }
+        pad.stick_direction = direction;
+        if direction.is_some() && self.wake() {
+            return Ok(());
+        }
+        match direction {
+            Some(direction) if self.screen == Screen::Playing => self.pad_turn(index, direction),
+            Some(direction) => self.pad_menu_key(ctx, direction_key(direction))?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) -> GameResult {
+        if self.wake() {
+            return Ok(());
+        }
+        // A click answers the question, the right button says no
+        if self.confirm.is_some() && self.screen == Screen::Playing {
+            return match button {
+                MouseButton::Left => self.key_pressed(ctx, KeyCode::Return),
+                MouseButton::Right => self.key_pressed(ctx, KeyCode::Escape),
+                _ => Ok(()),
+            };
+        }
+        if matches!(self.screen, Screen::Playing | Screen::Watching) || self.binding {
+            return Ok(());
+        }
+        let (x, y) = self.window_to_screen(ctx, x, y);
+        match button {
+            MouseButton::Left => self.click(ctx, x, y),
+            // The right button goes back like Esc
+            MouseButton::Right => self.key_pressed(ctx, KeyCode::Escape),
+            _ => Ok(()),
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) -> GameResult {
+        if button == MouseButton::Left {
+            self.dragging = None;
+        }
+        Ok(())
+    }
+
+    // Hovering a line selects it, dragging moves the held slider
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) -> GameResult {
+        if matches!(self.screen, Screen::Playing | Screen::Watching) {
+            return Ok(());
+        }
+        let (x, y) = self.window_to_screen(ctx, x, y);
+        if let Some(item) = self.dragging {
+            let (left, right) = self.slider_track();
+            return self.set_slider(ctx, item, (x - left) / (right - left));
+        }
+        if let Some(line) = self.page_line_at(y) {
+            self.select_line(line);
+        }
+        Ok(())
+    }
+
+    // Don't let the snake run into a wall while the window is in the background
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult {
+        if !gained {
+            self.pause_in_background();
+        }
+        Ok(())
+    }
+
+    // Minimizing shrinks the window to nothing on some platforms without
+    // taking the focus away first
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        if width == 0.0 || height == 0.0 {
+            self.pause_in_background();
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        // The backtick that opened it comes in as text too
+        #[cfg(feature = "console")]
+        if self.console.open {
+            if !character.is_control() && character != '`' {
+                self.console.line.push(character);
+            }
+            return Ok(());
+        }
+        if self.screen == Screen::NameEntry && !character.is_control() && self.name.chars().count() < MAX_NAME_LEN {
+            self.name.push(character);
+        }
         Ok(())
     }
+
+    // Autosave a game in progress when the window closes, and keep a high
+    // score whose name wasn't confirmed yet. Closing in the middle of a
+    // run asks first, returning true keeps the window open.
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        if self.screen == Screen::Playing && self.can_resume() && !self.quit_confirmed {
+            self.ask(Confirm::Quit);
+            return Ok(true);
+        }
+        self.save_high_score();
+        if self.can_resume() && self.tutorial.is_none() {
+            if let Err(e) = self.persistence.save_autosave(&self.game.snapshot()) {
+                eprintln!("could not autosave: {}", e);
+            }
+        }
+        Ok(false)
+    }
 }
 
 // Main function for the native executable
 pub fn main() -> GameResult {
+    let args = Args::parse();
+    // Exporting doesn't need a window
+    if let (Some(path), Some(replay)) = (&args.export, &args.replay) {
+        let options = ExportOptions { cell_px: args.export_scale, speed: args.export_speed, ..ExportOptions::default() };
+        export_replay(&read_replay(replay)?, &options, path).map_err(GameError::CustomError)?;
+        println!("Saved {}", path.display());
+        return Ok(());
+    }
     let (mut ctx, event_loop) = ContextBuilder::new("snake_game", "Gemini")
         .window_setup(conf::WindowSetup::default().title("Snake Game (Rust Native)"))
         .window_mode(
             conf::WindowMode::default()
-                .dimensions(GRID_SIZE.0 as f32 * PIXEL_SCALE, GRID_SIZE.1 as f32 * PIXEL_SCALE),
+                .dimensions(BOARD_SIZES[0].0 as f32 * PIXEL_SCALE, BOARD_SIZES[0].1 as f32 * PIXEL_SCALE)
+                .resizable(true)
+                .min_dimensions(200.0, 200.0),
         )
         .build()?;
 
-    let state = AppState::new(&mut ctx);
+    let state = AppState::new(&mut ctx, args)?;
     event::run(ctx, event_loop, state)
 }
\ No newline at end of file
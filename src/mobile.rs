@@ -0,0 +1,838 @@
+// src/mobile.rs
+
+// Kotlin and Swift bindings through UniFFI, so Android and iOS apps run
+// the same engine as the desktop and web frontends instead of a port of the
+// rules. Only with the `uniffi` feature. The library goes into the app and
+// uniffi-bindgen writes the Kotlin/Swift code from it:
+//
+//     cargo ndk -t arm64-v8a build --release --lib --no-default-features --features uniffi
+//     cargo rustc --release --lib --no-default-features --features uniffi \
+//         --target aarch64-apple-ios --crate-type staticlib
+//     cargo run --bin uniffi-bindgen --features uniffi-cli -- generate \
+//         --library target/release/libsnake_game.so --language kotlin --out-dir bindings
+//
+// Then, in Kotlin:
+//
+//     val game = Game.withConfig(defaultConfig().copy(width = 30, seed = 42uL))
+//     game.handleAction(Action.CONFIRM)
+//     game.advance(Duration.ofMillis(16))
+//     for (event in game.drainEvents()) { ... }
+//     draw(game.state())
+//
+// The types here mirror the core ones with the few changes the bindings
+// need (no usize, no tuples), like the proto ones do. A `Game` can be used
+// from any thread, the calls take turns. Errors are a `GameException`
+// (Kotlin) or `GameError` (Swift) with the `GameError::code` as `code`.
+
+use std::prelude::rust_2021::*;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{InputAction, Level, Replay, SnakeSpawn, Theme};
+
+#[derive(Debug, uniffi::Error)]
+pub enum GameError {
+    Failed { code: String, message: String },
+}
+
+impl From<crate::GameError> for GameError {
+    fn from(e: crate::GameError) -> GameError {
+        GameError::Failed { code: e.code().to_string(), message: e.to_string() }
+    }
+}
+
+impl core::fmt::Display for GameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GameError::Failed { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<crate::Point> for Point {
+    fn from(p: crate::Point) -> Point {
+        Point { x: p.x, y: p.y }
+    }
+}
+
+impl From<Point> for crate::Point {
+    fn from(p: Point) -> crate::Point {
+        crate::Point { x: p.x, y: p.y }
+    }
+}
+
+fn points(points: &[crate::Point]) -> Vec<Point> {
+    points.iter().map(|&p| p.into()).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<crate::Direction> for Direction {
+    fn from(d: crate::Direction) -> Direction {
+        match d {
+            crate::Direction::Up => Direction::Up,
+            crate::Direction::Down => Direction::Down,
+            crate::Direction::Left => Direction::Left,
+            crate::Direction::Right => Direction::Right,
+        }
+    }
+}
+
+impl From<Direction> for crate::Direction {
+    fn from(d: Direction) -> crate::Direction {
+        match d {
+            Direction::Up => crate::Direction::Up,
+            Direction::Down => crate::Direction::Down,
+            Direction::Left => crate::Direction::Left,
+            Direction::Right => crate::Direction::Right,
+        }
+    }
+}
+
+// Flat version of `InputAction`, like the WASM module's and the Node addon's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Restart,
+    Boost,
+    Confirm,
+}
+
+impl From<Action> for InputAction {
+    fn from(a: Action) -> InputAction {
+        match a {
+            Action::Up => InputAction::Turn(crate::Direction::Up),
+            Action::Down => InputAction::Turn(crate::Direction::Down),
+            Action::Left => InputAction::Turn(crate::Direction::Left),
+            Action::Right => InputAction::Turn(crate::Direction::Right),
+            Action::Pause => InputAction::Pause,
+            Action::Restart => InputAction::Restart,
+            Action::Boost => InputAction::Boost,
+            Action::Confirm => InputAction::Confirm,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Controller {
+    Greedy,
+    Pathfinder,
+    Chaser,
+}
+
+impl From<crate::Controller> for Controller {
+    fn from(c: crate::Controller) -> Controller {
+        match c {
+            crate::Controller::Greedy => Controller::Greedy,
+            crate::Controller::Pathfinder => Controller::Pathfinder,
+            crate::Controller::Chaser => Controller::Chaser,
+        }
+    }
+}
+
+impl From<Controller> for crate::Controller {
+    fn from(c: Controller) -> crate::Controller {
+        match c {
+            Controller::Greedy => crate::Controller::Greedy,
+            Controller::Pathfinder => crate::Controller::Pathfinder,
+            Controller::Chaser => crate::Controller::Chaser,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum AfkMode {
+    Straight,
+    Safe,
+}
+
+impl From<crate::AfkMode> for AfkMode {
+    fn from(m: crate::AfkMode) -> AfkMode {
+        match m {
+            crate::AfkMode::Straight => AfkMode::Straight,
+            crate::AfkMode::Safe => AfkMode::Safe,
+        }
+    }
+}
+
+impl From<AfkMode> for crate::AfkMode {
+    fn from(m: AfkMode) -> crate::AfkMode {
+        match m {
+            AfkMode::Straight => crate::AfkMode::Straight,
+            AfkMode::Safe => crate::AfkMode::Safe,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ThemePreset {
+    Classic,
+    Neon,
+    ColorblindSafe,
+}
+
+impl From<crate::ThemePreset> for ThemePreset {
+    fn from(p: crate::ThemePreset) -> ThemePreset {
+        match p {
+            crate::ThemePreset::Classic => ThemePreset::Classic,
+            crate::ThemePreset::Neon => ThemePreset::Neon,
+            crate::ThemePreset::ColorblindSafe => ThemePreset::ColorblindSafe,
+        }
+    }
+}
+
+impl From<ThemePreset> for crate::ThemePreset {
+    fn from(p: ThemePreset) -> crate::ThemePreset {
+        match p {
+            ThemePreset::Classic => crate::ThemePreset::Classic,
+            ThemePreset::Neon => crate::ThemePreset::Neon,
+            ThemePreset::ColorblindSafe => crate::ThemePreset::ColorblindSafe,
+        }
+    }
+}
+
+// The language of `Game::death_message`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Language {
+    English,
+    Portuguese,
+}
+
+impl From<Language> for crate::Language {
+    fn from(l: Language) -> crate::Language {
+        match l {
+            Language::English => crate::Language::English,
+            Language::Portuguese => crate::Language::Portuguese,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FoodKind {
+    Apple,
+    Bonus,
+    SlowDown,
+}
+
+impl From<crate::FoodKind> for FoodKind {
+    fn from(k: crate::FoodKind) -> FoodKind {
+        match k {
+            crate::FoodKind::Apple => FoodKind::Apple,
+            crate::FoodKind::Bonus => FoodKind::Bonus,
+            crate::FoodKind::SlowDown => FoodKind::SlowDown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct Food {
+    pub pos: Point,
+    pub kind: FoodKind,
+    // Ticks left before it disappears, null for food that stays
+    pub ttl: Option<u32>,
+}
+
+impl From<crate::Food> for Food {
+    fn from(f: crate::Food) -> Food {
+        Food { pos: f.pos.into(), kind: f.kind.into(), ttl: f.ttl }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum GameMode {
+    Classic,
+    Timed { limit_secs: u32 },
+}
+
+impl From<crate::GameMode> for GameMode {
+    fn from(m: crate::GameMode) -> GameMode {
+        match m {
+            crate::GameMode::Classic => GameMode::Classic,
+            crate::GameMode::Timed { limit_secs } => GameMode::Timed { limit_secs },
+        }
+    }
+}
+
+impl From<GameMode> for crate::GameMode {
+    fn from(m: GameMode) -> crate::GameMode {
+        match m {
+            GameMode::Classic => crate::GameMode::Classic,
+            GameMode::Timed { limit_secs } => crate::GameMode::Timed { limit_secs },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct SpeedCurve {
+    pub base_interval: Duration,
+    pub step: Duration,
+    pub min_interval: Duration,
+    pub points_per_level: u32,
+}
+
+impl From<crate::SpeedCurve> for SpeedCurve {
+    fn from(s: crate::SpeedCurve) -> SpeedCurve {
+        SpeedCurve {
+            base_interval: s.base_interval,
+            step: s.step,
+            min_interval: s.min_interval,
+            points_per_level: s.points_per_level,
+        }
+    }
+}
+
+impl From<SpeedCurve> for crate::SpeedCurve {
+    fn from(s: SpeedCurve) -> crate::SpeedCurve {
+        crate::SpeedCurve {
+            base_interval: s.base_interval,
+            step: s.step,
+            min_interval: s.min_interval,
+            points_per_level: s.points_per_level,
+        }
+    }
+}
+
+// Start from `default_config()` and change what's needed
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct GameConfig {
+    pub width: i32,
+    pub height: i32,
+    // null picks a random seed
+    pub seed: Option<u64>,
+    pub mode: GameMode,
+    pub wrap_walls: bool,
+    pub food_count: u32,
+    pub speed_curve: SpeedCurve,
+    pub snake_length: u32,
+    // One per extra snake: the AI steering it, or null for a second player
+    pub opponents: Vec<Option<Controller>>,
+    pub theme: ThemePreset,
+    pub countdown_secs: u32,
+    pub reduced_motion: bool,
+    // Fraction of a tick a late turn still counts for it, 0 for none
+    pub input_grace: f32,
+    // Ticks without input before a player's snake counts as away, 0 for never
+    pub afk_ticks: u64,
+    pub afk_mode: AfkMode,
+    // Boss encounters at score milestones, null for none
+    pub boss: Option<BossConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct BossConfig {
+    // Points between encounters
+    pub every: u32,
+    // Ticks each one lasts
+    pub ticks: u64,
+    pub length: u32,
+}
+
+impl From<crate::BossConfig> for BossConfig {
+    fn from(c: crate::BossConfig) -> BossConfig {
+        BossConfig { every: c.every, ticks: c.ticks, length: c.length as u32 }
+    }
+}
+
+impl From<BossConfig> for crate::BossConfig {
+    fn from(c: BossConfig) -> crate::BossConfig {
+        crate::BossConfig { every: c.every, ticks: c.ticks, length: c.length as usize }
+    }
+}
+
+impl From<crate::GameConfig> for GameConfig {
+    fn from(c: crate::GameConfig) -> GameConfig {
+        GameConfig {
+            width: c.width,
+            height: c.height,
+            seed: c.seed,
+            mode: c.mode.into(),
+            wrap_walls: c.wrap_walls,
+            food_count: c.food_count as u32,
+            speed_curve: c.speed_curve.into(),
+            snake_length: c.snake_length as u32,
+            opponents: c.opponents.iter().map(|o| o.map(Into::into)).collect(),
+            theme: c.theme.into(),
+            countdown_secs: c.countdown_secs,
+            reduced_motion: c.reduced_motion,
+            input_grace: c.input_grace,
+            afk_ticks: c.afk_ticks,
+            afk_mode: c.afk_mode.into(),
+            boss: c.boss.map(Into::into),
+        }
+    }
+}
+
+impl From<GameConfig> for crate::GameConfig {
+    fn from(c: GameConfig) -> crate::GameConfig {
+        crate::GameConfig {
+            width: c.width,
+            height: c.height,
+            seed: c.seed,
+            mode: c.mode.into(),
+            wrap_walls: c.wrap_walls,
+            food_count: c.food_count as usize,
+            speed_curve: c.speed_curve.into(),
+            snake_length: c.snake_length as usize,
+            opponents: c.opponents.iter().map(|o| o.map(Into::into)).collect(),
+            theme: c.theme.into(),
+            countdown_secs: c.countdown_secs,
+            reduced_motion: c.reduced_motion,
+            input_grace: c.input_grace,
+            afk_ticks: c.afk_ticks,
+            afk_mode: c.afk_mode.into(),
+            boss: c.boss.map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum GameStatus {
+    NotStarted,
+    Countdown,
+    Running,
+    Paused,
+    Dying,
+    GameOver,
+}
+
+impl From<crate::GameStatus> for GameStatus {
+    fn from(s: crate::GameStatus) -> GameStatus {
+        match s {
+            crate::GameStatus::NotStarted => GameStatus::NotStarted,
+            crate::GameStatus::Countdown => GameStatus::Countdown,
+            crate::GameStatus::Running => GameStatus::Running,
+            crate::GameStatus::Paused => GameStatus::Paused,
+            crate::GameStatus::Dying => GameStatus::Dying,
+            crate::GameStatus::GameOver => GameStatus::GameOver,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum DeathCause {
+    Wall { at: Point },
+    Obstacle { at: Point },
+    // `segment` counts from the head (1 = neck)
+    SelfCollision { at: Point, segment: u32 },
+    BoardFull,
+    TimeExpired,
+    // `snake` is the index of the one run into, 0 being the player's
+    Opponent { at: Point, snake: u32 },
+    Rules,
+}
+
+impl From<crate::DeathCause> for DeathCause {
+    fn from(c: crate::DeathCause) -> DeathCause {
+        match c {
+            crate::DeathCause::Wall { at } => DeathCause::Wall { at: at.into() },
+            crate::DeathCause::Obstacle { at } => DeathCause::Obstacle { at: at.into() },
+            crate::DeathCause::SelfCollision { at, segment } => {
+                DeathCause::SelfCollision { at: at.into(), segment: segment as u32 }
+            }
+            crate::DeathCause::BoardFull => DeathCause::BoardFull,
+            crate::DeathCause::TimeExpired => DeathCause::TimeExpired,
+            crate::DeathCause::Opponent { at, snake } => DeathCause::Opponent { at: at.into(), snake: snake as u32 },
+            crate::DeathCause::Rules => DeathCause::Rules,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct Portal {
+    pub a: Point,
+    pub b: Point,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct SnakeState {
+    // Head first
+    pub body: Vec<Point>,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct OpponentState {
+    pub body: Vec<Point>,
+    pub direction: Direction,
+    pub score: u32,
+    pub alive: bool,
+    pub controller: Option<Controller>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct GameState {
+    pub width: i32,
+    pub height: i32,
+    pub snake: SnakeState,
+    pub food: Vec<Food>,
+    pub obstacles: Vec<Point>,
+    pub portals: Vec<Portal>,
+    pub score: u32,
+    pub mode: GameMode,
+    pub wrap_walls: bool,
+    pub status: GameStatus,
+    // Set once the game is over
+    pub death_cause: Option<DeathCause>,
+    pub tick: u64,
+    pub speed_level: u32,
+    pub boosting: bool,
+    pub slow_ticks_left: u32,
+    pub opponents: Vec<OpponentState>,
+}
+
+impl From<crate::GameState> for GameState {
+    fn from(s: crate::GameState) -> GameState {
+        GameState {
+            width: s.width,
+            height: s.height,
+            snake: SnakeState { body: points(&s.snake.body), direction: s.snake.direction.into() },
+            food: s.food.into_iter().map(Into::into).collect(),
+            obstacles: points(&s.obstacles),
+            portals: s.portals.iter().map(|&(a, b)| Portal { a: a.into(), b: b.into() }).collect(),
+            score: s.score,
+            mode: s.mode.into(),
+            wrap_walls: s.wrap_walls,
+            status: s.status.into(),
+            death_cause: s.death_cause.map(Into::into),
+            tick: s.tick,
+            speed_level: s.speed_level,
+            boosting: s.boosting,
+            slow_ticks_left: s.slow_ticks_left,
+            opponents: s
+                .opponents
+                .into_iter()
+                .map(|o| OpponentState {
+                    body: points(&o.body),
+                    direction: o.direction.into(),
+                    score: o.score,
+                    alive: o.alive,
+                    controller: o.controller.map(Into::into),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum GameEvent {
+    Started,
+    Countdown { remaining: u32 },
+    FoodEaten { at: Point, kind: FoodKind, score: u32 },
+    PowerUpCollected { at: Point, kind: FoodKind, duration_ticks: u32 },
+    FoodExpired { at: Point, kind: FoodKind },
+    LevelUp { level: u32 },
+    Turned { direction: Direction },
+    NearMiss { at: Point },
+    GameOver { cause: DeathCause, score: u32 },
+    SnakeAte { snake: u32, at: Point, kind: FoodKind, score: u32 },
+    SnakeDied { snake: u32, cause: DeathCause },
+    BossAppeared { snake: u32, ticks: u64 },
+    BossLeft { snake: u32, trapped: bool },
+}
+
+impl From<crate::GameEvent> for GameEvent {
+    fn from(e: crate::GameEvent) -> GameEvent {
+        match e {
+            crate::GameEvent::Started => GameEvent::Started,
+            crate::GameEvent::Countdown { remaining } => GameEvent::Countdown { remaining },
+            crate::GameEvent::FoodEaten { at, kind, score } => {
+                GameEvent::FoodEaten { at: at.into(), kind: kind.into(), score }
+            }
+            crate::GameEvent::PowerUpCollected { at, kind, duration_ticks } => {
+                GameEvent::PowerUpCollected { at: at.into(), kind: kind.into(), duration_ticks }
+            }
+            crate::GameEvent::FoodExpired { at, kind } => GameEvent::FoodExpired { at: at.into(), kind: kind.into() },
+            crate::GameEvent::LevelUp { level } => GameEvent::LevelUp { level },
+            crate::GameEvent::Turned { direction } => GameEvent::Turned { direction: direction.into() },
+            crate::GameEvent::NearMiss { at } => GameEvent::NearMiss { at: at.into() },
+            crate::GameEvent::GameOver { cause, score } => GameEvent::GameOver { cause: cause.into(), score },
+            crate::GameEvent::SnakeAte { snake, at, kind, score } => {
+                GameEvent::SnakeAte { snake: snake as u32, at: at.into(), kind: kind.into(), score }
+            }
+            crate::GameEvent::SnakeDied { snake, cause } => {
+                GameEvent::SnakeDied { snake: snake as u32, cause: cause.into() }
+            }
+            crate::GameEvent::BossAppeared { snake, ticks } => GameEvent::BossAppeared { snake: snake as u32, ticks },
+            crate::GameEvent::BossLeft { snake, trapped } => GameEvent::BossLeft { snake: snake as u32, trapped },
+        }
+    }
+}
+
+// A color as 0-255 channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl From<crate::Rgb> for Rgb {
+    fn from(c: crate::Rgb) -> Rgb {
+        Rgb { r: c.r, g: c.g, b: c.b }
+    }
+}
+
+// The colors the other frontends draw with, see `Theme`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct Colors {
+    pub background: Rgb,
+    pub head: Rgb,
+    pub body: Rgb,
+    pub opponent_head: Rgb,
+    pub opponent_body: Rgb,
+    pub apple: Rgb,
+    pub bonus: Rgb,
+    pub slow_down: Rgb,
+    pub obstacle: Rgb,
+    pub portal: Rgb,
+    pub text: Rgb,
+}
+
+impl From<Theme> for Colors {
+    fn from(t: Theme) -> Colors {
+        Colors {
+            background: t.background.into(),
+            head: t.head.into(),
+            body: t.body.into(),
+            opponent_head: t.opponent_head.into(),
+            opponent_body: t.opponent_body.into(),
+            apple: t.apple.into(),
+            bonus: t.bonus.into(),
+            slow_down: t.slow_down.into(),
+            obstacle: t.obstacle.into(),
+            portal: t.portal.into(),
+            text: t.text.into(),
+        }
+    }
+}
+
+// A body segment in fractional cells, see `Game::interpolated_body`
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Record)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+// The config every frontend starts from
+#[uniffi::export]
+pub fn default_config() -> GameConfig {
+    crate::GameConfig::default().into()
+}
+
+#[derive(uniffi::Object)]
+pub struct Game {
+    game: Mutex<crate::Game>,
+}
+
+impl Game {
+    fn wrap(game: crate::Game) -> Arc<Game> {
+        Arc::new(Game { game: Mutex::new(game) })
+    }
+
+    // A call that panicked left the game as it was after its last complete
+    // change, keep playing it
+    fn game(&self) -> MutexGuard<'_, crate::Game> {
+        self.game.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[uniffi::export]
+impl Game {
+    // An empty board, `seed` gives the same food placement as every other
+    // client using it
+    #[uniffi::constructor]
+    pub fn new(width: i32, height: i32, seed: Option<u64>) -> Result<Arc<Game>, GameError> {
+        let level = Level::empty(width, height);
+        let game = match seed {
+            Some(seed) => crate::Game::from_level_with_seed(&level, seed),
+            None => crate::Game::from_level(&level),
+        };
+        Ok(Game::wrap(game?))
+    }
+
+    #[uniffi::constructor]
+    pub fn with_config(config: GameConfig) -> Result<Arc<Game>, GameError> {
+        Ok(Game::wrap(crate::Game::from_config(&config.into())?))
+    }
+
+    // A level in the text format (see `Level::parse`)
+    #[uniffi::constructor]
+    pub fn from_level(text: String) -> Result<Arc<Game>, GameError> {
+        Ok(Game::wrap(crate::Game::from_level(&Level::parse(&text)?)?))
+    }
+
+    // The playback of an exported replay, it steers itself as the game
+    // advances
+    #[uniffi::constructor]
+    pub fn from_replay(data: String) -> Result<Arc<Game>, GameError> {
+        Ok(Game::wrap(Replay::from_base64(&data)?.game()?))
+    }
+
+    pub fn tick(&self) {
+        self.game().tick();
+    }
+
+    // Call every frame with the time since the last one, the game runs the
+    // right number of ticks for its speed level. Returns how many ran.
+    pub fn advance(&self, elapsed: Duration) -> u32 {
+        self.game().advance(elapsed)
+    }
+
+    // What happened since the last call, in order. Call it regularly, the
+    // events pile up otherwise.
+    pub fn drain_events(&self) -> Vec<GameEvent> {
+        self.game().drain_events().map(Into::into).collect()
+    }
+
+    pub fn handle_action(&self, action: Action) {
+        self.game().handle_action(action.into());
+    }
+
+    // A key name ("ArrowUp", "w", " ", "p"...) from a hardware keyboard.
+    // Returns false for keys the game doesn't use.
+    pub fn handle_key(&self, key: String) -> bool {
+        match InputAction::from_key(&key) {
+            Some(action) => {
+                self.game().handle_action(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn start_game(&self) {
+        self.game().start_game();
+    }
+
+    pub fn pause(&self) {
+        self.game().pause();
+    }
+
+    pub fn resume(&self) {
+        self.game().resume();
+    }
+
+    // The whole state in one go, for drawing a frame
+    pub fn state(&self) -> GameState {
+        self.game().state().into()
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.game().status().into()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game().score
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.game().seed()
+    }
+
+    // The seed the current round started from, what its replay uses
+    pub fn round_seed(&self) -> u64 {
+        self.game().round_seed()
+    }
+
+    // Compare between clients after the same tick to detect a desync
+    pub fn state_hash(&self) -> u64 {
+        self.game().state_hash()
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        self.game().tick_interval()
+    }
+
+    // Game time left in timed mode, null otherwise
+    pub fn time_left(&self) -> Option<Duration> {
+        self.game().time_left()
+    }
+
+    // The number to show before the snake starts moving, null after
+    pub fn countdown(&self) -> Option<u32> {
+        self.game().countdown()
+    }
+
+    // Why the game ended as a sentence ("Hit the wall at (20, 4)")
+    pub fn death_message(&self, language: Language) -> Option<String> {
+        self.game().death_cause.map(|cause| cause.message(language.into()))
+    }
+
+    // The current (or last finished) round as a URL-safe base64 string
+    pub fn export_replay(&self) -> String {
+        self.game().replay().to_base64()
+    }
+
+    pub fn colors(&self) -> Colors {
+        self.game().theme.into()
+    }
+
+    pub fn set_theme_preset(&self, preset: ThemePreset) {
+        self.game().theme = Theme::preset(preset.into());
+    }
+
+    // Multiple snakes: snake 0 is the player's, opponents are numbered from
+    // 1. Opponents without a controller are steered with `queue_turn_for`.
+
+    // Adds a snake at the given spot, returns its index
+    pub fn add_opponent(&self, head: Point, direction: Direction, length: u32, controller: Option<Controller>) -> Result<u32, GameError> {
+        let spawn = SnakeSpawn { head: head.into(), direction: direction.into(), length: length as usize };
+        Ok(self.game().add_opponent(spawn, controller.map(Into::into))? as u32)
+    }
+
+    // Adds a snake wherever there's room, returns its index
+    pub fn add_opponent_auto(&self, controller: Option<Controller>) -> Result<u32, GameError> {
+        Ok(self.game().add_opponent_auto(controller.map(Into::into))? as u32)
+    }
+
+    // Hands a snake to a built-in AI, or back to a player with null
+    pub fn set_controller(&self, index: u32, controller: Option<Controller>) {
+        self.game().set_controller(index as usize, controller.map(Into::into));
+    }
+
+    pub fn queue_turn_for(&self, index: u32, direction: Direction) {
+        self.game().queue_turn_for(index as usize, direction.into());
+    }
+
+    // Segments part way between the last two ticks, for smooth motion
+    pub fn interpolated_body(&self, index: u32) -> Vec<Position> {
+        self.game().interpolated_body(index as usize).into_iter().map(|(x, y)| Position { x, y }).collect()
+    }
+
+    // 0.0 right after a tick up to 1.0 just before the next one
+    pub fn tick_progress(&self) -> f32 {
+        self.game().tick_progress()
+    }
+
+    // How many segments of a snake to draw, fewer while the death
+    // animation plays
+    pub fn visible_segments(&self, index: u32) -> u32 {
+        self.game().visible_segments(index as usize) as u32
+    }
+
+    // How opaque to draw a snake (0 to 1)
+    pub fn snake_opacity(&self, index: u32) -> f32 {
+        self.game().snake_opacity(index as usize)
+    }
+
+    // The pathfinder's route to the closest food, for hints
+    pub fn ai_path(&self, index: u32) -> Vec<Point> {
+        points(&self.game().ai_path(index as usize))
+    }
+}
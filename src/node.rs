@@ -0,0 +1,667 @@
+// src/node.rs
+
+// Native Node.js addon with the same API as the WASM module: a `Game` class
+// with the same snake_case methods, and the same Direction, Action,
+// Controller, ThemePreset and FoodKind enums. Code written against the WASM
+// build runs on this one at full native speed, for server-side bots, bot
+// tournaments and Electron apps. What only makes sense in a browser (canvas,
+// keyboard, gamepad, audio, localStorage, touch) is left out. Only compiled
+// with the `node` feature:
+//
+//     cargo build --release --lib --no-default-features --features node
+//     cp target/release/libsnake_game.so snake_game.node
+//
+// (snake_game.dll on Windows, libsnake_game.dylib on macOS), then
+//
+//     const { Game, Action } = require("./snake_game.node");
+//     const game = new Game(20, 20, 42n);
+//     game.on_event((event) => console.log(event.type));
+//     game.handle_action(Action.Confirm);
+//     while (!game.game_over()) game.tick();
+//
+// Errors are thrown as `Error`s with the `GameError::code` as their `code`,
+// like the WASM module's. A listener that throws makes the call that fired
+// the event throw.
+
+use core::cell::{Ref, RefCell, RefMut};
+use core::time::Duration;
+// The crate is no_std, the code napi generates expects the std prelude
+use std::format;
+use std::prelude::rust_2021::*;
+
+use napi::bindgen_prelude::{BigInt, Float32Array, Function, FunctionRef, Int32Array, Uint32Array};
+use napi::Env;
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::{
+    Controller, Direction, FoodKind, Game, GameConfig, GameError, InputAction, Level, Point, Replay, SnakeSpawn, Theme,
+    ThemePreset,
+};
+
+type Listener = FunctionRef<Value, ()>;
+
+// Errors with a string status, which napi throws as the JS error's `code`
+fn game_error(err: GameError) -> napi::Error<String> {
+    napi::Error::new(err.code().to_string(), err.to_string())
+}
+
+// A JS value that couldn't be read as the expected options/theme object
+fn options_error(err: serde_json::Error) -> napi::Error<String> {
+    napi::Error::new("INVALID_OPTIONS".to_string(), err.to_string())
+}
+
+// Keeps the status, so an exception a listener threw still goes through
+fn with_code(err: napi::Error) -> napi::Error<String> {
+    napi::Error::new(err.status.as_ref().to_string(), err.reason)
+}
+
+// Seeds come in as BigInt, like in the WASM module
+fn seed_from(seed: BigInt) -> u64 {
+    seed.get_u64().1
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("the game types serialize to JSON")
+}
+
+// Every method takes `&self`: napi refuses a `&self` call while another
+// one is running, and listeners are free to call back into the game
+#[napi(js_name = "Game")]
+pub struct NodeGame {
+    game: RefCell<Game>,
+    listeners: RefCell<Vec<Listener>>, // Registered with `on_event`
+}
+
+impl NodeGame {
+    fn wrap(game: Game) -> NodeGame {
+        NodeGame { game: RefCell::new(game), listeners: RefCell::new(Vec::new()) }
+    }
+
+    fn game(&self) -> Ref<'_, Game> {
+        self.game.borrow()
+    }
+
+    fn game_mut(&self) -> RefMut<'_, Game> {
+        self.game.borrow_mut()
+    }
+
+    // Hands every queued core event to the listeners. The queue is drained
+    // even without listeners so it can't grow forever. No borrow is held
+    // while the listeners run, the ones they add only hear the next events.
+    fn dispatch_events(&self, env: &Env) -> napi::Result<()> {
+        let events: Vec<Value> = self.game_mut().drain_events().map(|event| to_json(&event)).collect();
+        let mut listeners = self.listeners.take();
+        let result = events.iter().try_for_each(|event| {
+            listeners.iter().try_for_each(|listener| listener.borrow_back(env)?.call(event.clone()))
+        });
+        let mut listeners_now = self.listeners.borrow_mut();
+        listeners.append(&mut listeners_now);
+        *listeners_now = listeners;
+        result
+    }
+}
+
+#[napi]
+impl NodeGame {
+    // Invalid dimensions are thrown. Pass a BigInt seed (`new Game(20, 20,
+    // 42n)`) to get the same food placement as every other client using it.
+    #[napi(constructor)]
+    pub fn new(width: i32, height: i32, seed: Option<BigInt>) -> napi::Result<NodeGame, String> {
+        let game = match seed {
+            Some(seed) => Game::from_level_with_seed(&Level::empty(width, height), seed_from(seed)),
+            None => Game::new(width, height),
+        };
+        game.map(NodeGame::wrap).map_err(game_error)
+    }
+
+    // Builds a game from an options object, the same one as the WASM
+    // module's `new_with_options`: `{ width, height, seed, mode, wrapWalls,
+    // foodCount, speedCurve, snakeLength }`, every field optional
+    #[napi(factory, js_name = "new_with_options")]
+    pub fn new_with_options(options: Option<Value>) -> napi::Result<NodeGame, String> {
+        let config: GameConfig = match options {
+            None | Some(Value::Null) => GameConfig::default(),
+            Some(options) => serde_json::from_value(options).map_err(options_error)?,
+        };
+        Game::from_config(&config).map(NodeGame::wrap).map_err(game_error)
+    }
+
+    // Same as the constructor but with a custom starting snake
+    #[napi(factory, js_name = "with_spawn")]
+    pub fn with_spawn(width: i32, height: i32, x: i32, y: i32, direction: NodeDirection, length: u32) -> napi::Result<NodeGame, String> {
+        let mut level = Level::empty(width, height);
+        level.spawn = SnakeSpawn { head: Point { x, y }, direction: direction.into(), length: length as usize };
+        Game::from_level(&level).map(NodeGame::wrap).map_err(game_error)
+    }
+
+    // Builds a game from the level text format (see `Level::parse`)
+    #[napi(factory, js_name = "from_level")]
+    pub fn from_level(text: String) -> napi::Result<NodeGame, String> {
+        Level::parse(&text).and_then(|level| Game::from_level(&level)).map(NodeGame::wrap).map_err(game_error)
+    }
+
+    #[napi]
+    pub fn tick(&self, env: Env) -> napi::Result<()> {
+        self.game_mut().tick();
+        self.dispatch_events(&env)
+    }
+
+    // Up to `n` ticks in one call, the listeners get their events
+    // afterwards. Returns a plain object, see `TickSummary`.
+    #[napi(js_name = "tick_n")]
+    pub fn tick_n(&self, env: Env, n: u32) -> napi::Result<Value> {
+        let summary = self.game_mut().tick_n(n.into());
+        self.dispatch_events(&env)?;
+        Ok(to_json(&summary))
+    }
+
+    // Registers a callback receiving every game event as a plain object with
+    // a `type` field, e.g. `{ type: "FoodEaten", at: { x, y }, score }`
+    #[napi(js_name = "on_event")]
+    pub fn on_event(&self, callback: Function<Value, ()>) -> napi::Result<()> {
+        self.listeners.borrow_mut().push(callback.create_ref()?);
+        Ok(())
+    }
+
+    // Removes a callback registered with `on_event`
+    #[napi(js_name = "off_event")]
+    pub fn off_event(&self, env: Env, callback: Function<Value, ()>) -> napi::Result<()> {
+        let mut listeners = self.listeners.borrow_mut();
+        let mut kept = Vec::with_capacity(listeners.len());
+        for listener in listeners.drain(..) {
+            if !env.strict_equals(listener.borrow_back(&env)?, callback.clone())? {
+                kept.push(listener);
+            }
+        }
+        *listeners = kept;
+        Ok(())
+    }
+
+    // Call with the milliseconds since the last call, the game runs the right
+    // number of ticks for its speed level
+    #[napi]
+    pub fn advance(&self, env: Env, elapsed_ms: f64) -> napi::Result<u32> {
+        // Negative, NaN or infinite times from JS count as no time
+        let elapsed = Duration::try_from_secs_f64(elapsed_ms / 1000.0).unwrap_or(Duration::ZERO);
+        let ticks = self.game_mut().advance(elapsed);
+        self.dispatch_events(&env)?;
+        Ok(ticks)
+    }
+
+    // The seed the game was created with, as a BigInt
+    #[napi]
+    pub fn seed(&self) -> u64 {
+        self.game().seed()
+    }
+
+    // The seed the current round started from, what its replay uses (BigInt)
+    #[napi(js_name = "round_seed")]
+    pub fn round_seed(&self) -> u64 {
+        self.game().round_seed()
+    }
+
+    // Compare between clients after the same tick to detect a desync
+    #[napi(js_name = "state_hash")]
+    pub fn state_hash(&self) -> u64 {
+        self.game().state_hash()
+    }
+
+    #[napi(js_name = "tick_interval_ms")]
+    pub fn tick_interval_ms(&self) -> f64 {
+        self.game().tick_interval().as_secs_f64() * 1000.0
+    }
+
+    #[napi(js_name = "handle_action")]
+    pub fn handle_action(&self, env: Env, action: NodeAction) -> napi::Result<()> {
+        self.game_mut().handle_action(action.into());
+        self.dispatch_events(&env)
+    }
+
+    // Takes a key name ("ArrowUp", "w", "up", " ", "p"...) and queues the
+    // matching action. Returns false for keys the game doesn't use.
+    #[napi(js_name = "queue_input_str")]
+    pub fn queue_input_str(&self, env: Env, key: String) -> napi::Result<bool> {
+        match InputAction::from_key(&key) {
+            Some(action) => {
+                self.game_mut().handle_action(action);
+                self.dispatch_events(&env)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    #[napi(js_name = "change_snake_direction")]
+    pub fn change_snake_direction(&self, direction: NodeDirection) {
+        self.game_mut().change_snake_direction(direction.into());
+    }
+
+    #[napi(js_name = "start_game")]
+    pub fn start_game(&self, env: Env) -> napi::Result<()> {
+        self.game_mut().start_game();
+        self.dispatch_events(&env)
+    }
+
+    #[napi]
+    pub fn pause(&self) {
+        self.game_mut().pause();
+    }
+
+    #[napi]
+    pub fn resume(&self) {
+        self.game_mut().resume();
+    }
+
+    #[napi(js_name = "toggle_pause")]
+    pub fn toggle_pause(&self) {
+        self.game_mut().toggle_pause();
+    }
+
+    // The current (or last finished) round as a URL-safe base64 string
+    #[napi(js_name = "export_replay")]
+    pub fn export_replay(&self) -> String {
+        self.game().replay().to_base64()
+    }
+
+    // Replaces this game with the playback of an exported replay. It plays
+    // itself as the game advances, player turns are ignored.
+    #[napi(js_name = "import_replay")]
+    pub fn import_replay(&self, env: Env, data: String) -> napi::Result<(), String> {
+        *self.game_mut() = Replay::from_base64(&data).and_then(|replay| replay.game()).map_err(game_error)?;
+        self.dispatch_events(&env).map_err(with_code)
+    }
+
+    #[napi(js_name = "is_replay")]
+    pub fn is_replay(&self) -> bool {
+        self.game().is_replay()
+    }
+
+    // True once a replay has played to the end of its recording
+    #[napi(js_name = "replay_finished")]
+    pub fn replay_finished(&self) -> bool {
+        self.game().replay_finished()
+    }
+
+    // The whole game state as a plain object, see `GameState`
+    #[napi]
+    pub fn state(&self) -> Value {
+        to_json(&self.game().state())
+    }
+
+    // Colors to draw with, see `Theme`
+    #[napi]
+    pub fn theme(&self) -> Value {
+        to_json(&self.game().theme)
+    }
+
+    #[napi(js_name = "set_theme")]
+    pub fn set_theme(&self, theme: Value) -> napi::Result<(), String> {
+        self.game_mut().theme = serde_json::from_value(theme).map_err(options_error)?;
+        Ok(())
+    }
+
+    #[napi(js_name = "set_theme_preset")]
+    pub fn set_theme_preset(&self, preset: NodeThemePreset) {
+        self.game_mut().theme = Theme::preset(preset.into());
+    }
+
+    #[napi(js_name = "reduced_motion")]
+    pub fn reduced_motion(&self) -> bool {
+        self.game().reduced_motion
+    }
+
+    #[napi(js_name = "set_reduced_motion")]
+    pub fn set_reduced_motion(&self, reduced: bool) {
+        self.game_mut().reduced_motion = reduced;
+    }
+
+    // Getters that return copies of data
+    #[napi]
+    pub fn width(&self) -> i32 {
+        self.game().width
+    }
+
+    #[napi]
+    pub fn height(&self) -> i32 {
+        self.game().height
+    }
+
+    // Position of the first apple, kept for code drawing a single food item
+    #[napi]
+    pub fn food(&self) -> NodePoint {
+        self.game()
+            .foods
+            .iter()
+            .find(|f| f.kind == FoodKind::Apple)
+            .map_or(NodePoint { x: -1, y: -1 }, |f| NodePoint { x: f.pos.x, y: f.pos.y })
+    }
+
+    #[napi(js_name = "slow_ticks_left")]
+    pub fn slow_ticks_left(&self) -> u32 {
+        self.game().slow_ticks_left()
+    }
+
+    #[napi]
+    pub fn score(&self) -> u32 {
+        self.game().score
+    }
+
+    #[napi(js_name = "game_over")]
+    pub fn game_over(&self) -> bool {
+        self.game().game_over
+    }
+
+    // Why the game ended as a sentence ("Hit the wall at (20, 4)")
+    #[napi(js_name = "death_message")]
+    pub fn death_message(&self) -> Option<String> {
+        self.game().death_cause.map(|c| c.to_string())
+    }
+
+    #[napi(js_name = "game_started")]
+    pub fn game_started(&self) -> bool {
+        self.game().game_started
+    }
+
+    #[napi]
+    pub fn paused(&self) -> bool {
+        self.game().paused
+    }
+
+    #[napi]
+    pub fn boosting(&self) -> bool {
+        self.game().boosting
+    }
+
+    // Numbers instead of BigInt, exact for any realistic game length
+    #[napi(js_name = "tick_count")]
+    pub fn tick_count(&self) -> f64 {
+        self.game().tick_count() as f64
+    }
+
+    #[napi(js_name = "elapsed_ms")]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.game().elapsed().as_secs_f64() * 1000.0
+    }
+
+    // Milliseconds of game time left in timed mode, undefined otherwise
+    #[napi(js_name = "time_left_ms")]
+    pub fn time_left_ms(&self) -> Option<f64> {
+        self.game().time_left().map(|t| t.as_secs_f64() * 1000.0)
+    }
+
+    #[napi(js_name = "wrap_walls")]
+    pub fn wrap_walls(&self) -> bool {
+        self.game().wrap_walls
+    }
+
+    #[napi(js_name = "speed_level")]
+    pub fn speed_level(&self) -> u32 {
+        self.game().speed_level()
+    }
+
+    // Snake body as interleaved x/y pairs, head first
+    #[napi(js_name = "body_coords")]
+    pub fn body_coords(&self) -> Int32Array {
+        self.game().snake.body.iter().flat_map(|p| [p.x, p.y]).collect::<Vec<i32>>().into()
+    }
+
+    // All food items as [x, y, kind, ttl] quadruples. `kind` is a `FoodKind`
+    // value, `ttl` is -1 for food that never expires.
+    #[napi(js_name = "foods_flat")]
+    pub fn foods_flat(&self) -> Int32Array {
+        self.game()
+            .foods
+            .iter()
+            .flat_map(|f| [f.pos.x, f.pos.y, NodeFoodKind::from(f.kind) as i32, f.ttl.map_or(-1, |t| t as i32)])
+            .collect::<Vec<i32>>()
+            .into()
+    }
+
+    // Same data as `foods_flat` as an array of `{ pos: { x, y }, kind, ttl }`
+    #[napi]
+    pub fn foods(&self) -> Value {
+        to_json(&self.game().foods)
+    }
+
+    // Level walls as interleaved x/y pairs
+    #[napi(js_name = "obstacles_flat")]
+    pub fn obstacles_flat(&self) -> Int32Array {
+        self.game().level.walls.iter().flat_map(|p| [p.x, p.y]).collect::<Vec<i32>>().into()
+    }
+
+    // Portals as [x1, y1, x2, y2] quadruples, one per linked pair
+    #[napi(js_name = "portals_flat")]
+    pub fn portals_flat(&self) -> Int32Array {
+        self.game().level.portals.iter().flat_map(|(a, b)| [a.x, a.y, b.x, b.y]).collect::<Vec<i32>>().into()
+    }
+
+    // Heatmap of where the player's head has been this round: ticks spent
+    // on each cell, row-major. Empty unless `set_track_visits(true)` was
+    // called before the round started.
+    #[napi]
+    pub fn visits(&self) -> Uint32Array {
+        self.game().visits().to_vec().into()
+    }
+
+    #[napi(js_name = "max_visits")]
+    pub fn max_visits(&self) -> u32 {
+        self.game().max_visits()
+    }
+
+    #[napi(js_name = "set_track_visits")]
+    pub fn set_track_visits(&self, track: bool) {
+        self.game_mut().track_visits = track;
+    }
+
+    #[napi(js_name = "get_body_len")]
+    pub fn get_body_len(&self) -> u32 {
+        self.game().snake.body.len() as u32
+    }
+
+    // Multiple snakes: snake 0 is the player's, opponents are numbered from 1.
+    // Opponents without a controller are steered with `queue_turn_for`.
+
+    // Adds a snake at the given spot, returns its index
+    #[napi(js_name = "add_opponent")]
+    pub fn add_opponent(
+        &self,
+        x: i32,
+        y: i32,
+        direction: NodeDirection,
+        length: u32,
+        controller: Option<NodeController>,
+    ) -> napi::Result<u32, String> {
+        let spawn = SnakeSpawn { head: Point { x, y }, direction: direction.into(), length: length as usize };
+        let index = self.game_mut().add_opponent(spawn, controller.map(Controller::from)).map_err(game_error)?;
+        Ok(index as u32)
+    }
+
+    // Adds a snake wherever there's room, returns its index
+    #[napi(js_name = "add_opponent_auto")]
+    pub fn add_opponent_auto(&self, controller: Option<NodeController>) -> napi::Result<u32, String> {
+        let index = self.game_mut().add_opponent_auto(controller.map(Controller::from)).map_err(game_error)?;
+        Ok(index as u32)
+    }
+
+    // Hands a snake to a built-in AI, or back to the player with undefined
+    #[napi(js_name = "set_controller")]
+    pub fn set_controller(&self, index: u32, controller: Option<NodeController>) {
+        self.game_mut().set_controller(index as usize, controller.map(Controller::from));
+    }
+
+    #[napi(js_name = "queue_turn_for")]
+    pub fn queue_turn_for(&self, index: u32, direction: NodeDirection) {
+        self.game_mut().queue_turn_for(index as usize, direction.into());
+    }
+
+    #[napi(js_name = "snake_count")]
+    pub fn snake_count(&self) -> u32 {
+        self.game().snake_count() as u32
+    }
+
+    // Interleaved x/y pairs, head first. Empty for dead opponents.
+    #[napi(js_name = "snake_body")]
+    pub fn snake_body(&self, index: u32) -> Int32Array {
+        self.game().snake_body(index as usize).iter().flat_map(|p| [p.x, p.y]).collect::<Vec<i32>>().into()
+    }
+
+    #[napi(js_name = "snake_score")]
+    pub fn snake_score(&self, index: u32) -> u32 {
+        self.game().snake_score(index as usize)
+    }
+
+    #[napi(js_name = "snake_alive")]
+    pub fn snake_alive(&self, index: u32) -> bool {
+        self.game().snake_alive(index as usize)
+    }
+
+    // Whether the snake's player is away, see `afkTicks` in the options
+    #[napi(js_name = "is_afk")]
+    pub fn is_afk(&self, index: u32) -> bool {
+        self.game().is_afk(index as usize)
+    }
+
+    // Interleaved x/y pairs in fractional cells, part way between the last
+    // two ticks
+    #[napi(js_name = "interpolated_body")]
+    pub fn interpolated_body(&self, index: u32) -> Float32Array {
+        self.game().interpolated_body(index as usize).iter().flat_map(|&(x, y)| [x, y]).collect::<Vec<f32>>().into()
+    }
+
+    // 0.0 right after a tick up to 1.0 just before the next one
+    #[napi(js_name = "tick_progress")]
+    pub fn tick_progress(&self) -> f64 {
+        self.game().tick_progress() as f64
+    }
+
+    // The number to show before the snake starts moving, undefined after
+    #[napi]
+    pub fn countdown(&self) -> Option<u32> {
+        self.game().countdown()
+    }
+
+    // How many segments of a snake to draw, fewer while the death
+    // animation plays
+    #[napi(js_name = "visible_segments")]
+    pub fn visible_segments(&self, index: u32) -> u32 {
+        self.game().visible_segments(index as usize) as u32
+    }
+
+    // How opaque to draw a snake (0 to 1)
+    #[napi(js_name = "snake_opacity")]
+    pub fn snake_opacity(&self, index: u32) -> f64 {
+        self.game().snake_opacity(index as usize) as f64
+    }
+
+    // The pathfinder's route to the closest food as interleaved x/y pairs
+    #[napi(js_name = "ai_path")]
+    pub fn ai_path(&self, index: u32) -> Int32Array {
+        self.game().ai_path(index as usize).iter().flat_map(|p| [p.x, p.y]).collect::<Vec<i32>>().into()
+    }
+}
+
+// The enums have the same names and values as the WASM module's
+#[napi(js_name = "Direction")]
+pub enum NodeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<NodeDirection> for Direction {
+    fn from(d: NodeDirection) -> Self {
+        match d {
+            NodeDirection::Up => Direction::Up,
+            NodeDirection::Down => Direction::Down,
+            NodeDirection::Left => Direction::Left,
+            NodeDirection::Right => Direction::Right,
+        }
+    }
+}
+
+// Flat version of `InputAction`
+#[napi(js_name = "Action")]
+pub enum NodeAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Restart,
+    Boost,
+    Confirm,
+}
+
+impl From<NodeAction> for InputAction {
+    fn from(a: NodeAction) -> Self {
+        match a {
+            NodeAction::Up => InputAction::Turn(Direction::Up),
+            NodeAction::Down => InputAction::Turn(Direction::Down),
+            NodeAction::Left => InputAction::Turn(Direction::Left),
+            NodeAction::Right => InputAction::Turn(Direction::Right),
+            NodeAction::Pause => InputAction::Pause,
+            NodeAction::Restart => InputAction::Restart,
+            NodeAction::Boost => InputAction::Boost,
+            NodeAction::Confirm => InputAction::Confirm,
+        }
+    }
+}
+
+#[napi(js_name = "Controller")]
+pub enum NodeController {
+    Greedy,
+    Pathfinder,
+    Chaser,
+}
+
+impl From<NodeController> for Controller {
+    fn from(c: NodeController) -> Self {
+        match c {
+            NodeController::Greedy => Controller::Greedy,
+            NodeController::Pathfinder => Controller::Pathfinder,
+            NodeController::Chaser => Controller::Chaser,
+        }
+    }
+}
+
+#[napi(js_name = "ThemePreset")]
+pub enum NodeThemePreset {
+    Classic,
+    Neon,
+    ColorblindSafe,
+}
+
+impl From<NodeThemePreset> for ThemePreset {
+    fn from(p: NodeThemePreset) -> Self {
+        match p {
+            NodeThemePreset::Classic => ThemePreset::Classic,
+            NodeThemePreset::Neon => ThemePreset::Neon,
+            NodeThemePreset::ColorblindSafe => ThemePreset::ColorblindSafe,
+        }
+    }
+}
+
+#[napi(js_name = "FoodKind")]
+pub enum NodeFoodKind {
+    Apple = 0,
+    Bonus = 1,
+    SlowDown = 2,
+}
+
+impl From<FoodKind> for NodeFoodKind {
+    fn from(k: FoodKind) -> Self {
+        match k {
+            FoodKind::Apple => NodeFoodKind::Apple,
+            FoodKind::Bonus => NodeFoodKind::Bonus,
+            FoodKind::SlowDown => NodeFoodKind::SlowDown,
+        }
+    }
+}
+
+#[napi(object, js_name = "Point")]
+pub struct NodePoint {
+    pub x: i32,
+    pub y: i32,
+}
@@ -0,0 +1,292 @@
+// src/opponent.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Body, Controller, DeathCause, Direction, FoodKind, Game, GameError, GameEvent, LastMove, Point, Snake, SnakeSpawn};
+
+// What `snake_body` lends for snakes that aren't there
+static NO_BODY: Body = Body::new();
+
+// Any snake besides the player's. Snakes are numbered with the player's
+// snake as 0 and `opponents[i]` as `i + 1`. An opponent is steered by its
+// controller, or with `queue_turn_for` when it has none (e.g. a second
+// local player).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Opponent {
+    pub snake: Snake,
+    pub spawn: SnakeSpawn,
+    pub score: u32,
+    pub alive: bool,
+    pub controller: Option<Controller>,
+    pub death_cause: Option<DeathCause>,
+    pub(crate) pending_turn: Option<Direction>,
+    // Tick of the last `queue_turn_for`, see afk.rs. Not saved, a restored
+    // game counts from the restore.
+    #[serde(skip)]
+    pub(crate) last_input: u64,
+}
+
+impl Opponent {
+    pub(crate) fn new(spawn: SnakeSpawn, controller: Option<Controller>) -> Opponent {
+        Opponent {
+            snake: Snake::from_spawn(&spawn),
+            spawn,
+            score: 0,
+            alive: true,
+            controller,
+            death_cause: None,
+            pending_turn: None,
+            last_input: 0,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = Opponent::new(self.spawn, self.controller);
+    }
+}
+
+impl Game {
+    // The player's snake plus every opponent, dead or alive
+    pub fn snake_count(&self) -> usize {
+        1 + self.opponents.len()
+    }
+
+    // Segments of a snake, head first. Empty for dead opponents and unknown
+    // indices.
+    pub fn snake_body(&self, index: usize) -> &Body {
+        match index {
+            0 => &self.snake.body,
+            _ => self.opponents.get(index - 1).map_or(&NO_BODY, |o| &o.snake.body),
+        }
+    }
+
+    pub fn snake_direction(&self, index: usize) -> Option<Direction> {
+        match index {
+            0 => Some(self.snake.direction),
+            _ => self.opponents.get(index - 1).map(|o| o.snake.direction),
+        }
+    }
+
+    pub fn snake_score(&self, index: usize) -> u32 {
+        match index {
+            0 => self.score,
+            _ => self.opponents.get(index - 1).map_or(0, |o| o.score),
+        }
+    }
+
+    pub fn snake_alive(&self, index: usize) -> bool {
+        match index {
+            0 => self.game_started && !self.game_over,
+            _ => self.opponents.get(index - 1).is_some_and(|o| o.alive),
+        }
+    }
+
+    // Adds a snake starting at `spawn`, returns its index. It must fit on the
+    // board without touching walls or another snake.
+    pub fn add_opponent(&mut self, spawn: SnakeSpawn, controller: Option<Controller>) -> Result<usize, GameError> {
+        spawn.check_length(self.width, self.height)?;
+        for segment in spawn.body() {
+            if segment.x < 0 || segment.x >= self.width || segment.y < 0 || segment.y >= self.height {
+                return Err(GameError::SpawnOutOfBounds(segment));
+            }
+            if self.board.is_wall(segment) || self.board.is_snake(segment) {
+                return Err(GameError::SpawnOnObstacle(segment));
+            }
+        }
+        let body = spawn.body();
+        self.opponents.push(Opponent::new(spawn, controller));
+        // Food can't stay under the new snake, put it somewhere else
+        self.foods.retain(|f| !body.contains(&f.pos));
+        for &segment in &body {
+            self.board.set_food(segment, false);
+            self.board.set_snake(segment, true);
+        }
+        self.replenish_apples()?;
+        Ok(self.opponents.len())
+    }
+
+    // Same as `add_opponent`, picking the first free spot among the board's
+    // quarter points (then any cell) for a snake as long as the player's
+    pub fn add_opponent_auto(&mut self, controller: Option<Controller>) -> Result<usize, GameError> {
+        let (w, h, length) = (self.width, self.height, self.spawn.length);
+        let quarters = [
+            (w / 4, h / 4, Direction::Right),
+            (w * 3 / 4, h * 3 / 4, Direction::Left),
+            (w * 3 / 4, h / 4, Direction::Left),
+            (w / 4, h * 3 / 4, Direction::Right),
+        ];
+        let anywhere = (0..h).flat_map(|y| (0..w).map(move |x| (x, y, Direction::Right)));
+        let spawn = quarters
+            .into_iter()
+            .chain(anywhere)
+            .map(|(x, y, direction)| SnakeSpawn { head: Point { x, y }, direction, length })
+            .find(|spawn| {
+                spawn.body().iter().all(|p| {
+                    p.x >= 0
+                        && p.x < w
+                        && p.y >= 0
+                        && p.y < h
+                        && !self.board.is_wall(*p)
+                        && !self.board.is_snake(*p)
+                        && !self.board.is_food(*p)
+                })
+            })
+            .ok_or(GameError::NoFreeCell)?;
+        self.add_opponent(spawn, controller)
+    }
+
+    // Gives a snake to (or takes it back from) a built-in AI. Snake 0 is the
+    // player's own snake, handy for demos and attract screens.
+    pub fn set_controller(&mut self, index: usize, controller: Option<Controller>) {
+        match index {
+            0 => self.autopilot = controller,
+            _ => {
+                if let Some(opponent) = self.opponents.get_mut(index - 1) {
+                    opponent.controller = controller;
+                }
+            }
+        }
+    }
+
+    pub fn controller(&self, index: usize) -> Option<Controller> {
+        match index {
+            0 => self.autopilot,
+            _ => self.opponents.get(index - 1).and_then(|o| o.controller),
+        }
+    }
+
+    // `queue_turn` for any snake, e.g. the second player on a shared keyboard
+    pub fn queue_turn_for(&mut self, index: usize, direction: Direction) {
+        if index == 0 {
+            self.queue_turn(direction);
+            return;
+        }
+        if self.playback.is_some() || index > self.opponents.len() {
+            return;
+        }
+        self.note_input(index);
+        self.record(index, crate::InputAction::Turn(direction));
+        self.opponents[index - 1].pending_turn = Some(direction);
+    }
+
+    // Index of the living snake covering `p`, if any
+    pub fn snake_at(&self, p: Point) -> Option<usize> {
+        if !self.board.is_snake(p) {
+            return None;
+        }
+        (0..self.snake_count()).find(|&index| self.snake_body(index).contains(&p))
+    }
+
+    // What happens to snake `mover` if its head enters `p`: biting itself
+    // (its head excluded) or running into another snake
+    pub(crate) fn snake_hit(&self, p: Point, mover: usize) -> Option<DeathCause> {
+        // Only the (rare) fatal moves search the bodies
+        if !self.board.is_snake(p) {
+            return None;
+        }
+        (0..self.snake_count()).find_map(|index| {
+            let skip = usize::from(index == mover);
+            let segment = self.snake_body(index).iter().skip(skip).position(|b| *b == p)?;
+            Some(if index == mover {
+                DeathCause::SelfCollision { at: p, segment: segment + 1 }
+            } else {
+                DeathCause::Opponent { at: p, snake: index }
+            })
+        })
+    }
+
+    // Runs after the player's snake has moved, opponents move one after the
+    // other in index order. Opponents score and grow like the player but
+    // power-ups have no effect on them and they never drop bonus items.
+    pub(crate) fn move_opponents(&mut self) {
+        for i in 0..self.opponents.len() {
+            if !self.opponents[i].alive {
+                continue;
+            }
+            let index = i + 1;
+            if let Some(direction) = self.opponents[i].pending_turn.take() {
+                self.opponents[i].snake.change_direction(direction);
+            }
+            let head = self.opponents[i].snake.body[0];
+            let moved = self.step(head, self.opponents[i].snake.direction).and_then(|p| {
+                match self.snake_hit(p, index) {
+                    Some(cause) => Err(cause),
+                    None if self.board.is_wall(p) => Err(DeathCause::Obstacle { at: p }),
+                    None => Ok(p),
+                }
+            });
+            let new_head = match moved {
+                Ok(p) => p,
+                Err(cause) => {
+                    let opponent = &mut self.opponents[i];
+                    opponent.alive = false;
+                    opponent.death_cause = Some(cause);
+                    // A dead snake leaves the board free for the others
+                    for &segment in &opponent.snake.body {
+                        self.board.set_snake(segment, false);
+                    }
+                    opponent.snake.body.clear();
+                    self.events.push(GameEvent::SnakeDied { snake: index, cause });
+                    continue;
+                }
+            };
+
+            // The old head is drawn as body from now on
+            self.board.mark_dirty(head);
+            self.opponents[i].snake.body.push_front(new_head);
+            self.board.set_snake(new_head, true);
+            let eaten = self.take_food(new_head);
+            let mut tail = None;
+            if eaten.is_none_or(|f| f.kind.is_power_up()) {
+                tail = self.opponents[i].snake.body.pop_back();
+                tail.inspect(|&p| self.board.set_snake(p, false));
+            }
+            self.last_moves[index] = LastMove::Moved { tail };
+            if let Some(food) = eaten {
+                let opponent = &mut self.opponents[i];
+                opponent.score += food.kind.points();
+                self.events.push(GameEvent::SnakeAte {
+                    snake: index,
+                    at: food.pos,
+                    kind: food.kind,
+                    score: opponent.score,
+                });
+                if food.kind == FoodKind::Apple {
+                    // Same as for the player, no room left ends the game
+                    if self.replenish_apples().is_err() {
+                        self.end_game(DeathCause::BoardFull);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // The snakes with a controller, and those whose player is away (see
+    // afk.rs), pick their turn for the coming tick, all from the same board
+    // state. The choices are recorded like player input so replays don't
+    // depend on who (or what) was steering.
+    pub(crate) fn run_controllers(&mut self) {
+        if self.playback.is_some() {
+            return;
+        }
+        for index in 0..self.snake_count() {
+            if !self.snake_alive(index) {
+                continue;
+            }
+            let decided = match self.controller(index) {
+                Some(controller) => controller.decide(self, index),
+                None => self.afk_move(index),
+            };
+            let Some(direction) = decided else { continue };
+            if Some(direction) == self.snake_direction(index) {
+                continue;
+            }
+            self.record(index, crate::InputAction::Turn(direction));
+            match index {
+                0 => self.pending_turn = Some(direction),
+                _ => self.opponents[index - 1].pending_turn = Some(direction),
+            }
+        }
+    }
+}
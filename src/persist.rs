@@ -0,0 +1,354 @@
+// src/persist.rs
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{BoardPattern, Game, GameConfig, GameError, KeyMap, Language, Replay, RunStats, Skins, Snapshot};
+
+// How many scores the high score table keeps
+pub const MAX_HIGH_SCORES: usize = 10;
+
+// Longer names are cut, in characters
+pub const MAX_NAME_LEN: usize = 12;
+
+// The oldest saved replay goes once there are more
+pub const MAX_SAVED_REPLAYS: usize = 20;
+
+// A key/value string store. Native builds keep one file per key, the browser
+// uses `localStorage`, so the same `Persistence` code runs on both.
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str) -> Result<(), GameError>;
+    fn remove(&mut self, key: &str) -> Result<(), GameError>;
+}
+
+// One row of the high score table. The name may be empty.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredHighScore")]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+}
+
+// Tables saved before names were added are plain lists of numbers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredHighScore {
+    Score(u32),
+    Entry { name: String, score: u32 },
+}
+
+impl From<StoredHighScore> for HighScore {
+    fn from(stored: StoredHighScore) -> Self {
+        match stored {
+            StoredHighScore::Score(score) => HighScore { name: String::new(), score },
+            StoredHighScore::Entry { name, score } => HighScore { name, score },
+        }
+    }
+}
+
+// Best scores, highest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    pub fn scores(&self) -> Vec<u32> {
+        self.entries.iter().map(|e| e.score).collect()
+    }
+
+    pub fn best(&self) -> Option<u32> {
+        self.entries.first().map(|e| e.score)
+    }
+
+    // The 0-based rank the score would get, `None` if it wouldn't make the
+    // table. Zero never counts, ties go below the existing score.
+    pub fn rank(&self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self.entries.iter().position(|e| e.score < score).unwrap_or(self.entries.len());
+        (rank < MAX_HIGH_SCORES).then_some(rank)
+    }
+
+    // Returns the rank if the score made it into the table
+    pub fn insert(&mut self, name: &str, score: u32) -> Option<usize> {
+        let rank = self.rank(score)?;
+        let name = name.trim().chars().take(MAX_NAME_LEN).collect();
+        self.entries.insert(rank, HighScore { name, score });
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+// What the player picked last time: the game options, audio and controls.
+// `volume` is for sound effects, `muted` silences effects and music.
+// `screen_effects` turns off shake and flashes for players sensitive to them.
+// `near_miss_slow_motion` slows the game down for a moment after a near miss.
+// `board_pattern` is drawn behind the board, in the theme's colors.
+// `player_name` is the name last entered for a high score.
+// `minimap` shows the whole board in a corner when it doesn't fit on screen.
+// `language` is the one text is shown in, see `Language`.
+// `skin` is the id of the player's skin, see `Skins`. Like everything else
+// here it's per profile, i.e. per `Persistence` prefix.
+// `twitch_channel` is the Twitch channel whose chat votes on the turns (the
+// `twitch` feature), empty for none, counting the votes of every
+// `twitch_vote_ticks` ticks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub config: GameConfig,
+    pub volume: f32,
+    pub music_volume: f32,
+    pub muted: bool,
+    pub keymap: KeyMap,
+    pub screen_effects: bool,
+    pub effect_intensity: f32,
+    pub near_miss_slow_motion: bool,
+    pub board_pattern: BoardPattern,
+    pub player_name: String,
+    pub minimap: bool,
+    pub language: Language,
+    pub skin: String,
+    pub twitch_channel: String,
+    pub twitch_vote_ticks: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            config: GameConfig::default(),
+            volume: 0.5,
+            music_volume: 0.5,
+            muted: false,
+            keymap: KeyMap::default(),
+            screen_effects: true,
+            effect_intensity: 1.0,
+            near_miss_slow_motion: true,
+            board_pattern: BoardPattern::Plain,
+            player_name: String::new(),
+            minimap: true,
+            language: Language::English,
+            skin: "classic".to_string(),
+            twitch_channel: String::new(),
+            twitch_vote_ticks: 5,
+        }
+    }
+}
+
+impl Settings {
+    // Settings from a hand-edited file. Unlike `Persistence::load_settings`,
+    // which falls back to the defaults, mistakes are reported: bad JSON,
+    // levels outside 0 to 1 and speed curves that never tick.
+    pub fn from_json(text: &str) -> Result<Settings, GameError> {
+        let settings: Settings = serde_json::from_str(text).map_err(|e| GameError::Storage(e.to_string()))?;
+        let levels = [
+            ("volume", settings.volume),
+            ("musicVolume", settings.music_volume),
+            ("effectIntensity", settings.effect_intensity),
+            ("inputGrace", settings.config.input_grace),
+        ];
+        if let Some((name, level)) = levels.iter().find(|(_, level)| !(0.0..=1.0).contains(level)) {
+            return Err(GameError::Storage(format!("{} {} is not between 0 and 1", name, level)));
+        }
+        Ok(settings)
+    }
+}
+
+// A replay kept for the replays list. The score and length are copied out
+// so the list can be shown without decoding every recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedReplay {
+    pub saved_at: u64, // Unix time in seconds, the core has no clock
+    pub score: u32,
+    pub ticks: u64,
+    pub data: String, // `Replay::to_base64`
+}
+
+impl SavedReplay {
+    pub fn new(replay: &Replay, saved_at: u64) -> SavedReplay {
+        SavedReplay { saved_at, score: replay.score, ticks: replay.ticks, data: replay.to_base64() }
+    }
+
+    pub fn replay(&self) -> Result<Replay, GameError> {
+        Replay::from_base64(&self.data)
+    }
+}
+
+const HIGH_SCORES_KEY: &str = "high_scores";
+const SETTINGS_KEY: &str = "settings";
+const AUTOSAVE_KEY: &str = "autosave";
+const REPLAYS_KEY: &str = "replays";
+const UNLOCKED_SKINS_KEY: &str = "unlocked_skins";
+
+// Saves and loads everything the game remembers between runs, as JSON under
+// `<prefix><name>` keys. Missing or corrupt entries load as defaults/None
+// rather than failing, a broken save should never stop the game from starting.
+pub struct Persistence<S> {
+    storage: S,
+    prefix: String,
+}
+
+impl<S: Storage> Persistence<S> {
+    pub fn new(storage: S, prefix: &str) -> Persistence<S> {
+        Persistence { storage, prefix: prefix.to_string() }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn load<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        span!(DEBUG, "load", name);
+        let text = self.storage.get(&self.key(name))?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn save<T: Serialize>(&mut self, name: &str, value: &T) -> Result<(), GameError> {
+        span!(DEBUG, "save", name);
+        let text = serde_json::to_string(value).map_err(|e| GameError::Storage(e.to_string()))?;
+        let key = self.key(name);
+        self.storage.set(&key, &text)
+    }
+
+    pub fn load_high_scores(&self) -> HighScores {
+        self.load(HIGH_SCORES_KEY).unwrap_or_default()
+    }
+
+    pub fn save_high_scores(&mut self, scores: &HighScores) -> Result<(), GameError> {
+        self.save(HIGH_SCORES_KEY, scores)
+    }
+
+    // Adds a finished game's score to the table, saving only if it ranked
+    pub fn record_score(&mut self, name: &str, score: u32) -> Result<Option<usize>, GameError> {
+        let mut scores = self.load_high_scores();
+        let rank = scores.insert(name, score);
+        if rank.is_some() {
+            self.save_high_scores(&scores)?;
+        }
+        Ok(rank)
+    }
+
+    pub fn load_settings(&self) -> Settings {
+        self.load(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save_settings(&mut self, settings: &Settings) -> Result<(), GameError> {
+        self.save(SETTINGS_KEY, settings)
+    }
+
+    // The autosave is only returned if it fits on `game`'s board, a save
+    // from another board size or level is ignored
+    pub fn load_autosave(&self, game: &Game) -> Option<Snapshot> {
+        self.load::<Snapshot>(AUTOSAVE_KEY).filter(|snapshot| snapshot.fits(game))
+    }
+
+    pub fn save_autosave(&mut self, snapshot: &Snapshot) -> Result<(), GameError> {
+        self.save(AUTOSAVE_KEY, snapshot)
+    }
+
+    pub fn clear_autosave(&mut self) -> Result<(), GameError> {
+        let key = self.key(AUTOSAVE_KEY);
+        self.storage.remove(&key)
+    }
+
+    // Ids of the skins unlocked so far, see `Skin::unlock`
+    pub fn load_unlocked_skins(&self) -> Vec<String> {
+        self.load(UNLOCKED_SKINS_KEY).unwrap_or_default()
+    }
+
+    // Unlocks the skins whose achievement a finished round reached, saving
+    // only if there were any. Returns the ids of the new ones.
+    pub fn unlock_skins(&mut self, skins: &Skins, stats: &RunStats) -> Result<Vec<String>, GameError> {
+        let mut unlocked = self.load_unlocked_skins();
+        let new: Vec<String> = skins
+            .skins
+            .iter()
+            .filter(|skin| skin.unlock.is_some_and(|a| a.reached(stats)) && !unlocked.contains(&skin.id))
+            .map(|skin| skin.id.clone())
+            .collect();
+        if !new.is_empty() {
+            unlocked.extend(new.iter().cloned());
+            self.save(UNLOCKED_SKINS_KEY, &unlocked)?;
+        }
+        Ok(new)
+    }
+
+    // Newest first
+    pub fn load_replays(&self) -> Vec<SavedReplay> {
+        self.load(REPLAYS_KEY).unwrap_or_default()
+    }
+
+    pub fn save_replay(&mut self, saved: SavedReplay) -> Result<(), GameError> {
+        let mut replays = self.load_replays();
+        replays.insert(0, saved);
+        replays.truncate(MAX_SAVED_REPLAYS);
+        self.save(REPLAYS_KEY, &replays)
+    }
+
+    pub fn delete_replay(&mut self, index: usize) -> Result<(), GameError> {
+        let mut replays = self.load_replays();
+        if index < replays.len() {
+            replays.remove(index);
+            self.save(REPLAYS_KEY, &replays)?;
+        }
+        Ok(())
+    }
+}
+
+// Native storage: one `<key>.json` file per key inside a directory
+#[cfg(feature = "std")]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> FileStorage {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Persistence<FileStorage> {
+    // The file the settings are saved in, for watching it
+    pub fn settings_path(&self) -> std::path::PathBuf {
+        self.storage.path(&self.key(SETTINGS_KEY))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Storage for FileStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)).ok()
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), GameError> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| GameError::Storage(e.to_string()))?;
+        std::fs::write(self.path(key), value).map_err(|e| GameError::Storage(e.to_string()))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), GameError> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(GameError::Storage(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,525 @@
+// src/proto.rs
+
+// Protocol Buffers versions of the config, state and input types, generated
+// from proto/snake_game.proto by build.rs (only with the `protobuf`
+// feature), and the conversions to and from the core types. Encode and
+// decode them with `prost::Message`:
+//
+//     let bytes = proto::GameState::from(game.state()).encode_to_vec();
+//     let state = GameState::try_from(proto::GameState::decode(&bytes[..])?)?;
+//
+// Going to protobuf always works. Coming back fails with
+// `GameError::InvalidMessage` when a message is missing a field the core
+// type needs or has an enum value it doesn't know (UNSPECIFIED included).
+// The optional fields of `GameConfig` fall back to the defaults, like in the
+// JSON.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::GameError;
+
+include!(concat!(env!("OUT_DIR"), "/snake_game.rs"));
+
+fn invalid(reason: String) -> GameError {
+    GameError::InvalidMessage(reason)
+}
+
+fn unspecified(field: &str) -> GameError {
+    invalid(format!("{} is unspecified", field))
+}
+
+fn missing(field: &str) -> GameError {
+    invalid(format!("{} is missing", field))
+}
+
+// Enum fields are plain numbers in the messages
+fn enum_field<P, T>(value: i32, field: &str) -> Result<T, GameError>
+where
+    P: TryFrom<i32>,
+    T: TryFrom<P, Error = GameError>,
+{
+    P::try_from(value).map_err(|_| invalid(format!("unknown {} {}", field, value)))?.try_into()
+}
+
+fn point_field(point: Option<Point>, field: &str) -> Result<crate::Point, GameError> {
+    point.map(Into::into).ok_or_else(|| missing(field))
+}
+
+fn points(points: &[crate::Point]) -> Vec<Point> {
+    points.iter().map(|&p| p.into()).collect()
+}
+
+fn millis(duration: Duration) -> u32 {
+    duration.as_millis().min(u32::MAX as u128) as u32
+}
+
+impl From<crate::Point> for Point {
+    fn from(p: crate::Point) -> Point {
+        Point { x: p.x, y: p.y }
+    }
+}
+
+impl From<Point> for crate::Point {
+    fn from(p: Point) -> crate::Point {
+        crate::Point { x: p.x, y: p.y }
+    }
+}
+
+impl From<crate::Direction> for Direction {
+    fn from(d: crate::Direction) -> Direction {
+        match d {
+            crate::Direction::Up => Direction::Up,
+            crate::Direction::Down => Direction::Down,
+            crate::Direction::Left => Direction::Left,
+            crate::Direction::Right => Direction::Right,
+        }
+    }
+}
+
+impl TryFrom<Direction> for crate::Direction {
+    type Error = GameError;
+
+    fn try_from(d: Direction) -> Result<crate::Direction, GameError> {
+        match d {
+            Direction::Unspecified => Err(unspecified("direction")),
+            Direction::Up => Ok(crate::Direction::Up),
+            Direction::Down => Ok(crate::Direction::Down),
+            Direction::Left => Ok(crate::Direction::Left),
+            Direction::Right => Ok(crate::Direction::Right),
+        }
+    }
+}
+
+impl From<crate::FoodKind> for FoodKind {
+    fn from(k: crate::FoodKind) -> FoodKind {
+        match k {
+            crate::FoodKind::Apple => FoodKind::Apple,
+            crate::FoodKind::Bonus => FoodKind::Bonus,
+            crate::FoodKind::SlowDown => FoodKind::SlowDown,
+        }
+    }
+}
+
+impl TryFrom<FoodKind> for crate::FoodKind {
+    type Error = GameError;
+
+    fn try_from(k: FoodKind) -> Result<crate::FoodKind, GameError> {
+        match k {
+            FoodKind::Unspecified => Err(unspecified("food kind")),
+            FoodKind::Apple => Ok(crate::FoodKind::Apple),
+            FoodKind::Bonus => Ok(crate::FoodKind::Bonus),
+            FoodKind::SlowDown => Ok(crate::FoodKind::SlowDown),
+        }
+    }
+}
+
+impl From<crate::Food> for Food {
+    fn from(f: crate::Food) -> Food {
+        Food { pos: Some(f.pos.into()), kind: FoodKind::from(f.kind) as i32, ttl: f.ttl }
+    }
+}
+
+impl TryFrom<Food> for crate::Food {
+    type Error = GameError;
+
+    fn try_from(f: Food) -> Result<crate::Food, GameError> {
+        Ok(crate::Food {
+            pos: point_field(f.pos, "food position")?,
+            kind: enum_field::<FoodKind, _>(f.kind, "food kind")?,
+            ttl: f.ttl,
+        })
+    }
+}
+
+impl From<crate::Controller> for Controller {
+    fn from(c: crate::Controller) -> Controller {
+        match c {
+            crate::Controller::Greedy => Controller::Greedy,
+            crate::Controller::Pathfinder => Controller::Pathfinder,
+            crate::Controller::Chaser => Controller::Chaser,
+        }
+    }
+}
+
+impl TryFrom<Controller> for crate::Controller {
+    type Error = GameError;
+
+    fn try_from(c: Controller) -> Result<crate::Controller, GameError> {
+        match c {
+            Controller::Unspecified => Err(unspecified("controller")),
+            Controller::Greedy => Ok(crate::Controller::Greedy),
+            Controller::Pathfinder => Ok(crate::Controller::Pathfinder),
+            Controller::Chaser => Ok(crate::Controller::Chaser),
+        }
+    }
+}
+
+impl From<crate::AfkMode> for AfkMode {
+    fn from(m: crate::AfkMode) -> AfkMode {
+        match m {
+            crate::AfkMode::Straight => AfkMode::Straight,
+            crate::AfkMode::Safe => AfkMode::Safe,
+        }
+    }
+}
+
+impl TryFrom<AfkMode> for crate::AfkMode {
+    type Error = GameError;
+
+    fn try_from(m: AfkMode) -> Result<crate::AfkMode, GameError> {
+        match m {
+            AfkMode::Unspecified => Err(unspecified("AFK mode")),
+            AfkMode::Straight => Ok(crate::AfkMode::Straight),
+            AfkMode::Safe => Ok(crate::AfkMode::Safe),
+        }
+    }
+}
+
+impl From<crate::ThemePreset> for ThemePreset {
+    fn from(p: crate::ThemePreset) -> ThemePreset {
+        match p {
+            crate::ThemePreset::Classic => ThemePreset::Classic,
+            crate::ThemePreset::Neon => ThemePreset::Neon,
+            crate::ThemePreset::ColorblindSafe => ThemePreset::ColorblindSafe,
+        }
+    }
+}
+
+impl TryFrom<ThemePreset> for crate::ThemePreset {
+    type Error = GameError;
+
+    fn try_from(p: ThemePreset) -> Result<crate::ThemePreset, GameError> {
+        match p {
+            ThemePreset::Unspecified => Err(unspecified("theme")),
+            ThemePreset::Classic => Ok(crate::ThemePreset::Classic),
+            ThemePreset::Neon => Ok(crate::ThemePreset::Neon),
+            ThemePreset::ColorblindSafe => Ok(crate::ThemePreset::ColorblindSafe),
+        }
+    }
+}
+
+impl From<crate::GameMode> for GameMode {
+    fn from(mode: crate::GameMode) -> GameMode {
+        match mode {
+            crate::GameMode::Classic => GameMode { timed_limit_secs: None },
+            crate::GameMode::Timed { limit_secs } => GameMode { timed_limit_secs: Some(limit_secs) },
+        }
+    }
+}
+
+impl From<GameMode> for crate::GameMode {
+    fn from(mode: GameMode) -> crate::GameMode {
+        match mode.timed_limit_secs {
+            None => crate::GameMode::Classic,
+            Some(limit_secs) => crate::GameMode::Timed { limit_secs },
+        }
+    }
+}
+
+impl From<crate::SpeedCurve> for SpeedCurve {
+    fn from(curve: crate::SpeedCurve) -> SpeedCurve {
+        SpeedCurve {
+            base_interval_ms: Some(millis(curve.base_interval)),
+            step_ms: Some(millis(curve.step)),
+            min_interval_ms: Some(millis(curve.min_interval)),
+            points_per_level: Some(curve.points_per_level),
+        }
+    }
+}
+
+// Unset fields keep the default curve's
+impl From<SpeedCurve> for crate::SpeedCurve {
+    fn from(curve: SpeedCurve) -> crate::SpeedCurve {
+        let default = crate::SpeedCurve::default();
+        let ms = |ms: Option<u32>, default: Duration| ms.map_or(default, |ms| Duration::from_millis(ms as u64));
+        crate::SpeedCurve {
+            base_interval: ms(curve.base_interval_ms, default.base_interval),
+            step: ms(curve.step_ms, default.step),
+            min_interval: ms(curve.min_interval_ms, default.min_interval),
+            points_per_level: curve.points_per_level.unwrap_or(default.points_per_level),
+        }
+    }
+}
+
+impl From<crate::BossConfig> for BossConfig {
+    fn from(boss: crate::BossConfig) -> BossConfig {
+        BossConfig { every: Some(boss.every), ticks: Some(boss.ticks), length: Some(boss.length as u32) }
+    }
+}
+
+impl From<BossConfig> for crate::BossConfig {
+    fn from(boss: BossConfig) -> crate::BossConfig {
+        let default = crate::BossConfig::default();
+        crate::BossConfig {
+            every: boss.every.unwrap_or(default.every),
+            ticks: boss.ticks.unwrap_or(default.ticks),
+            length: boss.length.map_or(default.length, |n| n as usize),
+        }
+    }
+}
+
+// Every field is set, so the other side doesn't depend on the defaults
+impl From<crate::GameConfig> for GameConfig {
+    fn from(config: crate::GameConfig) -> GameConfig {
+        GameConfig {
+            width: Some(config.width),
+            height: Some(config.height),
+            seed: config.seed,
+            mode: Some(config.mode.into()),
+            wrap_walls: Some(config.wrap_walls),
+            food_count: Some(config.food_count as u32),
+            speed_curve: Some(config.speed_curve.into()),
+            snake_length: Some(config.snake_length as u32),
+            opponents: config
+                .opponents
+                .iter()
+                .map(|c| OpponentConfig { controller: c.map(|c| Controller::from(c) as i32) })
+                .collect(),
+            theme: Some(ThemePreset::from(config.theme) as i32),
+            countdown_secs: Some(config.countdown_secs),
+            reduced_motion: Some(config.reduced_motion),
+            input_grace: Some(config.input_grace),
+            afk_ticks: Some(config.afk_ticks),
+            afk_mode: Some(AfkMode::from(config.afk_mode) as i32),
+            boss: config.boss.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<GameConfig> for crate::GameConfig {
+    type Error = GameError;
+
+    fn try_from(config: GameConfig) -> Result<crate::GameConfig, GameError> {
+        let default = crate::GameConfig::default();
+        let opponents = config
+            .opponents
+            .into_iter()
+            .map(|o| o.controller.map(|c| enum_field::<Controller, _>(c, "controller")).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        let theme = config.theme.map(|t| enum_field::<ThemePreset, _>(t, "theme")).transpose()?;
+        let afk_mode = config.afk_mode.map(|m| enum_field::<AfkMode, _>(m, "AFK mode")).transpose()?;
+        Ok(crate::GameConfig {
+            width: config.width.unwrap_or(default.width),
+            height: config.height.unwrap_or(default.height),
+            seed: config.seed,
+            mode: config.mode.map_or(default.mode, Into::into),
+            wrap_walls: config.wrap_walls.unwrap_or(default.wrap_walls),
+            food_count: config.food_count.map_or(default.food_count, |n| n as usize),
+            speed_curve: config.speed_curve.map_or(default.speed_curve, Into::into),
+            snake_length: config.snake_length.map_or(default.snake_length, |n| n as usize),
+            opponents,
+            theme: theme.unwrap_or(default.theme),
+            countdown_secs: config.countdown_secs.unwrap_or(default.countdown_secs),
+            reduced_motion: config.reduced_motion.unwrap_or(default.reduced_motion),
+            input_grace: config.input_grace.unwrap_or(default.input_grace),
+            afk_ticks: config.afk_ticks.unwrap_or(default.afk_ticks),
+            afk_mode: afk_mode.unwrap_or(default.afk_mode),
+            boss: config.boss.map(Into::into),
+        })
+    }
+}
+
+impl From<crate::InputAction> for Action {
+    fn from(action: crate::InputAction) -> Action {
+        match action {
+            crate::InputAction::Turn(crate::Direction::Up) => Action::TurnUp,
+            crate::InputAction::Turn(crate::Direction::Down) => Action::TurnDown,
+            crate::InputAction::Turn(crate::Direction::Left) => Action::TurnLeft,
+            crate::InputAction::Turn(crate::Direction::Right) => Action::TurnRight,
+            crate::InputAction::Pause => Action::Pause,
+            crate::InputAction::Restart => Action::Restart,
+            crate::InputAction::Boost => Action::Boost,
+            crate::InputAction::Confirm => Action::Confirm,
+        }
+    }
+}
+
+impl TryFrom<Action> for crate::InputAction {
+    type Error = GameError;
+
+    fn try_from(action: Action) -> Result<crate::InputAction, GameError> {
+        match action {
+            Action::Unspecified => Err(unspecified("action")),
+            Action::TurnUp => Ok(crate::InputAction::Turn(crate::Direction::Up)),
+            Action::TurnDown => Ok(crate::InputAction::Turn(crate::Direction::Down)),
+            Action::TurnLeft => Ok(crate::InputAction::Turn(crate::Direction::Left)),
+            Action::TurnRight => Ok(crate::InputAction::Turn(crate::Direction::Right)),
+            Action::Pause => Ok(crate::InputAction::Pause),
+            Action::Restart => Ok(crate::InputAction::Restart),
+            Action::Boost => Ok(crate::InputAction::Boost),
+            Action::Confirm => Ok(crate::InputAction::Confirm),
+        }
+    }
+}
+
+impl From<crate::InputAction> for Input {
+    fn from(action: crate::InputAction) -> Input {
+        Input { input: Some(input::Input::Action(Action::from(action) as i32)) }
+    }
+}
+
+// Key names go through `InputAction::from_key`
+impl TryFrom<Input> for crate::InputAction {
+    type Error = GameError;
+
+    fn try_from(input: Input) -> Result<crate::InputAction, GameError> {
+        match input.input {
+            Some(input::Input::Action(action)) => enum_field::<Action, _>(action, "action"),
+            Some(input::Input::Key(key)) => {
+                crate::InputAction::from_key(&key).ok_or_else(|| invalid(format!("no action for key '{}'", key)))
+            }
+            None => Err(missing("input")),
+        }
+    }
+}
+
+impl From<crate::GameStatus> for GameStatus {
+    fn from(status: crate::GameStatus) -> GameStatus {
+        match status {
+            crate::GameStatus::NotStarted => GameStatus::NotStarted,
+            crate::GameStatus::Countdown => GameStatus::Countdown,
+            crate::GameStatus::Running => GameStatus::Running,
+            crate::GameStatus::Paused => GameStatus::Paused,
+            crate::GameStatus::Dying => GameStatus::Dying,
+            crate::GameStatus::GameOver => GameStatus::GameOver,
+        }
+    }
+}
+
+impl TryFrom<GameStatus> for crate::GameStatus {
+    type Error = GameError;
+
+    fn try_from(status: GameStatus) -> Result<crate::GameStatus, GameError> {
+        match status {
+            GameStatus::Unspecified => Err(unspecified("status")),
+            GameStatus::NotStarted => Ok(crate::GameStatus::NotStarted),
+            GameStatus::Countdown => Ok(crate::GameStatus::Countdown),
+            GameStatus::Running => Ok(crate::GameStatus::Running),
+            GameStatus::Paused => Ok(crate::GameStatus::Paused),
+            GameStatus::Dying => Ok(crate::GameStatus::Dying),
+            GameStatus::GameOver => Ok(crate::GameStatus::GameOver),
+        }
+    }
+}
+
+impl From<crate::DeathCause> for DeathCause {
+    fn from(cause: crate::DeathCause) -> DeathCause {
+        let (kind, at, segment, snake) = match cause {
+            crate::DeathCause::Wall { at } => (DeathKind::Wall, Some(at), 0, 0),
+            crate::DeathCause::Obstacle { at } => (DeathKind::Obstacle, Some(at), 0, 0),
+            crate::DeathCause::SelfCollision { at, segment } => (DeathKind::SelfCollision, Some(at), segment, 0),
+            crate::DeathCause::BoardFull => (DeathKind::BoardFull, None, 0, 0),
+            crate::DeathCause::TimeExpired => (DeathKind::TimeExpired, None, 0, 0),
+            crate::DeathCause::Opponent { at, snake } => (DeathKind::Opponent, Some(at), 0, snake),
+            crate::DeathCause::Rules => (DeathKind::Rules, None, 0, 0),
+        };
+        DeathCause { kind: kind as i32, at: at.map(Into::into), segment: segment as u32, snake: snake as u32 }
+    }
+}
+
+impl TryFrom<DeathCause> for crate::DeathCause {
+    type Error = GameError;
+
+    fn try_from(cause: DeathCause) -> Result<crate::DeathCause, GameError> {
+        let kind = DeathKind::try_from(cause.kind).map_err(|_| invalid(format!("unknown death kind {}", cause.kind)))?;
+        let at = || point_field(cause.at, "death cell");
+        Ok(match kind {
+            DeathKind::Unspecified => return Err(unspecified("death kind")),
+            DeathKind::Wall => crate::DeathCause::Wall { at: at()? },
+            DeathKind::Obstacle => crate::DeathCause::Obstacle { at: at()? },
+            DeathKind::SelfCollision => crate::DeathCause::SelfCollision { at: at()?, segment: cause.segment as usize },
+            DeathKind::BoardFull => crate::DeathCause::BoardFull,
+            DeathKind::TimeExpired => crate::DeathCause::TimeExpired,
+            DeathKind::Opponent => crate::DeathCause::Opponent { at: at()?, snake: cause.snake as usize },
+            DeathKind::Rules => crate::DeathCause::Rules,
+        })
+    }
+}
+
+impl From<crate::OpponentState> for OpponentState {
+    fn from(o: crate::OpponentState) -> OpponentState {
+        OpponentState {
+            body: points(&o.body),
+            direction: Direction::from(o.direction) as i32,
+            score: o.score,
+            alive: o.alive,
+            controller: o.controller.map(|c| Controller::from(c) as i32),
+        }
+    }
+}
+
+impl TryFrom<OpponentState> for crate::OpponentState {
+    type Error = GameError;
+
+    fn try_from(o: OpponentState) -> Result<crate::OpponentState, GameError> {
+        Ok(crate::OpponentState {
+            body: o.body.into_iter().map(Into::into).collect(),
+            direction: enum_field::<Direction, _>(o.direction, "direction")?,
+            score: o.score,
+            alive: o.alive,
+            controller: o.controller.map(|c| enum_field::<Controller, _>(c, "controller")).transpose()?,
+        })
+    }
+}
+
+impl From<crate::GameState> for GameState {
+    fn from(state: crate::GameState) -> GameState {
+        GameState {
+            width: state.width,
+            height: state.height,
+            snake: Some(SnakeState {
+                body: points(&state.snake.body),
+                direction: Direction::from(state.snake.direction) as i32,
+            }),
+            food: state.food.into_iter().map(Into::into).collect(),
+            obstacles: points(&state.obstacles),
+            portals: state.portals.iter().map(|&(a, b)| Portal { a: Some(a.into()), b: Some(b.into()) }).collect(),
+            score: state.score,
+            mode: Some(state.mode.into()),
+            wrap_walls: state.wrap_walls,
+            status: GameStatus::from(state.status) as i32,
+            death_cause: state.death_cause.map(Into::into),
+            tick: state.tick,
+            speed_level: state.speed_level,
+            boosting: state.boosting,
+            slow_ticks_left: state.slow_ticks_left,
+            opponents: state.opponents.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<GameState> for crate::GameState {
+    type Error = GameError;
+
+    fn try_from(state: GameState) -> Result<crate::GameState, GameError> {
+        let snake = state.snake.ok_or_else(|| missing("snake"))?;
+        Ok(crate::GameState {
+            width: state.width,
+            height: state.height,
+            snake: crate::SnakeState {
+                body: snake.body.into_iter().map(Into::into).collect(),
+                direction: enum_field::<Direction, _>(snake.direction, "direction")?,
+            },
+            food: state.food.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            obstacles: state.obstacles.into_iter().map(Into::into).collect(),
+            portals: state
+                .portals
+                .into_iter()
+                .map(|p| Ok((point_field(p.a, "portal")?, point_field(p.b, "portal")?)))
+                .collect::<Result<_, GameError>>()?,
+            score: state.score,
+            mode: state.mode.map(Into::into).unwrap_or_default(),
+            wrap_walls: state.wrap_walls,
+            status: enum_field::<GameStatus, _>(state.status, "status")?,
+            death_cause: state.death_cause.map(TryInto::try_into).transpose()?,
+            tick: state.tick,
+            speed_level: state.speed_level,
+            boosting: state.boosting,
+            slow_ticks_left: state.slow_ticks_left,
+            opponents: state.opponents.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+        })
+    }
+}
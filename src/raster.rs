@@ -0,0 +1,71 @@
+// src/raster.rs
+
+use alloc::vec::Vec;
+
+use crate::{CellKind, Game, Rgb, Sprite, Tile, TILE_SIZE};
+
+impl Game {
+    // Width and height in pixels of a `render_rgba` frame
+    pub fn frame_size(&self, cell_px: u32) -> (u32, u32) {
+        let cell_px = cell_px.max(1);
+        (self.width as u32 * cell_px, self.height as u32 * cell_px)
+    }
+
+    // The board drawn in software as RGBA rows, `cell_px` pixels per cell,
+    // with the same tiles and theme colors as the frontends. Snakes are at
+    // whole cells, there's no sliding between ticks. For images and
+    // animations made without a window (see the `apng` feature).
+    pub fn render_rgba(&self, cell_px: u32) -> Vec<u8> {
+        let cell_px = cell_px.max(1) as usize;
+        let (width, height) = (self.width as usize * cell_px, self.height as usize * cell_px);
+        let theme = &self.theme;
+        let background = theme.background;
+        let mut pixels: Vec<u8> = [background.r, background.g, background.b, 255].repeat(width * height);
+
+        // Nearest neighbour scaling of the tile, turned clockwise. A `None`
+        // tile fills the whole cell.
+        let mut draw = |x: i32, y: i32, sprite: Option<Sprite>, color: Rgb| {
+            let tile = TILE_SIZE as usize;
+            for dy in 0..cell_px {
+                for dx in 0..cell_px {
+                    let shade = match sprite {
+                        None => 255,
+                        Some(sprite) => {
+                            let (mut sx, mut sy) = (dx * tile / cell_px, dy * tile / cell_px);
+                            for _ in 0..sprite.quarter_turns % 4 {
+                                (sx, sy) = (sy, tile - 1 - sx);
+                            }
+                            match sprite.tile.shade(sx, sy) {
+                                Some(shade) => shade as u16,
+                                None => continue,
+                            }
+                        }
+                    };
+                    let i = ((y as usize * cell_px + dy) * width + x as usize * cell_px + dx) * 4;
+                    let tint = |channel: u8| (channel as u16 * shade / 255) as u8;
+                    pixels[i..i + 3].copy_from_slice(&[tint(color.r), tint(color.g), tint(color.b)]);
+                }
+            }
+        };
+
+        for (p, kind) in self.cells() {
+            let Some(color) = theme.cell_color(kind) else { continue };
+            let tile = match kind {
+                CellKind::Food(_) => Some(Tile::Apple),
+                CellKind::Obstacle => Some(Tile::Wall),
+                CellKind::Portal => None,
+                _ => continue,
+            };
+            draw(p.x, p.y, tile.map(|tile| Sprite { tile, quarter_turns: 0 }), color);
+        }
+
+        // Tail first, so the head ends up on top
+        for index in 0..self.snake_count() {
+            let segments = self.snake_body(index).iter().zip(self.snake_sprites(index)).take(self.visible_segments(index));
+            for (i, (p, sprite)) in segments.enumerate().rev() {
+                draw(p.x, p.y, Some(sprite), self.segment_color(index, i));
+            }
+        }
+        pixels
+    }
+}
@@ -0,0 +1,298 @@
+// src/replay.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{BossConfig, CheatInput, Game, GameConfig, GameError, GameEvent, GameMode, InputAction, Level, Point, RunStats, SnakeSpawn, SpeedCurve};
+
+// Bumped whenever the binary layout or the simulation rules change, old
+// replays would otherwise play back as a different game
+const REPLAY_VERSION: u8 = 8;
+
+// An action, the snake it was for (0 is the player) and the tick count at
+// the moment it was taken, i.e. it applies to tick `tick + 1`. A `late`
+// turn came in within the grace window and redoes tick `tick` if it can,
+// see `Game::queue_turn`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub snake: usize,
+    pub action: InputAction,
+    pub late: bool,
+}
+
+// Everything needed to play a round again tick for tick: the board and rules,
+// the seed the round started from and the inputs that changed the simulation
+// (turns and boost). Pauses and frame timing don't matter, the core only
+// moves in whole ticks. Turns taken by AI controllers are recorded like any
+// other, so opponents play back without their AI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub width: i32,
+    pub height: i32,
+    pub obstacles: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub spawn: SnakeSpawn,
+    pub mode: GameMode,
+    pub wrap_walls: bool,
+    pub food_count: usize,
+    pub speed: SpeedCurve,
+    pub opponents: Vec<SnakeSpawn>,
+    // Boss encounters, the bosses themselves aren't in `opponents`
+    pub boss: Option<BossConfig>,
+    pub seed: u64,
+    pub inputs: Vec<ReplayInput>,
+    // Cheats used in the round, played back at their tick, see `Cheat`
+    pub cheats: Vec<CheatInput>,
+    // Length of the recording and the score reached at its end
+    pub ticks: u64,
+    pub score: u32,
+    // The player's stats at the end, which playing it back counts again
+    pub stats: RunStats,
+}
+
+// Playback state kept inside a `Game` built by `Replay::game`
+#[derive(Debug, Clone)]
+pub(crate) struct Playback {
+    pub(crate) inputs: Vec<ReplayInput>,
+    pub(crate) next: usize,
+    pub(crate) cheats: Vec<CheatInput>,
+    pub(crate) next_cheat: usize,
+    pub(crate) seed: u64,
+    pub(crate) end: u64,
+}
+
+impl Replay {
+    // A version byte followed by the postcard encoding (varints, no field names)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        span!(DEBUG, "replay_to_bytes", inputs = self.inputs.len());
+        let mut bytes = Vec::from([REPLAY_VERSION]);
+        // Encoding into a Vec can't fail for these types
+        bytes.extend(postcard::to_allocvec(self).unwrap_or_default());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, GameError> {
+        span!(DEBUG, "replay_from_bytes", bytes = bytes.len());
+        match bytes.split_first() {
+            Some((&REPLAY_VERSION, rest)) => {
+                postcard::from_bytes(rest).map_err(|e| GameError::InvalidReplay(e.to_string()))
+            }
+            Some((version, _)) => Err(GameError::InvalidReplay(alloc::format!("unsupported version {}", version))),
+            None => Err(GameError::InvalidReplay("empty replay".to_string())),
+        }
+    }
+
+    // URL-safe base64 without padding, so it can go straight into a link
+    pub fn to_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    pub fn from_base64(text: &str) -> Result<Replay, GameError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(text.trim())
+            .map_err(|e| GameError::InvalidReplay(e.to_string()))?;
+        Replay::from_bytes(&bytes)
+    }
+
+    // Whether cheats were used in the round, its score then doesn't count
+    pub fn cheated(&self) -> bool {
+        !self.cheats.is_empty()
+    }
+
+    // A started game that plays the replay back by itself as it's ticked.
+    // Player turns and boosts are ignored, restarting plays it from the top.
+    pub fn game(&self) -> Result<Game, GameError> {
+        let level = Level {
+            width: self.width,
+            height: self.height,
+            walls: self.obstacles.clone(),
+            portals: self.portals.clone(),
+            spawn: self.spawn,
+        };
+        let config = GameConfig {
+            seed: Some(self.seed),
+            mode: self.mode,
+            wrap_walls: self.wrap_walls,
+            food_count: self.food_count,
+            speed_curve: self.speed,
+            boss: self.boss,
+            ..GameConfig::default()
+        };
+        let mut game = Game::from_level_with_config(&level, &config)?;
+        for &spawn in &self.opponents {
+            game.add_opponent(spawn, None)?;
+        }
+        // For the viewer's heatmap
+        game.track_visits = true;
+        game.playback = Some(Playback {
+            inputs: self.inputs.clone(),
+            next: 0,
+            cheats: self.cheats.clone(),
+            next_cheat: 0,
+            seed: self.seed,
+            end: self.ticks,
+        });
+        game.start_game();
+        Ok(game)
+    }
+}
+
+// Watches a replay like a video: pause, speed, single ticks and seeking.
+// Seeking back plays the recording again from the top, the simulation is
+// deterministic and that's cheaper than keeping snapshots along the way.
+pub struct ReplayPlayer {
+    replay: Replay,
+    game: Game,
+    pub paused: bool,
+    pub speed: f32, // 1.0 is the recorded speed
+}
+
+impl ReplayPlayer {
+    // Starts right away, without the countdown
+    pub fn new(replay: Replay) -> Result<ReplayPlayer, GameError> {
+        let mut game = replay.game()?;
+        game.countdown_left = Duration::ZERO;
+        game.events.clear();
+        Ok(ReplayPlayer { replay, game, paused: false, speed: 1.0 })
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Replays don't record it, it's up to whoever is watching
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.game.reduced_motion = reduced;
+    }
+
+    // Events of the ticks played since the last call, skipped ones excluded
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.game.drain_events()
+    }
+
+    // Current tick and the length of the recording
+    pub fn position(&self) -> u64 {
+        self.game.tick_count()
+    }
+
+    pub fn length(&self) -> u64 {
+        self.replay.ticks
+    }
+
+    pub fn finished(&self) -> bool {
+        self.game.replay_finished()
+    }
+
+    // Plays on real time, scaled by `speed`. Returns the ticks that were run.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        if self.paused {
+            return 0;
+        }
+        self.game.advance(elapsed.mul_f32(self.speed))
+    }
+
+    // One tick forward, pausing first
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.game.tick();
+        self.settle();
+    }
+
+    // Jumps to a tick, clamped to the recording. Events of the skipped
+    // ticks are dropped, a seek shouldn't replay every sound on the way.
+    pub fn seek(&mut self, tick: u64) -> Result<(), GameError> {
+        let tick = tick.min(self.replay.ticks);
+        if tick < self.game.tick_count() {
+            let reduced_motion = self.game.reduced_motion;
+            self.game = self.replay.game()?;
+            self.game.reduced_motion = reduced_motion;
+        }
+        self.game.tick_n(tick - self.game.tick_count());
+        self.game.events.clear();
+        self.settle();
+        Ok(())
+    }
+
+    // Shows the current tick as it is, without sliding from the last one
+    // or the death animation
+    fn settle(&mut self) {
+        self.game.countdown_left = Duration::ZERO;
+        self.game.dying_left = Duration::ZERO;
+        self.game.accumulator = Duration::ZERO;
+        self.game.last_moves.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Controller, Snapshot};
+
+    fn config(seed: u64) -> GameConfig {
+        GameConfig { seed: Some(seed), width: 12, height: 10, ..GameConfig::default() }
+    }
+
+    // The pathfinder steers, so the round turns and eats a lot
+    fn play(game: &mut Game, ticks: u64) {
+        for _ in 0..ticks {
+            if game.game_over {
+                break;
+            }
+            if let Some(direction) = Controller::Pathfinder.decide(game, 0) {
+                game.queue_turn(direction);
+            }
+            game.tick();
+        }
+    }
+
+    // The hash a replay ends on, played from its bytes
+    fn replayed_hash(replay: &Replay) -> u64 {
+        let mut game = Replay::from_bytes(&replay.to_bytes()).unwrap().game().unwrap();
+        while !game.replay_finished() {
+            game.tick();
+        }
+        game.state_hash()
+    }
+
+    #[test]
+    fn replays_end_where_the_round_did() {
+        for seed in 0..8 {
+            let mut game = Game::from_config(&config(seed)).unwrap();
+            game.start_game();
+            play(&mut game, 400);
+            let replay = game.replay();
+            assert!(!replay.inputs.is_empty());
+            assert_eq!(replayed_hash(&replay), game.state_hash(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn restored_rounds_go_on_and_replay_like_the_original() {
+        for seed in 0..8 {
+            let mut original = Game::from_config(&config(seed)).unwrap();
+            original.start_game();
+            play(&mut original, 60);
+            let json = serde_json::to_string(&original.snapshot()).unwrap();
+
+            // A new session builds its game from another seed first
+            let mut restored = Game::from_config(&config(seed + 100)).unwrap();
+            restored.restore(&serde_json::from_str::<Snapshot>(&json).unwrap());
+            assert_eq!(restored.state_hash(), original.state_hash(), "seed {}", seed);
+
+            play(&mut original, 300);
+            play(&mut restored, 300);
+            assert_eq!(restored.state_hash(), original.state_hash(), "seed {}", seed);
+            assert_eq!(restored.replay(), original.replay(), "seed {}", seed);
+            assert_eq!(replayed_hash(&restored.replay()), restored.state_hash(), "seed {}", seed);
+        }
+    }
+}
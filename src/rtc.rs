@@ -0,0 +1,206 @@
+// src/rtc.rs
+
+// The native side of peer-to-peer games, only with the `webrtc` feature: a
+// WebRTC data channel (webrtc-rs) carrying a `Lockstep` session, so a
+// native frontend can play another one, or a browser's `PeerGame`. The
+// session descriptions are plain SDP text and can go through snake-server's
+// /rooms routes like the browsers' do, or any other way:
+//
+//     let (peer, offer) = RtcPeer::offer()?;     // host
+//     let (peer, answer) = RtcPeer::answer(&offer)?; // guest
+//     peer.accept(&answer)?;                     // host
+//
+// then `exchange` every frame, before advancing the session. The calls
+// block, the connection runs on a small tokio runtime of its own.
+
+use std::prelude::rust_2021::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::vec;
+
+use tokio::runtime::Runtime;
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::data_channel_state::RTCDataChannelState;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+use crate::{GameError, Lockstep};
+
+const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+
+fn rtc_error(e: impl ToString) -> GameError {
+    GameError::WebRtc(e.to_string())
+}
+
+pub struct RtcPeer {
+    runtime: Runtime,
+    connection: Arc<RTCPeerConnection>,
+    // The host's from the start, the guest's once connected
+    channel: Arc<Mutex<Option<Arc<RTCDataChannel>>>>,
+    incoming: Receiver<Vec<u8>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl RtcPeer {
+    fn new() -> Result<(RtcPeer, Sender<Vec<u8>>), GameError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().map_err(rtc_error)?;
+        let config = RTCConfiguration {
+            ice_servers: vec![RTCIceServer { urls: vec![STUN_SERVER.to_string()], ..Default::default() }],
+            ..Default::default()
+        };
+        let connection = runtime.block_on(APIBuilder::new().build().new_peer_connection(config)).map_err(rtc_error)?;
+        let closed = Arc::new(AtomicBool::new(false));
+        let on_state = Arc::clone(&closed);
+        connection.on_peer_connection_state_change(Box::new(move |state| {
+            if matches!(state, RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed) {
+                on_state.store(true, Ordering::Relaxed);
+            }
+            Box::pin(async {})
+        }));
+        let (sender, incoming) = mpsc::channel();
+        let peer = RtcPeer {
+            runtime,
+            connection: Arc::new(connection),
+            channel: Arc::new(Mutex::new(None)),
+            incoming,
+            closed,
+        };
+        Ok((peer, sender))
+    }
+
+    // Messages go to `incoming`, a closed channel ends the game
+    fn attach(channel: &Arc<RTCDataChannel>, sender: Sender<Vec<u8>>, closed: &Arc<AtomicBool>) {
+        let sender = Mutex::new(sender);
+        channel.on_message(Box::new(move |message: DataChannelMessage| {
+            // Nobody receiving means the peer was dropped
+            let _ = sender.lock().map(|sender| sender.send(message.data.to_vec()));
+            Box::pin(async {})
+        }));
+        let closed = Arc::clone(closed);
+        channel.on_close(Box::new(move || {
+            closed.store(true, Ordering::Relaxed);
+            Box::pin(async {})
+        }));
+    }
+
+    // Sets our side of the connection and waits for the ICE candidates, so
+    // the description has all of them (no trickle ICE)
+    async fn describe(connection: &RTCPeerConnection, description: RTCSessionDescription) -> Result<String, GameError> {
+        let mut gathered = connection.gathering_complete_promise().await;
+        connection.set_local_description(description).await.map_err(rtc_error)?;
+        let _ = gathered.recv().await;
+        match connection.local_description().await {
+            Some(description) => Ok(description.sdp),
+            None => Err(rtc_error("no local description")),
+        }
+    }
+
+    // The host's side: opens the data channel and returns the offer to send
+    // to the guest
+    pub fn offer() -> Result<(RtcPeer, String), GameError> {
+        let (peer, sender) = RtcPeer::new()?;
+        let connection = Arc::clone(&peer.connection);
+        let sdp = peer.runtime.block_on(async {
+            let channel = connection.create_data_channel("snake", None).await.map_err(rtc_error)?;
+            RtcPeer::attach(&channel, sender, &peer.closed);
+            *peer.channel.lock().unwrap_or_else(|e| e.into_inner()) = Some(channel);
+            let offer = connection.create_offer(None).await.map_err(rtc_error)?;
+            RtcPeer::describe(&connection, offer).await
+        })?;
+        Ok((peer, sdp))
+    }
+
+    // The guest's side: takes the host's offer and returns the answer
+    pub fn answer(offer: &str) -> Result<(RtcPeer, String), GameError> {
+        let (peer, sender) = RtcPeer::new()?;
+        let (channel, closed) = (Arc::clone(&peer.channel), Arc::clone(&peer.closed));
+        let sender = Mutex::new(Some(sender));
+        peer.connection.on_data_channel(Box::new(move |data_channel: Arc<RTCDataChannel>| {
+            // Only the first channel, the host opens one
+            if let Some(sender) = sender.lock().ok().and_then(|mut sender| sender.take()) {
+                RtcPeer::attach(&data_channel, sender, &closed);
+                *channel.lock().unwrap_or_else(|e| e.into_inner()) = Some(data_channel);
+            }
+            Box::pin(async {})
+        }));
+        let connection = Arc::clone(&peer.connection);
+        let sdp = peer.runtime.block_on(async {
+            let offer = RTCSessionDescription::offer(offer.to_string()).map_err(rtc_error)?;
+            connection.set_remote_description(offer).await.map_err(rtc_error)?;
+            let answer = connection.create_answer(None).await.map_err(rtc_error)?;
+            RtcPeer::describe(&connection, answer).await
+        })?;
+        Ok((peer, sdp))
+    }
+
+    // The host's last step, with the guest's answer
+    pub fn accept(&self, answer: &str) -> Result<(), GameError> {
+        let answer = RTCSessionDescription::answer(answer.to_string()).map_err(rtc_error)?;
+        self.runtime.block_on(self.connection.set_remote_description(answer)).map_err(rtc_error)
+    }
+
+    fn channel(&self) -> Option<Arc<RTCDataChannel>> {
+        self.channel.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Whether messages can be sent, i.e. the peers are connected
+    pub fn is_open(&self) -> bool {
+        !self.is_closed() && self.channel().is_some_and(|c| c.ready_state() == RTCDataChannelState::Open)
+    }
+
+    // The other peer hung up or the connection failed
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    pub fn send(&self, bytes: &[u8]) -> Result<(), GameError> {
+        let channel = self.channel().ok_or_else(|| rtc_error("not connected"))?;
+        let bytes = bytes::Bytes::copy_from_slice(bytes);
+        self.runtime.block_on(channel.send(&bytes)).map(drop).map_err(rtc_error)
+    }
+
+    // The next message from the other peer, without waiting
+    pub fn try_recv(&self) -> Option<Vec<u8>> {
+        self.incoming.try_recv().ok()
+    }
+
+    // Hands what the other peer sent to the session and sends what it has
+    // for the other peer. Messages wait in the session until the channel
+    // is open.
+    pub fn exchange(&self, lockstep: &mut Lockstep) -> Result<(), GameError> {
+        while let Some(bytes) = self.try_recv() {
+            lockstep.receive_bytes(&bytes)?;
+        }
+        if self.is_open() {
+            for bytes in lockstep.take_bytes() {
+                self.send(&bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The guest's session, once the host's first message has arrived
+    pub fn join(&self) -> Result<Option<Lockstep>, GameError> {
+        match self.try_recv() {
+            Some(bytes) => Lockstep::join(&crate::LockstepMessage::from_bytes(&bytes)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        let _ = self.runtime.block_on(self.connection.close());
+    }
+}
+
+impl Drop for RtcPeer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -0,0 +1,29 @@
+// src/rules.rs
+
+// Hooks for custom rules, so mods can change how a game plays without
+// touching the core: set `Game::rules` and the game calls them as it runs.
+// The hooks get the whole game and can change anything public on it (the
+// score, the obstacles...) or end the round with `Game::end_game`. Scripts
+// written in Lua get them through `lua::LuaRules` with the `lua` feature.
+//
+// Clones of a game share its rules. Replays don't record them, so a round
+// played with rules only replays the same with the same rules set.
+
+use core::fmt;
+
+use crate::{Food, Game};
+
+pub trait Rules: fmt::Debug + Send + Sync {
+    // After every tick that ran and didn't end the round
+    fn on_tick(&self, _game: &mut Game) {}
+
+    // After the player's snake ate `food` and its points were counted
+    fn on_food_eaten(&self, _game: &mut Game, _food: Food) {}
+
+    // Before `food` goes on the board, at a free cell. Changing it changes
+    // what appears (moving it to a cell that isn't free keeps it off the
+    // board), returning false keeps it off the board.
+    fn on_spawn_food(&self, _game: &mut Game, _food: &mut Food) -> bool {
+        true
+    }
+}
@@ -0,0 +1,625 @@
+// src/server.rs
+
+// HTTP frontend: the game over a small REST API plus Server-Sent Events, so
+// web pages without the WASM module, scripts and dashboards can drive and
+// watch games. The games run on the server's clock, and the JSON is the
+// same serde types the other frontends use (`GameConfig`, `InputAction`,
+// `GameState`):
+//
+//     POST /games               body: a GameConfig, or nothing for the defaults
+//                               -> 201 {"id": 1, "state": GameState}
+//     GET  /games/{id}          -> GameState
+//     POST /games/{id}/input    body: an InputAction ({"Turn": "Up"}, "Confirm"...)
+//                               or a key name ({"key": "arrowup"}) -> GameState
+//     GET  /games/{id}/events   text/event-stream, a `state` event with the
+//                               GameState every time it changes
+//
+//     cargo run --bin snake-server --features server -- --port 8080
+//
+// Errors come back as {"error": code, "message": text}, with the codes from
+// `GameError::code` for games that can't be built. A game nobody sent input
+// to for a while is dropped, which also ends its event streams. Requests
+// get a 503 while too many others are being served or, for event streams,
+// too many streams are open.
+//
+// Each game has a lock of its own: the clock advances them one at a time
+// while requests for the others go through.
+//
+// Clients can use Protocol Buffers instead (proto/snake_game.proto): a
+// `Content-Type: application/x-protobuf` body is a GameConfig or an Input
+// message, and with `Accept: application/x-protobuf` the replies are
+// Created, GameState and Error messages. The event stream is text, it stays
+// JSON. With the `grpc` feature and `--grpc-port`, the same games are also
+// served over gRPC, see src/grpc.rs.
+//
+// It's also the signaling server for peer-to-peer games (see `Lockstep`).
+// Two browsers only need it to swap their WebRTC session descriptions, the
+// game itself goes over a data channel between them:
+//
+//     POST /rooms               body: {"sdp": offer} -> 201 Room
+//     GET  /rooms/{code}        -> Room, the host polls it for the answer
+//     POST /rooms/{code}/answer body: {"sdp": answer} -> Room
+//
+// where a Room is {"room": code, "offer": sdp, "answer": sdp or null}.
+// Rooms are JSON only and dropped a few minutes after they're opened.
+
+// This code will only be compiled for native targets, not for wasm32.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use prost::Message;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use snake_game::{proto, Game, GameConfig, GameError, GameState, InputAction};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[cfg(feature = "grpc")]
+mod grpc;
+
+// How often the clock thread advances the games
+const FRAME: Duration = Duration::from_millis(10);
+// Games without input for this long are dropped
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+// Creating more games fails until some time out
+const MAX_GAMES: usize = 100;
+// Threads serving requests at once, event streams included. The ones past
+// that are turned away rather than each getting a thread.
+const MAX_REQUEST_THREADS: usize = 256;
+// Event streams (and gRPC state streams) open at once, each holds a thread
+// for as long as its game runs, so well below `MAX_REQUEST_THREADS`
+const MAX_STREAMS: usize = 64;
+// Largest board side, so one request can't take all the memory
+const MAX_BOARD_SIZE: i32 = 200;
+// Every snake and every piece of food costs time on each tick, these keep a
+// single game from slowing down the clock for all the others
+const MAX_OPPONENTS: usize = 16;
+const MAX_FOOD_COUNT: usize = 100;
+const MAX_SNAKE_LENGTH: usize = 1000;
+// A comment this often keeps proxies from closing a quiet event stream,
+// and notices clients that went away
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+// Request bodies are small JSON objects
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const PROTOBUF: &str = "application/x-protobuf";
+// Long enough to share the code and for both peers to connect
+const ROOM_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const MAX_ROOMS: usize = 100;
+// Room codes are read out loud, so no 0/O or 1/I
+const ROOM_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const ROOM_CODE_LEN: usize = 6;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Serves games over HTTP with a REST API and Server-Sent Events")]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1", help = "Address to listen on")]
+    host: String,
+    #[arg(long, default_value_t = 8080, help = "Port to listen on, 0 for any free one")]
+    port: u16,
+    #[cfg(feature = "grpc")]
+    #[arg(long, help = "Also serve the gRPC API on this port, 0 for any free one")]
+    grpc_port: Option<u16>,
+}
+
+struct Session {
+    game: Game,
+    last_input: Instant,
+}
+
+// Session descriptions of a peer-to-peer game while the peers connect
+struct Room {
+    offer: String,
+    answer: Option<String>,
+    opened: Instant,
+}
+
+#[derive(Default)]
+struct Table {
+    sessions: HashMap<u64, Arc<Mutex<Session>>>,
+    next_id: u64,
+    rooms: HashMap<String, Room>,
+}
+
+// Shared by the clock thread and the request threads. The table is only
+// locked to find a session, which is then locked on its own: never the
+// other way around.
+#[derive(Default)]
+struct Games {
+    table: Mutex<Table>,
+    ticked: Condvar, // Notified after every frame of the clock
+    requests: Arc<AtomicUsize>, // Request threads running, see `Slot`
+    streams: Arc<AtomicUsize>,  // Event streams open
+}
+
+impl Games {
+    // A request thread that panicked leaves the games as they were after
+    // its last complete change, so keep serving them
+    fn lock(&self) -> MutexGuard<'_, Table> {
+        self.table.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn session(&self, id: u64) -> Option<Arc<Mutex<Session>>> {
+        self.lock().sessions.get(&id).cloned()
+    }
+
+    // Blocks until the clock has advanced the games once more, then gives
+    // the session `id` if it's still there
+    fn next_frame(&self, id: u64) -> Option<Arc<Mutex<Session>>> {
+        let table = self.lock();
+        let table = match self.ticked.wait_timeout(table, FRAME * 10) {
+            Ok((table, _)) => table,
+            Err(e) => e.into_inner().0,
+        };
+        table.sessions.get(&id).cloned()
+    }
+}
+
+// Same as `Games::lock`, for one game
+fn lock_session(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    session.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// One of a limited number of threads, given back when dropped
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    // `None` when `max` threads hold one already
+    fn take(count: &Arc<AtomicUsize>, max: usize) -> Option<Slot> {
+        count.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then_some(n + 1)).ok()?;
+        Some(Slot(Arc::clone(count)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// The body of POST /games/{id}/input
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Input {
+    Action(InputAction),
+    Key { key: String },
+}
+
+#[derive(Serialize)]
+struct Created {
+    id: u64,
+    state: GameState,
+}
+
+// The body of POST /rooms and POST /rooms/{code}/answer
+#[derive(Deserialize)]
+struct Description {
+    sdp: String,
+}
+
+#[derive(Serialize)]
+struct RoomReply {
+    room: String,
+    offer: String,
+    answer: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: &'static str,
+    message: String,
+}
+
+// How a request body is encoded, or how the client wants the reply
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Protobuf,
+}
+
+impl Format {
+    // From a Content-Type or Accept header, JSON unless it asks for protobuf
+    fn from_header(request: &Request, name: &'static str) -> Format {
+        let value = request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str());
+        match value {
+            Some(value) if value.contains(PROTOBUF) => Format::Protobuf,
+            _ => Format::Json,
+        }
+    }
+}
+
+enum Body {
+    Created(Created),
+    State(GameState),
+    Room(RoomReply),
+    Error(ApiError),
+}
+
+struct Reply {
+    status: u16,
+    body: Body,
+}
+
+impl Reply {
+    fn encode(self, format: Format) -> Vec<u8> {
+        match format {
+            Format::Json => match &self.body {
+                Body::Created(created) => serde_json::to_vec(created),
+                Body::State(state) => serde_json::to_vec(state),
+                Body::Room(room) => serde_json::to_vec(room),
+                Body::Error(error) => serde_json::to_vec(error),
+            }
+            .expect("the API types serialize to JSON"),
+            Format::Protobuf => match self.body {
+                Body::Created(Created { id, state }) => proto::Created { id, state: Some(state.into()) }.encode_to_vec(),
+                Body::State(state) => proto::GameState::from(state).encode_to_vec(),
+                Body::Room(_) => unreachable!("rooms are only sent as JSON"),
+                Body::Error(ApiError { error, message }) => proto::Error { error: error.to_string(), message }.encode_to_vec(),
+            },
+        }
+    }
+}
+
+fn state(status: u16, state: GameState) -> Reply {
+    Reply { status, body: Body::State(state) }
+}
+
+fn error(status: u16, code: &'static str, message: impl ToString) -> Reply {
+    Reply { status, body: Body::Error(api_error(code, message)) }
+}
+
+fn not_found(id: u64) -> Reply {
+    error(404, "NOT_FOUND", format!("no game {}", id))
+}
+
+fn header(text: &str) -> Header {
+    text.parse().expect("a valid header")
+}
+
+// Advances every game by the real time that passed, and drops the idle ones
+fn run_clock(games: &Games) {
+    let mut last = Instant::now();
+    loop {
+        thread::sleep(FRAME);
+        let now = Instant::now();
+        let mut table = games.lock();
+        table.sessions.retain(|_, session| now - lock_session(session).last_input < IDLE_TIMEOUT);
+        table.rooms.retain(|_, room| now - room.opened < ROOM_TIMEOUT);
+        let sessions: Vec<_> = table.sessions.iter().map(|(&id, session)| (id, session.clone())).collect();
+        drop(table);
+        let mut crashed = Vec::new();
+        for (id, session) in sessions {
+            let mut session = lock_session(&session);
+            // A game that panics mid-tick is in no state to go on, it's
+            // dropped instead of taking the clock (and every game) with it
+            let advanced = panic::catch_unwind(AssertUnwindSafe(|| {
+                session.game.advance(now - last);
+                // Nothing listens to the events, clients compare states instead
+                session.game.drain_events().for_each(drop);
+            }));
+            if advanced.is_err() {
+                crashed.push(id);
+            }
+        }
+        if !crashed.is_empty() {
+            let mut table = games.lock();
+            for id in crashed {
+                eprintln!("game {} panicked and was dropped", id);
+                table.sessions.remove(&id);
+            }
+        }
+        games.ticked.notify_all();
+        last = now;
+    }
+}
+
+fn api_error(code: &'static str, message: impl ToString) -> ApiError {
+    ApiError { error: code, message: message.to_string() }
+}
+
+// An empty body is the default config, in both formats
+fn read_config(body: &[u8], format: Format) -> Result<GameConfig, ApiError> {
+    match format {
+        Format::Json if body.iter().all(u8::is_ascii_whitespace) => Ok(GameConfig::default()),
+        Format::Json => serde_json::from_slice(body).map_err(|e| api_error("INVALID_JSON", e)),
+        Format::Protobuf => match proto::GameConfig::decode(body) {
+            Ok(config) => config.try_into().map_err(|e: GameError| api_error(e.code(), e)),
+            Err(e) => Err(api_error("INVALID_PROTOBUF", e)),
+        },
+    }
+}
+
+fn read_input(body: &[u8], format: Format) -> Result<Input, ApiError> {
+    match format {
+        Format::Json => serde_json::from_slice(body).map_err(|e| api_error("INVALID_JSON", e)),
+        Format::Protobuf => match proto::Input::decode(body) {
+            Ok(proto::Input { input: Some(proto::input::Input::Key(key)) }) => Ok(Input::Key { key }),
+            Ok(input) => InputAction::try_from(input).map(Input::Action).map_err(|e| api_error(e.code(), e)),
+            Err(e) => Err(api_error("INVALID_PROTOBUF", e)),
+        },
+    }
+}
+
+// The HTTP status for an error code
+fn status_of(e: &ApiError) -> u16 {
+    match e.error {
+        "NOT_FOUND" => 404,
+        "TOO_MANY_GAMES" | "TOO_MANY_ROOMS" | "TOO_MANY_STREAMS" | "SERVER_BUSY" => 503,
+        "ROOM_TAKEN" => 409,
+        _ => 400,
+    }
+}
+
+fn failed(e: ApiError) -> Reply {
+    Reply { status: status_of(&e), body: Body::Error(e) }
+}
+
+// What the REST and gRPC APIs share, errors with the same codes for both
+
+fn build_game(config: &GameConfig) -> Result<Game, ApiError> {
+    if config.width > MAX_BOARD_SIZE || config.height > MAX_BOARD_SIZE {
+        let e = GameError::InvalidDimensions { width: config.width, height: config.height };
+        return Err(api_error(e.code(), format!("{}, at most {} cells per side", e, MAX_BOARD_SIZE)));
+    }
+    if config.opponents.len() > MAX_OPPONENTS {
+        return Err(api_error("TOO_MANY_OPPONENTS", format!("at most {} opponents per game", MAX_OPPONENTS)));
+    }
+    if config.food_count > MAX_FOOD_COUNT {
+        return Err(api_error("TOO_MUCH_FOOD", format!("at most {} pieces of food at once", MAX_FOOD_COUNT)));
+    }
+    if config.snake_length > MAX_SNAKE_LENGTH {
+        let e = GameError::InvalidSnakeLength(config.snake_length);
+        return Err(api_error(e.code(), format!("{}, at most {} segments", e, MAX_SNAKE_LENGTH)));
+    }
+    Game::from_config(config).map_err(|e| api_error(e.code(), e))
+}
+
+fn add_game(games: &Games, config: &GameConfig) -> Result<(u64, GameState), ApiError> {
+    let game = build_game(config)?;
+    let mut table = games.lock();
+    if table.sessions.len() >= MAX_GAMES {
+        return Err(api_error("TOO_MANY_GAMES", "too many games running, try again later"));
+    }
+    table.next_id += 1;
+    let id = table.next_id;
+    let state = game.state();
+    table.sessions.insert(id, Arc::new(Mutex::new(Session { game, last_input: Instant::now() })));
+    Ok((id, state))
+}
+
+fn apply_input(games: &Games, id: u64, action: InputAction) -> Result<GameState, ApiError> {
+    let session = games.session(id).ok_or_else(|| api_error("NOT_FOUND", format!("no game {}", id)))?;
+    let mut session = lock_session(&session);
+    session.game.handle_action(action);
+    session.last_input = Instant::now();
+    Ok(session.game.state())
+}
+
+// Event streams over REST and state streams over gRPC count together
+fn too_many_streams() -> ApiError {
+    api_error("TOO_MANY_STREAMS", "too many streams open, try again later")
+}
+
+fn key_action(key: &str) -> Result<InputAction, ApiError> {
+    InputAction::from_key(key).ok_or_else(|| api_error("UNKNOWN_KEY", format!("no action for key '{}'", key)))
+}
+
+fn create_game(games: &Games, body: &[u8], format: Format) -> Reply {
+    match read_config(body, format).and_then(|config| add_game(games, &config)) {
+        Ok((id, state)) => Reply { status: 201, body: Body::Created(Created { id, state }) },
+        Err(e) => failed(e),
+    }
+}
+
+fn send_input(games: &Games, id: u64, body: &[u8], format: Format) -> Reply {
+    let action = read_input(body, format).and_then(|input| match input {
+        Input::Action(action) => Ok(action),
+        Input::Key { key } => key_action(&key),
+    });
+    match action.and_then(|action| apply_input(games, id, action)) {
+        Ok(game_state) => state(200, game_state),
+        Err(e) => failed(e),
+    }
+}
+
+fn read_description(body: &[u8]) -> Result<String, ApiError> {
+    serde_json::from_slice(body).map(|d: Description| d.sdp).map_err(|e| api_error("INVALID_JSON", e))
+}
+
+fn room_reply(code: &str, room: &Room) -> Reply {
+    Reply {
+        status: 200,
+        body: Body::Room(RoomReply { room: code.to_string(), offer: room.offer.clone(), answer: room.answer.clone() }),
+    }
+}
+
+fn room_not_found(code: &str) -> Reply {
+    error(404, "NOT_FOUND", format!("no room {}", code))
+}
+
+fn open_room(games: &Games, body: &[u8]) -> Reply {
+    let offer = match read_description(body) {
+        Ok(offer) => offer,
+        Err(e) => return failed(e),
+    };
+    let mut table = games.lock();
+    if table.rooms.len() >= MAX_ROOMS {
+        return failed(api_error("TOO_MANY_ROOMS", "too many rooms open, try again later"));
+    }
+    let code = loop {
+        let code: String = (0..ROOM_CODE_LEN)
+            .map(|_| ROOM_ALPHABET[OsRng.next_u32() as usize % ROOM_ALPHABET.len()] as char)
+            .collect();
+        if !table.rooms.contains_key(&code) {
+            break code;
+        }
+    };
+    let room = Room { offer, answer: None, opened: Instant::now() };
+    let reply = Reply { status: 201, ..room_reply(&code, &room) };
+    table.rooms.insert(code, room);
+    reply
+}
+
+// A room takes one answer, a third peer can't barge in
+fn answer_room(games: &Games, code: &str, body: &[u8]) -> Reply {
+    let answer = match read_description(body) {
+        Ok(answer) => answer,
+        Err(e) => return failed(e),
+    };
+    let mut table = games.lock();
+    match table.rooms.get_mut(code) {
+        Some(room) if room.answer.is_some() => failed(api_error("ROOM_TAKEN", format!("room {} already has two players", code))),
+        Some(room) => {
+            room.answer = Some(answer);
+            room_reply(code, room)
+        }
+        None => room_not_found(code),
+    }
+}
+
+// Writes the response by hand, tiny_http would buffer a streamed body.
+// Runs until the client goes away or the game is dropped.
+fn stream_events(games: &Games, id: u64, request: Request) {
+    let mut writer = request.into_writer();
+    let head = "HTTP/1.1 200 OK\r\n\
+        Content-Type: text/event-stream\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: close\r\n\
+        Access-Control-Allow-Origin: *\r\n\r\n";
+    if writer.write_all(head.as_bytes()).and_then(|_| writer.flush()).is_err() {
+        return;
+    }
+
+    let mut last_state: Option<GameState> = None;
+    let mut last_write = Instant::now();
+    loop {
+        let state = match games.next_frame(id) {
+            Some(session) => lock_session(&session).game.state(),
+            None => return,
+        };
+        let message = if last_state.as_ref() != Some(&state) {
+            let message = format!("event: state\ndata: {}\n\n", serde_json::to_string(&state).expect("the state serializes to JSON"));
+            last_state = Some(state);
+            message
+        } else if last_write.elapsed() >= KEEP_ALIVE {
+            ": keep-alive\n\n".to_string()
+        } else {
+            continue;
+        };
+        if writer.write_all(message.as_bytes()).and_then(|_| writer.flush()).is_err() {
+            return;
+        }
+        last_write = Instant::now();
+    }
+}
+
+fn handle(games: &Games, mut request: Request) {
+    let url = request.url().split('?').next().unwrap_or_default().to_string();
+    let path: Vec<&str> = url.split('/').filter(|part| !part.is_empty()).collect();
+    let method = request.method().clone();
+    let (body_format, reply_format) = (Format::from_header(&request, "Content-Type"), Format::from_header(&request, "Accept"));
+    let mut body = Vec::new();
+    if method == Method::Post && request.as_reader().take(MAX_BODY_BYTES).read_to_end(&mut body).is_err() {
+        return;
+    }
+
+    let id = path.get(1).and_then(|id| id.parse::<u64>().ok());
+    let reply = match (&method, path.as_slice(), id) {
+        // CORS preflight, browsers send one before posting JSON from another origin
+        (Method::Options, _, _) => {
+            let response = Response::empty(204)
+                .with_header(header("Access-Control-Allow-Origin: *"))
+                .with_header(header("Access-Control-Allow-Methods: GET, POST, OPTIONS"))
+                .with_header(header("Access-Control-Allow-Headers: Content-Type"));
+            // A client that hung up doesn't need an answer
+            let _ = request.respond(response);
+            return;
+        }
+        (Method::Post, ["games"], _) => create_game(games, &body, body_format),
+        (Method::Get, ["games", _], Some(id)) => match games.session(id) {
+            Some(session) => state(200, lock_session(&session).game.state()),
+            None => not_found(id),
+        },
+        (Method::Post, ["games", _, "input"], Some(id)) => send_input(games, id, &body, body_format),
+        (Method::Get, ["games", _, "events"], Some(id)) => {
+            if games.session(id).is_none() {
+                not_found(id)
+            } else if let Some(_slot) = Slot::take(&games.streams, MAX_STREAMS) {
+                stream_events(games, id, request);
+                return;
+            } else {
+                failed(too_many_streams())
+            }
+        }
+        (Method::Post, ["rooms"], _) => open_room(games, &body),
+        (Method::Get, ["rooms", code], _) => match games.lock().rooms.get(&code.to_ascii_uppercase()) {
+            Some(room) => room_reply(&code.to_ascii_uppercase(), room),
+            None => room_not_found(code),
+        },
+        (Method::Post, ["rooms", code, "answer"], _) => answer_room(games, &code.to_ascii_uppercase(), &body),
+        _ => error(404, "NOT_FOUND", format!("no route for {} {}", method, url)),
+    };
+    // There's no message for rooms in the .proto, signaling is JSON only
+    let reply_format = if matches!(reply.body, Body::Room(_)) { Format::Json } else { reply_format };
+
+    let content_type = match reply_format {
+        Format::Json => "Content-Type: application/json".to_string(),
+        Format::Protobuf => format!("Content-Type: {}", PROTOBUF),
+    };
+    let status = reply.status;
+    let response = Response::from_data(reply.encode(reply_format))
+        .with_status_code(status)
+        .with_header(header(&content_type))
+        .with_header(header("Access-Control-Allow-Origin: *"));
+    let _ = request.respond(response);
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let args = Args::parse();
+    let server = Server::http((args.host.as_str(), args.port))?;
+    println!("Listening on http://{}", server.server_addr());
+
+    let games = Arc::new(Games::default());
+    thread::spawn({
+        let games = Arc::clone(&games);
+        move || run_clock(&games)
+    });
+    #[cfg(feature = "grpc")]
+    if let Some(port) = args.grpc_port {
+        let listener = std::net::TcpListener::bind((args.host.as_str(), port))?;
+        println!("gRPC on http://{}", listener.local_addr()?);
+        let games = Arc::clone(&games);
+        thread::spawn(move || {
+            if let Err(e) = grpc::serve(games, listener) {
+                eprintln!("the gRPC server stopped: {}", e);
+            }
+        });
+    }
+    // A thread per request, event streams stay open for as long as the game
+    for request in server.incoming_requests() {
+        let Some(slot) = Slot::take(&games.requests, MAX_REQUEST_THREADS) else {
+            let busy = failed(api_error("SERVER_BUSY", "too many requests at once, try again later"));
+            let status = busy.status;
+            let response = Response::from_data(busy.encode(Format::Json))
+                .with_status_code(status)
+                .with_header(header("Content-Type: application/json"))
+                .with_header(header("Access-Control-Allow-Origin: *"));
+            let _ = request.respond(response);
+            continue;
+        };
+        let games = Arc::clone(&games);
+        thread::spawn(move || {
+            handle(&games, request);
+            drop(slot);
+        });
+    }
+    Ok(())
+}
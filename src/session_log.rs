@@ -0,0 +1,110 @@
+// src/session_log.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{Controller, DeathCause, Game, GameError, GameMode, RunStats, SpeedCurve};
+
+// One finished round as a line of the session log: how the game was set
+// up, the seed to replay it from, how it ended and its stats. Flat and
+// camelCase, so `jq 'select(.mode == "Classic") | .score'` is enough to
+// query a log without a database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    pub started_at: u64, // Unix time in seconds, the core has no clock
+    pub duration_ms: u64, // Real time the round took, pauses included
+    pub width: i32,
+    pub height: i32,
+    pub mode: GameMode,
+    pub wrap_walls: bool,
+    pub food_count: usize,
+    pub speed: SpeedCurve,
+    pub walls: usize,
+    pub portals: usize,
+    // One entry per extra snake, like `GameConfig::opponents`
+    pub opponents: Vec<Option<Controller>>,
+    // Set when an AI played the round instead of the player
+    pub autopilot: Option<Controller>,
+    pub seed: u64, // `Game::round_seed`, the one the replay starts from
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u64,
+    pub death_cause: Option<DeathCause>,
+    pub stats: RunStats,
+}
+
+impl SessionRecord {
+    // The round `game` is playing (or just finished), started at
+    // `started_at` and `duration` ago
+    pub fn new(game: &Game, started_at: u64, duration: Duration) -> SessionRecord {
+        SessionRecord {
+            started_at,
+            duration_ms: duration.as_millis() as u64,
+            width: game.width,
+            height: game.height,
+            mode: game.mode,
+            wrap_walls: game.wrap_walls,
+            food_count: game.food_count,
+            speed: game.speed,
+            walls: game.level.walls.len(),
+            portals: game.level.portals.len(),
+            opponents: game.opponents.iter().map(|o| o.controller).collect(),
+            autopilot: game.autopilot,
+            seed: game.round_seed(),
+            score: game.score,
+            length: game.snake_length(),
+            ticks: game.tick_count(),
+            death_cause: game.death_cause,
+            stats: game.run_stats(),
+        }
+    }
+
+    // The record as one line of JSON, without the line break
+    pub fn to_json_line(&self) -> Result<String, GameError> {
+        serde_json::to_string(self).map_err(|e| GameError::Storage(e.to_string()))
+    }
+}
+
+// Appends a `SessionRecord` per round to a local file, one JSON object per
+// line (JSON Lines). The file is only ever appended to, so it survives
+// crashes halfway through a write with at most the last line lost, and
+// other tools can read it while the game runs.
+#[cfg(feature = "std")]
+pub struct SessionLog {
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl SessionLog {
+    pub fn new(path: impl Into<std::path::PathBuf>) -> SessionLog {
+        SessionLog { path: path.into() }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    // Creates the file, and its directory, on the first round
+    pub fn append(&mut self, record: &SessionRecord) -> Result<(), GameError> {
+        use std::io::Write;
+        let storage_error = |e: std::io::Error| GameError::Storage(e.to_string());
+        let mut line = record.to_json_line()?;
+        line.push('\n');
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(storage_error)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path).map_err(storage_error)?;
+        // One write per line, so rounds logged by two games at once don't interleave
+        file.write_all(line.as_bytes()).map_err(storage_error)
+    }
+
+    // Every round logged so far, oldest first. Lines that don't parse (cut
+    // short or hand-edited) are skipped, a missing file is an empty log.
+    pub fn read(&self) -> Vec<SessionRecord> {
+        let Ok(text) = std::fs::read_to_string(&self.path) else { return Vec::new() };
+        text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+    }
+}
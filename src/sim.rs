@@ -0,0 +1,155 @@
+// src/sim.rs
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Controller, DeathCause, Game, GameConfig, GameError};
+
+// How a game played by an AI went, see `simulate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimResult {
+    pub seed: u64,
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u64,
+    // `None` when the game was still going at the tick limit
+    pub death_cause: Option<DeathCause>,
+}
+
+// Plays one game from `config` with `controller` steering the player's
+// snake, ticking as fast as possible (no clock, no countdown) until it ends
+// or `max_ticks` have run. The seed replaces the config's, so running a
+// batch with seeds 0, 1, 2... always gives the same results, and the games
+// are independent so a batch can be spread over threads.
+pub fn simulate(config: &GameConfig, controller: Controller, seed: u64, max_ticks: u64) -> Result<SimResult, GameError> {
+    let config = GameConfig { seed: Some(seed), ..config.clone() };
+    let mut game = Game::from_config(&config)?;
+    game.autopilot = Some(controller);
+    game.start_game();
+    while !game.game_over && game.tick_count() < max_ticks {
+        game.tick();
+        game.drain_events().for_each(drop);
+    }
+    Ok(SimResult {
+        seed,
+        score: game.score,
+        length: game.snake.body.len(),
+        ticks: game.tick_count(),
+        death_cause: game.death_cause,
+    })
+}
+
+// Games `first_seed`, `first_seed + 1`... played with `simulate`, results in
+// seed order. With the `parallel` feature they're spread over rayon's thread
+// pool (run it inside `ThreadPool::install` to pick the number of threads),
+// each game having its own seeded RNG, so the results are the same on one
+// thread or many.
+pub fn simulate_many(
+    config: &GameConfig,
+    controller: Controller,
+    first_seed: u64,
+    games: u64,
+    max_ticks: u64,
+) -> Result<Vec<SimResult>, GameError> {
+    let play = |game: u64| simulate(config, controller, first_seed.wrapping_add(game), max_ticks);
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        use rayon::prelude::*;
+        (0..games).into_par_iter().map(play).collect()
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    {
+        (0..games).map(play).collect()
+    }
+}
+
+// Totals over a batch of games, see `simulate_batch`. Two of them merge into
+// the totals of both batches, which is how the threads' parts are put
+// together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimStats {
+    pub games: u64,
+    pub total_score: u64,
+    pub max_score: u32,
+    pub total_length: u64,
+    pub max_length: usize,
+    pub total_ticks: u64,
+    // Games that were still going at the tick limit
+    pub unfinished: u64,
+}
+
+impl SimStats {
+    pub fn add(&mut self, result: &SimResult) {
+        self.merge(&SimStats::from(result));
+    }
+
+    pub fn merge(&mut self, other: &SimStats) {
+        self.games += other.games;
+        self.total_score += other.total_score;
+        self.max_score = self.max_score.max(other.max_score);
+        self.total_length += other.total_length;
+        self.max_length = self.max_length.max(other.max_length);
+        self.total_ticks += other.total_ticks;
+        self.unfinished += other.unfinished;
+    }
+
+    // The means are 0 for an empty batch
+    pub fn mean_score(&self) -> f64 {
+        self.total_score as f64 / self.games.max(1) as f64
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.total_length as f64 / self.games.max(1) as f64
+    }
+
+    pub fn mean_ticks(&self) -> f64 {
+        self.total_ticks as f64 / self.games.max(1) as f64
+    }
+}
+
+impl From<&SimResult> for SimStats {
+    fn from(result: &SimResult) -> SimStats {
+        SimStats {
+            games: 1,
+            total_score: result.score.into(),
+            max_score: result.score,
+            total_length: result.length as u64,
+            max_length: result.length,
+            total_ticks: result.ticks,
+            unfinished: result.death_cause.is_none().into(),
+        }
+    }
+}
+
+// The same games as `simulate_many`, keeping only the totals: each thread
+// adds up its own games and the parts are merged at the end, so batches of
+// millions of games (tuning an AI, say) need no memory per game
+pub fn simulate_batch(
+    config: &GameConfig,
+    controller: Controller,
+    first_seed: u64,
+    games: u64,
+    max_ticks: u64,
+) -> Result<SimStats, GameError> {
+    let play = |game: u64| simulate(config, controller, first_seed.wrapping_add(game), max_ticks);
+    let add = |mut stats: SimStats, result: SimResult| {
+        stats.add(&result);
+        stats
+    };
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        use rayon::prelude::*;
+        (0..games)
+            .into_par_iter()
+            .map(play)
+            .try_fold(SimStats::default, |stats, result| result.map(|r| add(stats, r)))
+            .try_reduce(SimStats::default, |mut a, b| {
+                a.merge(&b);
+                Ok(a)
+            })
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    {
+        (0..games).map(play).try_fold(SimStats::default(), |stats, result| result.map(|r| add(stats, r)))
+    }
+}
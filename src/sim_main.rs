@@ -0,0 +1,189 @@
+// src/sim_main.rs
+
+// Plays many headless games with one of the built-in AIs and prints how
+// they went, to compare the AIs or check that a change to the rules or the
+// AI code didn't make them worse:
+//
+//     cargo run --release --bin snake-sim -- --controller astar --games 1000 --size 30x30 --seed 7 --csv out.csv
+//
+// Game n is played with seed `--seed + n`, so the same arguments always
+// give the same results whatever the number of threads (rayon's pool, see
+// `simulate_many`). `--csv` writes one line per game, the summary always
+// goes to stdout.
+
+// This code will only be compiled for native targets, not for wasm32.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::Parser;
+use rayon::ThreadPoolBuilder;
+use snake_game::{simulate_many, Controller, GameConfig, SimResult};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Plays headless games with an AI and reports the results")]
+struct Args {
+    #[arg(long, value_parser = parse_controller, default_value = "pathfinder", help = "AI playing the games: greedy or pathfinder (astar)")]
+    controller: Controller,
+    #[arg(long, default_value_t = 100, help = "Number of games")]
+    games: u64,
+    #[arg(long, value_parser = parse_size, default_value = "20x20", help = "Board size, e.g. 30x30")]
+    size: (i32, i32),
+    #[arg(long, default_value_t = 0, help = "Seed of the first game, the next ones count up from it")]
+    seed: u64,
+    #[arg(long, help = "Leaving the board comes back in on the other side")]
+    wrap: bool,
+    #[arg(long, default_value_t = 100_000, help = "Ticks after which a game that's still going is stopped")]
+    max_ticks: u64,
+    #[arg(long, value_name = "FILE", help = "Write one line per game to a CSV file, - for stdout")]
+    csv: Option<PathBuf>,
+    #[arg(long, help = "Threads to spread the games over, one per core by default")]
+    threads: Option<usize>,
+}
+
+// The pathfinder does a breadth-first search, which is what A* comes down
+// to on a grid where every step costs the same
+fn parse_controller(text: &str) -> Result<Controller, String> {
+    match text.to_lowercase().as_str() {
+        "greedy" => Ok(Controller::Greedy),
+        "pathfinder" | "astar" | "a*" | "bfs" => Ok(Controller::Pathfinder),
+        _ => Err(format!("unknown controller '{}', expected greedy or pathfinder", text)),
+    }
+}
+
+fn parse_size(text: &str) -> Result<(i32, i32), String> {
+    let parsed = text.split_once(['x', 'X']).and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+    match parsed {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("expected a size like 30x30, got '{}'", text)),
+    }
+}
+
+fn controller_name(controller: Controller) -> &'static str {
+    match controller {
+        Controller::Greedy => "greedy",
+        Controller::Pathfinder => "pathfinder",
+        Controller::Chaser => "chaser",
+    }
+}
+
+// Why a game ended as one word for the CSV and the summary: "wall", "self"...
+fn outcome(result: &SimResult) -> &'static str {
+    match result.death_cause {
+        Some(cause) => cause.message_key().trim_start_matches("death-"),
+        None => "tick-limit",
+    }
+}
+
+struct Stats {
+    mean: f64,
+    sd: f64,
+    min: f64,
+    median: f64,
+    max: f64,
+}
+
+fn stats(mut values: Vec<f64>) -> Stats {
+    values.sort_by(f64::total_cmp);
+    let n = values.len().max(1) as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    let median = match values.len() {
+        0 => 0.0,
+        len if len % 2 == 0 => (values[len / 2 - 1] + values[len / 2]) / 2.0,
+        len => values[len / 2],
+    };
+    Stats {
+        mean,
+        sd: variance.sqrt(),
+        min: values.first().copied().unwrap_or(0.0),
+        median,
+        max: values.last().copied().unwrap_or(0.0),
+    }
+}
+
+fn print_stats(name: &str, values: Vec<f64>) {
+    let s = stats(values);
+    println!(
+        "{:<12} mean {:.1}  sd {:.1}  min {}  median {}  max {}",
+        name, s.mean, s.sd, s.min, s.median, s.max
+    );
+}
+
+fn write_csv(out: &mut dyn Write, results: &[SimResult]) -> io::Result<()> {
+    writeln!(out, "game,seed,score,length,ticks,outcome")?;
+    for (game, result) in results.iter().enumerate() {
+        writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            game,
+            result.seed,
+            result.score,
+            result.length,
+            result.ticks,
+            outcome(result)
+        )?;
+    }
+    out.flush()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let (width, height) = args.size;
+    let config = GameConfig { width, height, wrap_walls: args.wrap, ..GameConfig::default() };
+    // 0 threads is rayon's default, one per core
+    let pool = ThreadPoolBuilder::new().num_threads(args.threads.unwrap_or(0)).build()?;
+    let threads = pool.current_num_threads();
+
+    let start = Instant::now();
+    let results = pool.install(|| simulate_many(&config, args.controller, args.seed, args.games, args.max_ticks))?;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    match args.csv.as_deref() {
+        Some(path) if path.as_os_str() == "-" => write_csv(&mut io::stdout().lock(), &results)?,
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+            write_csv(&mut BufWriter::new(file), &results)?;
+        }
+        None => {}
+    }
+
+    let last_seed = args.seed.wrapping_add(args.games.saturating_sub(1));
+    println!(
+        "{:<12} {} ({}, {}x{}, seeds {}..={})",
+        "games",
+        results.len(),
+        controller_name(args.controller),
+        width,
+        height,
+        args.seed,
+        last_seed
+    );
+    print_stats("score", results.iter().map(|r| r.score as f64).collect());
+    print_stats("length", results.iter().map(|r| r.length as f64).collect());
+    print_stats("ticks", results.iter().map(|r| r.ticks as f64).collect());
+
+    // Most common outcome first
+    let mut outcomes: Vec<(&str, usize)> = Vec::new();
+    for result in &results {
+        match outcomes.iter_mut().find(|(name, _)| *name == outcome(result)) {
+            Some((_, count)) => *count += 1,
+            None => outcomes.push((outcome(result), 1)),
+        }
+    }
+    outcomes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let outcomes: Vec<String> = outcomes.iter().map(|(name, count)| format!("{} {}", name, count)).collect();
+    println!("{:<12} {}", "outcomes", outcomes.join("  "));
+    println!(
+        "{:<12} {:.2} s ({:.0} games/s, {} threads)",
+        "elapsed",
+        elapsed,
+        results.len() as f64 / elapsed.max(f64::EPSILON),
+        threads
+    );
+    Ok(())
+}
@@ -0,0 +1,146 @@
+// src/skin.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, GameError, Rgb, RunStats, Theme};
+
+// The skins the frontends ship with, see skins.json. A new one is a pattern,
+// an id and a name key added to `Language`, no code.
+const BUILTIN: &str = include_str!("skins.json");
+
+// How the player's snake body is colored, part of the `Theme`. The head
+// always keeps the theme's head color so it stands out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SkinPattern {
+    // The theme's body color
+    #[default]
+    Solid,
+    // From `from` behind the head to `to` at the tail
+    Gradient { from: Rgb, to: Rgb },
+    // Bands of `width` segments, `a` first
+    Striped { a: Rgb, b: Rgb, width: u8 },
+    // Every hue along the body, cycling toward the tail once every
+    // `period` ticks
+    Rainbow { period: u32 },
+}
+
+// A round reaching one of these unlocks the skin, see
+// `Persistence::unlock_skins`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Achievement {
+    Score { points: u32 },
+    Length { segments: usize },
+    NearMisses { count: u32 },
+}
+
+impl Achievement {
+    pub fn reached(self, stats: &RunStats) -> bool {
+        match self {
+            Achievement::Score { points } => stats.score >= points,
+            Achievement::Length { segments } => stats.max_length >= segments,
+            Achievement::NearMisses { count } => stats.near_misses >= count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Skin {
+    pub id: String,
+    // Translation key of the name shown when picking it
+    pub name: String,
+    pub pattern: SkinPattern,
+    // `None` for skins everyone has
+    #[serde(default)]
+    pub unlock: Option<Achievement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Skins {
+    pub skins: Vec<Skin>,
+}
+
+impl Skins {
+    pub fn from_json(text: &str) -> Result<Skins, GameError> {
+        serde_json::from_str(text).map_err(|e| GameError::Storage(e.to_string()))
+    }
+
+    pub fn builtin() -> Skins {
+        Skins::from_json(BUILTIN).expect("the built-in skins are valid")
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Skin> {
+        self.skins.iter().find(|skin| skin.id == id)
+    }
+
+    // The skins a player with `unlocked` (see `Persistence::load_unlocked_skins`)
+    // can pick, in the order of the list
+    pub fn available<'a>(&'a self, unlocked: &'a [String]) -> impl Iterator<Item = &'a Skin> + 'a {
+        self.skins.iter().filter(|skin| skin.unlock.is_none() || unlocked.contains(&skin.id))
+    }
+
+    // The pattern of `id`, solid when it doesn't exist or is still locked
+    // (a hand-edited settings file may ask for one)
+    pub fn pattern(&self, id: &str, unlocked: &[String]) -> SkinPattern {
+        self.available(unlocked).find(|skin| skin.id == id).map_or(SkinPattern::Solid, |skin| skin.pattern)
+    }
+}
+
+impl Theme {
+    // Color of segment `index` (0 is the head) of the player's snake of
+    // `length` segments at tick `tick`, only the rainbow moves with it
+    pub fn segment_color(&self, index: usize, length: usize, tick: u64) -> Rgb {
+        if index == 0 {
+            return self.head;
+        }
+        match self.skin {
+            SkinPattern::Solid => self.body,
+            SkinPattern::Gradient { from, to } => {
+                let amount = match length {
+                    0..=2 => 0.0,
+                    _ => (index - 1) as f32 / (length - 2) as f32,
+                };
+                from.mix(to, amount)
+            }
+            SkinPattern::Striped { a, b, width } => match (index - 1) / (width.max(1) as usize) % 2 {
+                0 => a,
+                _ => b,
+            },
+            SkinPattern::Rainbow { period } => {
+                let shift = (tick % period.max(1) as u64) as f32 / period.max(1) as f32;
+                hue(index as f32 / 12.0 - shift)
+            }
+        }
+    }
+}
+
+// A bright color of hue `turns` (0 red, 1/3 green, 2/3 blue, wrapping around)
+fn hue(turns: f32) -> Rgb {
+    let h = (turns - (turns as i32) as f32 + 1.0) * 6.0 % 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let channel = |c: f32| (64.0 + c * 191.0) as u8;
+    Rgb::new(channel(r), channel(g), channel(b))
+}
+
+impl Game {
+    // Color of segment `index` of snake `snake`: the player's in the skin
+    // (see `Theme::segment_color`), opponents in their plain colors
+    pub fn segment_color(&self, snake: usize, index: usize) -> Rgb {
+        match snake {
+            0 => self.theme.segment_color(index, self.snake.body.len(), self.tick_count()),
+            _ if index == 0 => self.theme.opponent_head,
+            _ => self.theme.opponent_body,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// src/snapshot.rs
+
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{Body, BossFight, CheatInput, DeathCause, Direction, Food, Game, Opponent, Point, ReplayInput, RunStats};
+
+// The part of a `Game` that changes from tick to tick. Board size, walls,
+// spawn and speed curve never change during a game, so they're left out.
+// The RNG's position and the round's inputs do come along: a restored
+// round spawns the same food as the original and its `replay` still plays
+// from the top. `CompactState` keeps the same, in less space.
+//
+// A snapshot can only be restored into the game it was taken from (or one
+// built from the same level). Snapshots serialize so a running game can be
+// autosaved, see `Persistence`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) body: Body,
+    pub(crate) direction: Direction,
+    pub(crate) foods: Vec<Food>,
+    pub(crate) score: u32,
+    pub(crate) game_over: bool,
+    pub(crate) game_started: bool,
+    pub(crate) paused: bool,
+    pub(crate) boosting: bool,
+    pub(crate) pending_turn: Option<Direction>,
+    pub(crate) slow_ticks: u32,
+    pub(crate) accumulator: Duration,
+    pub(crate) death_cause: Option<DeathCause>,
+    pub(crate) tick_count: u64,
+    pub(crate) game_time: Duration,
+    pub(crate) opponents: Vec<Opponent>,
+    // Missing from autosaves made before there were stats
+    #[serde(default)]
+    pub(crate) stats: RunStats,
+    #[serde(default)]
+    pub(crate) boss_fight: Option<BossFight>,
+    #[serde(default)]
+    pub(crate) bosses: u32,
+    // Autosaves from before these were kept have a round that can't be
+    // replayed, so they don't load at all
+    pub(crate) rng_seed: [u8; 32],
+    pub(crate) rng_word_pos: u128,
+    pub(crate) round_seed: u64,
+    pub(crate) recording: Vec<ReplayInput>,
+    pub(crate) cheats: Vec<CheatInput>,
+    // `Game::level_fingerprint` of the game it was taken from, same story
+    // for autosaves without it
+    pub(crate) level_fingerprint: u64,
+}
+
+impl Snapshot {
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn snake_len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // Whether it was taken from a game with `game`'s level and every segment
+    // and food lies on a free cell of its board, i.e. it's safe to `restore`
+    // a snapshot loaded from storage into it
+    pub fn fits(&self, game: &Game) -> bool {
+        let free = |p: &Point| {
+            p.x >= 0 && p.x < game.width && p.y >= 0 && p.y < game.height && !game.level.walls.contains(p)
+        };
+        self.level_fingerprint == game.level_fingerprint()
+            && !self.body.is_empty()
+            && self.body.iter().all(free)
+            && self.foods.iter().all(|f| free(&f.pos))
+            && self.opponents.iter().all(|o| o.snake.body.iter().all(free))
+    }
+}
@@ -0,0 +1,140 @@
+// src/sound.rs
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{Game, GameEvent};
+
+// One beep of a sound effect or note of the music, 0 Hz is a rest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    pub frequency_hz: f32,
+    pub duration_ms: u32,
+}
+
+// The game's sound effects, described as short tone sequences instead of
+// audio files so every frontend (Web Audio, native) plays exactly the same
+// thing without shipping assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    Turn,
+    Eat,
+    PowerUp,
+    LevelUp,
+    Crash,
+}
+
+// Loudness of a tone as it starts, it fades to silence by its end
+const PEAK_GAIN: f32 = 0.2;
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 5] =
+        [SoundEffect::Turn, SoundEffect::Eat, SoundEffect::PowerUp, SoundEffect::LevelUp, SoundEffect::Crash];
+
+    // Which effect (if any) a game event should trigger
+    pub fn for_event(event: &GameEvent) -> Option<SoundEffect> {
+        match event {
+            GameEvent::Turned { .. } => Some(SoundEffect::Turn),
+            GameEvent::FoodEaten { .. } => Some(SoundEffect::Eat),
+            GameEvent::PowerUpCollected { .. } => Some(SoundEffect::PowerUp),
+            GameEvent::LevelUp { .. } => Some(SoundEffect::LevelUp),
+            GameEvent::GameOver { .. } => Some(SoundEffect::Crash),
+            _ => None,
+        }
+    }
+
+    pub fn tones(self) -> &'static [Tone] {
+        match self {
+            SoundEffect::Turn => &TURN,
+            SoundEffect::Eat => &EAT,
+            SoundEffect::PowerUp => &POWER_UP,
+            SoundEffect::LevelUp => &LEVEL_UP,
+            SoundEffect::Crash => &CRASH,
+        }
+    }
+
+    pub fn to_wav(self, sample_rate: u32) -> Vec<u8> {
+        tones_to_wav(self.tones(), sample_rate)
+    }
+}
+
+// Tones as a 16-bit mono WAV file, for frontends that play audio files
+// rather than synthesizing. Same square waves and fade out as the Web Audio
+// version.
+pub fn tones_to_wav(tones: &[Tone], sample_rate: u32) -> Vec<u8> {
+    let mut samples: Vec<i16> = Vec::new();
+    for tone in tones {
+        let len = (sample_rate as u64 * tone.duration_ms as u64 / 1000) as usize;
+        for i in 0..len {
+            let gain = if tone.frequency_hz > 0.0 { PEAK_GAIN * (1.0 - i as f32 / len as f32) } else { 0.0 };
+            // Which half of the wave period sample `i` falls in
+            let half_periods = (i as f32 * tone.frequency_hz * 2.0 / sample_rate as f32) as u64;
+            let value = if half_periods.is_multiple_of(2) { gain } else { -gain };
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+    }
+
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // Size of the format chunk
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // Bytes per second
+    wav.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample
+    wav.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+const fn tone(frequency_hz: f32, duration_ms: u32) -> Tone {
+    Tone { frequency_hz, duration_ms }
+}
+
+impl Game {
+    // How fast the background music should play, 1.0 being `MUSIC_LOOP` as
+    // written. It steps up every 10 segments of snake and again in the last
+    // 10 seconds of a timed game. Steps rather than a smooth curve, so a
+    // frontend only has to restart the loop now and then.
+    pub fn music_tempo(&self) -> f32 {
+        let growth = (self.snake.body.len() / 10).min(4) as f32 * 0.1;
+        let hurry = if self.time_left().is_some_and(|t| t <= Duration::from_secs(10)) { 0.3 } else { 0.0 };
+        1.0 + growth + hurry
+    }
+}
+
+// Background music, meant to be looped: an A minor / G major arpeggio
+pub const MUSIC_LOOP: [Tone; 16] = [
+    tone(440.0, 180),
+    tone(523.0, 180),
+    tone(659.0, 180),
+    tone(523.0, 180),
+    tone(440.0, 180),
+    tone(330.0, 180),
+    tone(440.0, 180),
+    tone(0.0, 180),
+    tone(392.0, 180),
+    tone(494.0, 180),
+    tone(587.0, 180),
+    tone(494.0, 180),
+    tone(392.0, 180),
+    tone(294.0, 180),
+    tone(392.0, 180),
+    tone(0.0, 180),
+];
+
+// A short tick for a turn, a quick high blip for an apple, a rising pair
+// for power-ups, a major arpeggio for a new speed level and a falling low
+// buzz when the snake dies
+const TURN: [Tone; 1] = [tone(1200.0, 15)];
+const EAT: [Tone; 1] = [tone(880.0, 60)];
+const POWER_UP: [Tone; 2] = [tone(660.0, 60), tone(990.0, 90)];
+const LEVEL_UP: [Tone; 3] = [tone(523.0, 80), tone(659.0, 80), tone(784.0, 120)];
+const CRASH: [Tone; 2] = [tone(220.0, 120), tone(147.0, 200)];
@@ -0,0 +1,130 @@
+// src/speed.rs
+
+use alloc::format;
+use core::time::Duration;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::GameError;
+
+// The shortest tick a curve may ask for. At 0 the clock would run ticks
+// forever without catching up.
+pub const MIN_TICK_INTERVAL: Duration = Duration::from_millis(1);
+
+// How fast the game runs. The snake starts at `base_interval` per tick and
+// gets `step` faster every `points_per_level` points, never going below
+// `min_interval`. Durations are (de)serialized as whole milliseconds, and
+// deserializing checks the curve, see `validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "UncheckedCurve")]
+pub struct SpeedCurve {
+    #[serde(rename = "baseIntervalMs", with = "millis")]
+    pub base_interval: Duration,
+    #[serde(rename = "stepMs", with = "millis")]
+    pub step: Duration,
+    #[serde(rename = "minIntervalMs", with = "millis")]
+    pub min_interval: Duration,
+    pub points_per_level: u32,
+}
+
+mod millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u64(d.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        u64::deserialize(d).map(Duration::from_millis)
+    }
+}
+
+// A curve as read, before `validate` said it's usable
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct UncheckedCurve {
+    #[serde(rename = "baseIntervalMs", with = "millis")]
+    base_interval: Duration,
+    #[serde(rename = "stepMs", with = "millis")]
+    step: Duration,
+    #[serde(rename = "minIntervalMs", with = "millis")]
+    min_interval: Duration,
+    points_per_level: u32,
+}
+
+impl Default for UncheckedCurve {
+    fn default() -> Self {
+        let SpeedCurve { base_interval, step, min_interval, points_per_level } = SpeedCurve::default();
+        UncheckedCurve { base_interval, step, min_interval, points_per_level }
+    }
+}
+
+impl TryFrom<UncheckedCurve> for SpeedCurve {
+    type Error = GameError;
+
+    fn try_from(c: UncheckedCurve) -> Result<SpeedCurve, GameError> {
+        let curve = SpeedCurve {
+            base_interval: c.base_interval,
+            step: c.step,
+            min_interval: c.min_interval,
+            points_per_level: c.points_per_level,
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+}
+
+impl Default for SpeedCurve {
+    // 10 ticks per second at the start, same as the original fixed speed
+    fn default() -> Self {
+        SpeedCurve {
+            base_interval: Duration::from_millis(100),
+            step: Duration::from_millis(5),
+            min_interval: Duration::from_millis(50),
+            points_per_level: 5,
+        }
+    }
+}
+
+impl SpeedCurve {
+    // A curve that never speeds up
+    pub fn constant(interval: Duration) -> SpeedCurve {
+        SpeedCurve {
+            base_interval: interval,
+            step: Duration::ZERO,
+            min_interval: interval,
+            points_per_level: 1,
+        }
+    }
+
+    // Whether the game can run on this curve: no tick shorter than
+    // `MIN_TICK_INTERVAL`, and a start no faster than the floor. Checked when
+    // a curve is read and again by `Game::from_config` and friends, so curves
+    // built in code (protobuf, FFI, mobile configs) don't get past either.
+    pub fn validate(&self) -> Result<(), GameError> {
+        if self.min_interval < MIN_TICK_INTERVAL {
+            return Err(GameError::InvalidSpeedCurve(format!(
+                "minIntervalMs {} is below {} ms",
+                self.min_interval.as_millis(),
+                MIN_TICK_INTERVAL.as_millis()
+            )));
+        }
+        if self.base_interval < self.min_interval {
+            return Err(GameError::InvalidSpeedCurve(format!(
+                "baseIntervalMs {} is below minIntervalMs {}",
+                self.base_interval.as_millis(),
+                self.min_interval.as_millis()
+            )));
+        }
+        Ok(())
+    }
+
+    // Levels start at 1
+    pub fn level_for_score(&self, score: u32) -> u32 {
+        1 + score / self.points_per_level.max(1)
+    }
+
+    pub fn interval_for_level(&self, level: u32) -> Duration {
+        let speedup = self.step.saturating_mul(level.saturating_sub(1));
+        self.base_interval.saturating_sub(speedup).max(self.min_interval)
+    }
+}
@@ -0,0 +1,214 @@
+// src/sprite.rs
+
+use alloc::vec::Vec;
+
+use crate::{Direction, Game, Point};
+
+// Tiles are tiny pixel art, scale them by whole numbers with nearest
+// neighbour filtering to keep them crisp
+pub const TILE_SIZE: u32 = 10;
+
+// The pieces of the texture atlas, in atlas order (left to right)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Head,   // Facing up, the body attaches at the bottom
+    Body,   // Vertical
+    Corner, // Joins the bottom and right edges
+    Tail,   // The next segment is above
+    Apple,
+    Wall,
+}
+
+impl Tile {
+    pub const ALL: [Tile; 6] = [Tile::Head, Tile::Body, Tile::Corner, Tile::Tail, Tile::Apple, Tile::Wall];
+
+    // Position in the atlas, in tiles
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    fn pixels(self) -> &'static [&'static str; TILE_SIZE as usize] {
+        match self {
+            Tile::Head => &HEAD,
+            Tile::Body => &BODY,
+            Tile::Corner => &CORNER,
+            Tile::Tail => &TAIL,
+            Tile::Apple => &APPLE,
+            Tile::Wall => &WALL,
+        }
+    }
+
+    // Grey level of a pixel, `None` where the tile is transparent
+    pub(crate) fn shade(self, x: usize, y: usize) -> Option<u8> {
+        match self.pixels()[y].as_bytes()[x] {
+            b'#' => Some(255),
+            b'+' => Some(190),
+            b'o' => Some(70),
+            _ => None,
+        }
+    }
+}
+
+// A tile and how many clockwise quarter turns to draw it with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sprite {
+    pub tile: Tile,
+    pub quarter_turns: u8,
+}
+
+// The whole atlas as RGBA pixels, `TILE_SIZE` high and one tile wide per
+// `Tile`. Everything is white or grey so frontends can tint the tiles with
+// the theme colors.
+pub fn atlas_rgba() -> Vec<u8> {
+    let width = TILE_SIZE as usize * Tile::ALL.len();
+    let mut rgba = Vec::with_capacity(width * TILE_SIZE as usize * 4);
+    for row in 0..TILE_SIZE as usize {
+        for tile in Tile::ALL {
+            for x in 0..TILE_SIZE as usize {
+                let (shade, alpha) = tile.shade(x, row).map_or((0, 0), |shade| (shade, 255));
+                rgba.extend_from_slice(&[shade, shade, shade, alpha]);
+            }
+        }
+    }
+    rgba
+}
+
+fn quarter_turns(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Right => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+    }
+}
+
+impl Game {
+    // Direction of the cell next door, seen from `from`. Cells on opposite
+    // edges are neighbours when walls wrap; `None` if the snake jumped
+    // through a portal.
+    fn toward(&self, from: Point, to: Point) -> Option<Direction> {
+        let wrap = |d: i32, size: i32| if d == size - 1 { -1 } else if d == 1 - size { 1 } else { d };
+        match (wrap(to.x - from.x, self.width), wrap(to.y - from.y, self.height)) {
+            (0, -1) => Some(Direction::Up),
+            (1, 0) => Some(Direction::Right),
+            (0, 1) => Some(Direction::Down),
+            (-1, 0) => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    // The sprite for each segment of a snake, head first: a head facing the
+    // way it moves, straight pieces, corners where it bends and a tail
+    pub fn snake_sprites(&self, index: usize) -> Vec<Sprite> {
+        let body = self.snake_body(index);
+        let heading = self.snake_direction(index).unwrap_or(Direction::Up);
+        let straight = |d: Option<Direction>| Sprite {
+            tile: Tile::Body,
+            quarter_turns: if matches!(d, Some(Direction::Left | Direction::Right)) { 1 } else { 0 },
+        };
+        (0..body.len())
+            .map(|i| {
+                let ahead = i.checked_sub(1).and_then(|j| self.toward(body[i], body[j]));
+                let behind = body.get(i + 1).and_then(|&next| self.toward(body[i], next));
+                if i == 0 {
+                    let facing = body.get(1).and_then(|&neck| self.toward(neck, body[0])).unwrap_or(heading);
+                    return Sprite { tile: Tile::Head, quarter_turns: quarter_turns(facing) };
+                }
+                if i == body.len() - 1 {
+                    return match ahead {
+                        Some(d) => Sprite { tile: Tile::Tail, quarter_turns: quarter_turns(d) },
+                        None => straight(None),
+                    };
+                }
+                let (Some(a), Some(b)) = (ahead, behind) else { return straight(ahead.or(behind)) };
+                let turns = match (a, b) {
+                    (Direction::Down, Direction::Right) | (Direction::Right, Direction::Down) => 0,
+                    (Direction::Down, Direction::Left) | (Direction::Left, Direction::Down) => 1,
+                    (Direction::Up, Direction::Left) | (Direction::Left, Direction::Up) => 2,
+                    (Direction::Up, Direction::Right) | (Direction::Right, Direction::Up) => 3,
+                    _ => return straight(Some(a)),
+                };
+                Sprite { tile: Tile::Corner, quarter_turns: turns }
+            })
+            .collect()
+    }
+}
+
+// '#' white, '+' light grey, 'o' dark grey, anything else transparent
+const HEAD: [&str; TILE_SIZE as usize] = [
+    "...####...",
+    "..######..",
+    ".########.",
+    ".#o####o#.",
+    ".#o####o#.",
+    ".########.",
+    ".########.",
+    ".########.",
+    ".########.",
+    ".########.",
+];
+
+const BODY: [&str; TILE_SIZE as usize] = [
+    ".########.",
+    ".###++###.",
+    ".########.",
+    ".########.",
+    ".###++###.",
+    ".########.",
+    ".########.",
+    ".###++###.",
+    ".########.",
+    ".########.",
+];
+
+const CORNER: [&str; TILE_SIZE as usize] = [
+    "..........",
+    "....######",
+    "...#######",
+    "..########",
+    ".#########",
+    ".####+####",
+    ".#########",
+    ".#########",
+    ".########.",
+    ".########.",
+];
+
+const TAIL: [&str; TILE_SIZE as usize] = [
+    ".########.",
+    ".###++###.",
+    ".########.",
+    "..######..",
+    "..######..",
+    "...####...",
+    "...####...",
+    "....##....",
+    "....##....",
+    "..........",
+];
+
+const APPLE: [&str; TILE_SIZE as usize] = [
+    ".....oo...",
+    "....o.....",
+    "..###.##..",
+    ".#+######.",
+    ".#+######.",
+    ".########.",
+    ".########.",
+    ".########.",
+    "..######..",
+    "...#..#...",
+];
+
+const WALL: [&str; TILE_SIZE as usize] = [
+    "####o#####",
+    "####o#####",
+    "####o#####",
+    "####o#####",
+    "oooooooooo",
+    "#########o",
+    "#########o",
+    "#########o",
+    "#########o",
+    "oooooooooo",
+];
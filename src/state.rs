@@ -0,0 +1,105 @@
+// src/state.rs
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Controller, DeathCause, Direction, Food, Game, GameMode, Point};
+
+// Where a game is in its lifecycle, derived from the individual flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStatus {
+    NotStarted,
+    Countdown, // Started, the snake moves once it's over
+    Running,
+    Paused,
+    Dying, // The game is over, the death animation is still playing
+    GameOver,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnakeState {
+    pub body: Vec<Point>,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpponentState {
+    pub body: Vec<Point>,
+    pub direction: Direction,
+    pub score: u32,
+    pub alive: bool,
+    pub controller: Option<Controller>,
+}
+
+// A plain-data copy of everything a renderer or a remote client needs to
+// know about a game. Used to send the whole state to JavaScript (or over
+// the wire) in one go instead of one getter per field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    pub width: i32,
+    pub height: i32,
+    pub snake: SnakeState,
+    pub food: Vec<Food>,
+    pub obstacles: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub score: u32,
+    pub mode: GameMode,
+    pub wrap_walls: bool,
+    pub status: GameStatus,
+    pub death_cause: Option<DeathCause>, // Set once the game is over
+    pub tick: u64,
+    pub speed_level: u32,
+    pub boosting: bool,
+    pub slow_ticks_left: u32,
+    pub opponents: Vec<OpponentState>,
+}
+
+impl Game {
+    pub fn status(&self) -> GameStatus {
+        if !self.game_started {
+            GameStatus::NotStarted
+        } else if self.game_over {
+            if self.death_progress().is_some() { GameStatus::Dying } else { GameStatus::GameOver }
+        } else if self.paused {
+            GameStatus::Paused
+        } else if self.countdown().is_some() {
+            GameStatus::Countdown
+        } else {
+            GameStatus::Running
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        GameState {
+            width: self.width,
+            height: self.height,
+            snake: SnakeState {
+                body: self.snake.body.iter().copied().collect(),
+                direction: self.snake.direction,
+            },
+            food: self.foods.clone(),
+            obstacles: self.level.walls.clone(),
+            portals: self.level.portals.clone(),
+            score: self.score,
+            mode: self.mode,
+            wrap_walls: self.wrap_walls,
+            status: self.status(),
+            death_cause: self.death_cause,
+            tick: self.tick_count(),
+            speed_level: self.speed_level(),
+            boosting: self.boosting,
+            slow_ticks_left: self.slow_ticks,
+            opponents: self
+                .opponents
+                .iter()
+                .map(|o| OpponentState {
+                    body: o.snake.body.iter().copied().collect(),
+                    direction: o.snake.direction,
+                    score: o.score,
+                    alive: o.alive,
+                    controller: o.controller,
+                })
+                .collect(),
+        }
+    }
+}
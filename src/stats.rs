@@ -0,0 +1,75 @@
+// src/stats.rs
+
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::FoodKind;
+
+// Ticks without a turn before the player counts as idle, about two seconds
+// at the starting speed
+pub const IDLE_AFTER_TICKS: u64 = 20;
+
+// Dodges only count as near misses at this tick interval or faster: from
+// speed level 5 on the default curve, or boosting
+pub const NEAR_MISS_INTERVAL: Duration = Duration::from_millis(80);
+
+// How the player's round went, counted as it's played and finished when it
+// ends, see `Game::run_stats`. Replays carry the stats of their round and
+// playing one back counts them again, so a leaderboard can check a claim
+// the way `snake-wasi verify` checks the score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStats {
+    // Ticks the snake moved, the one it died on included
+    pub ticks: u64,
+    pub apples: u32,
+    pub bonus: u32,
+    pub slow_downs: u32,
+    // Times the snake changed direction
+    pub turns: u32,
+    pub max_length: usize,
+    // Fatal cells dodged at speed, see `GameEvent::NearMiss`
+    pub near_misses: u32,
+    // Ticks run once `IDLE_AFTER_TICKS` had gone by without a turn
+    pub idle_ticks: u64,
+    // Cheats used, see `Cheat`. Missing from stats saved before there were any.
+    #[serde(default)]
+    pub cheats: u32,
+    // Set when the round ended, see `Game::death_cause` for how
+    pub finished: bool,
+    pub score: u32,
+    pub length: usize,
+    // Ticks since the player last asked for a turn
+    pub(crate) since_turn: u64,
+}
+
+impl RunStats {
+    // Food of `kind` the snake ate
+    pub fn eaten(&self, kind: FoodKind) -> u32 {
+        match kind {
+            FoodKind::Apple => self.apples,
+            FoodKind::Bonus => self.bonus,
+            FoodKind::SlowDown => self.slow_downs,
+        }
+    }
+
+    pub fn food_eaten(&self) -> u32 {
+        self.apples + self.bonus + self.slow_downs
+    }
+
+    pub(crate) fn count_eaten(&mut self, kind: FoodKind) {
+        match kind {
+            FoodKind::Apple => self.apples += 1,
+            FoodKind::Bonus => self.bonus += 1,
+            FoodKind::SlowDown => self.slow_downs += 1,
+        }
+    }
+
+    // A tick ran, `turned` when the player asked for a turn before it
+    pub(crate) fn count_tick(&mut self, turned: bool) {
+        self.ticks += 1;
+        self.since_turn = if turned { 0 } else { self.since_turn + 1 };
+        if self.since_turn >= IDLE_AFTER_TICKS {
+            self.idle_ticks += 1;
+        }
+    }
+}
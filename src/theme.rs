@@ -0,0 +1,193 @@
+// src/theme.rs
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{CellKind, FoodKind, GameError, SkinPattern};
+
+// A color as written in CSS and config files: "#rrggbb"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    // Accepts "#rrggbb" and "rrggbb"
+    pub fn from_hex(text: &str) -> Option<Rgb> {
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Rgb { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+    }
+
+    // Blends toward `other`, 0.0 keeps this color and 1.0 gives `other`
+    pub fn mix(self, other: Rgb, amount: f32) -> Rgb {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount + 0.5) as u8;
+        Rgb { r: channel(self.r, other.r), g: channel(self.g, other.g), b: channel(self.b, other.b) }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Rgb, D::Error> {
+        let text = String::deserialize(d)?;
+        Rgb::from_hex(&text).ok_or_else(|| serde::de::Error::custom(format!("invalid color '{}'", text)))
+    }
+}
+
+// The built-in palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ThemePreset {
+    #[default]
+    Classic,
+    Neon,
+    // Okabe-Ito colors, told apart with every common kind of color blindness
+    ColorblindSafe,
+}
+
+// What is drawn behind the board to help judge distances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum BoardPattern {
+    #[default]
+    Plain,
+    Grid,         // Thin lines between cells
+    Checkerboard, // Every other cell slightly tinted
+}
+
+// Every color a renderer needs. Frontends draw with these instead of
+// hard-coding their own, so the native and web builds look the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Theme {
+    pub background: Rgb,
+    pub head: Rgb,
+    pub body: Rgb,
+    pub opponent_head: Rgb,
+    pub opponent_body: Rgb,
+    pub apple: Rgb,
+    pub bonus: Rgb,
+    pub slow_down: Rgb,
+    pub obstacle: Rgb,
+    pub portal: Rgb,
+    pub text: Rgb,
+    // The player's body, see `segment_color`
+    #[serde(default)]
+    pub skin: SkinPattern,
+}
+
+impl Theme {
+    pub const fn preset(preset: ThemePreset) -> Theme {
+        match preset {
+            ThemePreset::Classic => Theme {
+                background: Rgb::new(51, 51, 51),
+                head: Rgb::new(160, 255, 160),
+                body: Rgb::new(0, 255, 0),
+                opponent_head: Rgb::new(255, 200, 120),
+                opponent_body: Rgb::new(255, 140, 0),
+                apple: Rgb::new(255, 0, 0),
+                bonus: Rgb::new(255, 215, 0),
+                slow_down: Rgb::new(0, 200, 255),
+                obstacle: Rgb::new(128, 128, 128),
+                portal: Rgb::new(80, 120, 255),
+                text: Rgb::new(255, 255, 255),
+                skin: SkinPattern::Solid,
+            },
+            ThemePreset::Neon => Theme {
+                background: Rgb::new(10, 10, 24),
+                head: Rgb::new(180, 255, 255),
+                body: Rgb::new(0, 230, 255),
+                opponent_head: Rgb::new(255, 170, 255),
+                opponent_body: Rgb::new(255, 0, 200),
+                apple: Rgb::new(255, 40, 120),
+                bonus: Rgb::new(255, 255, 0),
+                slow_down: Rgb::new(120, 80, 255),
+                obstacle: Rgb::new(60, 60, 110),
+                portal: Rgb::new(0, 255, 140),
+                text: Rgb::new(230, 230, 255),
+                skin: SkinPattern::Solid,
+            },
+            ThemePreset::ColorblindSafe => Theme {
+                background: Rgb::new(30, 30, 30),
+                head: Rgb::new(120, 220, 190),
+                body: Rgb::new(0, 158, 115),
+                opponent_head: Rgb::new(255, 210, 120),
+                opponent_body: Rgb::new(230, 159, 0),
+                apple: Rgb::new(213, 94, 0),
+                bonus: Rgb::new(240, 228, 66),
+                slow_down: Rgb::new(86, 180, 233),
+                obstacle: Rgb::new(150, 150, 150),
+                portal: Rgb::new(204, 121, 167),
+                text: Rgb::new(255, 255, 255),
+                skin: SkinPattern::Solid,
+            },
+        }
+    }
+
+    // A custom palette from a theme file, every color as "#rrggbb"
+    pub fn from_json(text: &str) -> Result<Theme, GameError> {
+        serde_json::from_str(text).map_err(|e| GameError::Storage(e.to_string()))
+    }
+
+    // Grid lines and checkerboard squares are the background nudged toward
+    // the text color, so they stay faint on any palette
+    pub fn grid_color(&self) -> Rgb {
+        self.background.mix(self.text, 0.15)
+    }
+
+    pub fn checker_color(&self) -> Rgb {
+        self.background.mix(self.text, 0.06)
+    }
+
+    // Fill color of a cell, `None` for empty cells (just background)
+    pub fn cell_color(&self, kind: CellKind) -> Option<Rgb> {
+        Some(match kind {
+            CellKind::Empty => return None,
+            CellKind::SnakeHead => self.head,
+            CellKind::SnakeBody => self.body,
+            CellKind::OpponentHead(_) => self.opponent_head,
+            CellKind::OpponentBody(_) => self.opponent_body,
+            CellKind::Food(FoodKind::Apple) => self.apple,
+            CellKind::Food(FoodKind::Bonus) => self.bonus,
+            CellKind::Food(FoodKind::SlowDown) => self.slow_down,
+            CellKind::Obstacle => self.obstacle,
+            CellKind::Portal => self.portal,
+        })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset(ThemePreset::Classic)
+    }
+}
+
+impl From<ThemePreset> for Theme {
+    fn from(preset: ThemePreset) -> Self {
+        Theme::preset(preset)
+    }
+}
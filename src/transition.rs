@@ -0,0 +1,100 @@
+// src/transition.rs
+
+// The short phases around a round: a 3-2-1 countdown before the snake starts
+// moving and a death animation before the game over screen. Both run on the
+// real time fed to `advance`, the rules (ticks, replays, hashes) never see
+// them.
+
+use core::time::Duration;
+
+use crate::{DeathCause, Game, GameEvent};
+
+pub const DEFAULT_COUNTDOWN_SECS: u32 = 3;
+
+// How long the dying snake flashes and then collapses
+pub const DEATH_ANIMATION: Duration = Duration::from_millis(1200);
+
+// Flashes during the first half of the animation
+const DEATH_FLASHES: f32 = 4.0;
+
+// Seconds to show for the time left, 3 for anything between 2 and 3 seconds
+fn whole_secs(d: Duration) -> u32 {
+    (d.as_millis() as u32).div_ceil(1000)
+}
+
+impl Game {
+    pub(crate) fn start_countdown(&mut self) {
+        self.countdown_left = Duration::from_secs(self.countdown_secs.into());
+        if self.countdown_secs > 0 {
+            self.events.push(GameEvent::Countdown { remaining: self.countdown_secs });
+        }
+    }
+
+    // Runs the countdown on real time and returns what's left of `elapsed`
+    // once it's over. Emits a `Countdown` event for every second, 0 when
+    // the snake starts moving.
+    pub(crate) fn run_countdown(&mut self, elapsed: Duration) -> Duration {
+        if self.countdown_left.is_zero() {
+            return elapsed;
+        }
+        let before = whole_secs(self.countdown_left);
+        let leftover = elapsed.saturating_sub(self.countdown_left);
+        self.countdown_left = self.countdown_left.saturating_sub(elapsed);
+        for remaining in (whole_secs(self.countdown_left)..before).rev() {
+            self.events.push(GameEvent::Countdown { remaining });
+        }
+        leftover
+    }
+
+    // Crashes get an animation, running out of time or room or the rules
+    // ending the round don't
+    pub(crate) fn start_death_animation(&mut self, cause: DeathCause) {
+        if !matches!(cause, DeathCause::TimeExpired | DeathCause::BoardFull | DeathCause::Rules) {
+            self.dying_left = DEATH_ANIMATION;
+        }
+    }
+
+    // The number to show while counting down, `None` once the snake moves
+    pub fn countdown(&self) -> Option<u32> {
+        (self.game_started && !self.game_over && !self.countdown_left.is_zero()).then(|| whole_secs(self.countdown_left))
+    }
+
+    // How far the death animation is, from 0.0 to 1.0. `None` when it isn't
+    // playing.
+    pub fn death_progress(&self) -> Option<f32> {
+        if !self.game_over || self.dying_left.is_zero() {
+            return None;
+        }
+        Some(1.0 - self.dying_left.as_secs_f32() / DEATH_ANIMATION.as_secs_f32())
+    }
+
+    // How many of a snake's segments to draw, head first. All of them except
+    // while the player's snake is dying: it flashes (0 on the off beats),
+    // then collapses from the tail into its head. With `reduced_motion` it
+    // stays whole and fades out instead, see `snake_opacity`.
+    pub fn visible_segments(&self, index: usize) -> usize {
+        let len = self.snake_body(index).len();
+        if self.reduced_motion {
+            return len;
+        }
+        let Some(progress) = self.death_progress().filter(|_| index == 0) else { return len };
+        if progress < 0.5 {
+            let beat = (progress * 2.0 * DEATH_FLASHES * 2.0) as u32;
+            return if beat % 2 == 1 { 0 } else { len };
+        }
+        let left = 1.0 - (progress - 0.5) * 2.0;
+        // Rounded up by hand, `f32::ceil` needs std
+        let shown = len as f32 * left;
+        let whole = shown as usize;
+        (whole + usize::from((whole as f32) < shown)).min(len)
+    }
+
+    // How opaque to draw a snake, from 0.0 to 1.0. Only below 1.0 for the
+    // player's dying snake with `reduced_motion`, which fades out steadily.
+    pub fn snake_opacity(&self, index: usize) -> f32 {
+        match self.death_progress().filter(|_| index == 0 && self.reduced_motion) {
+            Some(progress) => 1.0 - progress,
+            None => 1.0,
+        }
+    }
+}
@@ -0,0 +1,549 @@
+// src/tui.rs
+
+// Terminal frontend. The board is drawn with "▀" half blocks, the upper
+// cell as the text color and the lower one as the background, so each line
+// of text holds two rows of square-ish cells and a 20x20 board fits an
+// 80x24 terminal. It uses the same core as the other frontends and shares
+// settings and high scores with the native app.
+
+// This code will only be compiled for native targets, not for wasm32.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use directories::ProjectDirs;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    DefaultTerminal, Frame,
+};
+use snake_game::{
+    CellKind, Controller, FileStorage, Game, GameConfig, GameError, GameEvent, GameMode, GameStatus, Level, Persistence, Rgb, SessionLog,
+    SessionRecord, Settings, SpeedCurve, Theme,
+};
+
+// How long to wait for a key before drawing the next frame
+const FRAME: Duration = Duration::from_millis(16);
+
+// Same choices as the native menus
+const MODES: [GameMode; 3] = [
+    GameMode::Classic,
+    GameMode::Timed { limit_secs: 60 },
+    GameMode::Timed { limit_secs: 120 },
+];
+
+const SPEEDS: [(&str, u64); 3] = [("slow", 150), ("normal", 100), ("fast", 70)];
+
+// The AI snake to play against, if any. A second player from the native
+// app's settings is left out, the terminal only has one set of keys.
+const OPPONENTS: [Option<Controller>; 3] = [None, Some(Controller::Greedy), Some(Controller::Pathfinder)];
+
+fn color(rgb: Rgb) -> Color {
+    Color::Rgb(rgb.r, rgb.g, rgb.b)
+}
+
+fn speed_curve(base_ms: u64) -> SpeedCurve {
+    SpeedCurve {
+        base_interval: Duration::from_millis(base_ms),
+        min_interval: Duration::from_millis(base_ms / 2),
+        ..SpeedCurve::default()
+    }
+}
+
+// Minutes and seconds, e.g. 1:05
+fn clock_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// Steps `index` by `delta` through a list of `len` options, wrapping around
+fn cycle(index: usize, len: usize, delta: isize) -> usize {
+    (index as isize + delta).rem_euclid(len as isize) as usize
+}
+
+// The key map's name for a key, see `KeyMap`
+fn key_name(key: KeyCode) -> Option<String> {
+    let name = match key {
+        KeyCode::Up => "arrowup".to_string(),
+        KeyCode::Down => "arrowdown".to_string(),
+        KeyCode::Left => "arrowleft".to_string(),
+        KeyCode::Right => "arrowright".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Esc => "escape".to_string(),
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_lowercase().to_string(),
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn read_level(path: &Path) -> Result<Level, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    Level::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Snake in the terminal")]
+struct Args {
+    #[arg(long, help = "Random seed, every game of the session gets the same food")]
+    seed: Option<u64>,
+    #[arg(long, help = "Leaving the board comes back in on the other side")]
+    wrap: bool,
+    #[arg(long, value_name = "FILE", help = "Play on a level from a text file")]
+    level: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Append a JSON line per finished game (settings, seed, result, stats) to a file")]
+    session_log: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Menu,
+    Playing,
+    HighScores,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuItem {
+    Play,
+    Mode,
+    Speed,
+    WrapWalls,
+    Opponent,
+    HighScores,
+    Quit,
+}
+
+const MENU_ITEMS: [MenuItem; 7] = [
+    MenuItem::Play,
+    MenuItem::Mode,
+    MenuItem::Speed,
+    MenuItem::WrapWalls,
+    MenuItem::Opponent,
+    MenuItem::HighScores,
+    MenuItem::Quit,
+];
+
+struct App {
+    game: Game,
+    // The board's colors, `None` for the background. Kept up to date from
+    // the cells that changed (see `update_cells`) instead of being worked
+    // out again for every frame.
+    cells: Vec<Option<Rgb>>,
+    screen: Screen,
+    menu_index: usize,
+    settings: Settings,
+    persistence: Persistence<FileStorage>,
+    // From the command line, kept out of the settings
+    seed: Option<u64>,
+    level: Option<Level>,
+    session_log: Option<SessionLog>,
+    // When the current round started, for its duration in the session log
+    round_started: Duration,
+    best_score: Option<u32>,
+    quit: bool,
+}
+
+impl App {
+    fn tr<'a>(&self, key: &'a str) -> &'a str {
+        self.settings.language.text(key)
+    }
+
+    fn trf(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        self.settings.language.format(key, args)
+    }
+
+    fn on_off(&self, on: bool) -> String {
+        self.tr(if on { "on" } else { "off" }).to_string()
+    }
+
+    // A game with the settings' rules and at most one AI opponent
+    fn build_game(&self) -> Result<Game, GameError> {
+        let config = &self.settings.config;
+        let config = GameConfig {
+            seed: self.seed.or(config.seed),
+            opponents: config.opponents.iter().copied().filter(Option::is_some).take(1).collect(),
+            ..config.clone()
+        };
+        match &self.level {
+            Some(level) => Game::from_level_with_config(level, &config),
+            None => Game::from_config(&config),
+        }
+    }
+
+    fn opponent(&self) -> Option<Controller> {
+        self.settings.config.opponents.iter().copied().flatten().next()
+    }
+
+    fn save_settings(&mut self) {
+        // The terminal is in raw mode, there's nowhere to show the error
+        let _ = self.persistence.save_settings(&self.settings);
+    }
+
+    fn menu_label(&self, item: MenuItem) -> String {
+        let config = &self.settings.config;
+        match item {
+            MenuItem::Play => self.tr("menu-play").to_string(),
+            MenuItem::Mode => {
+                let mode = match config.mode {
+                    GameMode::Classic => self.tr("mode-classic").to_string(),
+                    GameMode::Timed { limit_secs } => self.trf("mode-timed", &[("secs", &limit_secs)]),
+                };
+                self.trf("menu-mode", &[("mode", &mode)])
+            }
+            MenuItem::Speed => {
+                let base_ms = config.speed_curve.base_interval.as_millis() as u64;
+                let key = SPEEDS.iter().find(|s| s.1 == base_ms).map_or("speed-custom".to_string(), |s| format!("speed-{}", s.0));
+                self.trf("setting-speed", &[("value", &self.tr(&key))])
+            }
+            MenuItem::WrapWalls => self.trf("setting-wrap-walls", &[("value", &self.on_off(config.wrap_walls))]),
+            MenuItem::Opponent => {
+                let key = match self.opponent() {
+                    None => "opponent-none",
+                    Some(Controller::Greedy) => "opponent-greedy",
+                    Some(Controller::Pathfinder) => "opponent-pathfinder",
+                    Some(Controller::Chaser) => "opponent-chaser",
+                };
+                self.trf("setting-opponent", &[("value", &self.tr(key))])
+            }
+            MenuItem::HighScores => self.tr("menu-high-scores").to_string(),
+            MenuItem::Quit => self.tr("menu-quit").to_string(),
+        }
+    }
+
+    // Moves a menu option one step left (-1) or right (1)
+    fn change(&mut self, item: MenuItem, delta: isize) {
+        let current = self.opponent();
+        let config = &mut self.settings.config;
+        match item {
+            MenuItem::Mode => {
+                let index = MODES.iter().position(|&m| m == config.mode).unwrap_or(0);
+                config.mode = MODES[cycle(index, MODES.len(), delta)];
+            }
+            MenuItem::Speed => {
+                let base_ms = config.speed_curve.base_interval.as_millis() as u64;
+                let index = SPEEDS.iter().position(|s| s.1 == base_ms);
+                config.speed_curve = speed_curve(SPEEDS[index.map_or(1, |i| cycle(i, SPEEDS.len(), delta))].1);
+            }
+            MenuItem::WrapWalls => config.wrap_walls = !config.wrap_walls,
+            MenuItem::Opponent => {
+                let index = OPPONENTS.iter().position(|&o| o == current).unwrap_or(0);
+                config.opponents.retain(Option::is_none);
+                config.opponents.extend(OPPONENTS[cycle(index, OPPONENTS.len(), delta)].map(Some));
+            }
+            MenuItem::Play | MenuItem::HighScores | MenuItem::Quit => return,
+        }
+        self.save_settings();
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Result<(), GameError> {
+        match self.screen {
+            Screen::Menu => self.menu_key(key)?,
+            Screen::Playing => self.playing_key(key),
+            Screen::HighScores => {
+                if matches!(key, KeyCode::Esc | KeyCode::Enter | KeyCode::Backspace | KeyCode::Char(' ')) {
+                    self.screen = Screen::Menu;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn menu_key(&mut self, key: KeyCode) -> Result<(), GameError> {
+        let item = MENU_ITEMS[self.menu_index];
+        match key {
+            KeyCode::Up | KeyCode::Char('w') => self.menu_index = cycle(self.menu_index, MENU_ITEMS.len(), -1),
+            KeyCode::Down | KeyCode::Char('s') => self.menu_index = cycle(self.menu_index, MENU_ITEMS.len(), 1),
+            KeyCode::Left | KeyCode::Char('a') => self.change(item, -1),
+            KeyCode::Right | KeyCode::Char('d') => self.change(item, 1),
+            KeyCode::Enter | KeyCode::Char(' ') => match item {
+                MenuItem::Play => {
+                    self.game = self.build_game()?;
+                    self.game.start_game();
+                    self.screen = Screen::Playing;
+                }
+                MenuItem::HighScores => self.screen = Screen::HighScores,
+                MenuItem::Quit => self.quit = true,
+                _ => self.change(item, 1),
+            },
+            KeyCode::Esc | KeyCode::Char('q') => self.quit = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn playing_key(&mut self, key: KeyCode) {
+        // Q leaves from the pause screen only, so a stray key can't end a
+        // run. A finished game goes back to the menu with Esc too.
+        let finished = self.game.game_over;
+        if (key == KeyCode::Char('q') && self.game.paused) || (key == KeyCode::Esc && finished) {
+            self.screen = Screen::Menu;
+            return;
+        }
+        let action = key_name(key).and_then(|name| self.settings.keymap.action_for(&name));
+        if let Some(action) = action {
+            self.game.handle_action(action);
+        }
+    }
+
+    fn update(&mut self, elapsed: Duration) {
+        if self.screen != Screen::Playing {
+            return;
+        }
+        self.game.advance(elapsed);
+        self.update_cells();
+        let events: Vec<GameEvent> = self.game.drain_events().collect();
+        for event in events {
+            let score = match event {
+                GameEvent::Started => {
+                    self.round_started = unix_time();
+                    continue;
+                }
+                GameEvent::GameOver { score, .. } => score,
+                _ => continue,
+            };
+            if let Some(log) = &mut self.session_log {
+                let started = self.round_started;
+                let record = SessionRecord::new(&self.game, started.as_secs(), unix_time().saturating_sub(started));
+                // Same as the scores, a round that can't be logged is only lost
+                let _ = log.append(&record);
+            }
+            if score == 0 {
+                continue;
+            }
+            let name = self.settings.player_name.clone();
+            // Like the settings, a score that can't be saved is only lost
+            let _ = self.persistence.record_score(&name, score);
+            self.best_score = self.persistence.load_high_scores().best();
+        }
+    }
+
+    // Only the cells that changed while the round is running, the whole
+    // board otherwise: the death animation hides segments without changing
+    // the cells
+    fn update_cells(&mut self) {
+        let game = &mut self.game;
+        let (width, height) = (game.width as usize, game.height as usize);
+        let theme = game.theme;
+        let running = game.status() == GameStatus::Running && self.cells.len() == width * height;
+        if let Some(dirty) = game.take_dirty_cells().filter(|_| running) {
+            for p in dirty {
+                self.cells[p.y as usize * width + p.x as usize] = theme.cell_color(game.cell_at(p));
+            }
+            return;
+        }
+
+        // Snakes go on top of the other cells, only showing the segments
+        // the death animation keeps
+        self.cells.clear();
+        self.cells.resize(width * height, None);
+        for (p, kind) in game.cells() {
+            if !matches!(
+                kind,
+                CellKind::SnakeHead | CellKind::SnakeBody | CellKind::OpponentHead(_) | CellKind::OpponentBody(_)
+            ) {
+                self.cells[p.y as usize * width + p.x as usize] = theme.cell_color(kind);
+            }
+        }
+        for index in 0..game.snake_count() {
+            let visible = game.visible_segments(index);
+            for (i, p) in game.snake_body(index).iter().take(visible).enumerate().rev() {
+                self.cells[p.y as usize * width + p.x as usize] = Some(game.segment_color(index, i));
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        match self.screen {
+            Screen::Menu => {
+                let lines: Vec<String> = MENU_ITEMS.iter().map(|&item| self.menu_label(item)).collect();
+                self.draw_page(frame, self.tr("menu-title"), &lines, Some(self.menu_index), self.tr("hint-menu"));
+            }
+            Screen::HighScores => {
+                let scores = self.persistence.load_high_scores();
+                let mut lines: Vec<String> = scores
+                    .entries()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let name = if entry.name.is_empty() { "---" } else { &entry.name };
+                        format!("{:>2}. {}  {}", i + 1, name, entry.score)
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    lines.push(self.tr("no-scores").to_string());
+                }
+                self.draw_page(frame, self.tr("high-scores-title"), &lines, None, self.tr("hint-back"));
+            }
+            Screen::Playing => self.draw_game(frame),
+        }
+    }
+
+    // A title, a list of lines with one of them highlighted, and a hint at
+    // the bottom, all centered
+    fn draw_page(&self, frame: &mut Frame, title: &str, lines: &[String], selected: Option<usize>, hint: &str) {
+        let theme = Theme::preset(self.settings.config.theme);
+        let text = Style::new().fg(color(theme.text)).bg(color(theme.background));
+        frame.render_widget(Block::new().style(text), frame.area());
+
+        let mut content = vec![Line::styled(title, text.add_modifier(Modifier::BOLD)), Line::default()];
+        content.extend(lines.iter().enumerate().map(|(i, line)| {
+            if Some(i) == selected {
+                Line::styled(format!("> {} <", line), text.fg(color(theme.head)).add_modifier(Modifier::BOLD))
+            } else {
+                Line::styled(line.as_str(), text)
+            }
+        }));
+        content.push(Line::default());
+        content.push(Line::styled(hint, text.add_modifier(Modifier::DIM)));
+
+        let [area] = Layout::vertical([Constraint::Length(content.len() as u16)]).flex(Flex::Center).areas(frame.area());
+        frame.render_widget(Paragraph::new(content).alignment(Alignment::Center), area);
+    }
+
+    fn draw_game(&self, frame: &mut Frame) {
+        let game = &self.game;
+        let theme = &game.theme;
+        let (width, height) = (game.width as usize, game.height as usize);
+        let text = Style::new().fg(color(theme.text)).bg(color(theme.background));
+        frame.render_widget(Block::new().style(text), frame.area());
+
+        // Two board rows per line, plus the border and the HUD line
+        let (columns, rows) = (width as u16 + 2, height.div_ceil(2) as u16 + 2);
+        if frame.area().width < columns || frame.area().height < rows + 1 {
+            let message = self.trf("terminal-too-small", &[("width", &columns), ("height", &(rows + 1))]);
+            let [area] = Layout::vertical([Constraint::Length(1)]).flex(Flex::Center).areas(frame.area());
+            frame.render_widget(Paragraph::new(message).style(text).alignment(Alignment::Center), area);
+            return;
+        }
+        let [hud, board] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(rows)]).flex(Flex::Center).areas(frame.area());
+        let [hud] = Layout::horizontal([Constraint::Length(columns)]).flex(Flex::Center).areas(hud);
+        let [board] = Layout::horizontal([Constraint::Length(columns)]).flex(Flex::Center).areas(board);
+
+        let mut score = self.trf("hud-score", &[("score", &game.score), ("best", &self.best_score.unwrap_or(0))]);
+        if game.snake_count() > 1 {
+            score.push_str("  ");
+            score.push_str(&self.trf("hud-player-2", &[("score", &game.snake_score(1))]));
+        }
+        let clock = match game.time_left() {
+            Some(left) => self.trf("hud-time-left", &[("time", &clock_time(left))]),
+            None => clock_time(game.elapsed()),
+        };
+        frame.render_widget(Paragraph::new(score).style(text), hud);
+        frame.render_widget(Paragraph::new(clock).style(text).alignment(Alignment::Right), hud);
+
+        let cell = |x: usize, y: usize| color(self.cells.get(y * width + x).copied().flatten().unwrap_or(theme.background));
+        let lines: Vec<Line> = (0..height)
+            .step_by(2)
+            .map(|y| {
+                // An odd height leaves the last line's lower half empty
+                let spans = (0..width).map(|x| {
+                    let below = if y + 1 < height { cell(x, y + 1) } else { Color::Reset };
+                    Span::styled("▀", Style::new().fg(cell(x, y)).bg(below))
+                });
+                Line::from(spans.collect::<Vec<_>>())
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().style(text)), board);
+
+        let message = if game.status() == GameStatus::Dying {
+            None
+        } else if let Some(count) = game.countdown() {
+            Some(count.to_string())
+        } else if game.game_over {
+            let language = self.settings.language;
+            let headline = game.death_cause.map_or(self.tr("game-over").to_string(), |cause| cause.message(language));
+            let stats = game.run_stats();
+            let stats = self.trf(
+                "game-over-stats",
+                &[("apples", &stats.apples), ("turns", &stats.turns), ("near_misses", &stats.near_misses)],
+            );
+            Some(self.trf("game-over-message", &[("headline", &headline), ("score", &game.score), ("stats", &stats)]))
+        } else if game.paused {
+            Some(self.tr("paused-message").to_string())
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            self.draw_message(frame, &message, board);
+        }
+    }
+
+    // A boxed message over the middle of the board, cut to fit
+    fn draw_message(&self, frame: &mut Frame, message: &str, board: Rect) {
+        let lines: Vec<&str> = message.lines().collect();
+        let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16;
+        let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)]).flex(Flex::Center).areas(board);
+        let [area] = Layout::horizontal([Constraint::Length(longest + 4)]).flex(Flex::Center).areas(area);
+        let theme = &self.game.theme;
+        let text = Style::new().fg(color(theme.text)).bg(color(theme.background));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+                .alignment(Alignment::Center)
+                .block(Block::bordered().style(text)),
+            area,
+        );
+    }
+}
+
+// Time since the Unix epoch, for the session log
+fn unix_time() -> Duration {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default()
+}
+
+fn run(terminal: &mut DefaultTerminal, mut app: App) -> Result<(), Box<dyn Error>> {
+    let mut last_frame = Instant::now();
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if event::poll(FRAME)? {
+            // Some terminals report releases too, only presses count
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.key_pressed(key.code)?;
+                }
+            }
+        }
+        let now = Instant::now();
+        app.update(now - last_frame);
+        last_frame = now;
+    }
+    Ok(())
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    // The native app's data directory, see ggez's `user_data_dir`
+    let dir = ProjectDirs::from("", "Gemini", "snake_game").map(|dirs| dirs.data_local_dir().to_path_buf()).unwrap_or_default();
+    let persistence = Persistence::new(FileStorage::new(dir), "");
+    let mut settings = persistence.load_settings();
+    settings.config.wrap_walls |= args.wrap;
+    let mut app = App {
+        game: Game::from_config(&GameConfig::default())?,
+        cells: Vec::new(),
+        screen: Screen::Menu,
+        menu_index: 0,
+        best_score: persistence.load_high_scores().best(),
+        settings,
+        persistence,
+        seed: args.seed,
+        level: args.level.as_deref().map(read_level).transpose()?,
+        session_log: args.session_log.map(SessionLog::new),
+        round_started: unix_time(),
+        quit: false,
+    };
+    // A hand-edited settings file may ask for a board the game can't use
+    app.game = app.build_game().or_else(|_| Game::from_config(&GameConfig::default()))?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, app);
+    ratatui::restore();
+    result
+}
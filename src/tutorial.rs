@@ -0,0 +1,182 @@
+// src/tutorial.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, Game, GameConfig, GameError, GameEvent, GameMode, Language, Level};
+
+// The tutorial the frontends ship with, see tutorial.json. Steps are data:
+// a new one is a board, a goal and a prompt key added to `Language`, no code.
+const BUILTIN: &str = include_str!("tutorial.json");
+
+// Steps played one after the other, each on its own board. The frontend
+// shows the current step's prompt over the game and only moves on once
+// `TutorialRun::update` says the goal was reached.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tutorial {
+    pub steps: Vec<TutorialStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TutorialStep {
+    // Translation key of the text shown while the step is played, see
+    // `TutorialRun::prompt` for its placeholders
+    pub prompt: String,
+    pub goal: StepGoal,
+    // The board as the lines of a level, see `Level`
+    pub level: Vec<String>,
+    #[serde(default = "one")]
+    pub food_count: usize,
+    // Same food in the same places every time, `None` for a random seed
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn one() -> usize {
+    1
+}
+
+// What the player has to do to finish a step. Dying starts the step over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum StepGoal {
+    // Turn the snake this way
+    Turn { direction: Direction },
+    // Eat this many pieces of food, power-ups included
+    Eat { count: u32 },
+    // Stay alive for this many ticks
+    Survive { ticks: u64 },
+}
+
+impl StepGoal {
+    // How much there is to do, for "2/3" next to the prompt
+    pub fn target(self) -> u64 {
+        match self {
+            StepGoal::Turn { .. } => 1,
+            StepGoal::Eat { count } => count as u64,
+            StepGoal::Survive { ticks } => ticks,
+        }
+    }
+}
+
+// What `TutorialRun::update` made of the latest ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Playing,
+    // The goal was reached, the run is on the next step (or finished)
+    Done,
+    // The snake died, the step starts over with the game's next round
+    Failed,
+}
+
+impl Tutorial {
+    // Checks every step's board, so a typo in the data shows up when it's
+    // loaded rather than halfway through the tutorial
+    pub fn from_json(text: &str) -> Result<Tutorial, GameError> {
+        let tutorial: Tutorial = serde_json::from_str(text).map_err(|e| GameError::Storage(e.to_string()))?;
+        for step in &tutorial.steps {
+            step.level()?;
+        }
+        Ok(tutorial)
+    }
+
+    pub fn builtin() -> Tutorial {
+        Tutorial::from_json(BUILTIN).expect("the built-in tutorial is valid")
+    }
+}
+
+impl TutorialStep {
+    pub fn level(&self) -> Result<Level, GameError> {
+        Level::parse(&self.level.join("\n"))
+    }
+
+    // The step's board with the rest taken from `config` (speed, theme,
+    // reduced motion...). Always a classic game without opponents or
+    // wrapping, so the goal is the only thing going on.
+    pub fn game(&self, config: &GameConfig) -> Result<Game, GameError> {
+        let config = GameConfig {
+            seed: self.seed,
+            mode: GameMode::Classic,
+            wrap_walls: false,
+            food_count: self.food_count,
+            opponents: Vec::new(),
+            ..config.clone()
+        };
+        Game::from_level_with_config(&self.level()?, &config)
+    }
+}
+
+// A player's way through a tutorial: the step they're on and how far they
+// got with its goal
+#[derive(Debug, Clone, PartialEq)]
+pub struct TutorialRun {
+    pub tutorial: Tutorial,
+    step: usize,
+    done: u64,
+}
+
+impl TutorialRun {
+    pub fn new(tutorial: Tutorial) -> TutorialRun {
+        TutorialRun { tutorial, step: 0, done: 0 }
+    }
+
+    pub fn step_index(&self) -> usize {
+        self.step
+    }
+
+    // `None` once every step is done
+    pub fn current(&self) -> Option<&TutorialStep> {
+        self.tutorial.steps.get(self.step)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.current().is_none()
+    }
+
+    // Progress on the current step's goal and the goal, (0, 0) when finished
+    pub fn progress(&self) -> (u64, u64) {
+        self.current().map_or((0, 0), |step| (self.done, step.goal.target()))
+    }
+
+    // The current step's prompt, or "tutorial-done" once finished. Prompts
+    // may use `{done}` and `{target}` (see `progress`) and `{step}`, counting from 1.
+    pub fn prompt(&self, language: Language) -> String {
+        let (done, target) = self.progress();
+        let key = self.current().map_or("tutorial-done", |step| step.prompt.as_str());
+        language.format(key, &[("done", &done), ("target", &target), ("step", &(self.step + 1))])
+    }
+
+    // Checks the goal against the events of the ticks that just ran on the
+    // step's game, drained from it. On `Done` the frontend builds the next
+    // step's game, on `Failed` it lets the player start the round over.
+    pub fn update(&mut self, game: &Game, events: &[GameEvent]) -> StepStatus {
+        let Some(goal) = self.current().map(|step| step.goal) else { return StepStatus::Playing };
+        for event in events {
+            match (goal, event) {
+                (_, GameEvent::Started) => self.done = 0,
+                (_, GameEvent::GameOver { .. }) => {
+                    self.done = 0;
+                    return StepStatus::Failed;
+                }
+                (StepGoal::Turn { direction }, GameEvent::Turned { direction: turned }) if direction == *turned => {
+                    self.done = 1;
+                }
+                (StepGoal::Eat { .. }, GameEvent::FoodEaten { .. } | GameEvent::PowerUpCollected { .. }) => {
+                    self.done += 1;
+                }
+                _ => {}
+            }
+        }
+        if let StepGoal::Survive { .. } = goal {
+            self.done = game.tick_count();
+        }
+        if self.done < goal.target() {
+            return StepStatus::Playing;
+        }
+        self.step += 1;
+        self.done = 0;
+        StepStatus::Done
+    }
+}
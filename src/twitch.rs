@@ -0,0 +1,143 @@
+// src/twitch.rs
+
+// "Twitch chat plays", only with the `twitch` feature: a read-only
+// connection to a channel's chat over IRC, turning "up", "down", "left"
+// and "right" messages into votes (see `VoteTally`). The connection is
+// anonymous, no account or token needed, and runs on a thread of its own:
+//
+//     let mut plays = TwitchPlays::new(TwitchChat::connect("somechannel"), 5, 0);
+//     plays.update(&mut game); // every frame, before advancing the game
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::prelude::rust_2021::*;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::{parse_vote, Direction, Game, VoteTally};
+
+const TWITCH_IRC: &str = "irc.chat.twitch.tv:6667";
+
+// Twitch lets any "justinfan" nick read chat without logging in
+const ANONYMOUS_NICK: &str = "justinfan31337";
+
+enum ChatEvent {
+    Vote(String, Direction),
+    Closed(String),
+}
+
+pub struct TwitchChat {
+    pub channel: String,
+    events: Receiver<ChatEvent>,
+    error: Option<String>,
+}
+
+impl TwitchChat {
+    // Connects in the background, `error` says when it failed or dropped
+    pub fn connect(channel: &str) -> TwitchChat {
+        let channel = channel.trim().trim_start_matches('#').to_lowercase();
+        let (sender, events) = mpsc::channel();
+        let joined = channel.clone();
+        thread::spawn(move || {
+            let reason = match read_chat(&joined, &sender) {
+                Ok(()) => "the chat closed the connection".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let _ = sender.send(ChatEvent::Closed(reason));
+        });
+        TwitchChat { channel, events, error: None }
+    }
+
+    // Votes read since the last call, with who sent them
+    pub fn votes(&mut self) -> Vec<(String, Direction)> {
+        let mut votes = Vec::new();
+        for event in self.events.try_iter() {
+            match event {
+                ChatEvent::Vote(voter, direction) => votes.push((voter, direction)),
+                ChatEvent::Closed(reason) => self.error = Some(reason),
+            }
+        }
+        votes
+    }
+
+    // Why the chat isn't being read any more, `None` while it is
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+// Reads the channel's messages until the connection drops or the
+// receiving end is gone
+fn read_chat(channel: &str, votes: &Sender<ChatEvent>) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(TWITCH_IRC)?;
+    write!(stream, "NICK {}\r\nJOIN #{}\r\n", ANONYMOUS_NICK, channel)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(stream, "PONG {}\r\n", server)?;
+            continue;
+        }
+        let Some((voter, message)) = chat_message(&line) else { continue };
+        let Some(direction) = parse_vote(message) else { continue };
+        if votes.send(ChatEvent::Vote(voter.to_string(), direction)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// The sender and text of a ":nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :text" line
+fn chat_message(line: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    Some((prefix.split('!').next()?, text))
+}
+
+// Chat steering a snake: the votes of each window of `window_ticks` ticks
+// are counted and the winning direction queued as the snake's next turn.
+// A window without votes leaves the snake going straight.
+pub struct TwitchPlays {
+    pub chat: TwitchChat,
+    pub tally: VoteTally,
+    pub window_ticks: u64,
+    // 0 for the player's snake, or an opponent steered by hand
+    pub snake: usize,
+    window_start: u64,
+}
+
+impl TwitchPlays {
+    pub fn new(chat: TwitchChat, window_ticks: u64, snake: usize) -> TwitchPlays {
+        TwitchPlays { chat, tally: VoteTally::default(), window_ticks: window_ticks.max(1), snake, window_start: 0 }
+    }
+
+    // Counts the new votes and, once the window is over, turns the snake.
+    // Returns the direction it was turned.
+    pub fn update(&mut self, game: &mut Game) -> Option<Direction> {
+        for (voter, direction) in self.chat.votes() {
+            self.tally.vote(&voter, direction);
+        }
+        let tick = game.tick_count();
+        // A new round
+        if tick < self.window_start {
+            self.window_start = 0;
+        }
+        if tick < self.window_start + self.window_ticks {
+            return None;
+        }
+        self.window_start = tick;
+        let winner = self.tally.winner();
+        self.tally.clear();
+        let direction = winner?;
+        match self.snake {
+            0 => game.queue_turn(direction),
+            index => game.queue_turn_for(index, direction),
+        }
+        Some(direction)
+    }
+
+    // Ticks left before the current window's votes count
+    pub fn ticks_left(&self, game: &Game) -> u64 {
+        (self.window_start + self.window_ticks).saturating_sub(game.tick_count())
+    }
+}
@@ -0,0 +1,8 @@
+// src/uniffi_bindgen.rs
+
+// UniFFI's bindings generator, built from the same uniffi version as the
+// library so the two always agree (see src/mobile.rs)
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
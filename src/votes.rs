@@ -0,0 +1,60 @@
+// src/votes.rs
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::Direction;
+
+// The directions in the order `VoteTally::counts` lists them
+pub const VOTE_DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+// "Chat plays" voting over a window of ticks: every voter gets one vote, a
+// later one replacing it, and the direction with the most votes wins. Ties
+// go to the direction of whoever voted first. The chat itself comes
+// from a frontend, see `twitch::TwitchPlays` for the Twitch one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoteTally {
+    votes: Vec<(String, Direction)>,
+}
+
+impl VoteTally {
+    pub fn vote(&mut self, voter: &str, direction: Direction) {
+        match self.votes.iter_mut().find(|(name, _)| name == voter) {
+            Some(vote) => vote.1 = direction,
+            None => self.votes.push((voter.to_string(), direction)),
+        }
+    }
+
+    // Votes for each of `VOTE_DIRECTIONS`
+    pub fn counts(&self) -> [u32; 4] {
+        VOTE_DIRECTIONS.map(|d| self.votes.iter().filter(|(_, vote)| *vote == d).count() as u32)
+    }
+
+    // Voters so far
+    pub fn total(&self) -> usize {
+        self.votes.len()
+    }
+
+    // `None` without any votes
+    pub fn winner(&self) -> Option<Direction> {
+        let counts = self.counts();
+        let count = |d: Direction| counts[VOTE_DIRECTIONS.iter().position(|&v| v == d).unwrap_or(0)];
+        // `max_by_key` keeps the last of equal ones, going backwards keeps the first
+        self.votes.iter().rev().map(|&(_, d)| d).max_by_key(|&d| count(d))
+    }
+
+    pub fn clear(&mut self) {
+        self.votes.clear();
+    }
+}
+
+// A chat message read as a vote: its first word, "up", "down", "left" or
+// "right" in any case, or the same with "!" in front
+pub fn parse_vote(message: &str) -> Option<Direction> {
+    let word = message.split_whitespace().next()?;
+    let word = word.strip_prefix('!').unwrap_or(word);
+    [("up", Direction::Up), ("down", Direction::Down), ("left", Direction::Left), ("right", Direction::Right)]
+        .into_iter()
+        .find(|(name, _)| word.eq_ignore_ascii_case(name))
+        .map(|(_, direction)| direction)
+}
@@ -0,0 +1,201 @@
+// src/wasi_main.rs
+
+// The headless core as a small command-line tool that also builds for
+// WASI, so replays can be checked and AIs evaluated inside a WASI sandbox
+// (a serverless function verifying leaderboard entries, say) instead of
+// shipping a native binary:
+//
+//     cargo build --release --target wasm32-wasip1 --bin snake-wasi --no-default-features --features wasi
+//     wasmtime target/wasm32-wasip1/release/snake-wasi.wasm verify < replay.txt
+//     wasmtime target/wasm32-wasip1/release/snake-wasi.wasm analyze < replay.txt
+//     wasmtime target/wasm32-wasip1/release/snake-wasi.wasm sim --games 20 --size 30x30
+//
+// `verify` plays a replay (base64 or binary, from a file or stdin) to its
+// end and checks the score and stats it claims, `analyze` plays one back
+// for a `ReplayReport` (score and speed over time, risky moves, food the
+// player took the long way to), `sim` plays AI games like snake-sim does,
+// on one thread. All print one line of JSON, and exit with 1 when a replay
+// doesn't check out and 2 for bad arguments or input. No clap, to keep the
+// .wasm small.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+use serde::Serialize;
+use snake_game::{simulate_batch, simulate_many, Controller, DeathCause, GameConfig, GameError, Replay, ReplayPlayer, RunStats};
+
+const USAGE: &str = "usage: snake-wasi verify [FILE]
+       snake-wasi analyze [FILE]
+       snake-wasi sim [--controller greedy|pathfinder] [--games N] [--size WxH] [--seed N] [--wrap] [--max-ticks N] [--each]
+
+verify  plays a replay (base64 or binary, stdin without FILE) and checks its score and stats
+analyze plays a replay and reports its score and speed timelines, risky moves and missed shorter paths
+sim     plays AI games and prints the average results, or each game's with --each";
+
+#[derive(Serialize)]
+struct Verified {
+    valid: bool,
+    // Cheats were used, the score is genuine but shouldn't rank
+    cheated: bool,
+    // What the replay says and what playing it gave
+    claimed_score: u32,
+    score: u32,
+    ticks: u64,
+    length: usize,
+    death_cause: Option<DeathCause>,
+    // Same as `Game::state_hash` on any other client after the last tick
+    state_hash: u64,
+    // Counted again during playback, `valid` needs them to match too
+    stats: RunStats,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    games: u64,
+    mean_score: f64,
+    max_score: u32,
+    mean_length: f64,
+    mean_ticks: f64,
+    // Games that were still going at --max-ticks
+    unfinished: u64,
+}
+
+// The file's bytes, or stdin's
+fn read_input(path: Option<&str>) -> Result<Vec<u8>, String> {
+    match path {
+        Some("-") | None => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes).map_err(|e| format!("could not read stdin: {}", e))?;
+            Ok(bytes)
+        }
+        Some(path) => fs::read(path).map_err(|e| format!("could not read {}: {}", path, e)),
+    }
+}
+
+// Binary replays start with their version byte, which isn't in the
+// base64 alphabet
+fn parse_replay(bytes: &[u8]) -> Result<Replay, GameError> {
+    let base64 = |c: &u8| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'\n' | b'\r' | b' ');
+    match bytes.iter().all(base64) {
+        true => Replay::from_base64(&String::from_utf8_lossy(bytes)),
+        false => Replay::from_bytes(bytes),
+    }
+}
+
+fn verify(args: &[String]) -> Result<ExitCode, String> {
+    if args.len() > 1 {
+        return Err("verify takes at most one file".to_string());
+    }
+    let replay = parse_replay(&read_input(args.first().map(String::as_str))?).map_err(|e| e.to_string())?;
+    let claimed_score = replay.score;
+    let claimed_stats = replay.stats;
+    let cheated = replay.cheated();
+    let mut player = ReplayPlayer::new(replay).map_err(|e| e.to_string())?;
+    player.seek(player.length()).map_err(|e| e.to_string())?;
+    let game = player.game();
+    let verified = Verified {
+        valid: game.score == claimed_score && game.run_stats() == claimed_stats,
+        cheated,
+        claimed_score,
+        score: game.score,
+        ticks: game.tick_count(),
+        length: game.snake.body.len(),
+        death_cause: game.death_cause,
+        state_hash: game.state_hash(),
+        stats: game.run_stats(),
+    };
+    println!("{}", serde_json::to_string(&verified).map_err(|e| e.to_string())?);
+    Ok(if verified.valid { ExitCode::SUCCESS } else { ExitCode::from(1) })
+}
+
+fn analyze(args: &[String]) -> Result<ExitCode, String> {
+    if args.len() > 1 {
+        return Err("analyze takes at most one file".to_string());
+    }
+    let replay = parse_replay(&read_input(args.first().map(String::as_str))?).map_err(|e| e.to_string())?;
+    let report = replay.analyze().map_err(|e| e.to_string())?;
+    println!("{}", serde_json::to_string(&report).map_err(|e| e.to_string())?);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_controller(text: &str) -> Result<Controller, String> {
+    match text.to_lowercase().as_str() {
+        "greedy" => Ok(Controller::Greedy),
+        "pathfinder" | "astar" | "a*" | "bfs" => Ok(Controller::Pathfinder),
+        _ => Err(format!("unknown controller '{}', expected greedy or pathfinder", text)),
+    }
+}
+
+fn parse_size(text: &str) -> Result<(i32, i32), String> {
+    let parsed = text.split_once(['x', 'X']).and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+    match parsed {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("expected a size like 30x30, got '{}'", text)),
+    }
+}
+
+fn parse_number(option: &str, value: Option<&String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", option))?;
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", option, value))
+}
+
+fn sim(args: &[String]) -> Result<ExitCode, String> {
+    let mut controller = Controller::Pathfinder;
+    let (mut games, mut seed, mut max_ticks) = (100, 0, 100_000);
+    let mut config = GameConfig { width: 20, height: 20, ..GameConfig::default() };
+    let mut each = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--controller" => controller = parse_controller(args.next().ok_or("--controller needs a value")?)?,
+            "--games" => games = parse_number(arg, args.next())?,
+            "--seed" => seed = parse_number(arg, args.next())?,
+            "--max-ticks" => max_ticks = parse_number(arg, args.next())?,
+            "--size" => (config.width, config.height) = parse_size(args.next().ok_or("--size needs a value")?)?,
+            "--wrap" => config.wrap_walls = true,
+            "--each" => each = true,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    if each {
+        for result in simulate_many(&config, controller, seed, games, max_ticks).map_err(|e| e.to_string())? {
+            println!("{}", serde_json::to_string(&result).map_err(|e| e.to_string())?);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let stats = simulate_batch(&config, controller, seed, games, max_ticks).map_err(|e| e.to_string())?;
+    let summary = Summary {
+        games: stats.games,
+        mean_score: stats.mean_score(),
+        max_score: stats.max_score,
+        mean_length: stats.mean_length(),
+        mean_ticks: stats.mean_ticks(),
+        unfinished: stats.unfinished,
+    };
+    println!("{}", serde_json::to_string(&summary).map_err(|e| e.to_string())?);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("verify") => verify(&args[1..]),
+        Some("analyze") => analyze(&args[1..]),
+        Some("sim") => sim(&args[1..]),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
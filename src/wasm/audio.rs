@@ -0,0 +1,89 @@
+// src/wasm/audio.rs
+
+// Web Audio playback of the core sound effects, only compiled with the
+// `wasm-audio` feature. Tones are synthesized with oscillators, so there
+// are no audio files to load.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{AudioContext, GainNode, OscillatorType};
+
+use crate::SoundEffect;
+
+pub struct Sfx {
+    ctx: AudioContext,
+    master: GainNode,
+    volume: f32,
+    muted: bool,
+}
+
+impl Sfx {
+    // Browsers only allow audio after a user gesture, so this should be
+    // called from a click/key handler
+    pub fn new(volume: f32, muted: bool) -> Result<Sfx, JsValue> {
+        let ctx = AudioContext::new()?;
+        let master = ctx.create_gain()?;
+        master.connect_with_audio_node(&ctx.destination())?;
+        let sfx = Sfx {
+            ctx,
+            master,
+            volume,
+            muted,
+        };
+        sfx.apply_gain();
+        Ok(sfx)
+    }
+
+    pub fn resume(&self) {
+        let _ = self.ctx.resume();
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.apply_gain();
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_gain();
+    }
+
+    fn apply_gain(&self) {
+        let gain = if self.muted { 0.0 } else { self.volume };
+        self.master.gain().set_value(gain);
+    }
+
+    pub fn play(&self, effect: SoundEffect) -> Result<(), JsValue> {
+        if self.muted {
+            return Ok(());
+        }
+        let mut start = self.ctx.current_time();
+        for tone in effect.tones() {
+            let duration = tone.duration_ms as f64 / 1000.0;
+            let osc = self.ctx.create_oscillator()?;
+            osc.set_type(OscillatorType::Square);
+            osc.frequency().set_value(tone.frequency_hz);
+
+            // Short fade out so the tones don't click
+            let envelope = self.ctx.create_gain()?;
+            envelope.gain().set_value_at_time(0.2, start)?;
+            envelope
+                .gain()
+                .linear_ramp_to_value_at_time(0.0, start + duration)?;
+
+            osc.connect_with_audio_node(&envelope)?;
+            envelope.connect_with_audio_node(&self.master)?;
+            osc.start_with_when(start)?;
+            osc.stop_with_when(start + duration)?;
+            start += duration;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+// src/wasm/dpad.rs
+
+use crate::Direction;
+
+// An on-screen arrow button: its direction and rectangle in canvas pixels
+#[derive(Clone, Copy)]
+pub struct DpadButton {
+    pub direction: Direction,
+    pub x: f64,
+    pub y: f64,
+    pub size: f64,
+}
+
+impl DpadButton {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.size && y >= self.y && y < self.y + self.size
+    }
+}
+
+// Four arrow buttons laid out as a cross in the bottom-right corner of the
+// canvas, for phones without a keyboard. Buttons are square, sized from the
+// canvas' smaller side so they stay thumb-sized in portrait and landscape.
+pub struct Dpad {
+    pub buttons: [DpadButton; 4],
+}
+
+impl Dpad {
+    pub fn layout(width: f64, height: f64) -> Dpad {
+        let size = width.min(height) * 0.12;
+        let margin = size / 2.0;
+        // Center of the cross
+        let cx = width - margin - size * 1.5;
+        let cy = height - margin - size * 1.5;
+        let button = |direction, x: f64, y: f64| DpadButton { direction, x, y, size };
+        Dpad {
+            buttons: [
+                button(Direction::Up, cx - size / 2.0, cy - size * 1.5),
+                button(Direction::Down, cx - size / 2.0, cy + size / 2.0),
+                button(Direction::Left, cx - size * 1.5, cy - size / 2.0),
+                button(Direction::Right, cx + size / 2.0, cy - size / 2.0),
+            ],
+        }
+    }
+
+    pub fn hit(&self, x: f64, y: f64) -> Option<Direction> {
+        self.buttons.iter().find(|b| b.contains(x, y)).map(|b| b.direction)
+    }
+}
@@ -0,0 +1,36 @@
+// src/wasm/error.rs
+
+// Turns errors into JS `Error` objects with a stable `code` property, so
+// pages can tell a bad option from a bad level without parsing messages:
+//
+//   try { Game.from_level(text) } catch (e) { if (e.code === "INVALID_LEVEL") ... }
+
+use alloc::string::ToString;
+use wasm_bindgen::prelude::*;
+
+use crate::GameError;
+
+pub fn js_error(code: &str, message: &str) -> JsValue {
+    let error = js_sys::Error::new(message);
+    error.set_name("GameError");
+    // Setting a property on a fresh object can't fail
+    let _ = js_sys::Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from_str(code));
+    error.into()
+}
+
+pub fn game_error(err: GameError) -> JsValue {
+    js_error(err.code(), &err.to_string())
+}
+
+// A JS value that couldn't be read as the expected options/settings object
+pub fn options_error(err: serde_wasm_bindgen::Error) -> JsValue {
+    js_error("INVALID_OPTIONS", &err.to_string())
+}
+
+// Panics show the Rust message and location in the console instead of
+// "unreachable executed"
+#[cfg(feature = "wasm-panic-hook")]
+#[wasm_bindgen(start)]
+pub fn install_panic_hook() {
+    console_error_panic_hook::set_once();
+}
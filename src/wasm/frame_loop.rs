@@ -0,0 +1,106 @@
+// src/wasm/frame_loop.rs
+
+// requestAnimationFrame loop driven from Rust, used by `Game.run()`.
+// Every frame it feeds the real elapsed time to the core clock, hands the
+// resulting events to the JS listeners and redraws the canvas.
+
+use alloc::boxed::Box;
+use alloc::rc::Weak;
+use core::cell::Cell;
+use core::time::Duration;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::Inner;
+
+pub struct FrameLoop {
+    // Holds a weak reference so the loop never keeps a freed game alive
+    callback: Closure<dyn FnMut(f64)>,
+    handle: Cell<Option<i32>>, // Pending requestAnimationFrame id
+    last_frame: Cell<Option<f64>>, // Timestamp of the previous frame
+    running: Cell<bool>,
+}
+
+impl FrameLoop {
+    pub fn new(inner: Weak<Inner>) -> FrameLoop {
+        let callback = Closure::wrap(Box::new(move |timestamp: f64| {
+            if let Some(inner) = inner.upgrade() {
+                inner.on_frame(timestamp);
+            }
+        }) as Box<dyn FnMut(f64)>);
+
+        FrameLoop {
+            callback,
+            handle: Cell::new(None),
+            last_frame: Cell::new(None),
+            running: Cell::new(false),
+        }
+    }
+
+    pub fn start(&self) -> Result<(), JsValue> {
+        if self.running.replace(true) {
+            return Ok(());
+        }
+        // Time spent stopped doesn't count
+        self.last_frame.set(None);
+        self.request()
+    }
+
+    // Only cancels the pending frame, the closure stays alive because `stop`
+    // may be called from an event listener while it's running
+    pub fn stop(&self) {
+        self.running.set(false);
+        if let (Some(handle), Some(window)) = (self.handle.take(), web_sys::window()) {
+            let _ = window.cancel_animation_frame(handle);
+        }
+    }
+
+    fn request(&self) -> Result<(), JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+        let handle = window.request_animation_frame(self.callback.as_ref().unchecked_ref())?;
+        self.handle.set(Some(handle));
+        Ok(())
+    }
+}
+
+impl Drop for FrameLoop {
+    // A frame still scheduled would call a dropped closure
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Inner {
+    fn on_frame(&self, timestamp: f64) {
+        let elapsed = {
+            let frame_loop = self.frame_loop.borrow();
+            let Some(frame_loop) = frame_loop.as_ref() else { return };
+            frame_loop.handle.set(None);
+            let last = frame_loop.last_frame.replace(Some(timestamp));
+            last.map_or(0.0, |last| (timestamp - last).max(0.0))
+        };
+
+        #[cfg(feature = "wasm-gamepad")]
+        self.poll_gamepad();
+        // A timestamp jumping back counts as no time, and so does one that
+        // overflows a Duration
+        let elapsed = Duration::try_from_secs_f64(elapsed / 1000.0).unwrap_or(Duration::ZERO);
+        self.game.borrow_mut().advance(elapsed);
+        self.dispatch_events();
+
+        if let Some(renderer) = self.renderer.borrow().as_ref() {
+            if let Err(err) = renderer.draw(&mut self.game.borrow_mut()) {
+                web_sys::console::error_1(&err);
+            }
+        }
+
+        // A listener may have stopped the loop in the meantime
+        if let Some(frame_loop) = self.frame_loop.borrow().as_ref() {
+            if frame_loop.running.get() {
+                if let Err(err) = frame_loop.request() {
+                    web_sys::console::error_1(&err);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// src/wasm/gamepad.rs
+
+// Gamepad API polling, only compiled with the `wasm-gamepad` feature.
+// Browsers don't send events for gamepad input, so the pads are read once
+// per frame and compared with the previous frame: only a *change* of
+// direction or a newly pressed button becomes an action. Turns go through
+// the core input queue like keys do, so the last one within a tick wins.
+
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+use crate::{Direction, InputAction};
+
+// Button indices of the "standard" gamepad mapping
+const BUTTON_A: u32 = 0;
+const BUTTON_B: u32 = 1;
+const BUTTON_START: u32 = 9;
+const DPAD_UP: u32 = 12;
+const DPAD_DOWN: u32 = 13;
+const DPAD_LEFT: u32 = 14;
+const DPAD_RIGHT: u32 = 15;
+
+// What a pad was doing on the previous poll
+#[derive(Debug, Clone, Copy, Default)]
+struct PadState {
+    direction: Option<Direction>,
+    confirm: bool,
+    boost: bool,
+    pause: bool,
+}
+
+pub struct GamepadPoller {
+    pub dead_zone: f64,
+    pads: Vec<PadState>, // Indexed like `navigator.getGamepads()`
+}
+
+impl GamepadPoller {
+    pub fn new(dead_zone: f64) -> GamepadPoller {
+        GamepadPoller { dead_zone, pads: Vec::new() }
+    }
+
+    // Reads every connected pad and returns the actions to apply, in order
+    pub fn poll(&mut self) -> Result<Vec<InputAction>, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+        let gamepads = window.navigator().get_gamepads()?;
+        let mut actions = Vec::new();
+        for (index, pad) in gamepads.iter().enumerate() {
+            if self.pads.len() <= index {
+                self.pads.resize(index + 1, PadState::default());
+            }
+            // Empty slots are null, disconnected pads start from scratch
+            let Ok(pad) = pad.dyn_into::<Gamepad>() else {
+                self.pads[index] = PadState::default();
+                continue;
+            };
+            let now = PadState {
+                direction: self.direction(&pad),
+                confirm: pressed(&pad, BUTTON_A),
+                boost: pressed(&pad, BUTTON_B),
+                pause: pressed(&pad, BUTTON_START),
+            };
+            let before = core::mem::replace(&mut self.pads[index], now);
+
+            if let Some(direction) = now.direction.filter(|_| now.direction != before.direction) {
+                actions.push(InputAction::Turn(direction));
+            }
+            if now.confirm && !before.confirm {
+                actions.push(InputAction::Confirm);
+            }
+            if now.boost && !before.boost {
+                actions.push(InputAction::Boost);
+            }
+            if now.pause && !before.pause {
+                actions.push(InputAction::Pause);
+            }
+        }
+        Ok(actions)
+    }
+
+    // The d-pad wins over the left stick
+    fn direction(&self, pad: &Gamepad) -> Option<Direction> {
+        let dpad = [
+            (DPAD_UP, Direction::Up),
+            (DPAD_DOWN, Direction::Down),
+            (DPAD_LEFT, Direction::Left),
+            (DPAD_RIGHT, Direction::Right),
+        ];
+        if let Some(&(_, direction)) = dpad.iter().find(|(button, _)| pressed(pad, *button)) {
+            return Some(direction);
+        }
+
+        let axes = pad.axes();
+        let x = axes.get(0).as_f64().unwrap_or(0.0);
+        let y = axes.get(1).as_f64().unwrap_or(0.0);
+        Direction::from_stick(x, y, self.dead_zone)
+    }
+}
+
+fn pressed(pad: &Gamepad, index: u32) -> bool {
+    pad.buttons()
+        .get(index)
+        .dyn_into::<GamepadButton>()
+        .is_ok_and(|button| button.pressed())
+}
@@ -0,0 +1,55 @@
+// src/wasm/gesture.rs
+
+use crate::Direction;
+
+// Default distance (in CSS pixels) a finger must travel to count as a swipe
+pub const DEFAULT_DEAD_ZONE: f64 = 24.0;
+
+// Turns raw touch coordinates into swipe directions. A swipe is recognized
+// as soon as the finger has moved past the dead zone, along the axis it
+// moved the most on. The origin then moves to the current point, so one
+// long drag with a bend in it gives two turns.
+pub struct SwipeRecognizer {
+    pub dead_zone: f64,
+    origin: Option<(f64, f64)>,
+}
+
+impl SwipeRecognizer {
+    pub fn new(dead_zone: f64) -> SwipeRecognizer {
+        SwipeRecognizer {
+            dead_zone,
+            origin: None,
+        }
+    }
+
+    pub fn start(&mut self, x: f64, y: f64) {
+        self.origin = Some((x, y));
+    }
+
+    pub fn moved(&mut self, x: f64, y: f64) -> Option<Direction> {
+        let (ox, oy) = self.origin?;
+        let (dx, dy) = (x - ox, y - oy);
+        if dx.hypot(dy) < self.dead_zone {
+            return None;
+        }
+        self.origin = Some((x, y));
+        // Screen coordinates grow downwards
+        Some(if dx.abs() > dy.abs() {
+            if dx > 0.0 { Direction::Right } else { Direction::Left }
+        } else if dy > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        })
+    }
+
+    pub fn end(&mut self, x: f64, y: f64) -> Option<Direction> {
+        let direction = self.moved(x, y);
+        self.origin = None;
+        direction
+    }
+
+    pub fn cancel(&mut self) {
+        self.origin = None;
+    }
+}
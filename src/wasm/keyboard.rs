@@ -0,0 +1,47 @@
+// src/wasm/keyboard.rs
+
+// Keyboard input handled inside the module, only compiled with the
+// `wasm-input` feature. The listener is removed again when dropped, so
+// `detach_keyboard()` or freeing the game leaves nothing behind on the page.
+
+use alloc::boxed::Box;
+use alloc::rc::Weak;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{KeyboardEvent, Window};
+
+use super::Inner;
+use crate::InputAction;
+
+pub struct KeyboardListener {
+    window: Window,
+    callback: Closure<dyn FnMut(KeyboardEvent)>,
+}
+
+impl KeyboardListener {
+    pub fn attach(inner: Weak<Inner>) -> Result<KeyboardListener, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+        let callback = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+            let Some(inner) = inner.upgrade() else { return };
+            let Some(action) = InputAction::from_key(&event.key()) else { return };
+            // Holding a key down would keep toggling pause/boost otherwise
+            if event.repeat() && !matches!(action, InputAction::Turn(_)) {
+                return;
+            }
+            event.prevent_default();
+            inner.game.borrow_mut().handle_action(action);
+            inner.dispatch_events();
+        }) as Box<dyn FnMut(KeyboardEvent)>);
+
+        window.add_event_listener_with_callback("keydown", callback.as_ref().unchecked_ref())?;
+        Ok(KeyboardListener { window, callback })
+    }
+}
+
+impl Drop for KeyboardListener {
+    fn drop(&mut self) {
+        let _ = self
+            .window
+            .remove_event_listener_with_callback("keydown", self.callback.as_ref().unchecked_ref());
+    }
+}
@@ -0,0 +1,1058 @@
+// src/wasm/mod.rs
+
+// This module is only compiled for the wasm32 target (see lib.rs)
+
+use super::*; // Import everything from the parent module
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use core::cell::{Ref, RefCell, RefMut};
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm-audio")]
+mod audio;
+mod dpad;
+mod error;
+#[cfg(feature = "wasm-render")]
+mod frame_loop;
+#[cfg(feature = "wasm-gamepad")]
+mod gamepad;
+mod gesture;
+#[cfg(feature = "wasm-input")]
+mod keyboard;
+#[cfg(feature = "wasm-render")]
+mod render;
+#[cfg(feature = "wasm-storage")]
+mod storage;
+mod types;
+#[cfg(feature = "wasm-webrtc")]
+mod webrtc;
+mod worker;
+
+use error::{game_error, options_error};
+use gesture::{SwipeRecognizer, DEFAULT_DEAD_ZONE};
+use types::{JsEventListener, JsFoods, JsGameOptions, JsGameState, JsTheme, JsTickSummary};
+
+// This is a wrapper around the main `Game` struct that will be exposed to JS.
+// The state lives behind an `Rc` so the built-in frame loop can reach it too.
+#[wasm_bindgen(js_name = Game)]
+pub struct WasmGame {
+    inner: Rc<Inner>,
+    swipe: SwipeRecognizer, // Fed by the touch_* methods
+    dpad: Option<dpad::Dpad>, // Set by `layout_dpad`
+}
+
+pub(crate) struct Inner {
+    game: RefCell<Game>,
+    listeners: RefCell<Vec<js_sys::Function>>, // Registered with `on_event`
+    #[cfg(feature = "wasm-render")]
+    renderer: RefCell<Option<render::CanvasRenderer>>, // Set by `attach_canvas`
+    #[cfg(feature = "wasm-render")]
+    frame_loop: RefCell<Option<frame_loop::FrameLoop>>, // Set by `run`
+    #[cfg(feature = "wasm-input")]
+    keyboard: RefCell<Option<keyboard::KeyboardListener>>, // Set by `attach_keyboard`
+    #[cfg(feature = "wasm-audio")]
+    sfx: RefCell<Option<audio::Sfx>>, // Set by `enable_audio`
+    #[cfg(feature = "wasm-gamepad")]
+    gamepad: RefCell<Option<gamepad::GamepadPoller>>, // Set by `attach_gamepad`
+    #[cfg(feature = "wasm-storage")]
+    storage: RefCell<Option<Persistence<storage::LocalStorage>>>, // Set by `enable_storage`
+}
+
+impl Inner {
+    // Applies whatever the gamepads did since the last poll. Returns whether
+    // any action was taken.
+    #[cfg(feature = "wasm-gamepad")]
+    fn poll_gamepad(&self) -> bool {
+        let actions = match self.gamepad.borrow_mut().as_mut().map(|poller| poller.poll()) {
+            Some(Ok(actions)) => actions,
+            Some(Err(err)) => {
+                web_sys::console::error_1(&err);
+                return false;
+            }
+            None => return false,
+        };
+        let mut game = self.game.borrow_mut();
+        for &action in &actions {
+            game.handle_action(action);
+        }
+        !actions.is_empty()
+    }
+
+    // Hands every queued core event to the JS listeners. The queue is drained
+    // even without listeners so it can't grow forever. No borrow is held while
+    // the listeners run, so they're free to call back into the game.
+    fn dispatch_events(&self) {
+        let events: Vec<GameEvent> = self.game.borrow_mut().drain_events().collect();
+        #[cfg(feature = "wasm-audio")]
+        if let Some(sfx) = self.sfx.borrow().as_ref() {
+            for effect in events.iter().filter_map(SoundEffect::for_event) {
+                if let Err(err) = sfx.play(effect) {
+                    web_sys::console::error_1(&err);
+                }
+            }
+        }
+        #[cfg(feature = "wasm-storage")]
+        if let Some(storage) = self.storage.borrow_mut().as_mut() {
+            for event in &events {
+                if let GameEvent::GameOver { score, .. } = event {
+                    // A finished game is not worth resuming. The score goes
+                    // under the last name the player entered.
+                    let name = storage.load_settings().player_name;
+                    let saved = storage.record_score(&name, *score).and_then(|_| storage.clear_autosave());
+                    if let Err(err) = saved {
+                        web_sys::console::error_1(&game_error(err));
+                    }
+                    let stats = self.game.borrow().run_stats();
+                    if stats.cheats == 0 {
+                        if let Err(err) = storage.unlock_skins(&Skins::builtin(), &stats) {
+                            web_sys::console::error_1(&game_error(err));
+                        }
+                    }
+                }
+            }
+        }
+        let listeners = self.listeners.borrow().clone();
+        if listeners.is_empty() {
+            return;
+        }
+        for event in events {
+            let value = match serde_wasm_bindgen::to_value(&event) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            for listener in &listeners {
+                // A throwing listener must not stop the game or the other listeners
+                if let Err(err) = listener.call1(&JsValue::NULL, &value) {
+                    web_sys::console::error_1(&err);
+                }
+            }
+        }
+    }
+}
+
+impl WasmGame {
+    fn wrap(game: Game) -> WasmGame {
+        WasmGame {
+            inner: Rc::new(Inner {
+                game: RefCell::new(game),
+                listeners: RefCell::new(Vec::new()),
+                #[cfg(feature = "wasm-render")]
+                renderer: RefCell::new(None),
+                #[cfg(feature = "wasm-render")]
+                frame_loop: RefCell::new(None),
+                #[cfg(feature = "wasm-input")]
+                keyboard: RefCell::new(None),
+                #[cfg(feature = "wasm-audio")]
+                sfx: RefCell::new(None),
+                #[cfg(feature = "wasm-gamepad")]
+                gamepad: RefCell::new(None),
+                #[cfg(feature = "wasm-storage")]
+                storage: RefCell::new(None),
+            }),
+            swipe: SwipeRecognizer::new(DEFAULT_DEAD_ZONE),
+            dpad: None,
+        }
+    }
+
+    fn game(&self) -> Ref<'_, Game> {
+        self.inner.game.borrow()
+    }
+
+    fn game_mut(&self) -> RefMut<'_, Game> {
+        self.inner.game.borrow_mut()
+    }
+
+    fn dispatch_events(&self) {
+        self.inner.dispatch_events();
+    }
+
+    fn queue_touch_turn(&mut self, direction: Option<Direction>) -> bool {
+        match direction {
+            Some(direction) => {
+                self.game_mut().handle_action(InputAction::Turn(direction));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl WasmGame {
+    // Invalid dimensions are thrown as a JS exception. Pass a BigInt seed
+    // (`new Game(20, 20, 42n)`) to get the same food placement as every
+    // other client using that seed, e.g. for daily challenges or lockstep play.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: i32, height: i32, seed: Option<u64>) -> Result<WasmGame, JsValue> {
+        let game = match seed {
+            Some(seed) => Game::from_level_with_seed(&Level::empty(width, height), seed),
+            None => Game::new(width, height),
+        };
+        game.map(WasmGame::wrap)
+            .map_err(game_error)
+    }
+
+    // Builds a game from an options object, every field is optional:
+    // `{ width, height, seed, mode, wrapWalls, foodCount, speedCurve, snakeLength }`
+    // `mode` is "Classic" or `{ Timed: { limitSecs } }`, `speedCurve` is
+    // `{ baseIntervalMs, stepMs, minIntervalMs, pointsPerLevel }`.
+    #[wasm_bindgen(js_name = new_with_options)]
+    pub fn new_with_options(options: JsGameOptions) -> Result<WasmGame, JsValue> {
+        let options = JsValue::from(options);
+        let config: GameConfig = if options.is_undefined() || options.is_null() {
+            GameConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(options_error)?
+        };
+        Game::from_config(&config)
+            .map(WasmGame::wrap)
+            .map_err(game_error)
+    }
+
+    // Same as the constructor but with a custom starting snake
+    #[wasm_bindgen(js_name = with_spawn)]
+    pub fn with_spawn(
+        width: i32,
+        height: i32,
+        x: i32,
+        y: i32,
+        direction: WasmDirection,
+        length: usize,
+    ) -> Result<WasmGame, JsValue> {
+        let mut level = Level::empty(width, height);
+        level.spawn = SnakeSpawn { head: Point { x, y }, direction: direction.into(), length };
+        Game::from_level(&level)
+            .map(WasmGame::wrap)
+            .map_err(game_error)
+    }
+
+    // Builds a game from the level text format (see `Level::parse`)
+    #[wasm_bindgen(js_name = from_level)]
+    pub fn from_level(text: &str) -> Result<WasmGame, JsValue> {
+        Level::parse(text)
+            .and_then(|level| Game::from_level(&level))
+            .map(WasmGame::wrap)
+            .map_err(game_error)
+    }
+
+    pub fn tick(&mut self) {
+        self.game_mut().tick();
+        self.dispatch_events();
+    }
+
+    // Up to `n` ticks in one call instead of n calls from JS, for skipping
+    // ahead or running an AI at full speed. The listeners get the events of
+    // every tick afterwards. Returns what happened, see `TickSummary`.
+    #[wasm_bindgen(js_name = tick_n)]
+    pub fn tick_n(&mut self, n: u32) -> Result<JsTickSummary, JsValue> {
+        let summary = self.game_mut().tick_n(n.into());
+        self.dispatch_events();
+        Ok(serde_wasm_bindgen::to_value(&summary)?.unchecked_into())
+    }
+
+    // Registers a callback receiving every game event as a plain object with
+    // a `type` field, e.g. `{ type: "FoodEaten", at: { x, y }, score }` or
+    // `{ type: "GameOver", cause: { kind: "Wall", at: { x, y } }, score }`
+    #[wasm_bindgen(js_name = on_event)]
+    pub fn on_event(&mut self, callback: JsEventListener) {
+        self.inner.listeners.borrow_mut().push(callback.unchecked_into());
+    }
+
+    // Removes a callback registered with `on_event`
+    #[wasm_bindgen(js_name = off_event)]
+    pub fn off_event(&mut self, callback: &JsEventListener) {
+        let callback: &JsValue = callback.as_ref();
+        self.inner.listeners.borrow_mut().retain(|listener| JsValue::from(listener) != *callback);
+    }
+
+    // Call once per animation frame with the milliseconds since the last
+    // frame, the game runs the right number of ticks for its speed level
+    pub fn advance(&mut self, elapsed_ms: f64) -> u32 {
+        // Negative, NaN or infinite times from JS count as no time
+        let elapsed = Duration::try_from_secs_f64(elapsed_ms / 1000.0).unwrap_or(Duration::ZERO);
+        let ticks = self.game_mut().advance(elapsed);
+        self.dispatch_events();
+        ticks
+    }
+
+    // The seed the game was created with, as a BigInt
+    pub fn seed(&self) -> u64 {
+        self.game().seed()
+    }
+
+    // The seed the current round started from, what its replay uses (BigInt)
+    #[wasm_bindgen(js_name = round_seed)]
+    pub fn round_seed(&self) -> u64 {
+        self.game().round_seed()
+    }
+
+    // Compare between clients after the same tick to detect a desync
+    // (BigInt, see `Game::state_hash`)
+    #[wasm_bindgen(js_name = state_hash)]
+    pub fn state_hash(&self) -> u64 {
+        self.game().state_hash()
+    }
+
+    // Milliseconds between two ticks right now: the speed curve at the current
+    // level, stretched by the slow-down power-up and halved while boosting.
+    // For pages driving `tick()` from their own timer instead of `advance`.
+    #[wasm_bindgen(js_name = tick_interval_ms)]
+    pub fn tick_interval_ms(&self) -> f64 {
+        self.game().tick_interval().as_secs_f64() * 1000.0
+    }
+
+    #[wasm_bindgen(js_name = handle_action)]
+    pub fn handle_action(&mut self, action: WasmAction) {
+        self.game_mut().handle_action(action.into());
+        self.dispatch_events();
+    }
+
+    // Takes a `KeyboardEvent.key` value ("ArrowUp", "w", "up", " ", "p"...)
+    // and queues the matching action. Returns false for keys the game doesn't
+    // use, so the page knows whether to call `preventDefault()`.
+    #[wasm_bindgen(js_name = queue_input_str)]
+    pub fn queue_input_str(&mut self, key: &str) -> bool {
+        match InputAction::from_key(key) {
+            Some(action) => {
+                self.game_mut().handle_action(action);
+                self.dispatch_events();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Listens to keydown on the window and feeds the game directly: arrows
+    // and WASD steer, space confirms, P/Escape pause, R restarts, B boosts.
+    // Call `detach_keyboard` (or `free`) when the game is torn down.
+    #[cfg(feature = "wasm-input")]
+    #[wasm_bindgen(js_name = attach_keyboard)]
+    pub fn attach_keyboard(&self) -> Result<(), JsValue> {
+        let mut keyboard = self.inner.keyboard.borrow_mut();
+        if keyboard.is_none() {
+            *keyboard = Some(keyboard::KeyboardListener::attach(Rc::downgrade(&self.inner))?);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "wasm-input")]
+    #[wasm_bindgen(js_name = detach_keyboard)]
+    pub fn detach_keyboard(&self) {
+        self.inner.keyboard.borrow_mut().take();
+    }
+
+    // Starts reading gamepads: d-pad or left stick steer, A confirms,
+    // B boosts and Start pauses. The built-in loop (`run`) polls them every
+    // frame, custom loops call `poll_gamepad` once per animation frame.
+    #[cfg(feature = "wasm-gamepad")]
+    #[wasm_bindgen(js_name = attach_gamepad)]
+    pub fn attach_gamepad(&self) {
+        let mut gamepad = self.inner.gamepad.borrow_mut();
+        if gamepad.is_none() {
+            *gamepad = Some(gamepad::GamepadPoller::new(DEFAULT_STICK_DEAD_ZONE));
+        }
+    }
+
+    #[cfg(feature = "wasm-gamepad")]
+    #[wasm_bindgen(js_name = detach_gamepad)]
+    pub fn detach_gamepad(&self) {
+        self.inner.gamepad.borrow_mut().take();
+    }
+
+    // Returns whether any gamepad input was applied
+    #[cfg(feature = "wasm-gamepad")]
+    #[wasm_bindgen(js_name = poll_gamepad)]
+    pub fn poll_gamepad(&self) -> bool {
+        let applied = self.inner.poll_gamepad();
+        self.dispatch_events();
+        applied
+    }
+
+    // How far (0.0 to 1.0) the stick must be pushed to steer, 0.5 by default
+    #[cfg(feature = "wasm-gamepad")]
+    #[wasm_bindgen(js_name = set_stick_dead_zone)]
+    pub fn set_stick_dead_zone(&self, dead_zone: f64) {
+        if let Some(poller) = self.inner.gamepad.borrow_mut().as_mut() {
+            poller.dead_zone = dead_zone.clamp(0.0, 1.0);
+        }
+    }
+
+    // Turns sound effects on. Browsers block audio until the user interacts
+    // with the page, so call it from a click or key handler.
+    #[cfg(feature = "wasm-audio")]
+    #[wasm_bindgen(js_name = enable_audio)]
+    pub fn enable_audio(&self) -> Result<(), JsValue> {
+        let mut sfx = self.inner.sfx.borrow_mut();
+        match sfx.as_ref() {
+            Some(sfx) => sfx.resume(),
+            None => *sfx = Some(audio::Sfx::new(0.5, false)?),
+        }
+        Ok(())
+    }
+
+    // Volume from 0.0 to 1.0, only takes effect after `enable_audio`
+    #[cfg(feature = "wasm-audio")]
+    #[wasm_bindgen(js_name = set_volume)]
+    pub fn set_volume(&self, volume: f32) {
+        if let Some(sfx) = self.inner.sfx.borrow_mut().as_mut() {
+            sfx.set_volume(volume);
+        }
+    }
+
+    #[cfg(feature = "wasm-audio")]
+    pub fn volume(&self) -> f32 {
+        self.inner.sfx.borrow().as_ref().map_or(0.5, |sfx| sfx.volume())
+    }
+
+    #[cfg(feature = "wasm-audio")]
+    #[wasm_bindgen(js_name = set_muted)]
+    pub fn set_muted(&self, muted: bool) {
+        if let Some(sfx) = self.inner.sfx.borrow_mut().as_mut() {
+            sfx.set_muted(muted);
+        }
+    }
+
+    #[cfg(feature = "wasm-audio")]
+    pub fn muted(&self) -> bool {
+        self.inner.sfx.borrow().as_ref().is_some_and(|sfx| sfx.muted())
+    }
+
+    // Keeps this game's progress in localStorage under `prefix` (see
+    // `SaveStore`): scores are recorded when a game ends and `save_game` /
+    // `load_game` keep a running game across reloads. Call `save_game` from
+    // a `visibilitychange` or `pagehide` handler to autosave.
+    #[cfg(feature = "wasm-storage")]
+    #[wasm_bindgen(js_name = enable_storage)]
+    pub fn enable_storage(&self, prefix: Option<String>) -> Result<(), JsValue> {
+        *self.inner.storage.borrow_mut() = Some(storage::open(prefix)?);
+        Ok(())
+    }
+
+    // Saves the game if one is in progress, returns whether it did
+    #[cfg(feature = "wasm-storage")]
+    #[wasm_bindgen(js_name = save_game)]
+    pub fn save_game(&self) -> Result<bool, JsValue> {
+        let game = self.game();
+        let mut storage = self.inner.storage.borrow_mut();
+        let Some(storage) = storage.as_mut() else { return Ok(false) };
+        if !game.game_started || game.game_over {
+            return Ok(false);
+        }
+        storage.save_autosave(&game.snapshot()).map_err(game_error)?;
+        Ok(true)
+    }
+
+    // Restores the autosave, paused so the player can get ready. Returns
+    // false if there is none, it was saved on a different board or by an
+    // older version.
+    #[cfg(feature = "wasm-storage")]
+    #[wasm_bindgen(js_name = load_game)]
+    pub fn load_game(&self) -> bool {
+        let snapshot = match self.inner.storage.borrow().as_ref() {
+            Some(storage) => storage.load_autosave(&self.game()),
+            None => None,
+        };
+        let Some(snapshot) = snapshot else { return false };
+        let mut game = self.game_mut();
+        game.restore(&snapshot);
+        game.pause();
+        true
+    }
+
+    #[cfg(feature = "wasm-storage")]
+    #[wasm_bindgen(js_name = clear_saved_game)]
+    pub fn clear_saved_game(&self) -> Result<(), JsValue> {
+        match self.inner.storage.borrow_mut().as_mut() {
+            Some(storage) => storage.clear_autosave().map_err(game_error),
+            None => Ok(()),
+        }
+    }
+
+    // Touch swipes: forward touchstart/touchmove/touchend coordinates (e.g.
+    // `touch.clientX`, `touch.clientY`) and swipes past the dead zone are
+    // queued as turns. `touch_move`/`touch_end` return true when they turned.
+    #[wasm_bindgen(js_name = touch_start)]
+    pub fn touch_start(&mut self, x: f64, y: f64) {
+        self.swipe.start(x, y);
+    }
+
+    #[wasm_bindgen(js_name = touch_move)]
+    pub fn touch_move(&mut self, x: f64, y: f64) -> bool {
+        let direction = self.swipe.moved(x, y);
+        self.queue_touch_turn(direction)
+    }
+
+    #[wasm_bindgen(js_name = touch_end)]
+    pub fn touch_end(&mut self, x: f64, y: f64) -> bool {
+        let direction = self.swipe.end(x, y);
+        self.queue_touch_turn(direction)
+    }
+
+    #[wasm_bindgen(js_name = touch_cancel)]
+    pub fn touch_cancel(&mut self) {
+        self.swipe.cancel();
+    }
+
+    // Minimum swipe length in the same units as the touch coordinates
+    #[wasm_bindgen(js_name = set_swipe_dead_zone)]
+    pub fn set_swipe_dead_zone(&mut self, pixels: f64) {
+        self.swipe.dead_zone = pixels.max(0.0);
+    }
+
+    // On-screen arrow buttons: lays out four buttons in the bottom-right
+    // corner of a canvas of the given size (call again when it resizes).
+    // Draw them from `dpad_regions` and forward taps/clicks, in canvas
+    // pixels, to `dpad_press`.
+    #[wasm_bindgen(js_name = layout_dpad)]
+    pub fn layout_dpad(&mut self, canvas_width: f64, canvas_height: f64) {
+        self.dpad = Some(dpad::Dpad::layout(canvas_width, canvas_height));
+    }
+
+    #[wasm_bindgen(js_name = clear_dpad)]
+    pub fn clear_dpad(&mut self) {
+        self.dpad = None;
+    }
+
+    // `[x, y, width, height]` of the Up, Down, Left and Right buttons one
+    // after the other, empty before `layout_dpad`
+    #[wasm_bindgen(js_name = dpad_regions)]
+    pub fn dpad_regions(&self) -> Vec<f64> {
+        self.dpad
+            .iter()
+            .flat_map(|dpad| dpad.buttons.iter().flat_map(|b| [b.x, b.y, b.size, b.size]))
+            .collect()
+    }
+
+    // Queues the turn of the button under the point, returns false when no
+    // button was hit so the page can handle the tap itself
+    #[wasm_bindgen(js_name = dpad_press)]
+    pub fn dpad_press(&mut self, x: f64, y: f64) -> bool {
+        let direction = self.dpad.as_ref().and_then(|dpad| dpad.hit(x, y));
+        self.queue_touch_turn(direction)
+    }
+
+    #[wasm_bindgen(js_name = change_snake_direction)]
+    pub fn change_snake_direction(&mut self, direction: WasmDirection) {
+        self.game_mut().change_snake_direction(direction.into());
+    }
+
+    // New method to expose to JS
+    #[wasm_bindgen(js_name = start_game)]
+    pub fn start_game(&mut self) {
+        self.game_mut().start_game();
+        self.dispatch_events();
+    }
+
+    pub fn pause(&mut self) {
+        self.game_mut().pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.game_mut().resume();
+    }
+
+    #[wasm_bindgen(js_name = toggle_pause)]
+    pub fn toggle_pause(&mut self) {
+        self.game_mut().toggle_pause();
+    }
+
+    // Draw into the <canvas> with this id from now on, `cell_size` pixels per
+    // cell. The canvas is resized to fit the board.
+    #[cfg(feature = "wasm-render")]
+    #[wasm_bindgen(js_name = attach_canvas)]
+    pub fn attach_canvas(&mut self, canvas_id: &str, cell_size: f64) -> Result<(), JsValue> {
+        let renderer = render::CanvasRenderer::attach(canvas_id, &self.game(), cell_size)?;
+        *self.inner.renderer.borrow_mut() = Some(renderer);
+        Ok(())
+    }
+
+    // Runs the whole game from Rust: attaches the canvas, then ticks at the
+    // configured speed and redraws on every animation frame until `stop`.
+    // The page only has to forward input.
+    #[cfg(feature = "wasm-render")]
+    pub fn run(&mut self, canvas_id: &str, cell_size: f64) -> Result<(), JsValue> {
+        self.attach_canvas(canvas_id, cell_size)?;
+        let mut frame_loop = self.inner.frame_loop.borrow_mut();
+        frame_loop
+            .get_or_insert_with(|| frame_loop::FrameLoop::new(Rc::downgrade(&self.inner)))
+            .start()
+    }
+
+    // Stops the loop started by `run`, `run` can start it again later
+    #[cfg(feature = "wasm-render")]
+    pub fn stop(&self) {
+        if let Some(frame_loop) = self.inner.frame_loop.borrow().as_ref() {
+            frame_loop.stop();
+        }
+    }
+
+    // Draws the current frame, call it once per animation frame
+    #[cfg(feature = "wasm-render")]
+    pub fn render(&self) -> Result<(), JsValue> {
+        match self.inner.renderer.borrow().as_ref() {
+            Some(renderer) => renderer.draw(&mut self.game_mut()),
+            None => Err(JsValue::from_str("call attach_canvas before render")),
+        }
+    }
+
+    // The current (or last finished) round as a URL-safe base64 string, e.g.
+    // for a "watch my run" link
+    #[wasm_bindgen(js_name = export_replay)]
+    pub fn export_replay(&self) -> String {
+        self.game().replay().to_base64()
+    }
+
+    // Replaces this game with the playback of an exported replay. It plays
+    // itself as the game advances, player turns are ignored. Throws if the
+    // data can't be decoded.
+    #[wasm_bindgen(js_name = import_replay)]
+    pub fn import_replay(&mut self, data: &str) -> Result<(), JsValue> {
+        let game = Replay::from_base64(data)
+            .and_then(|replay| replay.game())
+            .map_err(game_error)?;
+        *self.game_mut() = game;
+        self.dispatch_events();
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = is_replay)]
+    pub fn is_replay(&self) -> bool {
+        self.game().is_replay()
+    }
+
+    // True once a replay has played to the end of its recording
+    #[wasm_bindgen(js_name = replay_finished)]
+    pub fn replay_finished(&self) -> bool {
+        self.game().replay_finished()
+    }
+
+    // The whole game state as a plain JS object, see `GameState`
+    pub fn state(&self) -> Result<JsGameState, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game().state())?.unchecked_into())
+    }
+
+    // Colors to draw with, see `Theme`. The built-in renderer uses them too.
+    pub fn theme(&self) -> Result<JsTheme, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game().theme)?.unchecked_into())
+    }
+
+    #[wasm_bindgen(js_name = set_theme)]
+    pub fn set_theme(&mut self, theme: JsTheme) -> Result<(), JsValue> {
+        self.game_mut().theme = serde_wasm_bindgen::from_value(theme.into()).map_err(options_error)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = set_theme_preset)]
+    pub fn set_theme_preset(&mut self, preset: WasmThemePreset) {
+        self.game_mut().theme = Theme::preset(preset.into());
+    }
+
+    // Puts the player's snake in one of the built-in skins ("classic",
+    // "forest"...). With `enable_storage` the ones the saved games unlocked
+    // can be picked too. Returns false for a skin that's unknown or locked.
+    #[wasm_bindgen(js_name = set_skin)]
+    pub fn set_skin(&self, id: &str) -> bool {
+        #[cfg(feature = "wasm-storage")]
+        let unlocked = self.inner.storage.borrow().as_ref().map(|s| s.load_unlocked_skins()).unwrap_or_default();
+        #[cfg(not(feature = "wasm-storage"))]
+        let unlocked = Vec::new();
+        let skins = Skins::builtin();
+        if !skins.available(&unlocked).any(|skin| skin.id == id) {
+            return false;
+        }
+        self.game_mut().theme.skin = skins.pattern(id, &unlocked);
+        true
+    }
+
+    // CSS color of segment `index` (0 is the head) of the player's snake,
+    // in the theme's skin
+    #[wasm_bindgen(js_name = segment_color)]
+    pub fn segment_color(&self, index: usize) -> String {
+        self.game().segment_color(0, index).to_hex()
+    }
+
+    // Whether the death animation fades instead of flashing, also set with
+    // the `reducedMotion` option. Pages can pass the
+    // `prefers-reduced-motion` media query here and read it back to tone
+    // down their own effects.
+    #[wasm_bindgen(js_name = reduced_motion)]
+    pub fn reduced_motion(&self) -> bool {
+        self.game().reduced_motion
+    }
+
+    #[wasm_bindgen(js_name = set_reduced_motion)]
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.game_mut().reduced_motion = reduced;
+    }
+
+    // Grid lines or a checkerboard behind the board, for the built-in
+    // renderer. Call after `attach_canvas`.
+    #[cfg(feature = "wasm-render")]
+    #[wasm_bindgen(js_name = set_board_pattern)]
+    pub fn set_board_pattern(&self, pattern: WasmBoardPattern) -> Result<(), JsValue> {
+        match self.inner.renderer.borrow_mut().as_mut() {
+            Some(renderer) => {
+                renderer.pattern = pattern.into();
+                renderer.redraw_all();
+                Ok(())
+            }
+            None => Err(JsValue::from_str("call attach_canvas before set_board_pattern")),
+        }
+    }
+
+    // Whether the built-in renderer slides the snakes between cells (the
+    // default). Without it they jump from cell to cell and frames only
+    // redraw the cells that changed, much cheaper on big boards. Call after
+    // `attach_canvas`.
+    #[cfg(feature = "wasm-render")]
+    #[wasm_bindgen(js_name = set_smooth_motion)]
+    pub fn set_smooth_motion(&self, smooth: bool) -> Result<(), JsValue> {
+        match self.inner.renderer.borrow_mut().as_mut() {
+            Some(renderer) => {
+                renderer.smooth = smooth;
+                renderer.redraw_all();
+                Ok(())
+            }
+            None => Err(JsValue::from_str("call attach_canvas before set_smooth_motion")),
+        }
+    }
+
+    // Getters that return copies of data
+    pub fn width(&self) -> i32 { self.game().width }
+    pub fn height(&self) -> i32 { self.game().height }
+    // Position of the first apple, kept for pages drawing a single food item
+    pub fn food(&self) -> WasmPoint {
+        self.game()
+            .foods
+            .iter()
+            .find(|f| f.kind == FoodKind::Apple)
+            .map_or(WasmPoint { x: -1, y: -1 }, |f| f.pos.into())
+    }
+    #[wasm_bindgen(js_name = slow_ticks_left)]
+    pub fn slow_ticks_left(&self) -> u32 { self.game().slow_ticks_left() }
+    pub fn score(&self) -> u32 { self.game().score }
+    #[wasm_bindgen(js_name = game_over)]
+    pub fn game_over(&self) -> bool { self.game().game_over }
+    // Why the game ended as a sentence ("Hit the wall at (20, 4)"), see
+    // `state().death_cause` for the details
+    #[wasm_bindgen(js_name = death_message)]
+    pub fn death_message(&self) -> Option<String> { self.game().death_cause.map(|c| c.to_string()) }
+    #[wasm_bindgen(js_name = game_started)] // Expose new field
+    pub fn game_started(&self) -> bool { self.game().game_started }
+    pub fn paused(&self) -> bool { self.game().paused }
+    pub fn boosting(&self) -> bool { self.game().boosting }
+    // Numbers instead of BigInt, exact for any realistic game length
+    #[wasm_bindgen(js_name = tick_count)]
+    pub fn tick_count(&self) -> f64 { self.game().tick_count() as f64 }
+    #[wasm_bindgen(js_name = elapsed_ms)]
+    pub fn elapsed_ms(&self) -> f64 { self.game().elapsed().as_secs_f64() * 1000.0 }
+    // Milliseconds of game time left in timed mode, undefined otherwise
+    #[wasm_bindgen(js_name = time_left_ms)]
+    pub fn time_left_ms(&self) -> Option<f64> {
+        self.game().time_left().map(|t| t.as_secs_f64() * 1000.0)
+    }
+    #[wasm_bindgen(js_name = wrap_walls)]
+    pub fn wrap_walls(&self) -> bool { self.game().wrap_walls }
+    #[wasm_bindgen(js_name = speed_level)]
+    pub fn speed_level(&self) -> u32 { self.game().speed_level() }
+
+    // Snake body as interleaved x/y pairs, head first, arriving in JS as an
+    // Int32Array. It's a copy on purpose: a pointer into the body Vec would
+    // dangle as soon as the snake grows and the Vec reallocates.
+    #[wasm_bindgen(js_name = body_coords)]
+    pub fn body_coords(&self) -> Vec<i32> {
+        self.game().snake.body.iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    // All food items as [x, y, kind, ttl] quadruples in an Int32Array.
+    // `kind` is a `FoodKind` value, `ttl` is -1 for food that never expires.
+    #[wasm_bindgen(js_name = foods_flat)]
+    pub fn foods_flat(&self) -> Vec<i32> {
+        self.game()
+            .foods
+            .iter()
+            .flat_map(|f| {
+                let ttl = f.ttl.map_or(-1, |t| t as i32);
+                [f.pos.x, f.pos.y, WasmFoodKind::from(f.kind) as i32, ttl]
+            })
+            .collect()
+    }
+
+    // Same data as `foods_flat` as an array of `{ pos: { x, y }, kind, ttl }`
+    // objects, `kind` being the variant name and `ttl` null when it never expires
+    pub fn foods(&self) -> Result<JsFoods, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game().foods)?.unchecked_into())
+    }
+
+    // Level walls as interleaved x/y pairs
+    #[wasm_bindgen(js_name = obstacles_flat)]
+    pub fn obstacles_flat(&self) -> Vec<i32> {
+        self.game().level.walls.iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    // Portals as [x1, y1, x2, y2] quadruples, one per linked pair
+    #[wasm_bindgen(js_name = portals_flat)]
+    pub fn portals_flat(&self) -> Vec<i32> {
+        self.game().level.portals.iter().flat_map(|(a, b)| [a.x, a.y, b.x, b.y]).collect()
+    }
+
+    // Heatmap of where the player's head has been this round: ticks spent
+    // on each cell, row-major. Empty unless `set_track_visits(true)` was
+    // called before the round started. Replays always track them.
+    pub fn visits(&self) -> Vec<u32> {
+        self.game().visits().to_vec()
+    }
+
+    // The highest of `visits`, for scaling the colors
+    #[wasm_bindgen(js_name = max_visits)]
+    pub fn max_visits(&self) -> u32 {
+        self.game().max_visits()
+    }
+
+    #[wasm_bindgen(js_name = set_track_visits)]
+    pub fn set_track_visits(&mut self, track: bool) {
+        self.game_mut().track_visits = track;
+    }
+
+    #[wasm_bindgen(js_name = get_body_len)]
+    pub fn get_body_len(&self) -> usize {
+        self.game().snake.body.len()
+    }
+
+    // Multiple snakes: snake 0 is the player's, opponents are numbered from 1.
+    // Opponents without a controller are steered with `queue_turn_for`.
+
+    // Adds a snake at the given spot, returns its index
+    #[wasm_bindgen(js_name = add_opponent)]
+    pub fn add_opponent(
+        &mut self,
+        x: i32,
+        y: i32,
+        direction: WasmDirection,
+        length: usize,
+        controller: Option<WasmController>,
+    ) -> Result<usize, JsValue> {
+        let spawn = SnakeSpawn { head: Point { x, y }, direction: direction.into(), length };
+        self.game_mut().add_opponent(spawn, controller.map(Controller::from)).map_err(game_error)
+    }
+
+    // Adds a snake wherever there's room, returns its index
+    #[wasm_bindgen(js_name = add_opponent_auto)]
+    pub fn add_opponent_auto(&mut self, controller: Option<WasmController>) -> Result<usize, JsValue> {
+        self.game_mut().add_opponent_auto(controller.map(Controller::from)).map_err(game_error)
+    }
+
+    // Hands a snake to a built-in AI, or back to the player with undefined
+    #[wasm_bindgen(js_name = set_controller)]
+    pub fn set_controller(&mut self, index: usize, controller: Option<WasmController>) {
+        self.game_mut().set_controller(index, controller.map(Controller::from));
+    }
+
+    #[wasm_bindgen(js_name = queue_turn_for)]
+    pub fn queue_turn_for(&mut self, index: usize, direction: WasmDirection) {
+        self.game_mut().queue_turn_for(index, direction.into());
+    }
+
+    #[wasm_bindgen(js_name = snake_count)]
+    pub fn snake_count(&self) -> usize {
+        self.game().snake_count()
+    }
+
+    // Interleaved x/y pairs, head first. Empty for dead opponents.
+    #[wasm_bindgen(js_name = snake_body)]
+    pub fn snake_body(&self, index: usize) -> Vec<i32> {
+        self.game().snake_body(index).iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    #[wasm_bindgen(js_name = snake_score)]
+    pub fn snake_score(&self, index: usize) -> u32 {
+        self.game().snake_score(index)
+    }
+
+    #[wasm_bindgen(js_name = snake_alive)]
+    pub fn snake_alive(&self, index: usize) -> bool {
+        self.game().snake_alive(index)
+    }
+
+    // Whether the snake's player is away, see `afkTicks` in the options
+    #[wasm_bindgen(js_name = is_afk)]
+    pub fn is_afk(&self, index: usize) -> bool {
+        self.game().is_afk(index)
+    }
+
+    // Interleaved x/y pairs in fractional cells, part way between the last
+    // two ticks. Draw these every animation frame for smooth movement.
+    #[wasm_bindgen(js_name = interpolated_body)]
+    pub fn interpolated_body(&self, index: usize) -> Vec<f32> {
+        self.game().interpolated_body(index).iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
+
+    // 0.0 right after a tick up to 1.0 just before the next one
+    #[wasm_bindgen(js_name = tick_progress)]
+    pub fn tick_progress(&self) -> f32 {
+        self.game().tick_progress()
+    }
+
+    // The number to show before the snake starts moving, undefined after
+    pub fn countdown(&self) -> Option<u32> {
+        self.game().countdown()
+    }
+
+    // How many segments of a snake to draw, fewer while the death
+    // animation plays. Take them from the head end of `interpolated_body`.
+    #[wasm_bindgen(js_name = visible_segments)]
+    pub fn visible_segments(&self, index: usize) -> usize {
+        self.game().visible_segments(index)
+    }
+
+    // How opaque to draw a snake (0 to 1), below 1 while the player's snake
+    // fades out with reduced motion
+    #[wasm_bindgen(js_name = snake_opacity)]
+    pub fn snake_opacity(&self, index: usize) -> f32 {
+        self.game().snake_opacity(index)
+    }
+
+    // The pathfinder's route to the closest food as interleaved x/y pairs
+    #[wasm_bindgen(js_name = ai_path)]
+    pub fn ai_path(&self, index: usize) -> Vec<i32> {
+        self.game().ai_path(index).iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+}
+
+// We need to create wasm-bindgen compatible versions of our enums and structs
+// because the original ones are now pure Rust.
+#[wasm_bindgen(js_name = Direction)]
+#[derive(Clone, Copy)]
+pub enum WasmDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// Conversion from WasmDirection to the core Direction
+impl From<WasmDirection> for Direction {
+    fn from(d: WasmDirection) -> Self {
+        match d {
+            WasmDirection::Up => Direction::Up,
+            WasmDirection::Down => Direction::Down,
+            WasmDirection::Left => Direction::Left,
+            WasmDirection::Right => Direction::Right,
+        }
+    }
+}
+
+// Flat version of `InputAction`, wasm-bindgen enums can't carry data
+#[wasm_bindgen(js_name = Action)]
+#[derive(Clone, Copy)]
+pub enum WasmAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Restart,
+    Boost,
+    Confirm,
+}
+
+impl From<WasmAction> for InputAction {
+    fn from(a: WasmAction) -> Self {
+        match a {
+            WasmAction::Up => InputAction::Turn(Direction::Up),
+            WasmAction::Down => InputAction::Turn(Direction::Down),
+            WasmAction::Left => InputAction::Turn(Direction::Left),
+            WasmAction::Right => InputAction::Turn(Direction::Right),
+            WasmAction::Pause => InputAction::Pause,
+            WasmAction::Restart => InputAction::Restart,
+            WasmAction::Boost => InputAction::Boost,
+            WasmAction::Confirm => InputAction::Confirm,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = Controller)]
+#[derive(Clone, Copy)]
+pub enum WasmController {
+    Greedy,
+    Pathfinder,
+    Chaser,
+}
+
+impl From<WasmController> for Controller {
+    fn from(c: WasmController) -> Self {
+        match c {
+            WasmController::Greedy => Controller::Greedy,
+            WasmController::Pathfinder => Controller::Pathfinder,
+            WasmController::Chaser => Controller::Chaser,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = ThemePreset)]
+#[derive(Clone, Copy)]
+pub enum WasmThemePreset {
+    Classic,
+    Neon,
+    ColorblindSafe,
+}
+
+impl From<WasmThemePreset> for ThemePreset {
+    fn from(p: WasmThemePreset) -> Self {
+        match p {
+            WasmThemePreset::Classic => ThemePreset::Classic,
+            WasmThemePreset::Neon => ThemePreset::Neon,
+            WasmThemePreset::ColorblindSafe => ThemePreset::ColorblindSafe,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = BoardPattern)]
+#[derive(Clone, Copy)]
+pub enum WasmBoardPattern {
+    Plain,
+    Grid,
+    Checkerboard,
+}
+
+impl From<WasmBoardPattern> for BoardPattern {
+    fn from(p: WasmBoardPattern) -> Self {
+        match p {
+            WasmBoardPattern::Plain => BoardPattern::Plain,
+            WasmBoardPattern::Grid => BoardPattern::Grid,
+            WasmBoardPattern::Checkerboard => BoardPattern::Checkerboard,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = FoodKind)]
+#[derive(Clone, Copy)]
+pub enum WasmFoodKind {
+    Apple = 0,
+    Bonus = 1,
+    SlowDown = 2,
+}
+
+impl From<FoodKind> for WasmFoodKind {
+    fn from(k: FoodKind) -> Self {
+        match k {
+            FoodKind::Apple => WasmFoodKind::Apple,
+            FoodKind::Bonus => WasmFoodKind::Bonus,
+            FoodKind::SlowDown => WasmFoodKind::SlowDown,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = Point)]
+#[derive(Clone, Copy)]
+pub struct WasmPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+// Conversion from core Point to WasmPoint
+impl From<Point> for WasmPoint {
+    fn from(p: Point) -> Self {
+        WasmPoint { x: p.x, y: p.y }
+    }
+}
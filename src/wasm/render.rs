@@ -0,0 +1,212 @@
+// src/wasm/render.rs
+
+// Built-in canvas renderer, only compiled with the `wasm-render` feature.
+// Draws the board, the score and the start/pause/game over overlay onto a
+// 2D canvas so a page doesn't need its own drawing code.
+//
+// Snakes slide from cell to cell by default, which means drawing the whole
+// board on every frame. Without the sliding, frames of a running round
+// only redraw the cells that changed (see `Game::take_dirty_cells`), which
+// is what keeps big boards cheap.
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::{BoardPattern, CellKind, Game, GameStatus, Point, Theme};
+
+// Room the score takes in the top right corner, redrawn on every frame
+const SCORE_WIDTH: f64 = 150.0;
+const SCORE_HEIGHT: f64 = 32.0;
+
+pub struct CanvasRenderer {
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+    cell_size: f64,
+    pub pattern: BoardPattern, // Drawn under everything else
+    pub smooth: bool, // Snakes slide between cells instead of jumping
+    // Set when the last frame was the bare board with this theme, so the
+    // next one can redraw only the cells that changed
+    drawn: Cell<Option<Theme>>,
+}
+
+impl CanvasRenderer {
+    // Looks the canvas up by element id and sizes it to fit the board
+    pub fn attach(canvas_id: &str, game: &Game, cell_size: f64) -> Result<CanvasRenderer, JsValue> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or_else(|| JsValue::from_str("no document available"))?;
+        let canvas: HtmlCanvasElement = document
+            .get_element_by_id(canvas_id)
+            .ok_or_else(|| JsValue::from_str(&format!("no element with id '{}'", canvas_id)))?
+            .dyn_into()
+            .map_err(|_| JsValue::from_str(&format!("element '{}' is not a canvas", canvas_id)))?;
+        let ctx: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+            .dyn_into()?;
+
+        canvas.set_width((game.width as f64 * cell_size) as u32);
+        canvas.set_height((game.height as f64 * cell_size) as u32);
+
+        Ok(CanvasRenderer { canvas, ctx, cell_size, pattern: BoardPattern::Plain, smooth: true, drawn: Cell::new(None) })
+    }
+
+    // Changing `pattern` or `smooth` takes a full redraw
+    pub fn redraw_all(&self) {
+        self.drawn.set(None);
+    }
+
+    pub fn draw(&self, game: &mut Game) -> Result<(), JsValue> {
+        let width = self.canvas.width() as f64;
+        let height = self.canvas.height() as f64;
+
+        let dirty = game.take_dirty_cells();
+        let bare = !self.smooth && game.status() == GameStatus::Running;
+        let drawn = self.drawn.replace(bare.then_some(game.theme));
+        match dirty {
+            Some(cells) if bare && drawn == Some(game.theme) => {
+                // The cells under the score too, it may have changed
+                let cell = self.cell_size;
+                let columns = ((width - SCORE_WIDTH) / cell).max(0.0) as i32..game.width;
+                let rows = 0..((SCORE_HEIGHT / cell).ceil() as i32).min(game.height);
+                let score: Vec<Point> = rows.flat_map(|y| columns.clone().map(move |x| Point { x, y })).collect();
+                for p in cells.into_iter().chain(score) {
+                    self.draw_cell(game, p);
+                }
+            }
+            _ => self.draw_board(game, width, height),
+        }
+
+        let text_color = game.theme.text.to_hex();
+        self.ctx.set_fill_style_str(&text_color);
+        self.ctx.set_font("16px Arial");
+        self.ctx.set_text_align("right");
+        self.ctx.set_text_baseline("alphabetic");
+        self.ctx.fill_text(&format!("Score: {}", game.score), width - 10.0, 25.0)?;
+
+        let message = match game.status() {
+            GameStatus::NotStarted => "Press SPACE to Start".to_string(),
+            GameStatus::Paused => "Paused\nPress P to Resume".to_string(),
+            GameStatus::GameOver => {
+                let headline = game.death_cause.map_or("Game Over".to_string(), |cause| cause.to_string());
+                format!("{}!\nScore: {}\nPress SPACE to Restart", headline, game.score)
+            }
+            GameStatus::Countdown => game.countdown().unwrap_or(0).to_string(),
+            GameStatus::Running | GameStatus::Dying => return Ok(()),
+        };
+
+        // Dim the board behind the message
+        self.ctx.set_fill_style_str("rgba(0, 0, 0, 0.5)");
+        self.ctx.fill_rect(0.0, 0.0, width, height);
+
+        self.ctx.set_fill_style_str(&text_color);
+        self.ctx.set_font("30px Arial");
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let line_height = 30.0;
+        let lines: Vec<&str> = message.lines().collect();
+        let start_y = height / 2.0 - (lines.len() as f64 - 1.0) * line_height / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            self.ctx.fill_text(line, width / 2.0, start_y + i as f64 * line_height)?;
+        }
+        Ok(())
+    }
+
+    // One cell with its background and pattern, for frames that only
+    // redraw what changed
+    fn draw_cell(&self, game: &Game, p: Point) {
+        let cell = self.cell_size;
+        let (x, y) = (p.x as f64 * cell, p.y as f64 * cell);
+        let theme = &game.theme;
+        self.ctx.set_fill_style_str(&theme.background.to_hex());
+        self.ctx.fill_rect(x, y, cell, cell);
+        match self.pattern {
+            BoardPattern::Plain => {}
+            // The lines on the left and top edges, like `draw_pattern`
+            BoardPattern::Grid => {
+                self.ctx.set_fill_style_str(&theme.grid_color().to_hex());
+                if p.x > 0 {
+                    self.ctx.fill_rect(x, y, 1.0, cell);
+                }
+                if p.y > 0 {
+                    self.ctx.fill_rect(x, y, cell, 1.0);
+                }
+            }
+            BoardPattern::Checkerboard if (p.x + p.y) % 2 == 1 => {
+                self.ctx.set_fill_style_str(&theme.checker_color().to_hex());
+                self.ctx.fill_rect(x, y, cell, cell);
+            }
+            BoardPattern::Checkerboard => {}
+        }
+        if let Some(color) = theme.cell_color(game.cell_at(p)) {
+            self.ctx.set_fill_style_str(&color.to_hex());
+            self.ctx.fill_rect(x, y, cell, cell);
+        }
+    }
+
+    fn draw_board(&self, game: &Game, width: f64, height: f64) {
+        let cell = self.cell_size;
+        let theme = &game.theme;
+
+        self.ctx.set_fill_style_str(&theme.background.to_hex());
+        self.ctx.fill_rect(0.0, 0.0, width, height);
+        self.draw_pattern(game, width, height);
+
+        // Snakes are drawn on their own below, between cells while moving
+        for (p, kind) in game.cells() {
+            if matches!(
+                kind,
+                CellKind::SnakeHead | CellKind::SnakeBody | CellKind::OpponentHead(_) | CellKind::OpponentBody(_)
+            ) {
+                continue;
+            }
+            let Some(color) = theme.cell_color(kind) else { continue };
+            self.ctx.set_fill_style_str(&color.to_hex());
+            self.ctx.fill_rect(p.x as f64 * cell, p.y as f64 * cell, cell, cell);
+        }
+
+        // Tail first, so the head ends up on top
+        for index in 0..game.snake_count() {
+            let visible = game.visible_segments(index);
+            let segments = match self.smooth {
+                true => game.interpolated_body(index),
+                false => game.snake_body(index).iter().map(|p| (p.x as f32, p.y as f32)).collect(),
+            };
+            self.ctx.set_global_alpha(game.snake_opacity(index).into());
+            for (i, &(x, y)) in segments.iter().take(visible).enumerate().rev() {
+                self.ctx.set_fill_style_str(&game.segment_color(index, i).to_hex());
+                self.ctx.fill_rect(x as f64 * cell, y as f64 * cell, cell, cell);
+            }
+        }
+        self.ctx.set_global_alpha(1.0);
+    }
+
+    fn draw_pattern(&self, game: &Game, width: f64, height: f64) {
+        let cell = self.cell_size;
+        match self.pattern {
+            BoardPattern::Plain => {}
+            BoardPattern::Grid => {
+                self.ctx.set_fill_style_str(&game.theme.grid_color().to_hex());
+                for x in 1..game.width {
+                    self.ctx.fill_rect(x as f64 * cell, 0.0, 1.0, height);
+                }
+                for y in 1..game.height {
+                    self.ctx.fill_rect(0.0, y as f64 * cell, width, 1.0);
+                }
+            }
+            BoardPattern::Checkerboard => {
+                self.ctx.set_fill_style_str(&game.theme.checker_color().to_hex());
+                for y in 0..game.height {
+                    for x in (0..game.width).filter(|x| (x + y) % 2 == 1) {
+                        self.ctx.fill_rect(x as f64 * cell, y as f64 * cell, cell, cell);
+                    }
+                }
+            }
+        }
+    }
+}
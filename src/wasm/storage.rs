@@ -0,0 +1,123 @@
+// src/wasm/storage.rs
+
+// `window.localStorage` behind the core `Storage` trait, only compiled with
+// the `wasm-storage` feature. Uses the same `Persistence` layer (and the same
+// JSON) as the native build's save files.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+use super::error::{game_error, options_error};
+use super::types::{JsHighScores, JsPartialSettings, JsSettings};
+use crate::{GameError, HighScores, Persistence, Settings, Storage};
+
+pub const DEFAULT_PREFIX: &str = "snake_game:";
+
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    pub fn open() -> Result<LocalStorage, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+        // `None` when storage is disabled, e.g. some private browsing modes
+        let storage = window
+            .local_storage()?
+            .ok_or_else(|| JsValue::from_str("localStorage is not available"))?;
+        Ok(LocalStorage { storage })
+    }
+}
+
+fn storage_error(err: JsValue) -> GameError {
+    GameError::Storage(err.as_string().unwrap_or_else(|| "localStorage error".to_string()))
+}
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.storage.get_item(key).ok().flatten()
+    }
+
+    // Throws (and so fails here) when the storage quota is exceeded
+    fn set(&mut self, key: &str, value: &str) -> Result<(), GameError> {
+        self.storage.set_item(key, value).map_err(storage_error)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), GameError> {
+        self.storage.remove_item(key).map_err(storage_error)
+    }
+}
+
+pub fn open(prefix: Option<String>) -> Result<Persistence<LocalStorage>, JsValue> {
+    let prefix = prefix.unwrap_or_else(|| DEFAULT_PREFIX.to_string());
+    Ok(Persistence::new(LocalStorage::open()?, &prefix))
+}
+
+// High scores and settings, usable before any game exists (e.g. to build the
+// options for `Game.new_with_options` from the saved settings)
+#[wasm_bindgen(js_name = SaveStore)]
+pub struct WasmSaveStore {
+    persistence: Persistence<LocalStorage>,
+}
+
+#[wasm_bindgen(js_class = SaveStore)]
+impl WasmSaveStore {
+    // Every key is stored as `<prefix><name>`, "snake_game:" by default
+    #[wasm_bindgen(constructor)]
+    pub fn new(prefix: Option<String>) -> Result<WasmSaveStore, JsValue> {
+        Ok(WasmSaveStore { persistence: open(prefix)? })
+    }
+
+    pub fn prefix(&self) -> String {
+        self.persistence.prefix().to_string()
+    }
+
+    // Best scores, highest first
+    #[wasm_bindgen(js_name = high_scores)]
+    pub fn high_scores(&self) -> Vec<u32> {
+        self.persistence.load_high_scores().scores()
+    }
+
+    // The same table with the names, see `HighScore`
+    #[wasm_bindgen(js_name = high_score_entries)]
+    pub fn high_score_entries(&self) -> Result<JsHighScores, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(self.persistence.load_high_scores().entries())?.unchecked_into())
+    }
+
+    #[wasm_bindgen(js_name = best_score)]
+    pub fn best_score(&self) -> Option<u32> {
+        self.persistence.load_high_scores().best()
+    }
+
+    // Returns the 0-based rank, or undefined if the score didn't make the table
+    #[wasm_bindgen(js_name = record_score)]
+    pub fn record_score(&mut self, score: u32, name: Option<String>) -> Result<Option<usize>, JsValue> {
+        self.persistence.record_score(name.as_deref().unwrap_or(""), score).map_err(game_error)
+    }
+
+    #[wasm_bindgen(js_name = clear_high_scores)]
+    pub fn clear_high_scores(&mut self) -> Result<(), JsValue> {
+        self.persistence
+            .save_high_scores(&HighScores::default())
+            .map_err(game_error)
+    }
+
+    // `{ config: { width, height, ... }, volume, muted, keymap, ... }`, see
+    // `Settings`. Defaults if nothing was saved.
+    pub fn settings(&self) -> Result<JsSettings, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.persistence.load_settings())?.unchecked_into())
+    }
+
+    #[wasm_bindgen(js_name = save_settings)]
+    // Missing fields are saved with their default
+    pub fn save_settings(&mut self, settings: JsPartialSettings) -> Result<(), JsValue> {
+        let settings: Settings = serde_wasm_bindgen::from_value(settings.into()).map_err(options_error)?;
+        self.persistence.save_settings(&settings).map_err(game_error)
+    }
+
+    // Ids of the skins finished games unlocked, see `Game.set_skin`
+    #[wasm_bindgen(js_name = unlocked_skins)]
+    pub fn unlocked_skins(&self) -> Vec<String> {
+        self.persistence.load_unlocked_skins()
+    }
+}
@@ -0,0 +1,294 @@
+// src/wasm/types.rs
+
+// TypeScript declarations for the plain objects crossing the JS boundary
+// (options, state, events...). They are written by hand to match the serde
+// representation of the core types and get appended to the generated .d.ts,
+// the extern types below let the bindings use them instead of `any`.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &str = r#"
+export interface Point { x: number; y: number; }
+
+export type DirectionName = "Up" | "Down" | "Left" | "Right";
+export type FoodKindName = "Apple" | "Bonus" | "SlowDown";
+export type ControllerName = "Greedy" | "Pathfinder" | "Chaser";
+export type ThemePresetName = "Classic" | "Neon" | "ColorblindSafe";
+export type BoardPatternName = "Plain" | "Grid" | "Checkerboard";
+export type LanguageName = "English" | "Portuguese";
+
+/** CSS hex colors such as #33aa55, returned by `Game.theme()` */
+export interface Theme {
+    background: string;
+    head: string;
+    body: string;
+    opponentHead: string;
+    opponentBody: string;
+    apple: string;
+    bonus: string;
+    slowDown: string;
+    obstacle: string;
+    portal: string;
+    text: string;
+    /** How the player's body is colored, solid when missing */
+    skin?: SkinPattern;
+}
+
+export type SkinPattern =
+    | { kind: "solid" }
+    | { kind: "gradient"; from: string; to: string }
+    | { kind: "striped"; a: string; b: string; width: number }
+    | { kind: "rainbow"; period: number };
+
+export interface Food {
+    pos: Point;
+    kind: FoodKindName;
+    /** Ticks left before it disappears, missing for food that stays */
+    ttl?: number | null;
+}
+
+export type GameMode = "Classic" | { Timed: { limitSecs: number } };
+
+export interface SpeedCurve {
+    baseIntervalMs: number;
+    stepMs: number;
+    minIntervalMs: number;
+    pointsPerLevel: number;
+}
+
+/** Options for `Game.new_with_options`, every field is optional */
+export interface GameOptions {
+    width?: number;
+    height?: number;
+    seed?: number | bigint | null;
+    mode?: GameMode;
+    wrapWalls?: boolean;
+    foodCount?: number;
+    speedCurve?: Partial<SpeedCurve>;
+    snakeLength?: number;
+    /** One entry per extra snake: its AI, or null for a second player */
+    opponents?: (ControllerName | null)[];
+    theme?: ThemePresetName;
+    /** Seconds counted down before the snake moves, 0 for none (default 3) */
+    countdownSecs?: number;
+    /** The dying snake fades out instead of flashing, see `Game.snake_opacity` */
+    reducedMotion?: boolean;
+    /** Fraction of a tick (0 to 1) after it during which a turn still counts for it (default 0, off) */
+    inputGrace?: number;
+    /** Ticks without input before a player's snake counts as away in multiplayer (default 0, never) */
+    afkTicks?: number;
+    /** What a snake does while its player is away (default "Straight") */
+    afkMode?: "Straight" | "Safe";
+    /** Boss encounters at score milestones, null for none (the default) */
+    boss?: Partial<BossConfig> | null;
+}
+
+export interface BossConfig {
+    /** Points between encounters */
+    every: number;
+    /** Ticks each one lasts */
+    ticks: number;
+    length: number;
+}
+
+export type GameStatus = "NotStarted" | "Countdown" | "Running" | "Paused" | "Dying" | "GameOver";
+
+export interface SnakeState {
+    body: Point[];
+    direction: DirectionName;
+}
+
+export interface OpponentState {
+    body: Point[];
+    direction: DirectionName;
+    score: number;
+    alive: boolean;
+    controller?: ControllerName | null;
+}
+
+/** Returned by `Game.state()` */
+export interface GameState {
+    width: number;
+    height: number;
+    snake: SnakeState;
+    food: Food[];
+    obstacles: Point[];
+    portals: [Point, Point][];
+    score: number;
+    mode: GameMode;
+    wrap_walls: boolean;
+    status: GameStatus;
+    /** Why the game ended, null while it's still going */
+    death_cause: DeathCause | null;
+    tick: number;
+    speed_level: number;
+    boosting: boolean;
+    slow_ticks_left: number;
+    opponents: OpponentState[];
+}
+
+export type DeathCause =
+    | { kind: "Wall"; at: Point }
+    | { kind: "Obstacle"; at: Point }
+    | { kind: "SelfCollision"; at: Point; segment: number }
+    | { kind: "BoardFull" }
+    | { kind: "TimeExpired" }
+    | { kind: "Opponent"; at: Point; snake: number }
+    | { kind: "Rules" };
+
+/** Passed to the `Game.on_event` listeners */
+export type GameEvent =
+    | { type: "Started" }
+    | { type: "Countdown"; remaining: number }
+    | { type: "FoodEaten"; at: Point; kind: FoodKindName; score: number }
+    | { type: "PowerUpCollected"; at: Point; kind: FoodKindName; duration_ticks: number }
+    | { type: "FoodExpired"; at: Point; kind: FoodKindName }
+    | { type: "LevelUp"; level: number }
+    | { type: "Turned"; direction: DirectionName }
+    | { type: "NearMiss"; at: Point }
+    | { type: "GameOver"; cause: DeathCause; score: number }
+    | { type: "SnakeAte"; snake: number; at: Point; kind: FoodKindName; score: number }
+    | { type: "SnakeDied"; snake: number; cause: DeathCause }
+    | { type: "BossAppeared"; snake: number; ticks: number }
+    | { type: "BossLeft"; snake: number; trapped: boolean };
+
+export type GameEventListener = (event: GameEvent) => void;
+
+/** Returned by `Game.tick_n`, counts of the events the ticks raised */
+export interface TickSummary {
+    /** Fewer than asked when the round ended, the game was paused or a replay ran out */
+    ticks: number;
+    food_eaten: number;
+    food_expired: number;
+    level_ups: number;
+    turns: number;
+    snakes_died: number;
+    score: number;
+    /** Set when the round ended during the batch */
+    game_over: DeathCause | null;
+}
+
+export type InputActionName =
+    | { Turn: DirectionName }
+    | "Pause"
+    | "Restart"
+    | "Boost"
+    | "Confirm";
+
+/** Posted to a worker running `WorkerGame.handle_message` */
+export type WorkerRequest =
+    | { type: "New"; options?: GameOptions }
+    | { type: "Level"; text: string; options?: GameOptions }
+    | { type: "Action"; action: InputActionName }
+    | { type: "Key"; key: string }
+    | { type: "Advance"; elapsedMs: number }
+    | { type: "Tick"; count?: number }
+    | { type: "State" }
+    | { type: "Snapshot" }
+    | { type: "Restore"; data: Uint8Array };
+
+export type WorkerResponse =
+    | { type: "State"; state: GameState; events: GameEvent[]; ticks: number }
+    | { type: "Snapshot"; data: Uint8Array }
+    | { type: "Error"; code: GameErrorCode; message: string };
+
+/** The `code` property of errors thrown by the module (`e.code`) */
+export type GameErrorCode =
+    | "INVALID_DIMENSIONS"
+    | "SPAWN_ON_OBSTACLE"
+    | "SPAWN_OUT_OF_BOUNDS"
+    | "INVALID_SNAKE_LENGTH"
+    | "NO_FREE_CELL"
+    | "INVALID_LEVEL"
+    | "STORAGE"
+    | "INVALID_REPLAY"
+    | "INVALID_OPTIONS"
+    // `PeerGame`: a bad message from the other page, the signaling server's
+    // error codes, or a connection that couldn't be set up
+    | "INVALID_MESSAGE"
+    | "NOT_FOUND"
+    | "ROOM_TAKEN"
+    | "TOO_MANY_ROOMS"
+    | "SIGNALING"
+    | "WEBRTC";
+
+/** Saved with `SaveStore.save_settings` */
+/** Key names per action, lowercase `KeyboardEvent.key` values and "space" */
+export interface KeyMap {
+    up: string[];
+    down: string[];
+    left: string[];
+    right: string[];
+    pause: string[];
+    restart: string[];
+    boost: string[];
+    confirm: string[];
+}
+
+export interface Settings {
+    config: GameOptions;
+    volume: number;
+    musicVolume: number;
+    muted: boolean;
+    keymap: KeyMap;
+    /** Shake, flash and pulse feedback, toned down by `config.reducedMotion` */
+    screenEffects: boolean;
+    effectIntensity: number;
+    boardPattern: BoardPatternName;
+    /** Name last entered for a high score, used for new scores */
+    playerName: string;
+    /** Whole board in a corner when it doesn't fit on screen (native only) */
+    minimap: boolean;
+    /** Language of the native app's text and `DeathCause` messages */
+    language: LanguageName;
+    /** Id of the player's skin, see `Game.set_skin` */
+    skin: string;
+    /** Channel whose chat votes on the turns, empty for none (native only) */
+    twitchChannel: string;
+    /** Ticks of chat votes counted for each turn */
+    twitchVoteTicks: number;
+}
+
+/** A row of `SaveStore.high_score_entries()`, highest first */
+export interface HighScore {
+    name: string;
+    score: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "GameOptions | undefined")]
+    pub type JsGameOptions;
+
+    #[wasm_bindgen(typescript_type = "GameState")]
+    pub type JsGameState;
+
+    #[wasm_bindgen(typescript_type = "TickSummary")]
+    pub type JsTickSummary;
+
+    #[wasm_bindgen(typescript_type = "Food[]")]
+    pub type JsFoods;
+
+    #[wasm_bindgen(typescript_type = "Theme")]
+    pub type JsTheme;
+
+    #[wasm_bindgen(typescript_type = "GameEventListener")]
+    pub type JsEventListener;
+
+    #[wasm_bindgen(typescript_type = "Settings")]
+    pub type JsSettings;
+
+    #[wasm_bindgen(typescript_type = "Partial<Settings>")]
+    pub type JsPartialSettings;
+
+    #[wasm_bindgen(typescript_type = "HighScore[]")]
+    pub type JsHighScores;
+
+    #[wasm_bindgen(typescript_type = "WorkerRequest")]
+    pub type JsWorkerRequest;
+
+    #[wasm_bindgen(typescript_type = "WorkerResponse")]
+    pub type JsWorkerResponse;
+}
@@ -0,0 +1,401 @@
+// src/wasm/webrtc.rs
+
+// Peer-to-peer games between two browsers, only compiled with the
+// `wasm-webrtc` feature. The peers play a `Lockstep` session over an
+// RTCDataChannel, snake-server's /rooms routes only carry the session
+// descriptions while they connect (see src/server.rs):
+//
+//   const game = await PeerGame.host("http://localhost:8080", { width: 30 });
+//   showCode(game.room());  // the other player types it in
+//   const game = await PeerGame.join("http://localhost:8080", code);
+//
+// Then both pages call `advance` every frame and `turn` on key presses, and
+// draw `state()`. Nothing ticks until the channel is open and, for the
+// guest, the host's config has arrived.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::{Rc, Weak};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MessageEvent, RequestInit, Response, RtcConfiguration, RtcDataChannel, RtcDataChannelEvent, RtcDataChannelState,
+    RtcDataChannelType, RtcIceGatheringState, RtcIceServer, RtcPeerConnection, RtcSdpType, RtcSessionDescriptionInit,
+};
+
+use super::error::{game_error, js_error, options_error};
+use super::types::{JsEventListener, JsGameOptions, JsGameState};
+use super::WasmDirection;
+use crate::{GameConfig, GameError, GameEvent, Lockstep, LockstepMessage, LockstepStatus, DEFAULT_INPUT_DELAY};
+
+const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+// The candidates go in the description all at once (no trickle ICE), this
+// is how long to wait for them
+const ICE_GATHERING_TIMEOUT_MS: i32 = 5000;
+const ICE_GATHERING_POLL_MS: i32 = 50;
+// How often the host asks the signaling server for the guest's answer
+const ANSWER_POLL_MS: i32 = 1000;
+
+// What the signaling server sends and takes, see src/server.rs
+#[derive(Deserialize)]
+struct Room {
+    room: String,
+    offer: String,
+    answer: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Description<'a> {
+    sdp: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SignalError {
+    error: String,
+    message: String,
+}
+
+type Callback = Closure<dyn FnMut(JsValue)>;
+
+struct Peer {
+    connection: RtcPeerConnection,
+    channel: RefCell<Option<RtcDataChannel>>,
+    // The guest's is set by the host's first message
+    lockstep: RefCell<Option<Lockstep>>,
+    listeners: RefCell<Vec<js_sys::Function>>, // Registered with `on_event`
+    closed: Cell<bool>,
+    // The browser calls them for as long as the connection lives
+    callbacks: RefCell<Vec<Callback>>,
+}
+
+impl Peer {
+    fn new() -> Result<Rc<Peer>, JsValue> {
+        let server = RtcIceServer::new();
+        server.set_urls(&JsValue::from_str(STUN_SERVER));
+        let config = RtcConfiguration::new();
+        config.set_ice_servers(&js_sys::Array::of1(&server));
+        Ok(Rc::new(Peer {
+            connection: RtcPeerConnection::new_with_configuration(&config)?,
+            channel: RefCell::new(None),
+            lockstep: RefCell::new(None),
+            listeners: RefCell::new(Vec::new()),
+            closed: Cell::new(false),
+            callbacks: RefCell::new(Vec::new()),
+        }))
+    }
+
+    fn callback(peer: &Rc<Peer>, f: impl Fn(&Rc<Peer>, JsValue) + 'static) -> js_sys::Function {
+        let weak: Weak<Peer> = Rc::downgrade(peer);
+        let closure = Closure::wrap(Box::new(move |value: JsValue| {
+            if let Some(peer) = weak.upgrade() {
+                f(&peer, value);
+            }
+        }) as Box<dyn FnMut(JsValue)>);
+        let function = closure.as_ref().unchecked_ref::<js_sys::Function>().clone();
+        peer.callbacks.borrow_mut().push(closure);
+        function
+    }
+
+    fn attach_channel(peer: &Rc<Peer>, channel: RtcDataChannel) {
+        channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+        channel.set_onopen(Some(&Peer::callback(peer, |peer, _| peer.send_messages())));
+        channel.set_onclose(Some(&Peer::callback(peer, |peer, _| peer.closed.set(true))));
+        channel.set_onmessage(Some(&Peer::callback(peer, |peer, event| {
+            let data = event.unchecked_into::<MessageEvent>().data();
+            if let Err(err) = peer.receive(&js_sys::Uint8Array::new(&data).to_vec()) {
+                // The other page is broken or not a snake game, nothing to play with
+                web_sys::console::error_1(&err);
+                peer.close();
+            }
+        })));
+        *peer.channel.borrow_mut() = Some(channel);
+    }
+
+    fn is_open(&self) -> bool {
+        self.channel.borrow().as_ref().is_some_and(|c| c.ready_state() == RtcDataChannelState::Open)
+    }
+
+    fn receive(&self, bytes: &[u8]) -> Result<(), JsValue> {
+        let mut lockstep = self.lockstep.borrow_mut();
+        match lockstep.as_mut() {
+            Some(lockstep) => lockstep.receive_bytes(bytes).map_err(game_error)?,
+            None => {
+                let start = LockstepMessage::from_bytes(bytes).map_err(game_error)?;
+                *lockstep = Some(Lockstep::join(&start).map_err(game_error)?);
+            }
+        }
+        drop(lockstep);
+        self.send_messages();
+        Ok(())
+    }
+
+    // Messages wait in the session until the channel is open
+    fn send_messages(&self) {
+        if !self.is_open() {
+            return;
+        }
+        let messages = match self.lockstep.borrow_mut().as_mut() {
+            Some(lockstep) => lockstep.take_bytes(),
+            None => return,
+        };
+        if let Some(channel) = self.channel.borrow().as_ref() {
+            for message in messages {
+                if let Err(err) = channel.send_with_u8_array(&message) {
+                    web_sys::console::error_1(&err);
+                }
+            }
+        }
+    }
+
+    // Same as `Inner::dispatch_events`, for the session's game
+    fn dispatch_events(&self) {
+        let events: Vec<GameEvent> = match self.lockstep.borrow_mut().as_mut() {
+            Some(lockstep) => lockstep.game.drain_events().collect(),
+            None => return,
+        };
+        let listeners = self.listeners.borrow().clone();
+        for event in events {
+            let Ok(value) = serde_wasm_bindgen::to_value(&event) else { continue };
+            for listener in &listeners {
+                if let Err(err) = listener.call1(&JsValue::NULL, &value) {
+                    web_sys::console::error_1(&err);
+                }
+            }
+        }
+    }
+
+    // Sets our side of the connection and waits for the ICE candidates,
+    // the description to send then has all of them
+    async fn describe(&self, description: JsValue) -> Result<String, JsValue> {
+        JsFuture::from(self.connection.set_local_description(description.unchecked_ref())).await?;
+        let mut waited = 0;
+        while self.connection.ice_gathering_state() != RtcIceGatheringState::Complete && waited < ICE_GATHERING_TIMEOUT_MS {
+            sleep(ICE_GATHERING_POLL_MS).await?;
+            waited += ICE_GATHERING_POLL_MS;
+        }
+        match self.connection.local_description() {
+            Some(description) => Ok(description.sdp()),
+            None => Err(game_error(GameError::WebRtc("no local description".to_string()))),
+        }
+    }
+
+    async fn set_remote(&self, kind: RtcSdpType, sdp: &str) -> Result<(), JsValue> {
+        let description = RtcSessionDescriptionInit::new(kind);
+        description.set_sdp(sdp);
+        JsFuture::from(self.connection.set_remote_description(&description)).await.map(drop)
+    }
+
+    fn close(&self) {
+        self.closed.set(true);
+        if let Some(channel) = self.channel.borrow().as_ref() {
+            channel.close();
+        }
+        self.connection.close();
+    }
+}
+
+async fn sleep(ms: i32) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+    let mut set_timeout = |resolve: js_sys::Function, _reject: js_sys::Function| {
+        let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+    };
+    JsFuture::from(js_sys::Promise::new(&mut set_timeout)).await.map(drop)
+}
+
+// A GET, or a POST of a session description. The body goes as text/plain so
+// the browser doesn't need a CORS preflight, the server reads it as JSON.
+async fn signal(url: &str, sdp: Option<&str>) -> Result<Room, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window available"))?;
+    let init = RequestInit::new();
+    if let Some(sdp) = sdp {
+        init.set_method("POST");
+        let body = serde_json::to_string(&Description { sdp }).map_err(|e| js_error("SIGNALING", &e.to_string()))?;
+        init.set_body(&JsValue::from_str(&body));
+    }
+    let response: Response = JsFuture::from(window.fetch_with_str_and_init(url, &init)).await?.dyn_into()?;
+    let text = JsFuture::from(response.text()?).await?.as_string().unwrap_or_default();
+    if !response.ok() {
+        return Err(match serde_json::from_str::<SignalError>(&text) {
+            Ok(e) => js_error(&e.error, &e.message),
+            Err(_) => js_error("SIGNALING", &format!("the signaling server replied {}", response.status())),
+        });
+    }
+    serde_json::from_str(&text).map_err(|e| js_error("SIGNALING", &e.to_string()))
+}
+
+// The host's side of signaling, after handing out the room code
+async fn wait_for_answer(peer: Weak<Peer>, url: String) {
+    loop {
+        if let Err(err) = sleep(ANSWER_POLL_MS).await {
+            web_sys::console::error_1(&err);
+            return;
+        }
+        // Not kept while waiting, so a dropped game stops the polling
+        let Some(alive) = peer.upgrade().filter(|peer| !peer.closed.get()) else { return };
+        drop(alive);
+        let answer = match signal(&url, None).await {
+            Ok(Room { answer: Some(answer), .. }) => answer,
+            Ok(_) => continue,
+            Err(err) => {
+                // The room expired, or the server is gone
+                web_sys::console::error_1(&err);
+                return;
+            }
+        };
+        let Some(peer) = peer.upgrade() else { return };
+        if let Err(err) = peer.set_remote(RtcSdpType::Answer, &answer).await {
+            web_sys::console::error_1(&err);
+            peer.close();
+        }
+        return;
+    }
+}
+
+#[wasm_bindgen(js_name = PeerGame)]
+pub struct PeerGame {
+    peer: Rc<Peer>,
+    room: String,
+    host: bool,
+}
+
+#[wasm_bindgen(js_class = PeerGame)]
+impl PeerGame {
+    // Opens a room on the signaling server at `signal_url` for a game built
+    // from `options` (like `Game.new_with_options`, the opponents and the
+    // countdown are set by the session). Resolves once the room is open,
+    // the guest can join from then on.
+    pub async fn host(signal_url: String, options: JsGameOptions) -> Result<PeerGame, JsValue> {
+        let options = JsValue::from(options);
+        let config: GameConfig = if options.is_undefined() || options.is_null() {
+            GameConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(options_error)?
+        };
+        let lockstep = Lockstep::host(&config, DEFAULT_INPUT_DELAY).map_err(game_error)?;
+        let peer = Peer::new()?;
+        *peer.lockstep.borrow_mut() = Some(lockstep);
+        Peer::attach_channel(&peer, peer.connection.create_data_channel("snake"));
+        let offer = JsFuture::from(peer.connection.create_offer()).await?;
+        let sdp = peer.describe(offer).await?;
+        let room = signal(&format!("{}/rooms", signal_url.trim_end_matches('/')), Some(&sdp)).await?;
+        let url = format!("{}/rooms/{}", signal_url.trim_end_matches('/'), room.room);
+        wasm_bindgen_futures::spawn_local(wait_for_answer(Rc::downgrade(&peer), url));
+        Ok(PeerGame { peer, room: room.room, host: true })
+    }
+
+    // Answers the host of room `code`. Resolves once the answer is sent, the
+    // game shows up when the connection is made.
+    pub async fn join(signal_url: String, code: String) -> Result<PeerGame, JsValue> {
+        let url = format!("{}/rooms/{}", signal_url.trim_end_matches('/'), code.trim());
+        let room = signal(&url, None).await?;
+        let peer = Peer::new()?;
+        // The host opened the channel, it shows up once connected
+        peer.connection.set_ondatachannel(Some(&Peer::callback(&peer, |peer, event| {
+            Peer::attach_channel(peer, event.unchecked_into::<RtcDataChannelEvent>().channel());
+        })));
+        peer.set_remote(RtcSdpType::Offer, &room.offer).await?;
+        let answer = JsFuture::from(peer.connection.create_answer()).await?;
+        let sdp = peer.describe(answer).await?;
+        signal(&format!("{}/answer", url), Some(&sdp)).await?;
+        Ok(PeerGame { peer, room: room.room, host: false })
+    }
+
+    // The code the guest joins with
+    pub fn room(&self) -> String {
+        self.room.clone()
+    }
+
+    #[wasm_bindgen(js_name = is_host)]
+    pub fn is_host(&self) -> bool {
+        self.host
+    }
+
+    // The snake this page steers in `state()`: 0 is `snake`, 1 the opponent
+    pub fn player(&self) -> usize {
+        if self.host {
+            0
+        } else {
+            1
+        }
+    }
+
+    // "Connecting", "Running", "Waiting" (for the other peer's input),
+    // "GameOver", "Desynced" or "Closed"
+    pub fn status(&self) -> String {
+        if self.peer.closed.get() {
+            return "Closed".to_string();
+        }
+        let lockstep = self.peer.lockstep.borrow();
+        let status = match lockstep.as_ref().map(Lockstep::status) {
+            Some(status) if self.peer.is_open() => match status {
+                LockstepStatus::Running => "Running",
+                LockstepStatus::Waiting => "Waiting",
+                LockstepStatus::GameOver => "GameOver",
+                LockstepStatus::Desynced { .. } => "Desynced",
+            },
+            _ => "Connecting",
+        };
+        status.to_string()
+    }
+
+    // Call once per animation frame with the milliseconds since the last
+    // frame, like `Game.advance`
+    pub fn advance(&self, elapsed_ms: f64) -> u32 {
+        if !self.peer.is_open() {
+            return 0;
+        }
+        let ticks = match self.peer.lockstep.borrow_mut().as_mut() {
+            Some(lockstep) => lockstep.advance(Duration::from_secs_f64(elapsed_ms.max(0.0) / 1000.0)),
+            None => return 0,
+        };
+        self.peer.send_messages();
+        self.peer.dispatch_events();
+        ticks
+    }
+
+    // Steers this page's snake, a few ticks later (see `Lockstep`)
+    pub fn turn(&self, direction: WasmDirection) {
+        if let Some(lockstep) = self.peer.lockstep.borrow_mut().as_mut() {
+            lockstep.turn(direction.into());
+        }
+    }
+
+    // Another round once this one is over, only the host can start it
+    pub fn restart(&self) {
+        if let Some(lockstep) = self.peer.lockstep.borrow_mut().as_mut() {
+            lockstep.restart();
+        }
+        self.peer.send_messages();
+    }
+
+    // `null` until the guest has the host's config
+    pub fn state(&self) -> Result<Option<JsGameState>, JsValue> {
+        match self.peer.lockstep.borrow().as_ref() {
+            Some(lockstep) => Ok(Some(serde_wasm_bindgen::to_value(&lockstep.game.state())?.unchecked_into())),
+            None => Ok(None),
+        }
+    }
+
+    // Game events of the session's game, like `Game.on_event`
+    #[wasm_bindgen(js_name = on_event)]
+    pub fn on_event(&self, callback: JsEventListener) {
+        self.peer.listeners.borrow_mut().push(callback.unchecked_into());
+    }
+
+    // Hangs up, the other page sees "Closed"
+    pub fn close(&self) {
+        self.peer.close();
+    }
+}
+
+impl Drop for PeerGame {
+    fn drop(&mut self) {
+        self.peer.close();
+    }
+}
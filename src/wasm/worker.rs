@@ -0,0 +1,157 @@
+// src/wasm/worker.rs
+
+// A message-based driver for running the simulation inside a Web Worker.
+// It never touches the DOM (workers have none): the page posts requests,
+// the worker passes them to `handle_message` and posts the reply back.
+//
+//   // worker.js
+//   const driver = new WorkerGame();
+//   onmessage = (e) => {
+//       const reply = driver.handle_message(e.data);
+//       postMessage(reply, reply.data ? [reply.data.buffer] : []);
+//   };
+//
+// Snapshots travel as a Uint8Array whose buffer can be transferred instead
+// of copied, and restored later with a `Restore` request.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::error::{game_error, options_error};
+use super::types::{JsWorkerRequest, JsWorkerResponse};
+use crate::{Game, GameConfig, GameEvent, GameState, InputAction, Level, Snapshot};
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+enum Request {
+    // Replaces the game, `options` like `Game.new_with_options`
+    New {
+        #[serde(default)]
+        options: GameConfig,
+    },
+    // Replaces the game with a level in the text format
+    Level {
+        text: String,
+        #[serde(default)]
+        options: GameConfig,
+    },
+    Action { action: InputAction },
+    // A `KeyboardEvent.key` value forwarded from the page
+    Key { key: String },
+    Advance { elapsed_ms: f64 },
+    // `count` ticks in one go, 1 without it
+    Tick {
+        #[serde(default)]
+        count: Option<u32>,
+    },
+    State,
+    Snapshot,
+    // Carries a `data` Uint8Array from a `Snapshot` reply, read separately
+    Restore,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Response {
+    // Sent after every request that may have changed the game
+    State { state: GameState, events: Vec<GameEvent>, ticks: u32 },
+    Error { code: String, message: String },
+}
+
+#[wasm_bindgen(js_name = WorkerGame)]
+pub struct WorkerGame {
+    game: Game,
+}
+
+#[wasm_bindgen(js_class = WorkerGame)]
+impl WorkerGame {
+    // Starts with a default 20x20 game, send `New` to set it up
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WorkerGame, JsValue> {
+        Game::from_config(&GameConfig::default())
+            .map(|game| WorkerGame { game })
+            .map_err(game_error)
+    }
+
+    // Never throws, failures come back as `{ type: "Error", code, message }`
+    #[wasm_bindgen(js_name = handle_message)]
+    pub fn handle_message(&mut self, message: JsWorkerRequest) -> JsWorkerResponse {
+        let message = JsValue::from(message);
+        self.handle(&message).unwrap_or_else(error_reply).unchecked_into()
+    }
+}
+
+impl WorkerGame {
+    fn handle(&mut self, message: &JsValue) -> Result<JsValue, JsValue> {
+        let request: Request = serde_wasm_bindgen::from_value(message.clone()).map_err(options_error)?;
+        let mut ticks = 0;
+        match request {
+            Request::New { options } => self.game = Game::from_config(&options).map_err(game_error)?,
+            Request::Level { text, options } => {
+                let level = Level::parse(&text).map_err(game_error)?;
+                self.game = Game::from_level_with_config(&level, &options).map_err(game_error)?;
+            }
+            Request::Action { action } => self.game.handle_action(action),
+            Request::Key { key } => {
+                if let Some(action) = InputAction::from_key(&key) {
+                    self.game.handle_action(action);
+                }
+            }
+            Request::Advance { elapsed_ms } => {
+                // Negative, NaN or infinite times from the page count as no time
+                let elapsed = Duration::try_from_secs_f64(elapsed_ms / 1000.0).unwrap_or(Duration::ZERO);
+                ticks = self.game.advance(elapsed);
+            }
+            Request::Tick { count } => {
+                ticks = self.game.tick_n(count.unwrap_or(1).into()).ticks as u32;
+            }
+            Request::State => {}
+            Request::Snapshot => return self.snapshot_reply(),
+            Request::Restore => {
+                let data = js_sys::Reflect::get(message, &JsValue::from_str("data"))?;
+                let bytes = js_sys::Uint8Array::new(&data).to_vec();
+                let snapshot: Snapshot = postcard::from_bytes(&bytes)
+                    .map_err(|e| JsValue::from_str(&alloc::format!("invalid snapshot: {}", e)))?;
+                if !snapshot.fits(&self.game) {
+                    return Err(JsValue::from_str("snapshot does not fit this board"));
+                }
+                self.game.restore(&snapshot);
+            }
+        }
+
+        let response = Response::State {
+            state: self.game.state(),
+            events: self.game.drain_events().collect(),
+            ticks,
+        };
+        Ok(serde_wasm_bindgen::to_value(&response)?)
+    }
+
+    // `{ type: "Snapshot", data: Uint8Array }`, built by hand so the bytes
+    // stay a typed array instead of a plain array of numbers
+    fn snapshot_reply(&self) -> Result<JsValue, JsValue> {
+        let bytes = postcard::to_allocvec(&self.game.snapshot())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let reply = js_sys::Object::new();
+        js_sys::Reflect::set(&reply, &JsValue::from_str("type"), &JsValue::from_str("Snapshot"))?;
+        js_sys::Reflect::set(&reply, &JsValue::from_str("data"), &js_sys::Uint8Array::from(&bytes[..]))?;
+        Ok(reply.into())
+    }
+}
+
+// Errors from `super::error` carry a `code`, anything else is reported as a
+// bad message
+fn error_reply(err: JsValue) -> JsValue {
+    let code = js_sys::Reflect::get(&err, &JsValue::from_str("code"))
+        .ok()
+        .and_then(|code| code.as_string())
+        .unwrap_or_else(|| "INVALID_MESSAGE".to_string());
+    let message = match err.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => err.as_string().unwrap_or_else(|| "invalid message".to_string()),
+    };
+    serde_wasm_bindgen::to_value(&Response::Error { code, message }).unwrap_or(JsValue::NULL)
+}